@@ -0,0 +1,23 @@
+//! Helper process of the engine running a single isolated plugin.
+//! Started by the engine with `--socket <path> --plugin <file or name of a linked plugin>`, not meant to be started by hand.
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| args.get(index + 1))
+    };
+    let (socket, plugin) = match (value("--socket"), value("--plugin")) {
+        (Some(socket), Some(plugin)) => (socket, plugin),
+        _ => {
+            eprintln!("Usage: lightoros_plugin_host --socket <path> --plugin <file>");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = lightoros_engine::run_plugin_host(socket, plugin) {
+        eprintln!("[{}] {}", plugin, err);
+        std::process::exit(1);
+    }
+}
@@ -1,7 +1,13 @@
-use std::sync::Arc;
+use std::sync::atomic::AtomicU8;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
 
+use lightoros_plugin_base::transform::PluginTransformTrait;
 use lightoros_plugin_base::*;
 
+/// Transformations of a pipe, shared with the JSON server to change their parameters at runtime
+pub(crate) type Transformations = Arc<Mutex<Vec<Box<dyn PluginTransformTrait>>>>;
+
 #[derive(serde::Deserialize)]
 pub(crate) struct InputPipeDescription {
     pub name: String,
@@ -19,6 +25,29 @@ pub(crate) struct OutputPipeDescription {
 pub(crate) struct PluginDescription {
     pub kind: String,
     pub config: serde_json::Value,
+    // runs the plugin in a helper process, a crash of the plugin doesn't stop the engine
+    #[serde(default)]
+    pub isolated: bool,
+    // time in ms for the helper of an isolated plugin to answer, a helper not answering in time is restarted
+    pub timeout: Option<u64>,
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct JsonServerConfig {
+    #[serde(default = "default_json_server_address")]
+    pub address: String,
+    #[serde(default = "default_json_server_port")]
+    pub port: u16,
+    // number of LEDs used for colors, the size of the last frame is used if not set
+    pub leds: Option<usize>,
+}
+
+fn default_json_server_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_json_server_port() -> u16 {
+    19444
 }
 
 #[derive(serde::Deserialize)]
@@ -27,6 +56,36 @@ pub(crate) struct Config {
     pub max_input_inactivity_period: u64,
     pub input: Vec<InputPipeDescription>,
     pub output: Vec<OutputPipeDescription>,
+    pub json_server: Option<JsonServerConfig>,
+}
+
+/// Commands for the main engine thread
+pub(crate) enum EngineCommand {
+    /// stop the engine after waiting the given time in ms
+    Stop(u64),
+    /// the priority is not used anymore, the next event of any input pipe takes over
+    ReleasePriority(u8),
+    /// enable or disable sending data to the output pipes
+    EnableOutput(bool),
+}
+
+/// State of the main engine thread, updated with every event forwarded to the output pipes
+#[derive(Default)]
+pub(crate) struct EngineStatus {
+    pub current_priority: u8,
+    pub leds: usize,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+}
+
+/// Parts of an input pipe controlled by the JSON server
+pub(crate) struct InputPipeControl {
+    pub name: String,
+    pub priority: u8,
+    // priority used for the events of the pipe, differs from the configured one while it runs as an effect
+    pub current_priority: Arc<AtomicU8>,
+    // parameter changes for the input plugin as name and value, applied between two frames
+    pub parameters: Sender<(String, String)>,
 }
 
 pub(crate) struct InputEvent {
@@ -0,0 +1,109 @@
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+use lightoros_plugin_base::input::PluginInputTrait;
+use lightoros_plugin_base::output::PluginOutputTrait;
+use lightoros_plugin_base::transform::PluginTransformTrait;
+use lightoros_plugin_base::*;
+
+use super::ipc::{self, Request, Response};
+use super::plugin::LoadedPlugin;
+use super::registry::find_static_plugin;
+
+enum Plugin {
+    Input(Box<dyn PluginInputTrait>),
+    Transform(Box<dyn PluginTransformTrait>),
+    Output(Box<dyn PluginOutputTrait>),
+}
+
+/// Runs a single plugin for the engine connected to the socket, used by the helper `lightoros_plugin_host`.
+/// The plugin is the name of a plugin linked into the engine or a plugin file. Returns when the engine closes the connection.
+pub fn run_plugin_host(socket: &str, plugin: &str) -> Result<(), PluginError> {
+    let mut stream = connect(socket)?;
+    let lib = match find_static_plugin(plugin) {
+        Some(declaration) => LoadedPlugin::linked(declaration)?,
+        None => LoadedPlugin::load(Path::new(plugin))?,
+    };
+
+    let mut plugin: Option<Plugin> = None;
+    loop {
+        let request = match ipc::read_request(&mut stream) {
+            Ok(request) => request,
+            // the engine has stopped the plugin
+            Err(ref err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(err) => return plugin_err!("Error reading request: {}", err),
+        };
+        let response = match (request, plugin.as_mut()) {
+            (Request::Create(config), None) => match create(&lib, &config) {
+                Ok(created) => {
+                    plugin = Some(created);
+                    Response::Ok
+                }
+                Err(err) => Response::Error(err.to_string()),
+            },
+            (Request::Create(_), Some(_)) => Response::Error("Plugin already created".to_string()),
+            (_, None) => Response::Error("Plugin not created".to_string()),
+            (request, Some(plugin)) => handle(plugin, request),
+        };
+        if let Err(err) = ipc::write_response(&mut stream, &response) {
+            return plugin_err!("Error writing response: {}", err);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect(socket: &str) -> Result<UnixStream, PluginError> {
+    match UnixStream::connect(socket) {
+        Ok(stream) => Ok(stream),
+        Err(err) => plugin_err!("Cannot connect to socket '{}': {}", socket, err),
+    }
+}
+
+#[cfg(not(unix))]
+fn connect(socket: &str) -> Result<std::net::TcpStream, PluginError> {
+    plugin_err!(
+        "Cannot connect to socket '{}', isolated plugins are only supported on Unix",
+        socket
+    )
+}
+
+fn create(lib: &LoadedPlugin, config: &str) -> Result<Plugin, PluginError> {
+    let config: serde_json::Value = match serde_json::from_str(config) {
+        Ok(config) => config,
+        Err(err) => return plugin_err!("Invalid plugin configuration: {}", err),
+    };
+    let plugin = match lib.kind() {
+        Some(PluginKind::Input) => Plugin::Input(lib.create_input(&config)?),
+        Some(PluginKind::Transform) => Plugin::Transform(lib.create_transform(&config)?),
+        Some(PluginKind::Output) => Plugin::Output(lib.create_output(&config)?),
+        None => return plugin_err!("Plugin '{}' has an unknown kind", lib.name()),
+    };
+    Ok(plugin)
+}
+
+fn handle(plugin: &mut Plugin, request: Request) -> Response {
+    let result = match (plugin, request) {
+        (Plugin::Input(input), Request::Init) => input.init().map(|_| Response::Ok),
+        (Plugin::Output(output), Request::Init) => output.init().map(|_| Response::Ok),
+        (Plugin::Input(input), Request::Get) => input.get().map(Response::Data),
+        (Plugin::Transform(transform), Request::Transform(data)) => {
+            transform.transform(&data).map(Response::Data)
+        }
+        (Plugin::Output(output), Request::Send(data)) => output.send(&data).map(|_| Response::Ok),
+        (Plugin::Input(input), Request::SetParameter(name, value)) => {
+            input.set_parameter(&name, &value).map(Response::Bool)
+        }
+        (Plugin::Transform(transform), Request::SetParameter(name, value)) => {
+            transform.set_parameter(&name, &value).map(Response::Bool)
+        }
+        (Plugin::Transform(transform), Request::GetParameter(name)) => {
+            Ok(Response::Parameter(transform.get_parameter(&name)))
+        }
+        _ => plugin_err!("Request not supported by the plugin"),
+    };
+    match result {
+        Ok(response) => response,
+        Err(err) => Response::Error(err.to_string()),
+    }
+}
@@ -0,0 +1,217 @@
+//! Index of the available plugins, built once when the engine starts.
+//!
+//! Plugins linked into the engine come first, followed by the plugin libraries of the plugin folders in the given order.
+//! The first plugin with a name is used. Libraries which cannot be loaded or are incompatible are reported and skipped.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use lightoros_plugin_base::abi::PluginDeclaration;
+use lightoros_plugin_base::*;
+
+use super::plugin::{read_declaration, LoadedPlugin};
+use super::registry::{find_static_plugin, static_plugins};
+
+/// Plugin found by the discovery
+pub struct PluginEntry {
+    pub name: String,
+    pub kind: PluginKind,
+    pub api_version: u32,
+    /// None for plugins linked into the engine
+    pub file: Option<PathBuf>,
+    pub description: String,
+    /// JSON schema of the configuration, empty if the plugin does not provide one
+    pub config_schema: String,
+}
+
+/// Plugin folder or library skipped by the discovery
+pub struct DiscoveryError {
+    pub path: PathBuf,
+    /// name of the plugin if the library has a plugin declaration
+    pub name: Option<String>,
+    pub error: String,
+}
+
+pub struct PluginIndex {
+    folders: Vec<PathBuf>,
+    plugins: Vec<PluginEntry>,
+    errors: Vec<DiscoveryError>,
+}
+
+impl PluginEntry {
+    fn new(declaration: &PluginDeclaration, file: Option<PathBuf>) -> PluginResult<PluginEntry> {
+        declaration.check()?;
+        Ok(PluginEntry {
+            name: declaration.name(),
+            // checked above
+            kind: declaration.kind().unwrap(),
+            api_version: declaration.api_version,
+            file,
+            description: declaration.description(),
+            config_schema: declaration.config_schema(),
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let config_schema: serde_json::Value =
+            serde_json::from_str(&self.config_schema).unwrap_or(serde_json::Value::Null);
+        json!({
+            "name": self.name,
+            "kind": self.kind.to_string(),
+            "api_version": self.api_version,
+            "file": self.file,
+            "description": self.description,
+            "config_schema": config_schema,
+        })
+    }
+}
+
+impl std::fmt::Display for DiscoveryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+impl PluginIndex {
+    /// Discovers the plugins of the folders in `plugins_path`, several folders are separated like in `PATH`
+    pub fn discover(plugins_path: &str) -> PluginIndex {
+        let mut index = PluginIndex {
+            folders: std::env::split_paths(plugins_path)
+                .filter(|folder| !folder.as_os_str().is_empty())
+                .collect(),
+            plugins: Vec::new(),
+            errors: Vec::new(),
+        };
+        for declaration in static_plugins() {
+            index.add(declaration, None, Path::new("linked"));
+        }
+        for folder in index.folders.clone() {
+            index.scan(&folder);
+        }
+        index
+    }
+
+    fn scan(&mut self, folder: &Path) {
+        let entries = match fs::read_dir(folder) {
+            Ok(entries) => entries,
+            Err(err) => {
+                return self.error(folder, None, format!("Cannot read plugin folder: {}", err));
+            }
+        };
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| is_plugin_file(path))
+            .collect();
+        files.sort();
+
+        for file in files {
+            let file = match std::env::current_dir() {
+                Ok(dir) => dir.join(file),
+                Err(_) => file,
+            };
+            match read_declaration(&file) {
+                Ok(plugin) => self.add(plugin.declaration(), Some(file.clone()), &file),
+                Err(err) => self.error(&file, None, err.to_string()),
+            }
+        }
+    }
+
+    fn add(&mut self, declaration: &PluginDeclaration, file: Option<PathBuf>, path: &Path) {
+        let name = declaration.name();
+        if let Some(existing) = self.plugins.iter().find(|plugin| plugin.name == name) {
+            let source = match &existing.file {
+                Some(file) => file.display().to_string(),
+                None => "the engine".to_string(),
+            };
+            let error = format!("Plugin '{}' is already provided by {}", name, source);
+            return self.error(path, Some(name), error);
+        }
+        match PluginEntry::new(declaration, file) {
+            Ok(entry) => self.plugins.push(entry),
+            Err(err) => self.error(path, Some(name), err.to_string()),
+        }
+    }
+
+    fn error(&mut self, path: &Path, name: Option<String>, error: String) {
+        self.errors.push(DiscoveryError {
+            path: path.to_path_buf(),
+            name,
+            error,
+        });
+    }
+
+    pub fn folders(&self) -> &[PathBuf] {
+        &self.folders
+    }
+
+    pub fn plugins(&self) -> &[PluginEntry] {
+        &self.plugins
+    }
+
+    /// Folders and libraries skipped by the discovery
+    pub fn errors(&self) -> &[DiscoveryError] {
+        &self.errors
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let plugins: Vec<serde_json::Value> =
+            self.plugins.iter().map(|plugin| plugin.to_json()).collect();
+        let errors: Vec<serde_json::Value> = self
+            .errors
+            .iter()
+            .map(|error| json!({"path": error.path, "name": error.name, "error": error.error}))
+            .collect();
+        json!({ "plugins": plugins, "errors": errors })
+    }
+
+    /// Entry of the plugin with the name, an incompatible plugin returns the reason
+    pub fn find(&self, name: &str) -> Result<&PluginEntry, PluginError> {
+        if let Some(plugin) = self.plugins.iter().find(|plugin| plugin.name == name) {
+            return Ok(plugin);
+        }
+        let rejected = self
+            .errors
+            .iter()
+            .find(|error| error.name.as_deref() == Some(name));
+        if let Some(rejected) = rejected {
+            return plugin_err!("Cannot use plugin '{}': {}", name, rejected);
+        }
+        let folders: Vec<String> = self
+            .folders
+            .iter()
+            .map(|folder| format!("'{}'", folder.display()))
+            .collect();
+        plugin_err!(
+            "Cannot find plugin '{}' in folders {}",
+            name,
+            folders.join(", ")
+        )
+    }
+
+    /// Loads the plugin with the name
+    pub(crate) fn get(&self, name: &str) -> Result<LoadedPlugin, PluginError> {
+        match &self.find(name)?.file {
+            Some(file) => LoadedPlugin::load(file),
+            None => match find_static_plugin(name) {
+                Some(declaration) => LoadedPlugin::linked(declaration),
+                None => plugin_err!("Plugin '{}' is not linked into the engine", name),
+            },
+        }
+    }
+}
+
+// plugin libraries are named after the plugin crates
+fn is_plugin_file(path: &Path) -> bool {
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name.to_string_lossy(),
+        None => return false,
+    };
+    let is_dylib = file_name.ends_with(std::env::consts::DLL_EXTENSION);
+    let is_plugin = file_name.contains("lightoros_input")
+        || file_name.contains("lightoros_output")
+        || file_name.contains("lightoros_transform");
+    is_plugin && is_dylib
+}
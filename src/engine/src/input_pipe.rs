@@ -1,22 +1,25 @@
-use libloading::{Library, Symbol};
 use lightoros_plugin_base::input::PluginInputTrait;
 use lightoros_plugin_base::transform::PluginTransformTrait;
 use lightoros_plugin_base::*;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicU8};
 use std::sync::atomic::Ordering;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Mutex;
 use std::thread::JoinHandle;
 
 use super::data_types::*;
-use super::utils::*;
+use super::process::*;
+use super::index::PluginIndex;
 use super::*;
 
 pub(crate) struct InputPipe {
     description: InputPipeDescription,
     channel: Option<Sender<InputEvent>>,
-    _libs: Vec<Library>,
     input: Option<Box<dyn PluginInputTrait>>,
-    transformations: Option<Vec<Box<dyn PluginTransformTrait>>>,
+    transformations: Transformations,
+    priority: Arc<AtomicU8>,
+    parameters: Sender<(String, String)>,
+    parameters_receiver: Option<Receiver<(String, String)>>,
     should_stop: Arc<AtomicBool>,
     handle: Option<JoinHandle<()>>,
 }
@@ -25,27 +28,29 @@ impl InputPipe {
     pub fn create(
         description: InputPipeDescription,
         channel: Sender<InputEvent>,
-        plugins_folder: &str,
+        plugins: &PluginIndex,
     ) -> Result<InputPipe, PluginError> {
         let name = &description.name;
-        let mut libs: Vec<Library> = Vec::new();
-
         let input_plugin_info = match description.members.first() {
             Some(member) => member,
             None => return plugin_err!("Pipe {} is empty.", name),
         };
 
-        let input_plugin_library = get_plugin(&input_plugin_info.kind, plugins_folder)?;
-        let get_info: Symbol<fn() -> PluginInfo> =
-            unsafe { input_plugin_library.get(b"info").unwrap() };
-        let info = get_info();
-        if info.kind != PluginKind::Input {
+        // the kind is known from the index, isolated plugins are only loaded by their helper
+        let input_plugin_entry = plugins.find(&input_plugin_info.kind)?;
+        if input_plugin_entry.kind != PluginKind::Input {
             return plugin_err!("First member of a pipe ({}) must be an input plugin.", name);
         }
 
-        libs.push(input_plugin_library); // prevent deallocation
-        let lib = libs.last().unwrap();
-        let mut input_plugin = match create_input_plugin!(lib, &input_plugin_info.config) {
+        let result = if input_plugin_info.isolated {
+            start_isolated(input_plugin_info, plugins)
+                .map(|plugin| Box::new(ProcessInput(plugin)) as Box<dyn PluginInputTrait>)
+        } else {
+            plugins
+                .get(&input_plugin_info.kind)
+                .and_then(|plugin| plugin.create_input(&input_plugin_info.config))
+        };
+        let mut input_plugin = match result {
             Ok(plugin) => plugin,
             Err(err) => {
                 return plugin_err!(
@@ -61,7 +66,7 @@ impl InputPipe {
             Err(err) => {
                 return plugin_err!(
                     "Failed to initialize input plugin '{}' for pipe '{}': {}",
-                    info.name,
+                    input_plugin_entry.name,
                     name,
                     err
                 );
@@ -73,12 +78,18 @@ impl InputPipe {
         if description.members.len() > 1 {
             for i in 1..description.members.len() {
                 let transform_plugin_info = &description.members[i];
-                let transform_plugin_library =
-                    get_plugin(&transform_plugin_info.kind, plugins_folder)?;
-                libs.push(transform_plugin_library); // prevent deallocation
-                let lib = libs.last().unwrap();
+                let result = if transform_plugin_info.isolated {
+                    start_isolated(transform_plugin_info, plugins).map(|plugin| {
+                        Box::new(ProcessTransform(Mutex::new(plugin)))
+                            as Box<dyn PluginTransformTrait>
+                    })
+                } else {
+                    let transform_plugin_library =
+                        plugins.get(&transform_plugin_info.kind)?;
+                    transform_plugin_library.create_transform(&transform_plugin_info.config)
+                };
                 let transform_plugin =
-                    match create_transform_plugin!(lib, &transform_plugin_info.config) {
+                    match result {
                         Ok(plugin) => plugin,
                         Err(err) => {
                             return plugin_err!(
@@ -92,12 +103,17 @@ impl InputPipe {
             }
         }
 
+        let priority = Arc::new(AtomicU8::new(description.priority));
+        let (parameters, parameters_receiver) = std::sync::mpsc::channel();
+
         Ok(InputPipe {
             description,
             channel: Some(channel),
-            _libs: libs,
             input: Some(input_plugin),
-            transformations: Some(transformations),
+            transformations: Arc::new(Mutex::new(transformations)),
+            priority,
+            parameters,
+            parameters_receiver: Some(parameters_receiver),
             should_stop: Arc::new(AtomicBool::new(false)),
             handle: None,
         })
@@ -112,11 +128,12 @@ impl InputPipe {
         }
 
         let name = self.description.name.clone();
-        let priority = self.description.priority;
+        let priority = self.priority.clone();
         let should_stop = self.should_stop.clone();
         let channel = self.channel.take().unwrap(); // TODO error handling
         let mut input = self.input.take().unwrap(); // TODO error handling
-        let transformations = self.transformations.take().unwrap(); // TODO error handling
+        let transformations = self.transformations.clone();
+        let parameters = self.parameters_receiver.take().unwrap();
 
         should_stop.store(false, Ordering::SeqCst);
 
@@ -125,6 +142,20 @@ impl InputPipe {
             .name(name.clone())
             .spawn(move || {
                 while should_stop.load(Ordering::SeqCst) != true {
+                    // apply parameter changes requested while waiting for the last frame
+                    while let Ok((parameter, value)) = parameters.try_recv() {
+                        match input.set_parameter(&parameter, &value) {
+                            Ok(true) => {}
+                            Ok(false) => eprintln!(
+                                "[{}] Parameter '{}' is not supported by '{}'",
+                                name, parameter, input
+                            ),
+                            Err(err) => eprintln!(
+                                "[{}] Failed setting parameter '{}' of '{}': {}",
+                                name, parameter, input, err
+                            ),
+                        }
+                    }
                     // get data from input
                     let mut data_in = match input.get() {
                         Ok(data) => data,
@@ -135,7 +166,7 @@ impl InputPipe {
                         }
                     };
                     // transform data if necessary
-                    for transformator in &transformations {
+                    for transformator in transformations.lock().unwrap().iter() {
                         data_in = match transformator.transform(&data_in) {
                             Ok(data) => data,
                             Err(err) => {
@@ -149,7 +180,8 @@ impl InputPipe {
                         };
                     }
                     // send data to the engine thread
-                    let event = InputEvent::create(Arc::new(data_in), priority);
+                    let event =
+                        InputEvent::create(Arc::new(data_in), priority.load(Ordering::SeqCst));
                     if channel.send(event).is_err() {
                         // should only happen when the main engine stopped
                         eprintln!("[{}] Failed sending data to engine.", name);
@@ -164,6 +196,19 @@ impl InputPipe {
         Ok(())
     }
 
+    pub fn control(&self) -> InputPipeControl {
+        InputPipeControl {
+            name: self.description.name.clone(),
+            priority: self.description.priority,
+            current_priority: self.priority.clone(),
+            parameters: self.parameters.clone(),
+        }
+    }
+
+    pub fn transformations(&self) -> Transformations {
+        self.transformations.clone()
+    }
+
     pub fn stop(&mut self) -> Result<(), PluginError> {
         if self.handle.is_none() {
             return plugin_err!(
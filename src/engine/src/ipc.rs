@@ -0,0 +1,208 @@
+//! Messages between the engine and the helper processes of isolated plugins.
+//!
+//! Every message is sent as its length (u32, little endian) followed by a tag byte and the fields.
+//! Numbers are little endian, strings are UTF-8 with their length as u32 and frames are the pixel count,
+//! the RGB bytes and the metadata as string pairs.
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use lightoros_plugin_base::*;
+
+// larger messages are treated as a broken connection
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Requests of the engine, answered with exactly one response
+pub(crate) enum Request {
+    /// creates the plugin with the configuration as JSON, always the first request
+    Create(String),
+    Init,
+    Get,
+    Transform(TraitData),
+    Send(TraitData),
+    SetParameter(String, String),
+    GetParameter(String),
+}
+
+pub(crate) enum Response {
+    Ok,
+    Error(String),
+    Data(TraitData),
+    Bool(bool),
+    Parameter(Option<String>),
+}
+
+pub(crate) fn write_request(stream: &mut impl Write, request: &Request) -> Result<()> {
+    let mut message = Message::default();
+    match request {
+        Request::Create(config) => message.tag(1).string(config),
+        Request::Init => message.tag(2),
+        Request::Get => message.tag(3),
+        Request::Transform(data) => message.tag(4).data(data),
+        Request::Send(data) => message.tag(5).data(data),
+        Request::SetParameter(name, value) => message.tag(6).string(name).string(value),
+        Request::GetParameter(name) => message.tag(7).string(name),
+    };
+    message.write(stream)
+}
+
+pub(crate) fn read_request(stream: &mut impl Read) -> Result<Request> {
+    let buffer = read_message(stream)?;
+    let mut reader = Reader::new(&buffer);
+    let request = match reader.u8()? {
+        1 => Request::Create(reader.string()?),
+        2 => Request::Init,
+        3 => Request::Get,
+        4 => Request::Transform(reader.data()?),
+        5 => Request::Send(reader.data()?),
+        6 => Request::SetParameter(reader.string()?, reader.string()?),
+        7 => Request::GetParameter(reader.string()?),
+        tag => return Err(invalid(&format!("Unknown request {}", tag))),
+    };
+    Ok(request)
+}
+
+pub(crate) fn write_response(stream: &mut impl Write, response: &Response) -> Result<()> {
+    let mut message = Message::default();
+    match response {
+        Response::Ok => message.tag(1),
+        Response::Error(error) => message.tag(2).string(error),
+        Response::Data(data) => message.tag(3).data(data),
+        Response::Bool(value) => message.tag(4).tag(*value as u8),
+        Response::Parameter(None) => message.tag(5).tag(0),
+        Response::Parameter(Some(value)) => message.tag(5).tag(1).string(value),
+    };
+    message.write(stream)
+}
+
+pub(crate) fn read_response(stream: &mut impl Read) -> Result<Response> {
+    let buffer = read_message(stream)?;
+    let mut reader = Reader::new(&buffer);
+    let response = match reader.u8()? {
+        1 => Response::Ok,
+        2 => Response::Error(reader.string()?),
+        3 => Response::Data(reader.data()?),
+        4 => Response::Bool(reader.u8()? != 0),
+        5 => match reader.u8()? {
+            0 => Response::Parameter(None),
+            _ => Response::Parameter(Some(reader.string()?)),
+        },
+        tag => return Err(invalid(&format!("Unknown response {}", tag))),
+    };
+    Ok(response)
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn read_message(stream: &mut impl Read) -> Result<Vec<u8>> {
+    let mut length = [0; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        return Err(invalid(&format!("Message too large: {} bytes", length)));
+    }
+    let mut buffer = vec![0; length];
+    stream.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn tag(&mut self, tag: u8) -> &mut Self {
+        self.0.push(tag);
+        self
+    }
+
+    fn length(&mut self, length: usize) -> &mut Self {
+        self.0.extend_from_slice(&(length as u32).to_le_bytes());
+        self
+    }
+
+    fn string(&mut self, value: &str) -> &mut Self {
+        self.length(value.len());
+        self.0.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    fn data(&mut self, data: &TraitData) -> &mut Self {
+        self.length(data.rgb.len());
+        for rgb in &data.rgb {
+            self.0.extend_from_slice(&[rgb.r, rgb.g, rgb.b]);
+        }
+        self.length(data.meta.len());
+        for (key, value) in &data.meta {
+            self.string(key).string(value);
+        }
+        self
+    }
+
+    // length and content are written with a single call to avoid small writes on the socket
+    fn write(&self, stream: &mut impl Write) -> Result<()> {
+        let mut buffer = Vec::with_capacity(self.0.len() + 4);
+        buffer.extend_from_slice(&(self.0.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(&self.0);
+        stream.write_all(&buffer)?;
+        stream.flush()
+    }
+}
+
+struct Reader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buffer: &'a [u8]) -> Reader<'a> {
+        Reader {
+            buffer,
+            position: 0,
+        }
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        if self.buffer.len() - self.position < count {
+            return Err(invalid("Message too short"));
+        }
+        let bytes = &self.buffer[self.position..self.position + count];
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn length(&mut self) -> Result<usize> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let length = self.length()?;
+        let bytes = self.bytes(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("Invalid UTF-8 string"))
+    }
+
+    fn data(&mut self) -> Result<TraitData> {
+        let pixels = self.length()?;
+        let bytes = self.bytes(pixels.saturating_mul(3))?;
+        let rgb = bytes
+            .chunks(3)
+            .map(|rgb| RGB {
+                r: rgb[0],
+                g: rgb[1],
+                b: rgb[2],
+            })
+            .collect();
+        let entries = self.length()?;
+        let mut meta = HashMap::new();
+        for _ in 0..entries {
+            meta.insert(self.string()?, self.string()?);
+        }
+        Ok(TraitData { rgb, meta })
+    }
+}
@@ -0,0 +1,734 @@
+//! Control server compatible with the JSON API of Hyperion, used by remote control apps and home automation.
+//!
+//! Requests and responses are JSON objects, each terminated by a newline.
+//! Hyperion priorities are mapped to lightoros priorities as `256 - priority`, so the lowest Hyperion
+//! priority wins like the highest priority of an input pipe.
+
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use lightoros_plugin_base::*;
+use serde_json::{json, Map, Value};
+
+use super::data_types::*;
+
+// longest request line, clients sending longer lines are disconnected
+const MAX_LINE_LENGTH: usize = 1024 * 1024;
+// longest queue of unsent responses, clients not reading their responses are disconnected
+const MAX_OUTPUT_LENGTH: usize = 4 * 1024 * 1024;
+
+// adjustments reported by serverinfo, if a transformation provides them as runtime parameter
+const ADJUSTMENTS: [&str; 14] = [
+    "brightness",
+    "brightnessCompensation",
+    "red",
+    "green",
+    "blue",
+    "cyan",
+    "magenta",
+    "yellow",
+    "white",
+    "gammaRed",
+    "gammaGreen",
+    "gammaBlue",
+    "backlightThreshold",
+    "backlightColored",
+];
+
+/// Engine parts the JSON server sends data and commands to
+pub(crate) struct ServerTargets {
+    pub input_sender: Sender<InputEvent>,
+    pub engine_sender: Sender<EngineCommand>,
+    pub status: Arc<Mutex<EngineStatus>>,
+    pub pipes: Vec<InputPipeControl>,
+    pub transformations: Vec<Transformations>,
+}
+
+struct ColorSource {
+    origin: String,
+    colors: Vec<RGB>,
+    expires: Option<Instant>,
+}
+
+struct Client {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    // responses not written yet, the socket is nonblocking
+    output: Vec<u8>,
+    connected: bool,
+}
+
+struct ServerState {
+    description: String,
+    leds: Option<usize>,
+    refresh_interval: Duration,
+    targets: ServerTargets,
+    // solid colors set by clients, key is the lightoros priority
+    colors: BTreeMap<u8, ColorSource>,
+    // input pipes started as effect, the index of the pipe and the end time
+    effects: Vec<(usize, Option<Instant>)>,
+    output_enabled: bool,
+    last_refresh: Instant,
+}
+
+pub(crate) struct JsonServer {
+    address: String,
+    state: Option<ServerState>,
+    should_stop: Arc<AtomicBool>,
+    // the thread returns the state when it stops
+    handle: Option<JoinHandle<ServerState>>,
+}
+
+impl JsonServer {
+    pub fn create(
+        config: JsonServerConfig,
+        description: String,
+        targets: ServerTargets,
+        max_input_inactivity_period: u64,
+    ) -> Result<JsonServer, PluginError> {
+        if config.leds == Some(0) {
+            return plugin_err!("Invalid JSON server config value 'leds': 0");
+        }
+
+        // colors are sent again before the engine switches to a pipe with a lower priority
+        let refresh_interval =
+            Duration::from_millis(std::cmp::max(max_input_inactivity_period / 2, 10));
+
+        let state = ServerState {
+            description,
+            leds: config.leds,
+            refresh_interval,
+            targets,
+            colors: BTreeMap::new(),
+            effects: Vec::new(),
+            output_enabled: true,
+            last_refresh: Instant::now(),
+        };
+
+        Ok(JsonServer {
+            address: format!("{}:{}", config.address, config.port),
+            state: Some(state),
+            should_stop: Arc::new(AtomicBool::new(false)),
+            handle: None,
+        })
+    }
+
+    pub fn start(&mut self) -> Result<(), PluginError> {
+        if self.handle.is_some() {
+            return plugin_err!("Cannot start JSON server, because it's already running.");
+        }
+
+        let listener = match TcpListener::bind(&self.address) {
+            Ok(listener) => listener,
+            Err(err) => {
+                return plugin_err!("Error binding JSON server to '{}': {}", self.address, err)
+            }
+        };
+        if let Err(err) = listener.set_nonblocking(true) {
+            return plugin_err!("Error configuring JSON server socket: {}", err);
+        }
+
+        let mut state = match self.state.take() {
+            Some(state) => state,
+            None => return plugin_err!("Cannot start JSON server, its state is lost."),
+        };
+        let should_stop = self.should_stop.clone();
+        should_stop.store(false, Ordering::SeqCst);
+
+        let handle = std::thread::Builder::new()
+            .name("JSON server".to_string())
+            .spawn(move || {
+                let mut clients: Vec<Client> = Vec::new();
+                let mut buffer = vec![0u8; 4096];
+                while !should_stop.load(Ordering::SeqCst) {
+                    loop {
+                        match listener.accept() {
+                            Ok((stream, _)) => {
+                                if stream.set_nonblocking(true).is_ok() {
+                                    clients.push(Client {
+                                        stream,
+                                        buffer: Vec::new(),
+                                        output: Vec::new(),
+                                        connected: true,
+                                    });
+                                }
+                            }
+                            Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => break,
+                            Err(err) => {
+                                eprintln!("[JSON server] Error accepting connection: {}", err);
+                                break;
+                            }
+                        }
+                    }
+
+                    let mut received = false;
+                    for client in clients.iter_mut() {
+                        match client.stream.read(&mut buffer) {
+                            Ok(0) => client.connected = false,
+                            Ok(size) => {
+                                received = true;
+                                client.buffer.extend_from_slice(&buffer[..size]);
+                            }
+                            Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                            Err(_) => client.connected = false,
+                        }
+
+                        while let Some(position) = client.buffer.iter().position(|b| *b == b'\n') {
+                            let line: Vec<u8> = client.buffer.drain(..=position).collect();
+                            let line = String::from_utf8_lossy(&line);
+                            if line.trim().is_empty() {
+                                continue;
+                            }
+                            let response = state.handle_request(line.trim()).to_string();
+                            client.output.extend_from_slice(response.as_bytes());
+                            client.output.push(b'\n');
+                        }
+                        if client.buffer.len() > MAX_LINE_LENGTH {
+                            eprintln!(
+                                "[JSON server] Request longer than {} bytes, closing connection",
+                                MAX_LINE_LENGTH
+                            );
+                            client.connected = false;
+                        }
+
+                        client.flush();
+                        if client.output.len() > MAX_OUTPUT_LENGTH {
+                            eprintln!(
+                                "[JSON server] Client does not read responses, closing connection"
+                            );
+                            client.connected = false;
+                        }
+                    }
+                    clients.retain(|client| client.connected);
+
+                    state.update();
+
+                    if !received {
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+                }
+                state
+            });
+        let handle = match handle {
+            Ok(handle) => handle,
+            Err(err) => return plugin_err!("Cannot start JSON server thread: {}", err),
+        };
+
+        self.handle = Some(handle);
+
+        println!("JSON server listening on {}", self.address);
+
+        Ok(())
+    }
+
+    pub fn stop(&mut self) -> Result<(), PluginError> {
+        if self.handle.is_none() {
+            return plugin_err!("Cannot stop JSON server, because it's not running.");
+        }
+
+        self.should_stop.store(true, Ordering::SeqCst);
+
+        match self.handle.take().map(|handle| handle.join()) {
+            Some(Ok(state)) => {
+                // kept for a restart
+                self.state = Some(state);
+                Ok(())
+            }
+            _ => plugin_err!("JSON server thread panicked"),
+        }
+    }
+}
+
+impl Client {
+    // writes as much of the queued responses as the socket takes
+    fn flush(&mut self) {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => {
+                    self.connected = false;
+                    return;
+                }
+                Ok(size) => {
+                    self.output.drain(..size);
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => return,
+                Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) => {
+                    self.connected = false;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl ServerState {
+    fn handle_request(&mut self, line: &str) -> Value {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => {
+                return json!({
+                    "success": false,
+                    "error": format!("Invalid JSON: {}", err),
+                })
+            }
+        };
+
+        let mut command = request["command"].as_str().unwrap_or("").to_string();
+        let tan = request.get("tan").cloned().unwrap_or_else(|| json!(0));
+
+        let result = match command.as_str() {
+            "serverinfo" => self.serverinfo().map(Some),
+            "sysinfo" => Ok(Some(self.sysinfo())),
+            "authorize" => {
+                let subcommand = request["subcommand"].as_str().unwrap_or("");
+                command = format!("authorize-{}", subcommand);
+                authorize(subcommand)
+            }
+            "color" => self.color(&request).map(|_| None),
+            "effect" => self.effect(&request).map(|_| None),
+            "clear" => self.clear(&request).map(|_| None),
+            "clearall" => {
+                self.clear_all();
+                Ok(None)
+            }
+            "adjustment" => self.adjustment(&request).map(|_| None),
+            "componentstate" => self.component_state(&request).map(|_| None),
+            _ => plugin_err!("Unknown command '{}'", command),
+        };
+
+        match result {
+            Ok(Some(info)) => json!({
+                "command": command,
+                "success": true,
+                "tan": tan,
+                "info": info,
+            }),
+            Ok(None) => json!({
+                "command": command,
+                "success": true,
+                "tan": tan,
+            }),
+            Err(err) => json!({
+                "command": command,
+                "success": false,
+                "tan": tan,
+                "error": err.to_string(),
+            }),
+        }
+    }
+
+    fn serverinfo(&self) -> PluginResult<Value> {
+        let current_priority = self.targets.status.lock().unwrap().current_priority;
+
+        let mut priorities = Vec::new();
+        for (priority, source) in &self.colors {
+            let color = source.colors[0];
+            let mut entry = json!({
+                "priority": to_hyperion_priority(*priority),
+                "componentId": "COLOR",
+                "origin": source.origin,
+                "owner": "",
+                "active": true,
+                "visible": *priority == current_priority,
+                "value": { "RGB": [color.r, color.g, color.b] },
+            });
+            if let Some(expires) = source.expires {
+                let remaining = expires.saturating_duration_since(Instant::now());
+                entry["duration_ms"] = json!(remaining.as_millis() as u64);
+            }
+            priorities.push(entry);
+        }
+        for (index, pipe) in self.targets.pipes.iter().enumerate() {
+            let priority = pipe.current_priority.load(Ordering::SeqCst);
+            let is_effect = self.effects.iter().any(|(effect, _)| *effect == index);
+            priorities.push(json!({
+                "priority": to_hyperion_priority(priority),
+                "componentId": if is_effect { "EFFECT" } else { "IMAGE" },
+                "origin": "lightoros",
+                "owner": pipe.name,
+                "active": true,
+                "visible": priority == current_priority,
+            }));
+        }
+        priorities.sort_by_key(|entry| entry["priority"].as_i64());
+
+        let mut adjustment = Map::new();
+        adjustment.insert("id".to_string(), json!("default"));
+        for key in ADJUSTMENTS.iter() {
+            if let Some(value) = self.get_parameter(&parameter_name(key)) {
+                adjustment.insert(key.to_string(), parameter_to_json(&value));
+            }
+        }
+
+        // input pipes can be started with the effect command
+        let effects: Vec<Value> = self
+            .targets
+            .pipes
+            .iter()
+            .map(|pipe| json!({ "name": pipe.name, "file": "", "script": "", "args": {} }))
+            .collect();
+
+        Ok(json!({
+            "priorities": priorities,
+            "priorities_autoselect": true,
+            "adjustment": [Value::Object(adjustment)],
+            "effects": effects,
+            "components": [
+                { "name": "ALL", "enabled": self.output_enabled },
+                { "name": "LEDDEVICE", "enabled": self.output_enabled },
+            ],
+            "instance": [
+                { "instance": 0, "running": true, "friendly_name": self.description },
+            ],
+            "videomode": "2D",
+        }))
+    }
+
+    fn sysinfo(&self) -> Value {
+        json!({
+            "hyperion": {
+                "build": "lightoros",
+                "version": env!("CARGO_PKG_VERSION"),
+                "id": self.description,
+            },
+        })
+    }
+
+    fn color(&mut self, request: &Value) -> PluginResult<()> {
+        let priority = get_priority(request)?;
+        let values = match request["color"].as_array() {
+            Some(values) if !values.is_empty() && values.len() % 3 == 0 => values,
+            _ => return plugin_err!("Invalid color, expected a list of RGB values"),
+        };
+        let mut colors = Vec::with_capacity(values.len() / 3);
+        for rgb in values.chunks(3) {
+            let mut channels = [0u8; 3];
+            for (channel, value) in channels.iter_mut().zip(rgb.iter()) {
+                *channel = match value.as_u64() {
+                    Some(value) if value <= 255 => value as u8,
+                    _ => return plugin_err!("Invalid color value: {}", value),
+                };
+            }
+            colors.push(RGB {
+                r: channels[0],
+                g: channels[1],
+                b: channels[2],
+            });
+        }
+
+        let source = ColorSource {
+            origin: request["origin"].as_str().unwrap_or("JSON API").to_string(),
+            colors,
+            expires: get_expiration(request),
+        };
+        let data = self.create_color_frame(&source.colors)?;
+        self.colors.insert(priority, source);
+        self.send(data, priority)
+    }
+
+    fn effect(&mut self, request: &Value) -> PluginResult<()> {
+        let priority = get_priority(request)?;
+        let name = match request["effect"]["name"].as_str() {
+            Some(name) => name,
+            None => return plugin_err!("Missing effect name"),
+        };
+        let index = match self.targets.pipes.iter().position(|pipe| pipe.name == name) {
+            Some(index) => index,
+            None => return plugin_err!("Unknown effect '{}'", name),
+        };
+
+        let pipe = &self.targets.pipes[index];
+        // effect arguments are passed to the input plugin of the pipe as parameters
+        if let Some(args) = request["effect"]["args"].as_object() {
+            for (key, value) in args {
+                let value = parameter_value(value)?;
+                if pipe.parameters.send((parameter_name(key), value)).is_err() {
+                    return plugin_err!("Effect '{}' is not running", name);
+                }
+            }
+        }
+        let previous = pipe.current_priority.swap(priority, Ordering::SeqCst);
+        self.effects.retain(|(effect, _)| *effect != index);
+        self.effects.push((index, get_expiration(request)));
+        self.release(previous);
+        Ok(())
+    }
+
+    fn clear(&mut self, request: &Value) -> PluginResult<()> {
+        let priority = match request["priority"].as_i64() {
+            Some(priority) => priority,
+            None => return plugin_err!("Missing priority"),
+        };
+        // Hyperion clients use -1 to clear all priorities
+        if priority < 0 {
+            self.clear_all();
+            return Ok(());
+        }
+
+        let priority = to_pipe_priority(priority)?;
+        if self.colors.remove(&priority).is_some() {
+            self.release(priority);
+        }
+        let effects: Vec<usize> = self
+            .effects
+            .iter()
+            .map(|(index, _)| *index)
+            .filter(|index| {
+                self.targets.pipes[*index]
+                    .current_priority
+                    .load(Ordering::SeqCst)
+                    == priority
+            })
+            .collect();
+        for index in effects {
+            self.stop_effect(index);
+        }
+        Ok(())
+    }
+
+    fn clear_all(&mut self) {
+        let priorities: Vec<u8> = self.colors.keys().cloned().collect();
+        self.colors.clear();
+        for priority in priorities {
+            self.release(priority);
+        }
+        let effects: Vec<usize> = self.effects.iter().map(|(index, _)| *index).collect();
+        for index in effects {
+            self.stop_effect(index);
+        }
+    }
+
+    fn adjustment(&mut self, request: &Value) -> PluginResult<()> {
+        let adjustment = match request["adjustment"].as_object() {
+            Some(adjustment) => adjustment,
+            None => return plugin_err!("Missing adjustment"),
+        };
+
+        for (key, value) in adjustment {
+            if key == "id" {
+                continue;
+            }
+            let name = parameter_name(key);
+            let value = parameter_value(value)?;
+            let mut accepted = false;
+            for transformations in &self.targets.transformations {
+                for transformation in transformations.lock().unwrap().iter_mut() {
+                    match transformation.set_parameter(&name, &value) {
+                        Ok(true) => accepted = true,
+                        Ok(false) => {}
+                        Err(err) => return plugin_err!("Invalid adjustment '{}': {}", key, err),
+                    }
+                }
+            }
+            if !accepted {
+                return plugin_err!(
+                    "Adjustment '{}' is not supported by any transformation",
+                    key
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn component_state(&mut self, request: &Value) -> PluginResult<()> {
+        let component = request["componentstate"]["component"]
+            .as_str()
+            .unwrap_or("");
+        let state = match request["componentstate"]["state"].as_bool() {
+            Some(state) => state,
+            None => return plugin_err!("Missing component state"),
+        };
+        match component {
+            "ALL" | "LEDDEVICE" => {
+                self.output_enabled = state;
+                self.send_command(EngineCommand::EnableOutput(state))
+            }
+            _ => plugin_err!("Component '{}' is not supported", component),
+        }
+    }
+
+    // drops expired colors and effects and sends the colors again to keep them active
+    fn update(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<u8> = self
+            .colors
+            .iter()
+            .filter(|(_, source)| source.expires.is_some_and(|time| time <= now))
+            .map(|(priority, _)| *priority)
+            .collect();
+        for priority in expired {
+            self.colors.remove(&priority);
+            self.release(priority);
+        }
+        let expired: Vec<usize> = self
+            .effects
+            .iter()
+            .filter(|(_, expires)| expires.is_some_and(|time| time <= now))
+            .map(|(index, _)| *index)
+            .collect();
+        for index in expired {
+            self.stop_effect(index);
+        }
+
+        if self.last_refresh.elapsed() < self.refresh_interval {
+            return;
+        }
+        self.last_refresh = Instant::now();
+        let mut frames = Vec::new();
+        for (priority, source) in &self.colors {
+            if let Ok(data) = self.create_color_frame(&source.colors) {
+                frames.push((data, *priority));
+            }
+        }
+        for (data, priority) in frames {
+            let _ = self.send(data, priority);
+        }
+    }
+
+    fn stop_effect(&mut self, index: usize) {
+        self.effects.retain(|(effect, _)| *effect != index);
+        let pipe = &self.targets.pipes[index];
+        let previous = pipe.current_priority.swap(pipe.priority, Ordering::SeqCst);
+        self.release(previous);
+    }
+
+    fn release(&self, priority: u8) {
+        let _ = self.send_command(EngineCommand::ReleasePriority(priority));
+    }
+
+    fn get_parameter(&self, name: &str) -> Option<String> {
+        for transformations in &self.targets.transformations {
+            for transformation in transformations.lock().unwrap().iter() {
+                if let Some(value) = transformation.get_parameter(name) {
+                    return Some(value);
+                }
+            }
+        }
+        None
+    }
+
+    // fills all LEDs with the colors, a list of several colors is repeated
+    fn create_color_frame(&self, colors: &[RGB]) -> PluginResult<TraitData> {
+        let status = self.targets.status.lock().unwrap();
+        let leds = self.leds.unwrap_or(status.leds);
+        if leds == 0 {
+            return plugin_err!("Unknown number of LEDs, set 'leds' in the JSON server config");
+        }
+        let rgb: Vec<RGB> = (0..leds).map(|i| colors[i % colors.len()]).collect();
+        match (self.leds, status.width, status.height) {
+            (None, Some(width), Some(height)) => Ok(plugin_data!(rgb, {
+                "width" => width,
+                "height" => height,
+            })),
+            _ => Ok(plugin_data!(rgb, {})),
+        }
+    }
+
+    fn send(&self, data: TraitData, priority: u8) -> PluginResult<()> {
+        let event = InputEvent::create(Arc::new(data), priority);
+        match self.targets.input_sender.send(event) {
+            Ok(_) => Ok(()),
+            Err(_) => plugin_err!("Engine is not running"),
+        }
+    }
+
+    fn send_command(&self, command: EngineCommand) -> PluginResult<()> {
+        match self.targets.engine_sender.send(command) {
+            Ok(_) => Ok(()),
+            Err(_) => plugin_err!("Engine is not running"),
+        }
+    }
+}
+
+fn authorize(subcommand: &str) -> PluginResult<Option<Value>> {
+    // authorization is not supported, all clients have full access
+    match subcommand {
+        "tokenRequired" => Ok(Some(json!({ "required": false }))),
+        "adminRequired" => Ok(Some(json!({ "adminRequired": false }))),
+        "login" => Ok(None),
+        _ => plugin_err!("Unsupported authorize subcommand '{}'", subcommand),
+    }
+}
+
+fn get_priority(request: &Value) -> PluginResult<u8> {
+    match request["priority"].as_i64() {
+        Some(priority) => to_pipe_priority(priority),
+        None => plugin_err!("Missing priority"),
+    }
+}
+
+// durations of zero or below mean that the source never expires
+fn get_expiration(request: &Value) -> Option<Instant> {
+    match request["duration"].as_i64() {
+        Some(duration) if duration > 0 => {
+            Some(Instant::now() + Duration::from_millis(duration as u64))
+        }
+        _ => None,
+    }
+}
+
+fn to_pipe_priority(priority: i64) -> PluginResult<u8> {
+    if !(1..=255).contains(&priority) {
+        return plugin_err!("Invalid priority {}. Valid range: [1-255]", priority);
+    }
+    Ok((256 - priority) as u8)
+}
+
+fn to_hyperion_priority(priority: u8) -> i64 {
+    256 - priority as i64
+}
+
+// Hyperion adjustments and effect arguments use camel case, parameters of the plugins use snake case
+fn parameter_name(key: &str) -> String {
+    let mut name = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            name.push('_');
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+// parameters are passed as strings, lists like colors are separated by commas
+fn parameter_value(value: &Value) -> PluginResult<String> {
+    match value {
+        Value::Bool(value) => Ok(value.to_string()),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::String(value) => Ok(value.clone()),
+        Value::Array(values) => {
+            let mut list = Vec::with_capacity(values.len());
+            for value in values {
+                list.push(parameter_value(value)?);
+            }
+            Ok(list.join(","))
+        }
+        _ => plugin_err!("Unsupported parameter value: {}", value),
+    }
+}
+
+fn parameter_to_json(value: &str) -> Value {
+    if value.contains(',') {
+        return Value::Array(value.split(',').map(parameter_to_json).collect());
+    }
+    if let Ok(number) = value.parse::<i64>() {
+        return json!(number);
+    }
+    if let Ok(number) = value.parse::<f64>() {
+        return json!(number);
+    }
+    match value {
+        "true" => json!(true),
+        "false" => json!(false),
+        _ => json!(value),
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender, TryRecvError};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
@@ -9,13 +9,24 @@ use lightoros_plugin_base::*;
 
 use data_types::*;
 use input_pipe::*;
+use json_server::*;
 use output_pipe::*;
 
 #[macro_use]
 mod data_types;
+mod host;
+mod index;
 mod input_pipe;
+mod ipc;
+mod json_server;
 mod output_pipe;
-mod utils;
+mod plugin;
+mod process;
+mod registry;
+
+pub use host::run_plugin_host;
+pub use index::{DiscoveryError, PluginEntry, PluginIndex};
+pub use process::PLUGIN_HOST;
 
 pub struct LightorosEngine {
     //max_input_inactivity_period: u64,
@@ -33,7 +44,8 @@ pub struct LightorosEngine {
     output_pipes: Vec<OutputPipe>,
     //output_pipe_handles: Vec<JoinHandle<()>>,
     //engine: Arc<Mutex<Engine>>,
-    engine_sender: Option<Sender<u64>>,
+    json_server: Option<JsonServer>,
+    engine_sender: Option<Sender<EngineCommand>>,
     handle: Option<JoinHandle<()>>,
 }
 
@@ -57,6 +69,7 @@ impl LightorosEngine {
             output_pipes: Vec::new(),
             //output_pipe_handles: Vec::new(),
             //engine: Arc::new(Mutex::new(engine)),
+            json_server: None,
             engine_sender: None,
             handle: None,
         }
@@ -74,6 +87,12 @@ impl LightorosEngine {
         };
         let max_input_inactivity_period = config.max_input_inactivity_period;
 
+        // find the plugins once for all pipes, unusable libraries are skipped
+        let plugins = PluginIndex::discover(&plugins_path);
+        for error in plugins.errors() {
+            eprintln!("Skipping plugin {}", error);
+        }
+
         let (input_pipe_sender, input_pipe_receiver) = mpsc::channel();
         let mut output_pipe_sender_list = Vec::new();
         //let mut priorities_map: HashMap<ThreadId, u8> = HashMap::new();
@@ -81,13 +100,19 @@ impl LightorosEngine {
         // iterate over input pipes
         for input_pipe_description in config.input {
             // create an input pipe which contains one input plugin and optional several transformation plugins
-            let mut input_pipe = InputPipe::create(
+            let input_pipe = InputPipe::create(
                 input_pipe_description,
                 input_pipe_sender.clone(),
-                &plugins_path,
-            )?;
-            input_pipe.start()?;
-            self.input_pipes.push(input_pipe);
+                &plugins,
+            )
+            .and_then(|mut pipe| pipe.start().map(|_| pipe));
+            match input_pipe {
+                Ok(input_pipe) => self.input_pipes.push(input_pipe),
+                Err(err) => {
+                    self.stop_started_pipes(&output_pipe_sender_list);
+                    return Err(err);
+                }
+            }
         }
 
         // iterate over output pipes
@@ -96,24 +121,61 @@ impl LightorosEngine {
             output_pipe_sender_list.push(Box::new(tx_output));
 
             // create an output pipe which contains one output plugin and optional several transformation plugins
-            let mut output_pipe =
-                OutputPipe::create(output_pipe_description, rx_output, &plugins_path)?;
-            output_pipe.start()?;
-            self.output_pipes.push(output_pipe);
+            let output_pipe = OutputPipe::create(output_pipe_description, rx_output, &plugins)
+                .and_then(|mut pipe| pipe.start().map(|_| pipe));
+            match output_pipe {
+                Ok(output_pipe) => self.output_pipes.push(output_pipe),
+                Err(err) => {
+                    self.stop_started_pipes(&output_pipe_sender_list);
+                    return Err(err);
+                }
+            }
         }
 
         let (engine_sender, engine_receiver) = mpsc::channel();
+        let status = Arc::new(Mutex::new(EngineStatus::default()));
+
+        if let Some(json_server_config) = config.json_server {
+            let mut transformations = Vec::new();
+            for pipe in &self.input_pipes {
+                transformations.push(pipe.transformations());
+            }
+            for pipe in &self.output_pipes {
+                transformations.push(pipe.transformations());
+            }
+            let json_server = JsonServer::create(
+                json_server_config,
+                config.description,
+                ServerTargets {
+                    input_sender: input_pipe_sender.clone(),
+                    engine_sender: engine_sender.clone(),
+                    status: status.clone(),
+                    pipes: self.input_pipes.iter().map(|pipe| pipe.control()).collect(),
+                    transformations,
+                },
+                max_input_inactivity_period,
+            )
+            .and_then(|mut server| server.start().map(|_| server));
+            match json_server {
+                Ok(json_server) => self.json_server = Some(json_server),
+                Err(err) => {
+                    self.stop_started_pipes(&output_pipe_sender_list);
+                    return Err(err);
+                }
+            }
+        }
 
         self.engine_sender = Some(engine_sender);
 
         let handle = std::thread::spawn(move || {
             let mut current_priority = 0;
             let mut last_event = Instant::now();
+            let mut output_enabled = true;
 
             // to block, or not to block, that is the question ...
             loop {
                 match engine_receiver.try_recv() {
-                    Ok(time_to_wait) => {
+                    Ok(EngineCommand::Stop(time_to_wait)) => {
                         // got signal to exit
                         
                         // send empty data to output pipe as stop signal
@@ -129,6 +191,25 @@ impl LightorosEngine {
                         std::thread::sleep(Duration::from_millis(time_to_wait));
                         return;
                     }
+                    Ok(EngineCommand::ReleasePriority(priority)) => {
+                        if current_priority == priority {
+                            current_priority = 0;
+                        }
+                    }
+                    Ok(EngineCommand::EnableOutput(enabled)) => {
+                        let leds = status.lock().unwrap().leds;
+                        // turn the LEDs off, an empty frame would stop the output pipes
+                        if output_enabled && !enabled && leds > 0 {
+                            for tx_out in output_pipe_sender_list.iter() {
+                                let data: TraitData = TraitData {
+                                    rgb: vec![RGB { r: 0, g: 0, b: 0 }; leds],
+                                    meta: HashMap::new(),
+                                };
+                                tx_out.send(Arc::new(data)).unwrap();
+                            }
+                        }
+                        output_enabled = enabled;
+                    }
                     Err(TryRecvError::Empty) => (),
                     Err(TryRecvError::Disconnected) => {
                         eprintln!("Error getting command from cmd channel. Disconnected.");
@@ -177,6 +258,18 @@ impl LightorosEngine {
                       */
                 };
 
+                {
+                    let mut status = status.lock().unwrap();
+                    status.current_priority = current_priority;
+                    status.leds = event.data.rgb.len();
+                    status.width = get_meta_value(&event.data.meta, "width").ok();
+                    status.height = get_meta_value(&event.data.meta, "height").ok();
+                }
+
+                if !output_enabled {
+                    continue;
+                }
+
                 for tx_out in output_pipe_sender_list.iter() {
                     tx_out.send(Arc::clone(&event.data)).unwrap();
                 }
@@ -190,14 +283,46 @@ impl LightorosEngine {
         Ok(())
     }
 
+    // stops the pipes of a failed start, the engine thread does not run yet
+    fn stop_started_pipes(&mut self, output_pipe_sender_list: &[Box<Sender<Arc<TraitData>>>]) {
+        while let Some(mut pipe) = self.input_pipes.pop() {
+            if let Err(err) = pipe.stop() {
+                eprintln!("Error stopping input pipe {}: {}", pipe, err);
+            }
+        }
+        // empty data stops the output pipes
+        for tx_out in output_pipe_sender_list.iter() {
+            let data: TraitData = TraitData {
+                rgb: Vec::with_capacity(0),
+                meta: HashMap::new(),
+            };
+            tx_out.send(Arc::new(data)).ok();
+        }
+        while let Some(mut pipe) = self.output_pipes.pop() {
+            if let Err(err) = pipe.stop() {
+                eprintln!("Error stopping output pipe {}: {}", pipe, err);
+            }
+        }
+    }
+
     pub fn stop(&mut self) -> Result<(), PluginError> {
         if self.handle.is_none() {
             return plugin_err!("Cannot stop engine, because it's not running.");
         }
         println!("Stopping lightoros engine...");
 
+        // stop JSON server, it sends data to the engine like an input pipe
+        if let Some(mut json_server) = self.json_server.take() {
+            println!("Stopping JSON server");
+            json_server.stop()?;
+        }
+
         // stop input pipes
-        self.engine_sender.take().unwrap().send(1000).unwrap();
+        self.engine_sender
+            .take()
+            .unwrap()
+            .send(EngineCommand::Stop(1000))
+            .unwrap();
         while let Some(mut pipe) = self.input_pipes.pop() {
             println!("Stopping input pipe {}", pipe);
             pipe.stop()?;
@@ -1,18 +1,18 @@
-use libloading::{Library, Symbol};
 use lightoros_plugin_base::output::PluginOutputTrait;
 use lightoros_plugin_base::transform::PluginTransformTrait;
 use lightoros_plugin_base::*;
+use std::sync::Mutex;
 use std::thread::JoinHandle;
 
-use super::utils::*;
+use super::process::*;
+use super::index::PluginIndex;
 use super::*;
 
 pub(crate) struct OutputPipe {
     description: OutputPipeDescription,
     channel: Option<Receiver<Arc<TraitData>>>,
-    _libs: Vec<Library>,
     output: Option<Box<dyn PluginOutputTrait>>,
-    transformations: Option<Vec<Box<dyn PluginTransformTrait>>>,
+    transformations: Transformations,
     handle: Option<JoinHandle<()>>,
 }
 
@@ -20,26 +20,28 @@ impl OutputPipe {
     pub fn create(
         description: OutputPipeDescription,
         channel: Receiver<Arc<TraitData>>,
-        plugins_folder: &str,
+        plugins: &PluginIndex,
     ) -> Result<OutputPipe, PluginError> {
         let name = &description.name;
-        let mut libs: Vec<Library> = Vec::new();
-
         let output_plugin_info = match description.members.last() {
             Some(member) => member,
             None => return plugin_err!("Pipe {} is empty.", name),
         };
-        let output_plugin_library = get_plugin(&output_plugin_info.kind, plugins_folder)?;
-        let get_info: Symbol<fn() -> PluginInfo> =
-            unsafe { output_plugin_library.get(b"info").unwrap() };
-        let info = get_info();
-        if info.kind != PluginKind::Output {
+        // the kind is known from the index, isolated plugins are only loaded by their helper
+        let output_plugin_entry = plugins.find(&output_plugin_info.kind)?;
+        if output_plugin_entry.kind != PluginKind::Output {
             return plugin_err!("Last member of a pipe ({}) must be an output plugin.", name);
         }
 
-        libs.push(output_plugin_library); // prevent deallocation
-        let lib = libs.last().unwrap();
-        let mut output_plugin = match create_output_plugin!(lib, &output_plugin_info.config) {
+        let result = if output_plugin_info.isolated {
+            start_isolated(output_plugin_info, plugins)
+                .map(|plugin| Box::new(ProcessOutput(plugin)) as Box<dyn PluginOutputTrait>)
+        } else {
+            plugins
+                .get(&output_plugin_info.kind)
+                .and_then(|plugin| plugin.create_output(&output_plugin_info.config))
+        };
+        let mut output_plugin = match result {
             Ok(plugin) => plugin,
             Err(err) => {
                 return plugin_err!(
@@ -55,7 +57,7 @@ impl OutputPipe {
             Err(err) => {
                 return plugin_err!(
                     "Failed to initialize output plugin '{}' for pipe '{}': {}",
-                    info.name,
+                    output_plugin_entry.name,
                     name,
                     err
                 )
@@ -67,12 +69,18 @@ impl OutputPipe {
         if description.members.len() > 1 {
             for i in 0..description.members.len() - 1 {
                 let transform_plugin_info = &description.members[i];
-                let transform_plugin_library =
-                    get_plugin(&transform_plugin_info.kind, plugins_folder)?;
-                libs.push(transform_plugin_library); // prevent deallocation
-                let lib = libs.last().unwrap();
+                let result = if transform_plugin_info.isolated {
+                    start_isolated(transform_plugin_info, plugins).map(|plugin| {
+                        Box::new(ProcessTransform(Mutex::new(plugin)))
+                            as Box<dyn PluginTransformTrait>
+                    })
+                } else {
+                    let transform_plugin_library =
+                        plugins.get(&transform_plugin_info.kind)?;
+                    transform_plugin_library.create_transform(&transform_plugin_info.config)
+                };
                 let transform_plugin =
-                    match create_transform_plugin!(lib, &transform_plugin_info.config) {
+                    match result {
                         Ok(plugin) => plugin,
                         Err(err) => {
                             return plugin_err!(
@@ -90,9 +98,8 @@ impl OutputPipe {
         Ok(OutputPipe {
             description,
             channel: Some(channel),
-            _libs: libs,
             output: Some(output_plugin),
-            transformations: Some(transformations),
+            transformations: Arc::new(Mutex::new(transformations)),
             handle: None,
         })
     }
@@ -107,7 +114,7 @@ impl OutputPipe {
 
         let channel = self.channel.take().unwrap(); // TODO error handling
         let mut output = self.output.take().unwrap(); // TODO error handling
-        let transformations = self.transformations.take().unwrap(); // TODO error handling
+        let transformations = self.transformations.clone();
         let pipe_name = self.description.name.clone();
 
         let handle = std::thread::Builder::new()
@@ -131,7 +138,7 @@ impl OutputPipe {
 
                 let mut data_out: TraitData;
                 // transform data if necessary
-                for transformator in &transformations {
+                for transformator in transformations.lock().unwrap().iter() {
                     data_out = match transformator.transform(data_ref) {
                         Ok(data) => data,
                         Err(err) => {
@@ -163,6 +170,10 @@ impl OutputPipe {
         Ok(())
     }
 
+    pub fn transformations(&self) -> Transformations {
+        self.transformations.clone()
+    }
+
     pub fn stop(&mut self) -> Result<(), PluginError> {
         if self.handle.is_none() {
             return plugin_err!(
@@ -0,0 +1,97 @@
+//! Plugins used through the C ABI of `lightoros_plugin_base::abi`, loaded from dynamic libraries or linked into the engine.
+
+use libloading::Library;
+use std::any::Any;
+use std::path::Path;
+use std::sync::Arc;
+
+use lightoros_plugin_base::abi::*;
+use lightoros_plugin_base::input::CreateInputPluginResult;
+use lightoros_plugin_base::output::CreateOutputPluginResult;
+use lightoros_plugin_base::transform::CreateTransformPluginResult;
+use lightoros_plugin_base::*;
+
+/// Plugin library with its declaration
+pub(crate) struct LoadedPlugin {
+    // None for plugins linked into the engine
+    lib: Option<Arc<Library>>,
+    declaration: *const PluginDeclaration,
+}
+
+/// Opens the library and gets the declaration without checking it, only the header may be used
+pub(crate) fn read_declaration(path: &Path) -> PluginResult<LoadedPlugin> {
+    let lib = match Library::new(path) {
+        Ok(lib) => lib,
+        Err(err) => return plugin_err!("Cannot load library '{}': {}", path.display(), err),
+    };
+    let declaration = match unsafe { lib.get::<DeclarationFunction>(DECLARATION_SYMBOL) } {
+        Ok(get_declaration) => unsafe { get_declaration(ABI_VERSION) },
+        Err(_) => {
+            return plugin_err!(
+                "Library '{}' does not export the plugin ABI, it may be built for an older engine or with the feature 'static'",
+                path.display()
+            )
+        }
+    };
+    if declaration.is_null() {
+        return plugin_err!(
+            "Plugin '{}' does not support the ABI version {} of the engine",
+            path.display(),
+            ABI_VERSION
+        );
+    }
+    Ok(LoadedPlugin {
+        lib: Some(Arc::new(lib)),
+        declaration,
+    })
+}
+
+impl LoadedPlugin {
+    /// Loads the plugin library and checks its versions
+    pub fn load(path: &Path) -> PluginResult<LoadedPlugin> {
+        let plugin = read_declaration(path)?;
+        plugin.declaration().check()?;
+        Ok(plugin)
+    }
+
+    /// Plugin of the static registry
+    pub fn linked(declaration: &'static PluginDeclaration) -> PluginResult<LoadedPlugin> {
+        declaration.check()?;
+        Ok(LoadedPlugin {
+            lib: None,
+            declaration,
+        })
+    }
+
+    pub fn declaration(&self) -> &PluginDeclaration {
+        // the declaration is static data of the library or the engine
+        unsafe { &*self.declaration }
+    }
+
+    pub fn name(&self) -> String {
+        self.declaration().name()
+    }
+
+    pub fn kind(&self) -> Option<PluginKind> {
+        self.declaration().kind()
+    }
+
+    // the created plugins keep the library loaded
+    fn library(&self) -> Option<Arc<dyn Any + Send + Sync>> {
+        self.lib
+            .clone()
+            .map(|lib| lib as Arc<dyn Any + Send + Sync>)
+    }
+
+    pub fn create_input(&self, config: &serde_json::Value) -> CreateInputPluginResult {
+        unsafe { self.declaration().create_input(config, self.library()) }
+    }
+
+    pub fn create_transform(&self, config: &serde_json::Value) -> CreateTransformPluginResult {
+        unsafe { self.declaration().create_transform(config, self.library()) }
+    }
+
+    pub fn create_output(&self, config: &serde_json::Value) -> CreateOutputPluginResult {
+        unsafe { self.declaration().create_output(config, self.library()) }
+    }
+}
@@ -0,0 +1,460 @@
+//! Plugins running in a helper process, enabled with `isolated` in the plugin description.
+//!
+//! The engine starts the helper `lightoros_plugin_host` for each isolated plugin and talks to it over a Unix socket.
+//! A crash of the plugin only ends the helper, the engine starts a new one and creates the plugin again.
+//! Helpers are only supported on Unix, on other platforms isolated plugins cannot be started.
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+#[cfg(unix)]
+use std::process::Command;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(unix)]
+use std::time::Instant;
+
+use lightoros_plugin_base::input::PluginInputTrait;
+use lightoros_plugin_base::output::PluginOutputTrait;
+use lightoros_plugin_base::transform::PluginTransformTrait;
+use lightoros_plugin_base::*;
+
+use super::data_types::PluginDescription;
+use super::index::PluginIndex;
+use super::ipc::{self, Request, Response};
+
+/// File name of the helper executable without the platform specific extension
+pub const PLUGIN_HOST: &str = "lightoros_plugin_host";
+
+// time for the helper to connect to the engine
+#[cfg(unix)]
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// time for the helper to answer requests other than get, a helper not answering in time is treated as crashed
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// socket folders of the helpers started by this process
+#[cfg(unix)]
+static SOCKETS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(unix)]
+type Stream = UnixStream;
+#[cfg(not(unix))]
+type Stream = std::net::TcpStream;
+
+/// Finds the helper in the plugin folders or next to the executable of the engine
+pub(crate) fn find_plugin_host(plugin_folders: &[PathBuf]) -> Result<PathBuf, PluginError> {
+    let file_name = format!("{}{}", PLUGIN_HOST, std::env::consts::EXE_SUFFIX);
+    let mut folders = plugin_folders.to_vec();
+    if let Ok(mut exe_path) = std::env::current_exe() {
+        exe_path.pop();
+        folders.push(exe_path);
+    }
+    for folder in &folders {
+        let path = folder.join(&file_name);
+        if path.is_file() {
+            return Ok(path);
+        }
+    }
+    plugin_err!(
+        "Cannot find the plugin host '{}' for isolated plugins in the plugin folders",
+        file_name
+    )
+}
+
+/// Starts the helper for the plugin of the description and creates the plugin in it
+pub(crate) fn start_isolated(
+    description: &PluginDescription,
+    plugins: &PluginIndex,
+) -> Result<ProcessPlugin, PluginError> {
+    let host = find_plugin_host(plugins.folders())?;
+    // the helper is built with the same plugins linked as the engine and gets their name instead of a file
+    let plugin = match &plugins.find(&description.kind)?.file {
+        Some(file) => file.clone(),
+        None => PathBuf::from(&description.kind),
+    };
+    if description.timeout == Some(0) {
+        return plugin_err!(
+            "Invalid timeout 0 of isolated plugin '{}'",
+            description.kind
+        );
+    }
+    let timeout = description.timeout.map(Duration::from_millis);
+    ProcessPlugin::start(
+        &description.kind,
+        host,
+        plugin,
+        &description.config,
+        timeout,
+    )
+}
+
+// running helper with its connection
+struct Helper {
+    child: Child,
+    stream: Stream,
+}
+
+// folder only accessible by the user for the socket of a helper, the name is not predictable
+#[cfg(unix)]
+fn create_socket_folder() -> Result<PathBuf, PluginError> {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::os::unix::fs::DirBuilderExt;
+
+    let mut last_error = None;
+    for _ in 0..10 {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u32(std::process::id());
+        let mut folder = std::env::temp_dir();
+        folder.push(format!(
+            "lightoros_{}_{}_{:016x}",
+            std::process::id(),
+            SOCKETS.fetch_add(1, Ordering::SeqCst),
+            hasher.finish()
+        ));
+        // fails if the folder exists, e.g. created by another user
+        match std::fs::DirBuilder::new().mode(0o700).create(&folder) {
+            Ok(_) => return Ok(folder),
+            Err(err) => last_error = Some(err),
+        }
+    }
+    plugin_err!(
+        "Cannot create socket folder in '{}': {}",
+        std::env::temp_dir().display(),
+        last_error.unwrap()
+    )
+}
+
+#[cfg(not(unix))]
+impl Helper {
+    fn start(_host: &Path, _plugin: &Path) -> Result<Helper, PluginError> {
+        plugin_err!("Isolated plugins are only supported on Unix")
+    }
+}
+
+#[cfg(unix)]
+impl Helper {
+    fn start(host: &Path, plugin: &Path) -> Result<Helper, PluginError> {
+        let socket_folder = create_socket_folder()?;
+        let socket_path = socket_folder.join("plugin.sock");
+        let listener = match UnixListener::bind(&socket_path) {
+            Ok(listener) => listener,
+            Err(err) => {
+                let _ = std::fs::remove_dir(&socket_folder);
+                return plugin_err!("Cannot create socket '{}': {}", socket_path.display(), err);
+            }
+        };
+
+        let child = Command::new(host)
+            .arg("--socket")
+            .arg(&socket_path)
+            .arg("--plugin")
+            .arg(plugin)
+            .spawn();
+        let result = match child {
+            Ok(mut child) => match Helper::accept(&listener, &mut child) {
+                Ok(stream) => Ok(Helper { child, stream }),
+                Err(err) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    Err(err)
+                }
+            },
+            Err(err) => plugin_err!("Cannot start plugin host '{}': {}", host.display(), err),
+        };
+        let _ = std::fs::remove_file(&socket_path);
+        let _ = std::fs::remove_dir(&socket_folder);
+        result
+    }
+
+    // waits for the connection of the helper unless it exits before
+    fn accept(listener: &UnixListener, child: &mut Child) -> Result<UnixStream, PluginError> {
+        let start = Instant::now();
+        if let Err(err) = listener.set_nonblocking(true) {
+            return plugin_err!("Cannot wait for the plugin host: {}", err);
+        }
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(err) = stream.set_nonblocking(false) {
+                        return plugin_err!("Cannot connect to the plugin host: {}", err);
+                    }
+                    return Ok(stream);
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(err) => return plugin_err!("Cannot connect to the plugin host: {}", err),
+            }
+            if let Ok(Some(status)) = child.try_wait() {
+                return plugin_err!("Plugin host exited before connecting: {}", status);
+            }
+            if start.elapsed() > CONNECT_TIMEOUT {
+                return plugin_err!("Plugin host did not connect within {:?}", CONNECT_TIMEOUT);
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Helper {
+    // a timeout fails the call like a broken connection, None waits for the response
+    fn call(&mut self, request: &Request, timeout: Option<Duration>) -> std::io::Result<Response> {
+        self.stream.set_write_timeout(timeout)?;
+        self.stream.set_read_timeout(timeout)?;
+        ipc::write_request(&mut self.stream, request)?;
+        ipc::read_response(&mut self.stream)
+    }
+
+    // stops the helper and returns how it ended
+    fn stop(mut self) -> String {
+        let _ = self.child.kill();
+        match self.child.wait() {
+            Ok(status) => status.to_string(),
+            Err(err) => err.to_string(),
+        }
+    }
+}
+
+/// Plugin in a helper process, restarted after a crash with the same configuration and parameters
+pub(crate) struct ProcessPlugin {
+    name: String,
+    host: PathBuf,
+    plugin: PathBuf,
+    config: serde_json::Value,
+    // timeout of all requests, get requests wait for the data if not set
+    timeout: Option<Duration>,
+    initialized: bool,
+    // parameters set at runtime in the order they were first set, set again after a restart
+    parameters: Vec<(String, String)>,
+    helper: Option<Helper>,
+}
+
+impl ProcessPlugin {
+    /// Starts the helper for the plugin file and creates the plugin with the configuration
+    pub fn start(
+        name: &str,
+        host: PathBuf,
+        plugin: PathBuf,
+        config: &serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<ProcessPlugin, PluginError> {
+        let mut process = ProcessPlugin {
+            name: name.to_string(),
+            host,
+            plugin,
+            config: config.clone(),
+            timeout,
+            initialized: false,
+            parameters: Vec::new(),
+            helper: None,
+        };
+        process.restart()?;
+        Ok(process)
+    }
+
+    fn restart(&mut self) -> Result<(), PluginError> {
+        let mut helper = Helper::start(&self.host, &self.plugin)?;
+        let mut requests = vec![Request::Create(self.config.to_string())];
+        if self.initialized {
+            requests.push(Request::Init);
+        }
+        for (name, value) in &self.parameters {
+            requests.push(Request::SetParameter(name.clone(), value.clone()));
+        }
+        for request in &requests {
+            match helper.call(request, self.timeout(request)) {
+                Ok(Response::Ok) | Ok(Response::Bool(_)) => {}
+                Ok(Response::Error(err)) => {
+                    helper.stop();
+                    return Err(PluginError::new(err));
+                }
+                Ok(_) => {
+                    helper.stop();
+                    return plugin_err!("Unexpected response of the plugin host");
+                }
+                Err(err) => {
+                    let status = helper.stop();
+                    return plugin_err!("Plugin host failed ({}): {}", status, err);
+                }
+            }
+        }
+        self.helper = Some(helper);
+        Ok(())
+    }
+
+    fn timeout(&self, request: &Request) -> Option<Duration> {
+        match request {
+            // inputs may wait for data as long as they need
+            Request::Get => self.timeout,
+            _ => Some(self.timeout.unwrap_or(DEFAULT_TIMEOUT)),
+        }
+    }
+
+    /// Sends the request to the helper, a failed or hanging helper is restarted and the request returns an error
+    fn call(&mut self, request: Request) -> Result<Response, PluginError> {
+        if self.helper.is_none() {
+            self.restart()?;
+        }
+        let timeout = self.timeout(&request);
+        let helper = self.helper.as_mut().unwrap();
+        let err = match helper.call(&request, timeout) {
+            Ok(Response::Error(err)) => return Err(PluginError::new(err)),
+            Ok(response) => return Ok(response),
+            Err(err) => err,
+        };
+
+        let status = self.helper.take().unwrap().stop();
+        match self.restart() {
+            Ok(_) => plugin_err!(
+                "Process of plugin '{}' failed ({}): {}. Restarted",
+                self.name,
+                status,
+                err
+            ),
+            Err(restart_err) => plugin_err!(
+                "Process of plugin '{}' failed ({}): {}. Restart failed: {}",
+                self.name,
+                status,
+                err,
+                restart_err
+            ),
+        }
+    }
+
+    fn init(&mut self) -> PluginResult<()> {
+        match self.call(Request::Init)? {
+            Response::Ok => {
+                self.initialized = true;
+                Ok(())
+            }
+            _ => plugin_err!("Unexpected response of the plugin host"),
+        }
+    }
+
+    fn data(&mut self, request: Request) -> PluginResult<TraitData> {
+        match self.call(request)? {
+            Response::Data(data) => Ok(data),
+            _ => plugin_err!("Unexpected response of the plugin host"),
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        let request = Request::SetParameter(name.to_string(), value.to_string());
+        match self.call(request)? {
+            Response::Bool(result) => {
+                // kept for a restart of the helper
+                match self.parameters.iter_mut().find(|(known, _)| known == name) {
+                    Some(parameter) => parameter.1 = value.to_string(),
+                    None => self.parameters.push((name.to_string(), value.to_string())),
+                }
+                Ok(result)
+            }
+            _ => plugin_err!("Unexpected response of the plugin host"),
+        }
+    }
+}
+
+impl Drop for ProcessPlugin {
+    fn drop(&mut self) {
+        if let Some(helper) = self.helper.take() {
+            helper.stop();
+        }
+    }
+}
+
+impl std::fmt::Display for ProcessPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} (isolated)", self.name)
+    }
+}
+
+pub(crate) struct ProcessInput(pub ProcessPlugin);
+
+impl std::fmt::Display for ProcessInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PluginInputTrait for ProcessInput {
+    fn init(&mut self) -> PluginResult<()> {
+        self.0.init()
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        self.0.data(Request::Get)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        self.0.set_parameter(name, value)
+    }
+}
+
+pub(crate) struct ProcessOutput(pub ProcessPlugin);
+
+impl std::fmt::Display for ProcessOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PluginOutputTrait for ProcessOutput {
+    fn init(&mut self) -> PluginResult<()> {
+        self.0.init()
+    }
+
+    fn send(&mut self, data: &TraitData) -> PluginResult<()> {
+        // the data is copied into the request, the engine shares it between the output pipes
+        let data = TraitData {
+            rgb: data.rgb.clone(),
+            meta: data.meta.clone(),
+        };
+        match self.0.call(Request::Send(data))? {
+            Response::Ok => Ok(()),
+            _ => plugin_err!("Unexpected response of the plugin host"),
+        }
+    }
+}
+
+// transform() only gets a shared reference, but the connection to the helper is changed by each call
+pub(crate) struct ProcessTransform(pub Mutex<ProcessPlugin>);
+
+impl std::fmt::Display for ProcessTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.0.lock() {
+            Ok(plugin) => plugin.fmt(f),
+            Err(_) => write!(f, "isolated plugin"),
+        }
+    }
+}
+
+impl PluginTransformTrait for ProcessTransform {
+    fn transform(&self, data: &TraitData) -> PluginResult<TraitData> {
+        let data = TraitData {
+            rgb: data.rgb.clone(),
+            meta: data.meta.clone(),
+        };
+        match self.0.lock() {
+            Ok(mut plugin) => plugin.data(Request::Transform(data)),
+            Err(_) => plugin_err!("Isolated plugin is not usable after a panic"),
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        match self.0.get_mut() {
+            Ok(plugin) => plugin.set_parameter(name, value),
+            Err(_) => plugin_err!("Isolated plugin is not usable after a panic"),
+        }
+    }
+
+    fn get_parameter(&self, name: &str) -> Option<String> {
+        let mut plugin = self.0.lock().ok()?;
+        match plugin.call(Request::GetParameter(name.to_string())) {
+            Ok(Response::Parameter(value)) => value,
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Plugins linked into the engine, enabled by the cargo features of the engine.
+//!
+//! The pipes resolve the `kind` of a plugin description here first and search the plugins folder only for other plugins.
+
+use lightoros_plugin_base::abi::PluginDeclaration;
+
+/// Declarations of the plugins linked into the engine
+pub(crate) fn static_plugins() -> Vec<&'static PluginDeclaration> {
+    vec![
+        #[cfg(feature = "lightoros_input_extra_lua")]
+        lightoros_input_extra_lua::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_extra_effects")]
+        lightoros_input_extra_effects::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_extra_text")]
+        lightoros_input_extra_text::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_extra_wasm")]
+        lightoros_input_extra_wasm::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_audio_spectrum")]
+        lightoros_input_audio_spectrum::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_grabber_remote_tcp")]
+        lightoros_input_grabber_remote_tcp::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_grabber_remote_unix")]
+        lightoros_input_grabber_remote_unix::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_grabber_remote_pipe")]
+        lightoros_input_grabber_remote_pipe::plugin_declaration(),
+        #[cfg(all(feature = "lightoros_input_grabber_screen_fb", target_os = "linux"))]
+        lightoros_input_grabber_screen_fb::plugin_declaration(),
+        #[cfg(all(feature = "lightoros_input_grabber_screen_osx", target_os = "macos"))]
+        lightoros_input_grabber_screen_osx::plugin_declaration(),
+        #[cfg(all(feature = "lightoros_input_grabber_screen_x11", target_os = "linux"))]
+        lightoros_input_grabber_screen_x11::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_filesystem_tpm2")]
+        lightoros_input_filesystem_tpm2::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_filesystem_image")]
+        lightoros_input_filesystem_image::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_filesystem_video")]
+        lightoros_input_filesystem_video::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_net_udp_tpm2")]
+        lightoros_input_net_udp_tpm2::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_net_udp_dmx")]
+        lightoros_input_net_udp_dmx::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_net_udp_ddp")]
+        lightoros_input_net_udp_ddp::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_net_tcp_opc")]
+        lightoros_input_net_tcp_opc::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_net_tcp_hyperion")]
+        lightoros_input_net_tcp_hyperion::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_net_tcp_boblight")]
+        lightoros_input_net_tcp_boblight::plugin_declaration(),
+        #[cfg(feature = "lightoros_input_net_tcp_prismatik")]
+        lightoros_input_net_tcp_prismatik::plugin_declaration(),
+        #[cfg(feature = "lightoros_output_serial_tpm2")]
+        lightoros_output_serial_tpm2::plugin_declaration(),
+        #[cfg(feature = "lightoros_output_serial_adalight")]
+        lightoros_output_serial_adalight::plugin_declaration(),
+        #[cfg(feature = "lightoros_output_net_udp_tpm2")]
+        lightoros_output_net_udp_tpm2::plugin_declaration(),
+        #[cfg(feature = "lightoros_output_filesystem_pipe")]
+        lightoros_output_filesystem_pipe::plugin_declaration(),
+        #[cfg(feature = "lightoros_output_extra_wasm")]
+        lightoros_output_extra_wasm::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_resize_image")]
+        lightoros_transform_resize_image::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_crop_image_fixed")]
+        lightoros_transform_crop_image_fixed::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_crop_image_black_border")]
+        lightoros_transform_crop_image_black_border::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_convert_rectangle")]
+        lightoros_transform_convert_rectangle::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_convert_shift")]
+        lightoros_transform_convert_shift::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_convert_reverse")]
+        lightoros_transform_convert_reverse::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_convert_dim")]
+        lightoros_transform_convert_dim::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_convert_serpentine")]
+        lightoros_transform_convert_serpentine::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_script_lua")]
+        lightoros_transform_script_lua::plugin_declaration(),
+        #[cfg(feature = "lightoros_transform_script_wasm")]
+        lightoros_transform_script_wasm::plugin_declaration(),
+    ]
+}
+
+/// Declaration of the linked plugin with the name
+pub(crate) fn find_static_plugin(name: &str) -> Option<&'static PluginDeclaration> {
+    static_plugins()
+        .into_iter()
+        .find(|declaration| declaration.name() == name)
+}
@@ -0,0 +1,290 @@
+use lightoros_plugin_base::abi::*;
+use lightoros_plugin_base::transform::{CreateTransformPluginResult, PluginTransformTrait};
+use lightoros_plugin_base::*;
+use serde_json::json;
+use std::os::raw::c_void;
+
+// plugin compiled into the test, called only through the exported declaration like the engine does
+const NAME: &str = "AbiTestTransform";
+
+struct AbiTestTransform {
+    level: u8,
+}
+
+impl std::fmt::Display for AbiTestTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl PluginTransformTrait for AbiTestTransform {
+    fn transform(&self, data: &TraitData) -> PluginResult<TraitData> {
+        if data.meta.contains_key("fail") {
+            return plugin_err!("Transform failed");
+        }
+        if data.meta.contains_key("panic") {
+            panic!("Transform panicked");
+        }
+        let rgb = data
+            .rgb
+            .iter()
+            .map(|rgb| RGB {
+                r: 255 - rgb.r,
+                g: 255 - rgb.g,
+                b: self.level,
+            })
+            .collect();
+        let mut meta = data.meta.clone();
+        meta.insert("level".to_string(), self.level.to_string());
+        Ok(TraitData { rgb, meta })
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        if name != "level" {
+            return Ok(false);
+        }
+        match value.parse() {
+            Ok(level) => {
+                self.level = level;
+                Ok(true)
+            }
+            Err(_) => plugin_err!("Invalid level: {}", value),
+        }
+    }
+
+    fn get_parameter(&self, name: &str) -> Option<String> {
+        match name {
+            "level" => Some(self.level.to_string()),
+            _ => None,
+        }
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateTransformPluginResult {
+    match config["level"].as_u64() {
+        Some(level) if level <= 255 => Ok(Box::new(AbiTestTransform { level: level as u8 })),
+        _ => plugin_err!("Missing level"),
+    }
+}
+
+const SCHEMA: &str = r#"{"type": "object", "required": ["level"]}"#;
+
+plugin_abi!(1, NAME, PluginKind::Transform, create, SCHEMA);
+
+fn declaration() -> &'static PluginDeclaration {
+    unsafe { &*lightoros_plugin(ABI_VERSION) }
+}
+
+fn take_string(string: AbiStr) -> String {
+    let value = unsafe { string.to_string() };
+    unsafe { (declaration().functions.free_string)(string) };
+    value
+}
+
+fn create_plugin(config: serde_json::Value) -> Result<*mut c_void, String> {
+    let config = config.to_string();
+    let mut error = AbiStr::empty();
+    let handle = unsafe { (declaration().create)(AbiStr::borrow(&config), &mut error) };
+    if handle.is_null() {
+        Err(take_string(error))
+    } else {
+        Ok(handle)
+    }
+}
+
+fn transform(handle: *mut c_void, data: &TraitData) -> Result<TraitData, String> {
+    let functions = &declaration().functions;
+    let input = BorrowedFrame::new(data);
+    let mut output = AbiFrame::empty();
+    let mut error = AbiStr::empty();
+    if !unsafe { (functions.transform)(handle, &input.frame, &mut output, &mut error) } {
+        return Err(take_string(error));
+    }
+    let result = unsafe { frame_from_abi(&output) };
+    unsafe { (functions.free_frame)(&mut output) };
+    Ok(result)
+}
+
+fn offset<T>(declaration: &PluginDeclaration, field: &T) -> usize {
+    field as *const T as usize - declaration as *const PluginDeclaration as usize
+}
+
+#[test]
+fn test_header() {
+    let declaration = declaration();
+    assert_eq!(declaration.abi_version, ABI_VERSION);
+    assert_eq!(declaration.api_version, API_VERSION as u32);
+    assert_eq!(declaration.kind, 2);
+    assert_eq!(declaration.name(), NAME);
+    assert_eq!(declaration.filename(), "lightoros_engine");
+    assert_eq!(declaration.description(), env!("CARGO_PKG_DESCRIPTION"));
+    assert_eq!(declaration.config_schema(), SCHEMA);
+    assert!(declaration.check().is_ok());
+
+    // the header must keep its layout in all ABI versions
+    let word = std::mem::size_of::<usize>();
+    assert_eq!(offset(declaration, &declaration.abi_version), 0);
+    assert_eq!(offset(declaration, &declaration.api_version), 4);
+    assert_eq!(offset(declaration, &declaration.kind), 8);
+    let name = (3 * 4_usize).div_ceil(word) * word;
+    assert_eq!(offset(declaration, &declaration.name), name);
+    assert_eq!(
+        offset(declaration, &declaration.filename),
+        offset(declaration, &declaration.name) + 2 * word
+    );
+}
+
+#[test]
+fn test_version_negotiation() {
+    // an engine older than the plugin supports gets no declaration
+    assert!(lightoros_plugin(0).is_null());
+    assert!(!lightoros_plugin(ABI_VERSION + 1).is_null());
+
+    let check = |abi_version: u32, api_version: u32, kind: u32| {
+        let declaration = PluginDeclaration {
+            abi_version,
+            api_version,
+            kind,
+            ..PluginDeclaration::new(1, NAME, 2, "test", "", "", declaration().create)
+        };
+        declaration.check().err().map(|err| err.to_string())
+    };
+    assert_eq!(check(ABI_VERSION, API_VERSION as u32, 2), None);
+    assert_eq!(
+        check(ABI_VERSION + 1, API_VERSION as u32, 2).unwrap(),
+        format!(
+            "Plugin 'AbiTestTransform' uses ABI version {}, the engine supports version {}. Rebuild the plugin for this engine",
+            ABI_VERSION + 1,
+            ABI_VERSION
+        )
+    );
+    assert!(check(0, API_VERSION as u32, 2)
+        .unwrap()
+        .contains("ABI version 0"));
+    assert_eq!(
+        check(ABI_VERSION, 2, 2).unwrap(),
+        format!(
+            "Plugin 'AbiTestTransform' uses API version 2, the engine supports version {}",
+            API_VERSION
+        )
+    );
+    assert_eq!(
+        check(ABI_VERSION, API_VERSION as u32, 7).unwrap(),
+        "Plugin 'AbiTestTransform' has the unknown kind 7"
+    );
+}
+
+#[test]
+fn test_create() {
+    assert_eq!(create_plugin(json!({})).err().unwrap(), "Missing level");
+    let handle = create_plugin(json!({"level": 3})).unwrap();
+    unsafe { (declaration().functions.destroy)(handle) };
+}
+
+#[test]
+fn test_transform() {
+    let handle = create_plugin(json!({"level": 3})).unwrap();
+    let data = plugin_data!(vec![RGB { r: 0, g: 10, b: 20 }, RGB { r: 255, g: 1, b: 2 }], {
+        "source" => "test",
+    });
+    let result = transform(handle, &data).unwrap();
+    let rgb: Vec<(u8, u8, u8)> = result.rgb.iter().map(|c| (c.r, c.g, c.b)).collect();
+    assert_eq!(rgb, vec![(255, 245, 3), (0, 254, 3)]);
+    assert_eq!(result.meta["source"], "test");
+    assert_eq!(result.meta["level"], "3");
+
+    let empty = transform(handle, &plugin_data!(Vec::new(), {})).unwrap();
+    assert!(empty.rgb.is_empty());
+    assert_eq!(empty.meta.len(), 1);
+
+    // errors and panics of the plugin are returned as errors
+    let failing = plugin_data!(Vec::new(), { "fail" => "1" });
+    assert_eq!(
+        transform(handle, &failing).err().unwrap(),
+        "Transform failed"
+    );
+    let panicking = plugin_data!(Vec::new(), { "panic" => "1" });
+    assert_eq!(
+        transform(handle, &panicking).err().unwrap(),
+        "Plugin panicked"
+    );
+
+    // functions of other plugin kinds are refused
+    let functions = &declaration().functions;
+    let mut error = AbiStr::empty();
+    assert!(!unsafe { (functions.init)(handle, &mut error) });
+    assert_eq!(
+        take_string(error),
+        "Function not supported by the plugin kind"
+    );
+
+    unsafe { (functions.destroy)(handle) };
+}
+
+#[test]
+fn test_parameters() {
+    let functions = &declaration().functions;
+    let handle = create_plugin(json!({"level": 3})).unwrap();
+    let set = |name: &str, value: &str| {
+        let mut changed = false;
+        let mut error = AbiStr::empty();
+        let ok = unsafe {
+            (functions.set_parameter)(
+                handle,
+                AbiStr::borrow(name),
+                AbiStr::borrow(value),
+                &mut changed,
+                &mut error,
+            )
+        };
+        if ok {
+            Ok(changed)
+        } else {
+            Err(take_string(error))
+        }
+    };
+    let get = |name: &str| {
+        let mut value = AbiStr::empty();
+        if !unsafe { (functions.get_parameter)(handle, AbiStr::borrow(name), &mut value) } {
+            return None;
+        }
+        Some(take_string(value))
+    };
+
+    assert_eq!(set("level", "9"), Ok(true));
+    assert_eq!(get("level"), Some("9".to_string()));
+    assert_eq!(set("unknown", "1"), Ok(false));
+    assert_eq!(get("unknown"), None);
+    assert_eq!(set("level", "x"), Err("Invalid level: x".to_string()));
+    assert_eq!(get("level"), Some("9".to_string()));
+
+    unsafe { (functions.destroy)(handle) };
+}
+
+#[test]
+fn test_dynamic_plugin() {
+    // the declaration wrapped into the plugin traits, like the engine uses it
+    let error = unsafe { declaration().create_input(&json!({"level": 3}), None) };
+    assert_eq!(
+        error.err().unwrap().to_string(),
+        "Plugin 'AbiTestTransform' has a different kind"
+    );
+    let error = unsafe { declaration().create_transform(&json!({}), None) };
+    assert_eq!(error.err().unwrap().to_string(), "Missing level");
+
+    let mut plugin = unsafe { declaration().create_transform(&json!({"level": 3}), None) }.unwrap();
+    assert_eq!(plugin.to_string(), NAME);
+    let data = plugin_data!(vec![RGB { r: 0, g: 10, b: 20 }], {});
+    let result = plugin.transform(&data).unwrap();
+    assert_eq!(
+        (result.rgb[0].r, result.rgb[0].g, result.rgb[0].b),
+        (255, 245, 3)
+    );
+    assert!(plugin.set_parameter("level", "9").unwrap());
+    assert_eq!(plugin.get_parameter("level"), Some("9".to_string()));
+    let failing = plugin_data!(Vec::new(), { "fail" => "1" });
+    assert_eq!(
+        plugin.transform(&failing).err().unwrap().to_string(),
+        "Transform failed"
+    );
+}
@@ -0,0 +1,146 @@
+use lightoros_engine::*;
+use lightoros_plugin_base::PluginKind;
+use serde_json::json;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// the helper is built with the engine, the plugins of the workspace are built into the same folder
+fn plugins_folder() -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_BIN_EXE_lightoros_plugin_host"));
+    path.pop();
+    path
+}
+
+// folder with a library which is not a plugin
+fn broken_folder(name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!(
+        "lightoros_index_test_{}_{}",
+        std::process::id(),
+        name
+    ));
+    std::fs::create_dir_all(&path).unwrap();
+    let file_name = format!(
+        "{}lightoros_input_broken.{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_EXTENSION
+    );
+    std::fs::write(path.join(file_name), b"not a library").unwrap();
+    path
+}
+
+fn plugins_path(folders: &[PathBuf]) -> String {
+    std::env::join_paths(folders)
+        .unwrap()
+        .into_string()
+        .unwrap()
+}
+
+fn config(port: u16) -> String {
+    json!({
+        "description": "Plugin index test",
+        "max_input_inactivity_period": 1000,
+        "input": [{
+            "name": "text",
+            "priority": 10,
+            "members": [{
+                "kind": "TextExtraInput",
+                "config": {
+                    "screen": { "width": 12, "height": 7 },
+                    "text": "Hi",
+                    "fps": 20
+                }
+            }]
+        }],
+        "output": [],
+        "json_server": { "address": "127.0.0.1", "port": port }
+    })
+    .to_string()
+}
+
+fn text_pipe_visible(port: u16) -> bool {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(2000)))
+        .unwrap();
+    stream
+        .write_all(b"{\"command\": \"serverinfo\"}\n")
+        .unwrap();
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response).unwrap();
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+    response["info"]["priorities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|entry| entry["owner"] == "text" && entry["visible"] == true)
+}
+
+fn wait_for(condition: impl Fn() -> bool) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if condition() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn test_discovery() {
+    let broken = broken_folder("discovery");
+    let index = PluginIndex::discover(&plugins_path(&[broken.clone(), plugins_folder()]));
+    assert_eq!(index.folders(), &[broken.clone(), plugins_folder()][..]);
+
+    // the broken library is reported and skipped
+    let error = index
+        .errors()
+        .iter()
+        .find(|error| error.path.starts_with(&broken))
+        .unwrap();
+    assert!(error.name.is_none());
+    assert!(error.to_string().contains("lightoros_input_broken"));
+
+    let text = index.find("TextExtraInput").unwrap();
+    assert!(text.kind == PluginKind::Input);
+    assert_eq!(text.api_version, 1);
+    if let Some(file) = &text.file {
+        assert!(file.starts_with(plugins_folder()));
+    }
+    assert!(!text.description.is_empty());
+    let schema: serde_json::Value = serde_json::from_str(&text.config_schema).unwrap();
+    assert_eq!(schema["required"], json!(["screen"]));
+
+    let json = index.to_json();
+    let entry = json["plugins"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|plugin| plugin["name"] == "TextExtraInput")
+        .unwrap();
+    assert_eq!(entry["kind"], "input");
+    assert_eq!(entry["config_schema"], schema);
+
+    let error = index.find("MissingInput").err().unwrap().to_string();
+    assert!(error.starts_with("Cannot find plugin 'MissingInput' in folders"));
+    std::fs::remove_dir_all(broken).unwrap();
+}
+
+#[test]
+fn test_start_with_broken_plugin() {
+    let broken = broken_folder("start");
+    let mut engine = LightorosEngine::new();
+    engine
+        .start(
+            config(46590),
+            plugins_path(&[broken.clone(), plugins_folder()]),
+        )
+        .unwrap();
+    assert!(wait_for(|| text_pipe_visible(46590)));
+    engine.stop().unwrap();
+    std::fs::remove_dir_all(broken).unwrap();
+}
@@ -0,0 +1,276 @@
+use lightoros_engine::*;
+use serde_json::{json, Value};
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::TcpStream;
+use std::time::Duration;
+
+struct Connection {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Connection {
+    fn open(port: u16) -> Connection {
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(2000)))
+            .unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        Connection { stream, reader }
+    }
+
+    fn request(&mut self, request: Value) -> Value {
+        let mut line = request.to_string();
+        line.push('\n');
+        self.stream.write_all(line.as_bytes()).unwrap();
+        let mut response = String::new();
+        self.reader.read_line(&mut response).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    fn serverinfo(&mut self) -> Value {
+        let response = self.request(json!({"command": "serverinfo", "tan": 1}));
+        assert_eq!(response["success"], true);
+        response["info"].clone()
+    }
+}
+
+fn start_engine(port: u16) -> LightorosEngine {
+    let config = json!({
+        "description": "JSON server test",
+        "max_input_inactivity_period": 1000,
+        "input": [],
+        "output": [],
+        "json_server": {
+            "address": "127.0.0.1",
+            "port": port,
+            "leds": 3
+        }
+    });
+    let mut engine = LightorosEngine::new();
+    engine.start(config.to_string(), String::new()).unwrap();
+    engine
+}
+
+#[test]
+fn test_serverinfo() {
+    let mut engine = start_engine(46560);
+    let mut connection = Connection::open(46560);
+
+    let response = connection.request(json!({"command": "serverinfo", "tan": 7}));
+    assert_eq!(response["command"], "serverinfo");
+    assert_eq!(response["success"], true);
+    assert_eq!(response["tan"], 7);
+    let info = &response["info"];
+    assert_eq!(info["priorities"], json!([]));
+    assert_eq!(info["effects"], json!([]));
+    assert_eq!(info["adjustment"], json!([{"id": "default"}]));
+    assert_eq!(
+        info["components"][0],
+        json!({"name": "ALL", "enabled": true})
+    );
+    assert_eq!(info["instance"][0]["friendly_name"], "JSON server test");
+
+    let response = connection.request(json!({"command": "sysinfo"}));
+    assert_eq!(response["info"]["hyperion"]["build"], "lightoros");
+
+    let response =
+        connection.request(json!({"command": "authorize", "subcommand": "tokenRequired"}));
+    assert_eq!(response["command"], "authorize-tokenRequired");
+    assert_eq!(response["info"]["required"], false);
+
+    engine.stop().unwrap();
+}
+
+#[test]
+fn test_color_and_clear() {
+    let mut engine = start_engine(46561);
+    let mut connection = Connection::open(46561);
+
+    let response = connection.request(json!({
+        "command": "color",
+        "priority": 50,
+        "color": [255, 0, 0],
+        "origin": "test",
+        "tan": 2
+    }));
+    assert_eq!(
+        response,
+        json!({"command": "color", "success": true, "tan": 2})
+    );
+
+    let info = connection.serverinfo();
+    let priorities = info["priorities"].as_array().unwrap();
+    assert_eq!(priorities.len(), 1);
+    assert_eq!(priorities[0]["priority"], 50);
+    assert_eq!(priorities[0]["componentId"], "COLOR");
+    assert_eq!(priorities[0]["origin"], "test");
+    assert_eq!(priorities[0]["value"]["RGB"], json!([255, 0, 0]));
+
+    let response = connection.request(json!({"command": "clear", "priority": 50}));
+    assert_eq!(response["success"], true);
+    assert_eq!(connection.serverinfo()["priorities"], json!([]));
+
+    engine.stop().unwrap();
+}
+
+#[test]
+fn test_color_duration() {
+    let mut engine = start_engine(46562);
+    let mut connection = Connection::open(46562);
+
+    let response = connection.request(json!({
+        "command": "color",
+        "priority": 1,
+        "color": [0, 0, 255],
+        "duration": 100
+    }));
+    assert_eq!(response["success"], true);
+    let info = connection.serverinfo();
+    assert!(info["priorities"][0]["duration_ms"].as_u64().unwrap() <= 100);
+
+    std::thread::sleep(Duration::from_millis(200));
+    assert_eq!(connection.serverinfo()["priorities"], json!([]));
+
+    engine.stop().unwrap();
+}
+
+#[test]
+fn test_invalid_requests() {
+    let mut engine = start_engine(46563);
+    let mut connection = Connection::open(46563);
+
+    let response =
+        connection.request(json!({"command": "color", "priority": 0, "color": [1, 2, 3]}));
+    assert_eq!(response["success"], false);
+    let response = connection.request(json!({"command": "color", "priority": 10, "color": [1, 2]}));
+    assert_eq!(response["success"], false);
+    let response = connection
+        .request(json!({"command": "effect", "priority": 10, "effect": {"name": "Rainbow"}}));
+    assert_eq!(response["error"], "Unknown effect 'Rainbow'");
+    let response =
+        connection.request(json!({"command": "adjustment", "adjustment": {"brightness": 50}}));
+    assert_eq!(
+        response["error"],
+        "Adjustment 'brightness' is not supported by any transformation"
+    );
+    let response = connection.request(json!({"command": "instance", "tan": 3}));
+    assert_eq!(response["success"], false);
+    assert_eq!(response["tan"], 3);
+
+    connection.stream.write_all(b"{invalid\n").unwrap();
+    let mut response = String::new();
+    connection.reader.read_line(&mut response).unwrap();
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["success"], false);
+
+    engine.stop().unwrap();
+}
+
+#[test]
+fn test_component_state() {
+    let mut engine = start_engine(46564);
+    let mut connection = Connection::open(46564);
+
+    let response = connection.request(json!({
+        "command": "componentstate",
+        "componentstate": {"component": "LEDDEVICE", "state": false}
+    }));
+    assert_eq!(response["success"], true);
+    let info = connection.serverinfo();
+    assert_eq!(
+        info["components"][1],
+        json!({"name": "LEDDEVICE", "enabled": false})
+    );
+
+    let response = connection.request(json!({
+        "command": "componentstate",
+        "componentstate": {"component": "V4L", "state": true}
+    }));
+    assert_eq!(response["success"], false);
+
+    engine.stop().unwrap();
+}
+
+#[test]
+fn test_large_responses() {
+    let mut engine = start_engine(46565);
+    let mut connection = Connection::open(46565);
+
+    // the responses are queued while the client does not read them
+    let requests = "{\"command\": \"serverinfo\"}\n".repeat(2000);
+    connection.stream.write_all(requests.as_bytes()).unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+    for _ in 0..2000 {
+        let mut response = String::new();
+        connection.reader.read_line(&mut response).unwrap();
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["success"], true);
+    }
+
+    engine.stop().unwrap();
+}
+
+#[test]
+fn test_line_too_long() {
+    let mut engine = start_engine(46566);
+    let mut connection = Connection::open(46566);
+
+    // the connection is closed instead of buffering the line
+    let chunk = vec![b' '; 64 * 1024];
+    for _ in 0..20 {
+        if connection.stream.write_all(&chunk).is_err() {
+            break;
+        }
+    }
+    let mut response = String::new();
+    let result = connection.reader.read_line(&mut response);
+    assert!(matches!(result, Ok(0) | Err(_)), "{:?}", result);
+
+    // other clients are still served
+    assert_eq!(
+        Connection::open(46566).serverinfo()["priorities"],
+        json!([])
+    );
+
+    engine.stop().unwrap();
+}
+
+#[test]
+fn test_bind_error() {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 46567)).unwrap();
+    let mut plugins_folder = std::path::PathBuf::from(env!("CARGO_BIN_EXE_lightoros_plugin_host"));
+    plugins_folder.pop();
+    let config = json!({
+        "description": "JSON server test",
+        "max_input_inactivity_period": 1000,
+        "input": [{
+            "name": "text",
+            "priority": 10,
+            "members": [{
+                "kind": "TextExtraInput",
+                "config": { "screen": { "width": 12, "height": 7 }, "text": "Hi" }
+            }]
+        }],
+        "output": [],
+        "json_server": { "address": "127.0.0.1", "port": 46567 }
+    })
+    .to_string();
+    let plugins_folder = plugins_folder.to_str().unwrap().to_owned();
+
+    // the started input pipe is stopped with the failed start
+    let mut engine = LightorosEngine::new();
+    let error = engine
+        .start(config.clone(), plugins_folder.clone())
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("Error binding JSON server"));
+    assert!(engine.stop().is_err());
+
+    drop(listener);
+    engine.start(config, plugins_folder).unwrap();
+    let info = Connection::open(46567).serverinfo();
+    assert_eq!(info["instance"][0]["friendly_name"], "JSON server test");
+    engine.stop().unwrap();
+}
@@ -0,0 +1,202 @@
+use lightoros_engine::*;
+use serde_json::json;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// the tests count the helpers of the test process, so they run one after the other
+static SERIAL: Mutex<()> = Mutex::new(());
+
+// the helper is built with the engine, the plugins of the workspace are built into the same folder
+fn plugins_folder() -> String {
+    let mut path = PathBuf::from(env!("CARGO_BIN_EXE_lightoros_plugin_host"));
+    path.pop();
+    path.to_str().unwrap().to_owned()
+}
+
+fn config(screen_width: usize, port: u16) -> String {
+    json!({
+        "description": "Isolated plugin test",
+        "max_input_inactivity_period": 1000,
+        "input": [{
+            "name": "text",
+            "priority": 10,
+            "members": [{
+                "kind": "TextExtraInput",
+                "isolated": true,
+                "config": {
+                    "screen": { "width": screen_width, "height": 7 },
+                    "text": "Hi",
+                    "fps": 20
+                }
+            }]
+        }],
+        "output": [],
+        "json_server": { "address": "127.0.0.1", "port": port }
+    })
+    .to_string()
+}
+
+// processes of the helpers started by the engine
+fn helpers() -> Vec<String> {
+    let mut pids = Vec::new();
+    for task in std::fs::read_dir("/proc/self/task").unwrap() {
+        let children = task.unwrap().path().join("children");
+        let children = std::fs::read_to_string(children).unwrap_or_default();
+        for pid in children.split_whitespace() {
+            let cmdline = std::fs::read_to_string(format!("/proc/{}/cmdline", pid));
+            if cmdline.unwrap_or_default().contains(PLUGIN_HOST) {
+                pids.push(pid.to_string());
+            }
+        }
+    }
+    pids
+}
+
+fn text_pipe_visible(port: u16) -> bool {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(2000)))
+        .unwrap();
+    stream
+        .write_all(b"{\"command\": \"serverinfo\"}\n")
+        .unwrap();
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response).unwrap();
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+    response["info"]["priorities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|entry| entry["owner"] == "text" && entry["visible"] == true)
+}
+
+fn wait_for(condition: impl Fn() -> bool) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if condition() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn test_isolated_plugin() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    // errors of the plugin in the helper are returned like errors of a loaded plugin
+    let mut engine = LightorosEngine::new();
+    let error = engine
+        .start(config(0, 46570), plugins_folder())
+        .err()
+        .unwrap();
+    assert!(
+        error.to_string().contains("Invalid screen size"),
+        "{}",
+        error
+    );
+    assert!(helpers().is_empty());
+
+    let mut engine = LightorosEngine::new();
+    engine.start(config(12, 46571), plugins_folder()).unwrap();
+    assert!(wait_for(|| text_pipe_visible(46571)));
+    let pids = helpers();
+    assert_eq!(pids.len(), 1);
+
+    // a crashed helper is restarted and the pipe continues
+    std::process::Command::new("kill")
+        .arg("-9")
+        .arg(&pids[0])
+        .status()
+        .unwrap();
+    assert!(wait_for(|| {
+        let restarted = helpers();
+        restarted.len() == 1 && restarted[0] != pids[0]
+    }));
+    assert!(text_pipe_visible(46571));
+
+    engine.stop().unwrap();
+    assert!(helpers().is_empty());
+}
+
+fn adjusted_brightness(port: u16) -> serde_json::Value {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    // the pipe pauses for 5 seconds after a failed transformation
+    stream
+        .set_read_timeout(Some(Duration::from_millis(10000)))
+        .unwrap();
+    stream
+        .write_all(b"{\"command\": \"serverinfo\"}\n")
+        .unwrap();
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response).unwrap();
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+    response["info"]["adjustment"][0]["brightness"].clone()
+}
+
+fn signal(signal: &str, pid: &str) {
+    std::process::Command::new("kill")
+        .arg(signal)
+        .arg(pid)
+        .status()
+        .unwrap();
+}
+
+#[test]
+fn test_isolated_parameters_and_timeout() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let config = json!({
+        "description": "Isolated parameter test",
+        "max_input_inactivity_period": 1000,
+        "input": [{
+            "name": "text",
+            "priority": 10,
+            "members": [{
+                "kind": "TextExtraInput",
+                "config": { "screen": { "width": 12, "height": 7 }, "text": "Hi", "fps": 20 }
+            }, {
+                "kind": "ConvertDimTransform",
+                "isolated": true,
+                "timeout": 500,
+                "config": { "brightness": 100 }
+            }]
+        }],
+        "output": [],
+        "json_server": { "address": "127.0.0.1", "port": 46572 }
+    });
+    let mut engine = LightorosEngine::new();
+    engine.start(config.to_string(), plugins_folder()).unwrap();
+    assert!(wait_for(|| text_pipe_visible(46572)));
+
+    let mut stream = TcpStream::connect(("127.0.0.1", 46572)).unwrap();
+    stream
+        .write_all(b"{\"command\": \"adjustment\", \"adjustment\": {\"brightness\": 50}}\n")
+        .unwrap();
+    assert!(wait_for(|| adjusted_brightness(46572) == 50));
+    let pids = helpers();
+    assert_eq!(pids.len(), 1);
+
+    // the parameter is set again in the restarted helper
+    signal("-9", &pids[0]);
+    assert!(wait_for(|| {
+        let restarted = helpers();
+        restarted.len() == 1 && restarted[0] != pids[0]
+    }));
+    assert_eq!(adjusted_brightness(46572), 50);
+
+    // a helper which does not answer is restarted like a crashed one
+    let pids = helpers();
+    signal("-STOP", &pids[0]);
+    assert!(wait_for(|| {
+        let restarted = helpers();
+        restarted.len() == 1 && restarted[0] != pids[0]
+    }));
+    assert_eq!(adjusted_brightness(46572), 50);
+
+    engine.stop().unwrap();
+    assert!(helpers().is_empty());
+}
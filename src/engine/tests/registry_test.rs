@@ -0,0 +1,140 @@
+use lightoros_engine::*;
+use serde_json::json;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+// run with `--features lightoros_input_extra_text`, the plugins folder has no plugin files
+fn config(isolated: bool, port: u16) -> String {
+    json!({
+        "description": "Static registry test",
+        "max_input_inactivity_period": 1000,
+        "input": [{
+            "name": "text",
+            "priority": 10,
+            "members": [{
+                "kind": "TextExtraInput",
+                "isolated": isolated,
+                "config": {
+                    "screen": { "width": 12, "height": 7 },
+                    "text": "Hi",
+                    "fps": 20
+                }
+            }]
+        }],
+        "output": [],
+        "json_server": { "address": "127.0.0.1", "port": port }
+    })
+    .to_string()
+}
+
+fn empty_folder(name: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(format!(
+        "lightoros_registry_test_{}_{}",
+        std::process::id(),
+        name
+    ));
+    std::fs::create_dir_all(&path).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+fn text_pipe_visible(port: u16) -> bool {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(2000)))
+        .unwrap();
+    stream
+        .write_all(b"{\"command\": \"serverinfo\"}\n")
+        .unwrap();
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response).unwrap();
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+    response["info"]["priorities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|entry| entry["owner"] == "text" && entry["visible"] == true)
+}
+
+fn wait_for(condition: impl Fn() -> bool) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if condition() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn test_static_plugin() {
+    let folder = empty_folder("static");
+    let mut engine = LightorosEngine::new();
+    engine.start(config(false, 46580), folder.clone()).unwrap();
+    assert!(wait_for(|| text_pipe_visible(46580)));
+    engine.stop().unwrap();
+
+    // plugins which are not linked are still searched in the plugins folder
+    let missing = config(false, 46581).replace("TextExtraInput", "MissingInput");
+    let mut engine = LightorosEngine::new();
+    let error = engine.start(missing, folder.clone()).err().unwrap();
+    assert!(
+        error
+            .to_string()
+            .contains("Cannot find plugin 'MissingInput'"),
+        "{}",
+        error
+    );
+    std::fs::remove_dir_all(folder).unwrap();
+}
+
+#[test]
+fn test_isolated_static_plugin() {
+    // the helper is built with the same plugins linked, it is the only file in the plugins folder
+    let folder = empty_folder("isolated");
+    let host = std::path::Path::new(env!("CARGO_BIN_EXE_lightoros_plugin_host"));
+    std::fs::copy(
+        host,
+        std::path::Path::new(&folder).join(host.file_name().unwrap()),
+    )
+    .unwrap();
+    let mut engine = LightorosEngine::new();
+    engine.start(config(true, 46582), folder.clone()).unwrap();
+    assert!(wait_for(|| text_pipe_visible(46582)));
+    engine.stop().unwrap();
+    std::fs::remove_dir_all(folder).unwrap();
+}
+
+#[test]
+fn test_plugin_library_exports() {
+    // libraries built in the same cargo invocation as a static engine lose their exports, see "Linked Plugins"
+    let mut folder = std::path::PathBuf::from(env!("CARGO_BIN_EXE_lightoros_plugin_host"));
+    folder.pop();
+    let file_name = format!(
+        "{}lightoros_input_extra_text.{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_EXTENSION
+    );
+    let library = folder.join(file_name);
+    if !library.is_file() {
+        eprintln!("{} not built, skipping", library.display());
+        return;
+    }
+
+    // the plugin is read from the library and skipped because it is linked
+    let index = PluginIndex::discover(folder.to_str().unwrap());
+    let error = index
+        .errors()
+        .iter()
+        .find(|error| error.path == library)
+        .unwrap();
+    assert_eq!(error.name.as_deref(), Some("TextExtraInput"));
+    assert!(
+        error.error.contains("is already provided by the engine"),
+        "{}",
+        error
+    );
+}
@@ -1,5 +1,5 @@
 use clap::crate_version;
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use lightoros_engine::*;
 use std::fs;
 use std::path::Path;
@@ -10,6 +10,7 @@ fn main() {
     let matches = App::new("lightoros")
         .version(crate_version!())
         .about("Flexible LED controlling engine")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("config")
                 .short("c")
@@ -24,12 +25,32 @@ fn main() {
                 .short("p")
                 .long("plugins_dir")
                 .value_name("FOLDER")
-                .help("Sets the path to the plugins folder")
+                .help("Sets the path to a plugins folder, can be used several times")
                 .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .global(true)
                 .required(false),
         )
+        .subcommand(
+            SubCommand::with_name("list-plugins")
+                .about("Lists the available plugins")
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Prints the plugins with their configuration schema as JSON"),
+                ),
+        )
         .get_matches();
 
+    if let Some(list_matches) = matches.subcommand_matches("list-plugins") {
+        list_plugins(
+            &plugin_folders(list_matches),
+            list_matches.is_present("json"),
+        );
+        return;
+    }
+
     // get config parameter and read the file content
     let cfg_path = matches.value_of("config").unwrap();
     let config_file_path = Path::new(cfg_path);
@@ -43,9 +64,7 @@ fn main() {
 
     println!("Config file '{}' loaded.", cfg_path);
 
-    let mut exepath = std::env::current_exe().unwrap();
-    exepath.pop();
-    let plugin_folder = exepath.into_os_string().into_string().unwrap().clone();
+    let plugin_folder = plugin_folders(&matches);
 
     let mut engine = LightorosEngine::new();
     engine.start(config_str.clone(), plugin_folder.clone()).unwrap();
@@ -71,3 +90,46 @@ fn main() {
         }
     }
 }
+
+// plugin folders joined like in PATH, the folder of the executable by default
+fn plugin_folders(matches: &ArgMatches) -> String {
+    let folders: Vec<String> = match matches.values_of("plugins dir") {
+        Some(folders) => folders.map(String::from).collect(),
+        None => {
+            let mut exepath = std::env::current_exe().unwrap();
+            exepath.pop();
+            vec![exepath.into_os_string().into_string().unwrap()]
+        }
+    };
+    match std::env::join_paths(&folders) {
+        Ok(path) => path.into_string().unwrap(),
+        Err(error) => {
+            eprintln!("Invalid plugins folder: {}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn list_plugins(plugin_folder: &str, json: bool) {
+    let index = PluginIndex::discover(plugin_folder);
+    if json {
+        println!("{:#}", index.to_json());
+        return;
+    }
+    for plugin in index.plugins() {
+        let source = match &plugin.file {
+            Some(file) => file.display().to_string(),
+            None => "linked".to_string(),
+        };
+        println!(
+            "{} ({}, API {}) {}",
+            plugin.name, plugin.kind, plugin.api_version, source
+        );
+        if !plugin.description.is_empty() {
+            println!("    {}", plugin.description);
+        }
+    }
+    for error in index.errors() {
+        eprintln!("Skipping plugin {}", error);
+    }
+}
@@ -0,0 +1,801 @@
+//! Stable C ABI between the engine and the plugins.
+//!
+//! Every plugin exports the function `lightoros_plugin` (see [`plugin_abi!`](../macro.plugin_abi.html)) returning a
+//! [`PluginDeclaration`]. The header of the declaration (ABI version, API version, kind, name and file name) keeps its
+//! layout in all ABI versions, so the engine can identify a plugin and refuse an incompatible one before calling any
+//! of its functions. Only C compatible types cross the boundary, so engine and plugins may be built by different
+//! compilers and with different versions of this crate.
+//!
+//! Memory is always released by the side which allocated it: frames and strings returned by a plugin are copied by
+//! the engine and released with `free_frame` and `free_string` of the plugin. Frames passed to a plugin are borrowed
+//! for the duration of the call.
+//!
+//! The engine uses a declaration through [`PluginDeclaration::create_input`] and its siblings, which wrap the created
+//! plugin into the usual plugin traits. The tests of the plugins use them the same way.
+
+#[cfg(any(feature = "input", feature = "output", feature = "transform"))]
+use std::any::Any;
+use std::collections::HashMap;
+use std::os::raw::c_void;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+#[cfg(any(feature = "input", feature = "output", feature = "transform"))]
+use std::sync::Arc;
+
+#[cfg(feature = "input")]
+use crate::input::{CreateInputPluginResult, PluginInputTrait};
+#[cfg(feature = "output")]
+use crate::output::{CreateOutputPluginResult, PluginOutputTrait};
+#[cfg(feature = "transform")]
+use crate::transform::{CreateTransformPluginResult, PluginTransformTrait};
+use crate::*;
+
+/// Version of the plugin ABI implemented by this crate
+pub const ABI_VERSION: u32 = 1;
+
+/// Version of the plugin API, the `api_version` of [`PluginDeclaration`]
+pub const API_VERSION: u8 = 1;
+
+/// Name of the function exported by the plugins
+pub const DECLARATION_SYMBOL: &[u8] = b"lightoros_plugin";
+
+/// Type of the exported function, gets the ABI version of the engine and may return null if the plugin cannot work with it
+pub type DeclarationFunction =
+    unsafe extern "C" fn(host_abi_version: u32) -> *const PluginDeclaration;
+
+/// UTF-8 string which is not NUL terminated
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct AbiStr {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+/// Metadata entry of a frame
+#[repr(C)]
+pub struct AbiMeta {
+    pub key: AbiStr,
+    pub value: AbiStr,
+}
+
+/// Frame with 3 bytes per pixel and its metadata
+#[repr(C)]
+pub struct AbiFrame {
+    pub rgb: *const u8,
+    pub pixels: usize,
+    pub meta: *const AbiMeta,
+    pub meta_len: usize,
+}
+
+impl AbiFrame {
+    pub fn empty() -> AbiFrame {
+        AbiFrame {
+            rgb: std::ptr::null(),
+            pixels: 0,
+            meta: std::ptr::null(),
+            meta_len: 0,
+        }
+    }
+}
+
+/// Functions of a created plugin. Functions returning a bool return false on errors and set the error message.
+#[repr(C)]
+pub struct PluginFunctions {
+    pub destroy: unsafe extern "C" fn(plugin: *mut c_void),
+    pub init: unsafe extern "C" fn(plugin: *mut c_void, error: *mut AbiStr) -> bool,
+    pub get:
+        unsafe extern "C" fn(plugin: *mut c_void, frame: *mut AbiFrame, error: *mut AbiStr) -> bool,
+    pub transform: unsafe extern "C" fn(
+        plugin: *mut c_void,
+        input: *const AbiFrame,
+        output: *mut AbiFrame,
+        error: *mut AbiStr,
+    ) -> bool,
+    pub send: unsafe extern "C" fn(
+        plugin: *mut c_void,
+        frame: *const AbiFrame,
+        error: *mut AbiStr,
+    ) -> bool,
+    pub set_parameter: unsafe extern "C" fn(
+        plugin: *mut c_void,
+        name: AbiStr,
+        value: AbiStr,
+        changed: *mut bool,
+        error: *mut AbiStr,
+    ) -> bool,
+    /// returns false if the plugin has no parameter with the name
+    pub get_parameter:
+        unsafe extern "C" fn(plugin: *mut c_void, name: AbiStr, value: *mut AbiStr) -> bool,
+    pub free_frame: unsafe extern "C" fn(frame: *mut AbiFrame),
+    pub free_string: unsafe extern "C" fn(string: AbiStr),
+}
+
+/// Description of a plugin, the fields up to `filename` are the header with the same layout in all ABI versions
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub abi_version: u32,
+    pub api_version: u32,
+    /// 0: input, 1: output, 2: transform
+    pub kind: u32,
+    pub name: AbiStr,
+    pub filename: AbiStr,
+    /// creates the plugin from its configuration as JSON, returns null on errors and sets the error message
+    pub create: unsafe extern "C" fn(config: AbiStr, error: *mut AbiStr) -> *mut c_void,
+    pub functions: PluginFunctions,
+    /// short description of the plugin
+    pub description: AbiStr,
+    /// JSON schema of the plugin configuration
+    pub config_schema: AbiStr,
+}
+
+// the declaration only points to static data and functions
+unsafe impl Sync for PluginDeclaration {}
+
+impl AbiStr {
+    pub const fn from_static(value: &'static str) -> AbiStr {
+        AbiStr {
+            ptr: value.as_ptr(),
+            len: value.len(),
+        }
+    }
+
+    pub fn borrow(value: &str) -> AbiStr {
+        AbiStr {
+            ptr: value.as_ptr(),
+            len: value.len(),
+        }
+    }
+
+    pub fn empty() -> AbiStr {
+        AbiStr::from_static("")
+    }
+
+    /// Copies the string, invalid UTF-8 is replaced
+    ///
+    /// # Safety
+    /// `ptr` must point to `len` readable bytes
+    pub unsafe fn to_string(&self) -> String {
+        if self.len == 0 {
+            return String::new();
+        }
+        let bytes = std::slice::from_raw_parts(self.ptr, self.len);
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+
+    // passes the ownership of the string to the caller, released with free_string
+    fn owned(value: String) -> AbiStr {
+        let bytes = value.into_bytes().into_boxed_slice();
+        let len = bytes.len();
+        AbiStr {
+            ptr: Box::into_raw(bytes) as *const u8,
+            len,
+        }
+    }
+}
+
+impl PluginKind {
+    pub const fn to_abi(&self) -> u32 {
+        match self {
+            PluginKind::Input => 0,
+            PluginKind::Output => 1,
+            PluginKind::Transform => 2,
+        }
+    }
+
+    pub fn from_abi(kind: u32) -> Option<PluginKind> {
+        match kind {
+            0 => Some(PluginKind::Input),
+            1 => Some(PluginKind::Output),
+            2 => Some(PluginKind::Transform),
+            _ => None,
+        }
+    }
+}
+
+impl PluginDeclaration {
+    /// Declaration of a plugin of this crate version, used by [`plugin_abi!`](../macro.plugin_abi.html)
+    pub const fn new(
+        api_version: u8,
+        name: &'static str,
+        kind: u32,
+        filename: &'static str,
+        description: &'static str,
+        config_schema: &'static str,
+        create: unsafe extern "C" fn(AbiStr, *mut AbiStr) -> *mut c_void,
+    ) -> PluginDeclaration {
+        PluginDeclaration {
+            abi_version: ABI_VERSION,
+            api_version: api_version as u32,
+            kind,
+            name: AbiStr::from_static(name),
+            filename: AbiStr::from_static(filename),
+            create,
+            functions: FUNCTIONS,
+            description: AbiStr::from_static(description),
+            config_schema: AbiStr::from_static(config_schema),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        unsafe { self.name.to_string() }
+    }
+
+    pub fn filename(&self) -> String {
+        unsafe { self.filename.to_string() }
+    }
+
+    pub fn kind(&self) -> Option<PluginKind> {
+        PluginKind::from_abi(self.kind)
+    }
+
+    /// Description of the plugin
+    pub fn description(&self) -> String {
+        unsafe { self.description.to_string() }
+    }
+
+    /// JSON schema of the plugin configuration
+    pub fn config_schema(&self) -> String {
+        unsafe { self.config_schema.to_string() }
+    }
+
+    /// Checks the header of a declaration, the other fields may only be used if the check succeeds
+    pub fn check(&self) -> PluginResult<()> {
+        // the header is valid in all versions
+        let name = self.name();
+        if self.abi_version != ABI_VERSION {
+            return plugin_err!(
+                "Plugin '{}' uses ABI version {}, the engine supports version {}. Rebuild the plugin for this engine",
+                name,
+                self.abi_version,
+                ABI_VERSION
+            );
+        }
+        if self.api_version != API_VERSION as u32 {
+            return plugin_err!(
+                "Plugin '{}' uses API version {}, the engine supports version {}",
+                name,
+                self.api_version,
+                API_VERSION
+            );
+        }
+        if self.kind().is_none() {
+            return plugin_err!("Plugin '{}' has the unknown kind {}", name, self.kind);
+        }
+        Ok(())
+    }
+}
+
+/// Plugin behind the handle passed to the functions of the declaration
+pub enum AbiPlugin {
+    #[cfg(feature = "input")]
+    Input(Box<dyn PluginInputTrait>),
+    #[cfg(feature = "output")]
+    Output(Box<dyn PluginOutputTrait>),
+    #[cfg(feature = "transform")]
+    Transform(Box<dyn PluginTransformTrait>),
+}
+
+#[cfg(feature = "input")]
+impl From<Box<dyn PluginInputTrait>> for AbiPlugin {
+    fn from(plugin: Box<dyn PluginInputTrait>) -> AbiPlugin {
+        AbiPlugin::Input(plugin)
+    }
+}
+
+#[cfg(feature = "output")]
+impl From<Box<dyn PluginOutputTrait>> for AbiPlugin {
+    fn from(plugin: Box<dyn PluginOutputTrait>) -> AbiPlugin {
+        AbiPlugin::Output(plugin)
+    }
+}
+
+#[cfg(feature = "transform")]
+impl From<Box<dyn PluginTransformTrait>> for AbiPlugin {
+    fn from(plugin: Box<dyn PluginTransformTrait>) -> AbiPlugin {
+        AbiPlugin::Transform(plugin)
+    }
+}
+
+/// Copies a frame, e.g. passed to or returned by a plugin
+///
+/// # Safety
+/// The pointers of the frame must be valid for the given lengths
+pub unsafe fn frame_from_abi(frame: &AbiFrame) -> TraitData {
+    let rgb = if frame.pixels == 0 {
+        Vec::new()
+    } else {
+        std::slice::from_raw_parts(frame.rgb, frame.pixels * 3)
+            .chunks(3)
+            .map(|rgb| RGB {
+                r: rgb[0],
+                g: rgb[1],
+                b: rgb[2],
+            })
+            .collect()
+    };
+    let mut meta = HashMap::new();
+    if frame.meta_len > 0 {
+        for entry in std::slice::from_raw_parts(frame.meta, frame.meta_len) {
+            meta.insert(entry.key.to_string(), entry.value.to_string());
+        }
+    }
+    TraitData { rgb, meta }
+}
+
+/// Frame borrowing the data, valid as long as the data and the returned buffers are not changed or dropped
+pub struct BorrowedFrame {
+    // keeps the buffers of the frame alive
+    _rgb: Vec<u8>,
+    _meta: Vec<AbiMeta>,
+    pub frame: AbiFrame,
+}
+
+impl BorrowedFrame {
+    pub fn new(data: &TraitData) -> BorrowedFrame {
+        let rgb: Vec<u8> = data
+            .rgb
+            .iter()
+            .flat_map(|rgb| vec![rgb.r, rgb.g, rgb.b])
+            .collect();
+        let meta: Vec<AbiMeta> = data
+            .meta
+            .iter()
+            .map(|(key, value)| AbiMeta {
+                key: AbiStr::borrow(key),
+                value: AbiStr::borrow(value),
+            })
+            .collect();
+        let frame = AbiFrame {
+            rgb: rgb.as_ptr(),
+            pixels: data.rgb.len(),
+            meta: meta.as_ptr(),
+            meta_len: meta.len(),
+        };
+        BorrowedFrame {
+            _rgb: rgb,
+            _meta: meta,
+            frame,
+        }
+    }
+}
+
+// passes the ownership of the frame to the caller, released with free_frame
+#[cfg(any(feature = "input", feature = "transform"))]
+fn frame_to_abi(data: TraitData) -> AbiFrame {
+    let rgb: Box<[u8]> = data
+        .rgb
+        .iter()
+        .flat_map(|rgb| vec![rgb.r, rgb.g, rgb.b])
+        .collect();
+    let meta: Box<[AbiMeta]> = data
+        .meta
+        .into_iter()
+        .map(|(key, value)| AbiMeta {
+            key: AbiStr::owned(key),
+            value: AbiStr::owned(value),
+        })
+        .collect();
+    AbiFrame {
+        pixels: data.rgb.len(),
+        rgb: Box::into_raw(rgb) as *const u8,
+        meta_len: meta.len(),
+        meta: Box::into_raw(meta) as *const AbiMeta,
+    }
+}
+
+// runs a function of a plugin, errors and panics are returned as error message
+unsafe fn call(error: *mut AbiStr, function: impl FnOnce() -> PluginResult<()>) -> bool {
+    let message = match catch_unwind(AssertUnwindSafe(function)) {
+        Ok(Ok(())) => return true,
+        Ok(Err(err)) => err.to_string(),
+        Err(_) => "Plugin panicked".to_string(),
+    };
+    if !error.is_null() {
+        *error = AbiStr::owned(message);
+    }
+    false
+}
+
+/// Creates the handle of a plugin, used by [`plugin_abi!`](../macro.plugin_abi.html)
+///
+/// # Safety
+/// `error` must be null or valid for writes
+pub unsafe fn create_handle<P: Into<AbiPlugin>>(
+    create: impl FnOnce() -> PluginResult<P>,
+    error: *mut AbiStr,
+) -> *mut c_void {
+    let mut plugin = None;
+    let created = call(error, || {
+        plugin = Some(create()?.into());
+        Ok(())
+    });
+    match plugin {
+        Some(plugin) if created => Box::into_raw(Box::new(plugin)) as *mut c_void,
+        _ => std::ptr::null_mut(),
+    }
+}
+
+fn unsupported<T>() -> PluginResult<T> {
+    plugin_err!("Function not supported by the plugin kind")
+}
+
+unsafe fn plugin<'a>(handle: *mut c_void) -> &'a mut AbiPlugin {
+    &mut *(handle as *mut AbiPlugin)
+}
+
+// transform() and get_parameter() only need a shared reference, like the trait functions
+unsafe fn shared_plugin<'a>(handle: *mut c_void) -> &'a AbiPlugin {
+    &*(handle as *const AbiPlugin)
+}
+
+unsafe extern "C" fn destroy(handle: *mut c_void) {
+    if !handle.is_null() {
+        let _ = catch_unwind(|| drop(Box::from_raw(handle as *mut AbiPlugin)));
+    }
+}
+
+#[allow(unreachable_patterns)]
+unsafe extern "C" fn init(handle: *mut c_void, error: *mut AbiStr) -> bool {
+    call(error, || match plugin(handle) {
+        #[cfg(feature = "input")]
+        AbiPlugin::Input(input) => input.init(),
+        #[cfg(feature = "output")]
+        AbiPlugin::Output(output) => output.init(),
+        _ => unsupported(),
+    })
+}
+
+#[allow(unreachable_patterns, unused_variables)]
+unsafe extern "C" fn get(handle: *mut c_void, frame: *mut AbiFrame, error: *mut AbiStr) -> bool {
+    call(error, || match plugin(handle) {
+        #[cfg(feature = "input")]
+        AbiPlugin::Input(input) => {
+            *frame = frame_to_abi(input.get()?);
+            Ok(())
+        }
+        _ => unsupported(),
+    })
+}
+
+#[allow(unreachable_patterns, unused_variables)]
+unsafe extern "C" fn transform(
+    handle: *mut c_void,
+    input: *const AbiFrame,
+    output: *mut AbiFrame,
+    error: *mut AbiStr,
+) -> bool {
+    call(error, || match shared_plugin(handle) {
+        #[cfg(feature = "transform")]
+        AbiPlugin::Transform(transform) => {
+            *output = frame_to_abi(transform.transform(&frame_from_abi(&*input))?);
+            Ok(())
+        }
+        _ => unsupported(),
+    })
+}
+
+#[allow(unreachable_patterns, unused_variables)]
+unsafe extern "C" fn send(handle: *mut c_void, frame: *const AbiFrame, error: *mut AbiStr) -> bool {
+    call(error, || match plugin(handle) {
+        #[cfg(feature = "output")]
+        AbiPlugin::Output(output) => output.send(&frame_from_abi(&*frame)),
+        _ => unsupported(),
+    })
+}
+
+#[allow(unreachable_patterns, unused_variables)]
+unsafe extern "C" fn set_parameter(
+    handle: *mut c_void,
+    name: AbiStr,
+    value: AbiStr,
+    changed: *mut bool,
+    error: *mut AbiStr,
+) -> bool {
+    let (name, value) = (name.to_string(), value.to_string());
+    call(error, || {
+        *changed = match plugin(handle) {
+            #[cfg(feature = "input")]
+            AbiPlugin::Input(input) => input.set_parameter(&name, &value)?,
+            #[cfg(feature = "transform")]
+            AbiPlugin::Transform(transform) => transform.set_parameter(&name, &value)?,
+            _ => false,
+        };
+        Ok(())
+    })
+}
+
+#[allow(unreachable_patterns, unused_variables)]
+unsafe extern "C" fn get_parameter(handle: *mut c_void, name: AbiStr, value: *mut AbiStr) -> bool {
+    let name = name.to_string();
+    let result = catch_unwind(AssertUnwindSafe(|| match shared_plugin(handle) {
+        #[cfg(feature = "transform")]
+        AbiPlugin::Transform(transform) => transform.get_parameter(&name),
+        _ => None,
+    }));
+    match result {
+        Ok(Some(parameter)) => {
+            *value = AbiStr::owned(parameter);
+            true
+        }
+        _ => false,
+    }
+}
+
+unsafe extern "C" fn free_string(string: AbiStr) {
+    if string.len > 0 {
+        let bytes = std::slice::from_raw_parts_mut(string.ptr as *mut u8, string.len);
+        drop(Box::from_raw(bytes as *mut [u8]));
+    }
+}
+
+unsafe extern "C" fn free_frame(frame: *mut AbiFrame) {
+    let frame = &*frame;
+    if frame.pixels > 0 {
+        let rgb = std::slice::from_raw_parts_mut(frame.rgb as *mut u8, frame.pixels * 3);
+        drop(Box::from_raw(rgb as *mut [u8]));
+    }
+    if frame.meta_len > 0 {
+        let meta = std::slice::from_raw_parts_mut(frame.meta as *mut AbiMeta, frame.meta_len);
+        let meta = Box::from_raw(meta as *mut [AbiMeta]);
+        for entry in meta.iter() {
+            free_string(entry.key);
+            free_string(entry.value);
+        }
+    }
+}
+
+const FUNCTIONS: PluginFunctions = PluginFunctions {
+    destroy,
+    init,
+    get,
+    transform,
+    send,
+    set_parameter,
+    get_parameter,
+    free_frame,
+    free_string,
+};
+
+#[cfg(any(feature = "input", feature = "output", feature = "transform"))]
+impl PluginDeclaration {
+    // creates a plugin of the kind, `library` keeps the declaration valid until the plugin is destroyed
+    unsafe fn create_plugin(
+        &self,
+        kind: PluginKind,
+        config: &serde_json::Value,
+        library: Option<Arc<dyn Any + Send + Sync>>,
+    ) -> PluginResult<DynamicPlugin> {
+        if self.kind() != Some(kind) {
+            return plugin_err!("Plugin '{}' has a different kind", self.name());
+        }
+        let config = config.to_string();
+        let mut error = AbiStr::empty();
+        let handle = (self.create)(AbiStr::borrow(&config), &mut error);
+        let plugin = DynamicPlugin {
+            _library: library,
+            declaration: self,
+            handle,
+            name: self.name(),
+        };
+        if handle.is_null() {
+            return Err(plugin.error(error));
+        }
+        Ok(plugin)
+    }
+
+    /// Creates an input plugin of the declaration after a successful [`check`](#method.check).
+    /// `library` is kept until the plugin is dropped, e.g. the library providing the declaration.
+    ///
+    /// # Safety
+    /// The declaration must stay valid as long as `library` is alive, or forever without a library
+    #[cfg(feature = "input")]
+    pub unsafe fn create_input(
+        &self,
+        config: &serde_json::Value,
+        library: Option<Arc<dyn Any + Send + Sync>>,
+    ) -> CreateInputPluginResult {
+        let plugin = self.create_plugin(PluginKind::Input, config, library)?;
+        Ok(Box::new(DynamicInput(plugin)))
+    }
+
+    /// Creates a transform plugin of the declaration, see [`create_input`](#method.create_input)
+    ///
+    /// # Safety
+    /// The declaration must stay valid as long as `library` is alive, or forever without a library
+    #[cfg(feature = "transform")]
+    pub unsafe fn create_transform(
+        &self,
+        config: &serde_json::Value,
+        library: Option<Arc<dyn Any + Send + Sync>>,
+    ) -> CreateTransformPluginResult {
+        let plugin = self.create_plugin(PluginKind::Transform, config, library)?;
+        Ok(Box::new(DynamicTransform(plugin)))
+    }
+
+    /// Creates an output plugin of the declaration, see [`create_input`](#method.create_input)
+    ///
+    /// # Safety
+    /// The declaration must stay valid as long as `library` is alive, or forever without a library
+    #[cfg(feature = "output")]
+    pub unsafe fn create_output(
+        &self,
+        config: &serde_json::Value,
+        library: Option<Arc<dyn Any + Send + Sync>>,
+    ) -> CreateOutputPluginResult {
+        let plugin = self.create_plugin(PluginKind::Output, config, library)?;
+        Ok(Box::new(DynamicOutput(plugin)))
+    }
+}
+
+// plugin created through a declaration
+#[cfg(any(feature = "input", feature = "output", feature = "transform"))]
+struct DynamicPlugin {
+    // dropped after the plugin is destroyed, a library is unloaded with its last plugin
+    _library: Option<Arc<dyn Any + Send + Sync>>,
+    declaration: *const PluginDeclaration,
+    handle: *mut c_void,
+    name: String,
+}
+
+// the plugins behind the handle are Send like the plugin traits
+#[cfg(any(feature = "input", feature = "output", feature = "transform"))]
+unsafe impl Send for DynamicPlugin {}
+
+// not every function is used by every plugin kind
+#[cfg(any(feature = "input", feature = "output", feature = "transform"))]
+#[allow(dead_code)]
+impl DynamicPlugin {
+    fn functions(&self) -> &PluginFunctions {
+        unsafe { &(*self.declaration).functions }
+    }
+
+    // takes the error message of the plugin
+    fn error(&self, error: AbiStr) -> PluginError {
+        let message = unsafe { error.to_string() };
+        unsafe { (self.functions().free_string)(error) };
+        PluginError::new(message)
+    }
+
+    fn call(&self, function: impl FnOnce(*mut AbiStr) -> bool) -> PluginResult<()> {
+        let mut error = AbiStr::empty();
+        if function(&mut error) {
+            Ok(())
+        } else {
+            Err(self.error(error))
+        }
+    }
+
+    fn init(&self) -> PluginResult<()> {
+        self.call(|error| unsafe { (self.functions().init)(self.handle, error) })
+    }
+
+    // copies a frame returned by the plugin and releases it
+    fn take_frame(&self, mut frame: AbiFrame) -> TraitData {
+        let data = unsafe { frame_from_abi(&frame) };
+        unsafe { (self.functions().free_frame)(&mut frame) };
+        data
+    }
+
+    fn set_parameter(&self, name: &str, value: &str) -> PluginResult<bool> {
+        let mut changed = false;
+        self.call(|error| unsafe {
+            (self.functions().set_parameter)(
+                self.handle,
+                AbiStr::borrow(name),
+                AbiStr::borrow(value),
+                &mut changed,
+                error,
+            )
+        })?;
+        Ok(changed)
+    }
+}
+
+#[cfg(any(feature = "input", feature = "output", feature = "transform"))]
+impl Drop for DynamicPlugin {
+    fn drop(&mut self) {
+        if !self.handle.is_null() {
+            unsafe { (self.functions().destroy)(self.handle) };
+        }
+    }
+}
+
+#[cfg(any(feature = "input", feature = "output", feature = "transform"))]
+impl std::fmt::Display for DynamicPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[cfg(feature = "input")]
+struct DynamicInput(DynamicPlugin);
+
+#[cfg(feature = "input")]
+impl std::fmt::Display for DynamicInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "input")]
+impl PluginInputTrait for DynamicInput {
+    fn init(&mut self) -> PluginResult<()> {
+        self.0.init()
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        let plugin = &self.0;
+        let mut frame = AbiFrame::empty();
+        plugin
+            .call(|error| unsafe { (plugin.functions().get)(plugin.handle, &mut frame, error) })?;
+        Ok(plugin.take_frame(frame))
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        self.0.set_parameter(name, value)
+    }
+}
+
+#[cfg(feature = "transform")]
+struct DynamicTransform(DynamicPlugin);
+
+#[cfg(feature = "transform")]
+impl std::fmt::Display for DynamicTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "transform")]
+impl PluginTransformTrait for DynamicTransform {
+    fn transform(&self, data: &TraitData) -> PluginResult<TraitData> {
+        let plugin = &self.0;
+        let input = BorrowedFrame::new(data);
+        let mut output = AbiFrame::empty();
+        plugin.call(|error| unsafe {
+            (plugin.functions().transform)(plugin.handle, &input.frame, &mut output, error)
+        })?;
+        Ok(plugin.take_frame(output))
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        self.0.set_parameter(name, value)
+    }
+
+    fn get_parameter(&self, name: &str) -> Option<String> {
+        let plugin = &self.0;
+        let mut value = AbiStr::empty();
+        let found = unsafe {
+            (plugin.functions().get_parameter)(plugin.handle, AbiStr::borrow(name), &mut value)
+        };
+        if !found {
+            return None;
+        }
+        let parameter = unsafe { value.to_string() };
+        unsafe { (plugin.functions().free_string)(value) };
+        Some(parameter)
+    }
+}
+
+#[cfg(feature = "output")]
+struct DynamicOutput(DynamicPlugin);
+
+#[cfg(feature = "output")]
+impl std::fmt::Display for DynamicOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "output")]
+impl PluginOutputTrait for DynamicOutput {
+    fn init(&mut self) -> PluginResult<()> {
+        self.0.init()
+    }
+
+    fn send(&mut self, data: &TraitData) -> PluginResult<()> {
+        let plugin = &self.0;
+        let frame = BorrowedFrame::new(data);
+        plugin
+            .call(|error| unsafe { (plugin.functions().send)(plugin.handle, &frame.frame, error) })
+    }
+}
@@ -3,6 +3,7 @@ use std::fmt::Display;
 use std::fmt::Formatter;
 
 pub use self::error::PluginError;
+pub mod abi;
 mod error;
 
 #[derive(PartialEq)]
@@ -12,6 +13,16 @@ pub enum PluginKind {
     Transform,
 }
 
+impl Display for PluginKind {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PluginKind::Input => write!(f, "input"),
+            PluginKind::Output => write!(f, "output"),
+            PluginKind::Transform => write!(f, "transform"),
+        }
+    }
+}
+
 pub type PluginResult<T> = Result<T, PluginError>;
 
 #[cfg(feature = "input")]
@@ -23,6 +34,12 @@ pub mod input {
     pub trait PluginInputTrait: Send + Display {
         fn init(&mut self) -> PluginResult<()>;
         fn get(&mut self) -> PluginResult<TraitData>;
+
+        /// Changes a parameter while the pipe is running, e.g. by a remote control.
+        /// Called between two get() calls. Returns false if the plugin has no parameter with that name.
+        fn set_parameter(&mut self, _name: &str, _value: &str) -> PluginResult<bool> {
+            Ok(false)
+        }
     }
 }
 
@@ -45,6 +62,17 @@ pub mod transform {
 
     pub trait PluginTransformTrait: Send + Display {
         fn transform(&self, data: &TraitData) -> PluginResult<TraitData>;
+
+        /// Changes a parameter while the pipe is running, e.g. by a remote control.
+        /// Returns false if the plugin has no parameter with that name.
+        fn set_parameter(&mut self, _name: &str, _value: &str) -> PluginResult<bool> {
+            Ok(false)
+        }
+
+        /// Current value of a runtime parameter, None if the plugin has no parameter with that name
+        fn get_parameter(&self, _name: &str) -> Option<String> {
+            None
+        }
     }
 }
 
@@ -53,19 +81,6 @@ pub struct TraitData {
     pub meta: HashMap<String, String>,
 }
 
-pub struct PluginInfo {
-    pub api_version: u8,
-    pub name: &'static str,
-    pub kind: PluginKind,
-    pub filename: &'static str,
-}
-
-impl Display for PluginInfo {
-    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "{}: {}", self.name, self.filename)
-    }
-}
-
 #[derive(Copy, Clone, Debug)]
 pub struct RGB {
     pub r: u8,
@@ -79,6 +94,57 @@ impl Display for RGB {
     }
 }
 
+/// Order of the color channels expected by a device
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColorOrder {
+    RGB,
+    RBG,
+    GRB,
+    GBR,
+    BRG,
+    BGR,
+}
+
+impl ColorOrder {
+    pub fn apply(&self, rgb: &RGB) -> [u8; 3] {
+        match self {
+            ColorOrder::RGB => [rgb.r, rgb.g, rgb.b],
+            ColorOrder::RBG => [rgb.r, rgb.b, rgb.g],
+            ColorOrder::GRB => [rgb.g, rgb.r, rgb.b],
+            ColorOrder::GBR => [rgb.g, rgb.b, rgb.r],
+            ColorOrder::BRG => [rgb.b, rgb.r, rgb.g],
+            ColorOrder::BGR => [rgb.b, rgb.g, rgb.r],
+        }
+    }
+
+    pub fn read(&self, c: &[u8]) -> RGB {
+        match self {
+            ColorOrder::RGB => RGB { r: c[0], g: c[1], b: c[2] },
+            ColorOrder::RBG => RGB { r: c[0], g: c[2], b: c[1] },
+            ColorOrder::GRB => RGB { r: c[1], g: c[0], b: c[2] },
+            ColorOrder::GBR => RGB { r: c[2], g: c[0], b: c[1] },
+            ColorOrder::BRG => RGB { r: c[1], g: c[2], b: c[0] },
+            ColorOrder::BGR => RGB { r: c[2], g: c[1], b: c[0] },
+        }
+    }
+}
+
+impl std::str::FromStr for ColorOrder {
+    type Err = PluginError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rgb" => Ok(ColorOrder::RGB),
+            "rbg" => Ok(ColorOrder::RBG),
+            "grb" => Ok(ColorOrder::GRB),
+            "gbr" => Ok(ColorOrder::GBR),
+            "brg" => Ok(ColorOrder::BRG),
+            "bgr" => Ok(ColorOrder::BGR),
+            _ => plugin_err!("Unsupported color order: {}", s),
+        }
+    }
+}
+
 pub fn get_meta_value<T: std::str::FromStr>(
     meta: &HashMap<String, String>,
     key: &str,
@@ -151,14 +217,59 @@ macro_rules! plugin_err {
     }
 }
 
+/// Exports the C ABI declaration of a plugin, see the `abi` module. `$create` is the `create` function of the plugin
+/// and `$schema` the JSON schema of its configuration. The description is the `description` of the plugin package.
+/// With the feature `static` of the plugin crate nothing is exported and the engine links the plugin by `plugin_declaration()`.
 #[macro_export]
-macro_rules! plugin_info {
-    ($api: expr, $name: expr, $kind: expr) => {
-        PluginInfo {
-            api_version: $api,
-            name: $name,
-            kind: $kind,
-            filename: env!("CARGO_PKG_NAME"),
+macro_rules! plugin_abi {
+    ($api: expr, $name: expr, $kind: expr, $create: path, $schema: expr) => {
+        static LIGHTOROS_PLUGIN_DECLARATION: $crate::abi::PluginDeclaration =
+            $crate::abi::PluginDeclaration::new(
+                $api,
+                $name,
+                $kind.to_abi(),
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_DESCRIPTION"),
+                $schema,
+                lightoros_create_plugin,
+            );
+
+        unsafe extern "C" fn lightoros_create_plugin(
+            config: $crate::abi::AbiStr,
+            error: *mut $crate::abi::AbiStr,
+        ) -> *mut std::os::raw::c_void {
+            let config = config.to_string();
+            $crate::abi::create_handle(
+                || {
+                    let config: serde_json::Value = match serde_json::from_str(&config) {
+                        Ok(config) => config,
+                        Err(err) => {
+                            return Err($crate::PluginError::new(format!(
+                                "Invalid plugin configuration: {}",
+                                err
+                            )))
+                        }
+                    };
+                    $create(&config)
+                },
+                error,
+            )
+        }
+
+        /// Declaration of the plugin for the static plugin registry of the engine
+        pub fn plugin_declaration() -> &'static $crate::abi::PluginDeclaration {
+            &LIGHTOROS_PLUGIN_DECLARATION
+        }
+
+        #[cfg_attr(not(feature = "static"), no_mangle)]
+        pub extern "C" fn lightoros_plugin(
+            host_abi_version: u32,
+        ) -> *const $crate::abi::PluginDeclaration {
+            // the engine is older than this plugin
+            if host_abi_version < $crate::abi::ABI_VERSION {
+                return std::ptr::null();
+            }
+            &LIGHTOROS_PLUGIN_DECLARATION
         }
     };
 }
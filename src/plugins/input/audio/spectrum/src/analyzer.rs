@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+// levels are mapped from this dBFS value up to 0 dBFS
+const MIN_DB: f32 = -60.0;
+// lowest peak the automatic gain control normalizes to, keeps noise from being amplified
+const MIN_AGC_PEAK: f32 = 0.25;
+// part of the peak lost per analyzed frame
+const AGC_DECAY: f32 = 0.995;
+// frequencies below this value are used for the beat detection
+const BEAT_FREQUENCY: f32 = 150.0;
+// energy below this value is considered silence and never a beat
+const MIN_BEAT_ENERGY: f32 = 1e-4;
+
+pub struct AnalyzerConfig {
+    pub sample_rate: u32,
+    pub fft_size: usize,
+    pub bands: usize,
+    pub min_frequency: f32,
+    pub max_frequency: f32,
+    pub smoothing: f32,
+    pub agc: bool,
+    pub beat_sensitivity: f32,
+    // number of analyzed frames the beat detection compares the energy with
+    pub beat_history: usize,
+}
+
+/// Result of the analysis of the latest samples, all levels are in the range 0.0-1.0
+pub struct Analysis<'a> {
+    pub bands: &'a [f32],
+    pub level: f32,
+    pub beat: bool,
+}
+
+// normalizes values to the highest value seen recently
+struct Agc {
+    enabled: bool,
+    peak: f32,
+}
+
+impl Agc {
+    fn update(&mut self, value: f32) {
+        self.peak = (self.peak * AGC_DECAY).max(value).max(MIN_AGC_PEAK);
+    }
+
+    fn apply(&self, value: f32) -> f32 {
+        if self.enabled {
+            (value / self.peak).min(1.0)
+        } else {
+            value
+        }
+    }
+}
+
+pub struct Analyzer {
+    config: AnalyzerConfig,
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    samples: VecDeque<f32>,
+    // range of FFT bins of each band
+    band_bins: Vec<(usize, usize)>,
+    bands: Vec<f32>,
+    level: f32,
+    band_agc: Agc,
+    level_agc: Agc,
+    energy_history: VecDeque<f32>,
+    // bass energy of the last frame was above the beat threshold
+    above: bool,
+    beat: bool,
+}
+
+impl Analyzer {
+    pub fn new(config: AnalyzerConfig) -> Analyzer {
+        let fft = FftPlanner::new().plan_fft_forward(config.fft_size);
+        // Hann window
+        let window = (0..config.fft_size)
+            .map(|i| {
+                let phase = 2.0 * std::f32::consts::PI * i as f32 / config.fft_size as f32;
+                0.5 - 0.5 * phase.cos()
+            })
+            .collect();
+        let band_bins = band_bins(&config);
+
+        Analyzer {
+            fft,
+            window,
+            samples: vec![0.0; config.fft_size].into(),
+            bands: vec![0.0; config.bands],
+            band_bins,
+            level: 0.0,
+            band_agc: Agc {
+                enabled: config.agc,
+                peak: MIN_AGC_PEAK,
+            },
+            level_agc: Agc {
+                enabled: config.agc,
+                peak: MIN_AGC_PEAK,
+            },
+            energy_history: VecDeque::with_capacity(config.beat_history),
+            above: false,
+            beat: false,
+            config,
+        }
+    }
+
+    /// Adds mono samples, only the latest samples fitting into the FFT are kept
+    pub fn push(&mut self, samples: &[f32]) {
+        for sample in samples {
+            self.samples.pop_front();
+            self.samples.push_back(*sample);
+        }
+    }
+
+    pub fn analyze(&mut self) -> Analysis<'_> {
+        let mut buffer: Vec<Complex<f32>> = self
+            .samples
+            .iter()
+            .zip(self.window.iter())
+            .map(|(sample, window)| Complex::new(sample * window, 0.0))
+            .collect();
+        self.fft.process(&mut buffer);
+
+        // amplitude of a full scale sine wave is 1.0
+        let scale = 2.0 / self.window.iter().sum::<f32>();
+        let amplitudes: Vec<f32> = buffer[..self.config.fft_size / 2]
+            .iter()
+            .map(|bin| bin.norm() * scale)
+            .collect();
+
+        let bands: Vec<f32> = self
+            .band_bins
+            .iter()
+            .map(|(first, last)| {
+                let amplitude = amplitudes[*first..=*last]
+                    .iter()
+                    .cloned()
+                    .fold(0.0, f32::max);
+                to_level(amplitude)
+            })
+            .collect();
+        self.band_agc
+            .update(bands.iter().cloned().fold(0.0, f32::max));
+        for (band, value) in self.bands.iter_mut().zip(bands) {
+            *band = smooth(*band, self.band_agc.apply(value), self.config.smoothing);
+        }
+
+        let rms = (self
+            .samples
+            .iter()
+            .map(|sample| sample * sample)
+            .sum::<f32>()
+            / self.samples.len() as f32)
+            .sqrt();
+        let level = to_level(rms);
+        self.level_agc.update(level);
+        self.level = smooth(
+            self.level,
+            self.level_agc.apply(level),
+            self.config.smoothing,
+        );
+
+        self.detect_beat(&amplitudes);
+
+        Analysis {
+            bands: &self.bands,
+            level: self.level,
+            beat: self.beat,
+        }
+    }
+
+    // a beat is a sudden rise of the bass energy compared to its recent average
+    fn detect_beat(&mut self, amplitudes: &[f32]) {
+        let bin_frequency = self.config.sample_rate as f32 / self.config.fft_size as f32;
+        let bass_bins = ((BEAT_FREQUENCY / bin_frequency) as usize).clamp(2, amplitudes.len());
+        // the DC bin is skipped
+        let energy = amplitudes[1..bass_bins]
+            .iter()
+            .map(|amplitude| amplitude * amplitude)
+            .sum::<f32>();
+
+        let average = if self.energy_history.is_empty() {
+            0.0
+        } else {
+            self.energy_history.iter().sum::<f32>() / self.energy_history.len() as f32
+        };
+        let above = energy > MIN_BEAT_ENERGY && energy > average * self.config.beat_sensitivity;
+        // only the first frame of a rising energy is reported
+        self.beat = above && !self.above;
+        self.above = above;
+
+        if self.energy_history.len() == self.config.beat_history {
+            self.energy_history.pop_front();
+        }
+        self.energy_history.push_back(energy);
+    }
+}
+
+// maps an amplitude logarithmically to 0.0-1.0
+fn to_level(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        return 0.0;
+    }
+    ((20.0 * amplitude.log10() - MIN_DB) / -MIN_DB).clamp(0.0, 1.0)
+}
+
+// rising values are shown immediately, falling values fade out
+fn smooth(previous: f32, value: f32, smoothing: f32) -> f32 {
+    if value >= previous {
+        value
+    } else {
+        previous * smoothing + value * (1.0 - smoothing)
+    }
+}
+
+// splits the frequency range logarithmically into bands, each band has at least one bin
+fn band_bins(config: &AnalyzerConfig) -> Vec<(usize, usize)> {
+    let bin_frequency = config.sample_rate as f32 / config.fft_size as f32;
+    let last_bin = config.fft_size / 2 - 1;
+    let ratio = config.max_frequency / config.min_frequency;
+
+    (0..config.bands)
+        .map(|band| {
+            let low = config.min_frequency * ratio.powf(band as f32 / config.bands as f32);
+            let high = config.min_frequency * ratio.powf((band + 1) as f32 / config.bands as f32);
+            let first = ((low / bin_frequency).round() as usize).clamp(1, last_bin);
+            let last = ((high / bin_frequency).round() as usize)
+                .saturating_sub(1)
+                .clamp(first, last_bin);
+            (first, last)
+        })
+        .collect()
+}
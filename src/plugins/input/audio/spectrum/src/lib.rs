@@ -0,0 +1,470 @@
+use std::ffi::CString;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, Error};
+use std::os::raw::{c_char, c_int, c_ushort};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+
+use serde::Deserialize;
+
+mod analyzer;
+mod visualization;
+
+use analyzer::{Analyzer, AnalyzerConfig};
+use visualization::{Renderer, Visualization};
+
+const NAME: &str = "AudioInputSpectrum";
+
+// path used for reading from the standard input
+const STDIN_PATH: &str = "-";
+
+// the beat detection compares the bass energy with the average of about one second
+const BEAT_HISTORY: Duration = Duration::from_secs(1);
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    Wav,
+    // signed 16 bit little endian samples, channels are interleaved
+    Pcm,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum VisualizationKind {
+    Vu,
+    Spectrum,
+    Pulse,
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    path: String,
+    #[serde(default = "default_format")]
+    format: Format,
+    #[serde(default = "default_sample_rate")]
+    sample_rate: u32,
+    #[serde(default = "default_channels")]
+    channels: u16,
+    #[serde(default)]
+    create_pipe: bool,
+    #[serde(default = "default_repeat")]
+    repeat: bool,
+    #[serde(default = "default_fps")]
+    fps: f32,
+    #[serde(default = "default_fft_size")]
+    fft_size: usize,
+    #[serde(default = "default_min_frequency")]
+    min_frequency: f32,
+    #[serde(default = "default_max_frequency")]
+    max_frequency: f32,
+    #[serde(default = "default_smoothing")]
+    smoothing: f32,
+    #[serde(default = "default_agc")]
+    agc: bool,
+    #[serde(default = "default_beat_sensitivity")]
+    beat_sensitivity: f32,
+    visualization: VisualizationKind,
+    leds: Option<usize>,
+    width: Option<usize>,
+    height: Option<usize>,
+    #[serde(default = "default_colors")]
+    colors: Vec<[u8; 3]>,
+}
+
+fn default_format() -> Format {
+    Format::Wav
+}
+
+fn default_sample_rate() -> u32 {
+    44100
+}
+
+fn default_channels() -> u16 {
+    2
+}
+
+fn default_repeat() -> bool {
+    true
+}
+
+fn default_fps() -> f32 {
+    30.0
+}
+
+fn default_fft_size() -> usize {
+    1024
+}
+
+fn default_min_frequency() -> f32 {
+    50.0
+}
+
+fn default_max_frequency() -> f32 {
+    16000.0
+}
+
+fn default_smoothing() -> f32 {
+    0.7
+}
+
+fn default_agc() -> bool {
+    true
+}
+
+fn default_beat_sensitivity() -> f32 {
+    1.5
+}
+
+fn default_colors() -> Vec<[u8; 3]> {
+    vec![[0, 255, 0], [255, 255, 0], [255, 0, 0]]
+}
+
+enum Source {
+    Wav(hound::WavReader<Box<dyn Read + Send>>),
+    Pcm(Box<dyn Read + Send>),
+}
+
+struct AudioSpectrumInput {
+    config: Config,
+    source: Option<Source>,
+    channels: usize,
+    // mono samples per frame
+    hop_size: usize,
+    // files are played in real time, pipes are paced by their writer
+    paced: bool,
+    analyzer: Option<Analyzer>,
+    renderer: Renderer,
+    finished: bool,
+    next_frame: Option<Instant>,
+}
+
+impl std::fmt::Display for AudioSpectrumInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl AudioSpectrumInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        if config.sample_rate == 0 || config.channels == 0 {
+            return plugin_err!("Invalid config: sample_rate and channels must not be 0");
+        }
+        if config.fps <= 0.0 {
+            return plugin_err!("Invalid config value 'fps': {}", config.fps);
+        }
+        if config.fft_size < 64 {
+            return plugin_err!(
+                "Invalid config value 'fft_size': {}. Minimum: 64",
+                config.fft_size
+            );
+        }
+        if config.min_frequency <= 0.0 || config.max_frequency <= config.min_frequency {
+            return plugin_err!(
+                "Invalid frequency range: {}-{}",
+                config.min_frequency,
+                config.max_frequency
+            );
+        }
+        if !(0.0..1.0).contains(&config.smoothing) {
+            return plugin_err!(
+                "Invalid config value 'smoothing': {}. Valid range: [0-1)",
+                config.smoothing
+            );
+        }
+        if config.beat_sensitivity <= 0.0 {
+            return plugin_err!(
+                "Invalid config value 'beat_sensitivity': {}",
+                config.beat_sensitivity
+            );
+        }
+        if config.colors.is_empty() {
+            return plugin_err!("Config value 'colors' must not be empty");
+        }
+        if config.create_pipe && config.path == STDIN_PATH {
+            return plugin_err!("Cannot create a named pipe for the standard input");
+        }
+
+        let visualization = match (
+            config.visualization,
+            config.leds,
+            config.width,
+            config.height,
+        ) {
+            (VisualizationKind::Vu, Some(leds), _, _) if leds > 0 => Visualization::Vu { leds },
+            (VisualizationKind::Pulse, Some(leds), _, _) if leds > 0 => {
+                Visualization::Pulse { leds }
+            }
+            (VisualizationKind::Spectrum, _, Some(width), Some(height))
+                if width > 0 && height > 0 =>
+            {
+                Visualization::Spectrum { width, height }
+            }
+            (VisualizationKind::Spectrum, _, _, _) => return plugin_err!(
+                "Config values 'width' and 'height' are required for the spectrum visualization"
+            ),
+            _ => {
+                return plugin_err!(
+                    "Config value 'leds' is required for the {:?} visualization",
+                    config.visualization
+                )
+            }
+        };
+        let colors = config
+            .colors
+            .iter()
+            .map(|color| RGB {
+                r: color[0],
+                g: color[1],
+                b: color[2],
+            })
+            .collect();
+
+        let plugin = AudioSpectrumInput {
+            renderer: Renderer::new(visualization, colors, config.smoothing),
+            config,
+            source: None,
+            channels: 0,
+            hop_size: 0,
+            paced: false,
+            analyzer: None,
+            finished: false,
+            next_frame: None,
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn open(&mut self) -> PluginResult<()> {
+        let (reader, paced): (Box<dyn Read + Send>, bool) = if self.config.path == STDIN_PATH {
+            (Box::new(BufReader::new(std::io::stdin())), false)
+        } else {
+            // opening a pipe for reading blocks until another side opens it for writing
+            let file = match File::open(&self.config.path) {
+                Ok(file) => file,
+                Err(err) => {
+                    return plugin_err!("Error opening file '{}': {}", self.config.path, err);
+                }
+            };
+            let paced = file.metadata().is_ok_and(|metadata| metadata.is_file());
+            (Box::new(BufReader::new(file)), paced)
+        };
+
+        let (source, sample_rate, channels) = match self.config.format {
+            Format::Wav => {
+                let reader = match hound::WavReader::new(reader) {
+                    Ok(reader) => reader,
+                    Err(err) => {
+                        return plugin_err!(
+                            "Error reading WAV file '{}': {}",
+                            self.config.path,
+                            err
+                        );
+                    }
+                };
+                let spec = reader.spec();
+                (Source::Wav(reader), spec.sample_rate, spec.channels)
+            }
+            Format::Pcm => (
+                Source::Pcm(reader),
+                self.config.sample_rate,
+                self.config.channels,
+            ),
+        };
+        if sample_rate == 0 || channels == 0 {
+            return plugin_err!("Invalid audio format in '{}'", self.config.path);
+        }
+
+        self.source = Some(source);
+        self.channels = channels as usize;
+        self.hop_size = ((sample_rate as f32 / self.config.fps).round() as usize).max(1);
+        self.paced = paced;
+        self.analyzer = Some(Analyzer::new(AnalyzerConfig {
+            sample_rate,
+            fft_size: self.config.fft_size,
+            bands: self.config.width.unwrap_or(1),
+            min_frequency: self.config.min_frequency,
+            max_frequency: self.config.max_frequency.min(sample_rate as f32 / 2.0),
+            smoothing: self.config.smoothing,
+            agc: self.config.agc,
+            beat_sensitivity: self.config.beat_sensitivity,
+            beat_history: ((BEAT_HISTORY.as_secs_f32() * self.config.fps).round() as usize).max(1),
+        }));
+        Ok(())
+    }
+
+    // reads the mono samples of one frame, returns None at the end of the stream
+    fn read_samples(&mut self) -> PluginResult<Option<Vec<f32>>> {
+        let count = self.hop_size * self.channels;
+        let samples: Vec<f32> = match self.source.as_mut().unwrap() {
+            Source::Wav(reader) => {
+                let spec = reader.spec();
+                let result: Result<Vec<f32>, hound::Error> = match spec.sample_format {
+                    hound::SampleFormat::Float => reader.samples::<f32>().take(count).collect(),
+                    hound::SampleFormat::Int => {
+                        let scale = (1u64 << (spec.bits_per_sample - 1)) as f32;
+                        reader
+                            .samples::<i32>()
+                            .take(count)
+                            .map(|sample| sample.map(|sample| sample as f32 / scale))
+                            .collect()
+                    }
+                };
+                match result {
+                    Ok(samples) => samples,
+                    Err(err) => {
+                        return plugin_err!(
+                            "Error reading WAV file '{}': {}",
+                            self.config.path,
+                            err
+                        )
+                    }
+                }
+            }
+            Source::Pcm(reader) => {
+                let mut data = vec![0u8; count * 2];
+                let mut size = 0;
+                while size < data.len() {
+                    match reader.read(&mut data[size..]) {
+                        Ok(0) => break,
+                        Ok(read) => size += read,
+                        Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                        Err(err) => {
+                            return plugin_err!(
+                                "Error reading file '{}': {}",
+                                self.config.path,
+                                err
+                            )
+                        }
+                    }
+                }
+                data[..size]
+                    .chunks_exact(2)
+                    .map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as f32 / 32768.0)
+                    .collect()
+            }
+        };
+
+        // an incomplete frame at the end of the stream is dropped
+        if samples.len() < count {
+            return Ok(None);
+        }
+        let mono = samples
+            .chunks(self.channels)
+            .map(|frame| frame.iter().sum::<f32>() / self.channels as f32)
+            .collect();
+        Ok(Some(mono))
+    }
+}
+
+impl PluginInputTrait for AudioSpectrumInput {
+    fn init(&mut self) -> PluginResult<()> {
+        if !self.config.create_pipe {
+            return Ok(());
+        }
+
+        let pipe_path = Path::new(&self.config.path);
+        if pipe_path.exists() {
+            if let Err(err) = std::fs::remove_file(pipe_path) {
+                return plugin_err!(
+                    "File '{}' already exists and could not be removed: {}",
+                    &self.config.path,
+                    err
+                );
+            }
+        }
+
+        let path: CString = CString::new(self.config.path.as_bytes()).unwrap();
+        let result = unsafe { mkfifo(path.as_ptr(), 0o644) };
+        if result != 0 {
+            let err = Error::last_os_error();
+            return plugin_err!(
+                "Failed to create named pipe '{}': {}",
+                &self.config.path,
+                err
+            );
+        }
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.finished {
+            return plugin_err!("End of stream '{}' reached", self.config.path);
+        }
+
+        // at the end of a file it starts over, a pipe waits for the next writer
+        let mut reopened = false;
+        let samples = loop {
+            if self.source.is_none() {
+                self.open()?;
+            }
+            match self.read_samples() {
+                Ok(Some(samples)) => break samples,
+                Ok(None) => {
+                    self.source = None;
+                    if reopened {
+                        return plugin_err!("'{}' contains no audio data", self.config.path);
+                    }
+                    if !self.config.repeat || self.config.path == STDIN_PATH {
+                        self.finished = true;
+                        return plugin_err!("End of stream '{}' reached", self.config.path);
+                    }
+                    reopened = true;
+                }
+                Err(err) => {
+                    // the stream is opened again with the next call
+                    self.source = None;
+                    return Err(err);
+                }
+            }
+        };
+
+        let analyzer = self.analyzer.as_mut().unwrap();
+        analyzer.push(&samples);
+        let analysis = analyzer.analyze();
+        let beat = analysis.beat;
+        let rgb = self.renderer.render(&analysis);
+
+        if self.paced {
+            let interval = Duration::from_secs_f32(1.0 / self.config.fps);
+            let now = Instant::now();
+            match self.next_frame {
+                Some(next_frame) if next_frame > now => {
+                    thread::sleep(next_frame - now);
+                    self.next_frame = Some(next_frame + interval);
+                }
+                _ => self.next_frame = Some(now + interval),
+            }
+        }
+
+        let pixels = rgb.len();
+        let result = match self.config.visualization {
+            VisualizationKind::Spectrum => plugin_data!(rgb, {
+                "width" => self.config.width.unwrap(),
+                "height" => self.config.height.unwrap(),
+                "beat" => beat,
+            }),
+            _ => plugin_data!(rgb, {
+                "pixels" => pixels,
+                "beat" => beat,
+            }),
+        };
+        Ok(result)
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    AudioSpectrumInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
+
+extern "C" {
+    pub fn mkfifo(pathname: *const c_char, mode: c_ushort) -> c_int;
+}
@@ -0,0 +1,111 @@
+use lightoros_plugin_base::RGB;
+
+use crate::analyzer::Analysis;
+
+const BLACK: RGB = RGB { r: 0, g: 0, b: 0 };
+
+pub enum Visualization {
+    /// LED strip filled according to the overall level
+    Vu { leds: usize },
+    /// one bar per band on a matrix, the first row is the top one
+    Spectrum { width: usize, height: usize },
+    /// all LEDs flash on each beat and fade out, every beat switches to the next color
+    Pulse { leds: usize },
+}
+
+pub struct Renderer {
+    visualization: Visualization,
+    colors: Vec<RGB>,
+    fading: f32,
+    color_index: usize,
+    brightness: f32,
+}
+
+impl Renderer {
+    pub fn new(visualization: Visualization, colors: Vec<RGB>, fading: f32) -> Renderer {
+        Renderer {
+            visualization,
+            colors,
+            fading,
+            color_index: 0,
+            brightness: 0.0,
+        }
+    }
+
+    pub fn render(&mut self, analysis: &Analysis) -> Vec<RGB> {
+        match self.visualization {
+            Visualization::Vu { leds } => {
+                let lit = (analysis.level * leds as f32).round() as usize;
+                (0..leds)
+                    .map(|led| {
+                        if led < lit {
+                            gradient(&self.colors, position(led, leds))
+                        } else {
+                            BLACK
+                        }
+                    })
+                    .collect()
+            }
+            Visualization::Spectrum { width, height } => {
+                let bars: Vec<usize> = analysis
+                    .bands
+                    .iter()
+                    .map(|level| (level * height as f32).round() as usize)
+                    .collect();
+                let mut rgb = Vec::with_capacity(width * height);
+                for row in 0..height {
+                    // rows are counted from the bottom for the bars and the colors
+                    let level = height - 1 - row;
+                    let color = gradient(&self.colors, position(level, height));
+                    rgb.extend(
+                        bars.iter()
+                            .map(|bar| if level < *bar { color } else { BLACK }),
+                    );
+                }
+                rgb
+            }
+            Visualization::Pulse { leds } => {
+                if analysis.beat {
+                    self.color_index = (self.color_index + 1) % self.colors.len();
+                    self.brightness = 1.0;
+                } else {
+                    self.brightness *= self.fading;
+                }
+                let color = self.colors[self.color_index];
+                let dim = |value: u8| (value as f32 * self.brightness).round() as u8;
+                let color = RGB {
+                    r: dim(color.r),
+                    g: dim(color.g),
+                    b: dim(color.b),
+                };
+                vec![color; leds]
+            }
+        }
+    }
+}
+
+// position of an element in the range 0.0-1.0
+fn position(index: usize, count: usize) -> f32 {
+    if count > 1 {
+        index as f32 / (count - 1) as f32
+    } else {
+        0.0
+    }
+}
+
+// color at the position in the range 0.0-1.0 of a gradient through all colors
+fn gradient(colors: &[RGB], position: f32) -> RGB {
+    if colors.len() == 1 {
+        return colors[0];
+    }
+    let scaled = position * (colors.len() - 1) as f32;
+    let index = (scaled as usize).min(colors.len() - 2);
+    let fraction = scaled - index as f32;
+    let (from, to) = (colors[index], colors[index + 1]);
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * fraction).round() as u8;
+    RGB {
+        r: mix(from.r, to.r),
+        g: mix(from.g, to.g),
+        b: mix(from.b, to.b),
+    }
+}
@@ -0,0 +1,286 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+const SAMPLE_RATE: u32 = 44100;
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn create_plugin(config: serde_json::Value) -> Box<dyn PluginInputTrait> {
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    plugin
+}
+
+fn temp_path(name: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(name);
+    path.to_str().unwrap().to_owned()
+}
+
+// sine wave with the given frequency and amplitude for each 10ms segment
+fn write_wav(name: &str, segments: &[(f32, f32)]) -> String {
+    let path = temp_path(name);
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+    let segment_size = SAMPLE_RATE as usize / 100;
+    for (index, (frequency, amplitude)) in segments.iter().enumerate() {
+        for i in 0..segment_size {
+            let t = (index * segment_size + i) as f32 / SAMPLE_RATE as f32;
+            let value = amplitude * (2.0 * std::f32::consts::PI * frequency * t).sin();
+            writer
+                .write_sample((value * i16::MAX as f32) as i16)
+                .unwrap();
+        }
+    }
+    writer.finalize().unwrap();
+    path
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "AudioInputSpectrum");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_audio_spectrum");
+}
+
+#[test]
+fn test_create() {
+    assert!(call_create(&json!({"path": "-", "visualization": "vu", "leds": 10})).is_ok());
+    assert!(call_create(&json!({
+        "path": "/tmp/audio",
+        "format": "pcm",
+        "sample_rate": 48000,
+        "channels": 1,
+        "create_pipe": true,
+        "repeat": false,
+        "fps": 60,
+        "fft_size": 2048,
+        "min_frequency": 30,
+        "max_frequency": 10000,
+        "smoothing": 0.5,
+        "agc": false,
+        "beat_sensitivity": 1.3,
+        "visualization": "spectrum",
+        "width": 16,
+        "height": 8,
+        "colors": [[0, 0, 255], [255, 0, 255]]
+    }))
+    .is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    assert!(call_create(&json!({})).is_err());
+    assert!(call_create(&json!({"path": "-"})).is_err());
+    assert!(call_create(&json!({"path": "-", "visualization": "vu"})).is_err());
+    assert!(call_create(&json!({"path": "-", "visualization": "spectrum", "leds": 10})).is_err());
+    assert!(call_create(&json!({"path": "-", "visualization": "bars", "leds": 10})).is_err());
+    assert!(
+        call_create(&json!({"path": "-", "visualization": "pulse", "leds": 10, "colors": []}))
+            .is_err()
+    );
+    assert!(call_create(
+        &json!({"path": "-", "visualization": "vu", "leds": 10, "smoothing": 1.0})
+    )
+    .is_err());
+    assert!(
+        call_create(&json!({"path": "-", "visualization": "vu", "leds": 10, "fft_size": 16}))
+            .is_err()
+    );
+    assert!(call_create(&json!({
+        "path": "-",
+        "visualization": "vu",
+        "leds": 10,
+        "min_frequency": 1000,
+        "max_frequency": 100
+    }))
+    .is_err());
+}
+
+#[test]
+fn test_spectrum() {
+    let path = write_wav(
+        "lightoros_input_audio_spectrum_sine.wav",
+        &[(1000.0, 0.5); 10],
+    );
+    let mut plugin = create_plugin(json!({
+        "path": path,
+        "fps": 100,
+        "agc": false,
+        "visualization": "spectrum",
+        "width": 8,
+        "height": 4,
+        "colors": [[0, 255, 0], [255, 0, 0]]
+    }));
+
+    let mut data = plugin.get().unwrap();
+    for _ in 0..4 {
+        data = plugin.get().unwrap();
+    }
+    assert_eq!(data.meta.get("width").unwrap(), "8");
+    assert_eq!(data.meta.get("height").unwrap(), "4");
+    assert_eq!(data.rgb.len(), 32);
+
+    // only the band containing 1kHz reaches the top row
+    for column in 0..8 {
+        if column == 4 {
+            assert_rgb(&data.rgb[column], 255, 0, 0);
+        } else {
+            assert_rgb(&data.rgb[column], 0, 0, 0);
+        }
+    }
+    // the bottom row has the first color
+    assert_rgb(&data.rgb[24 + 4], 0, 255, 0);
+    // low and high bands stay dark
+    for row in 0..4 {
+        assert_rgb(&data.rgb[row * 8], 0, 0, 0);
+        assert_rgb(&data.rgb[row * 8 + 7], 0, 0, 0);
+    }
+}
+
+#[test]
+fn test_vu_meter() {
+    let path = write_wav(
+        "lightoros_input_audio_spectrum_vu.wav",
+        &[
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (440.0, 1.0),
+            (440.0, 1.0),
+            (440.0, 1.0),
+        ],
+    );
+    let mut plugin = create_plugin(json!({
+        "path": path,
+        "fps": 100,
+        "fft_size": 256,
+        "agc": false,
+        "visualization": "vu",
+        "leds": 10
+    }));
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.meta.get("pixels").unwrap(), "10");
+    assert!(data
+        .rgb
+        .iter()
+        .all(|rgb| (rgb.r, rgb.g, rgb.b) == (0, 0, 0)));
+
+    plugin.get().unwrap();
+    plugin.get().unwrap();
+    plugin.get().unwrap();
+    let data = plugin.get().unwrap();
+    let lit = data
+        .rgb
+        .iter()
+        .filter(|rgb| (rgb.r, rgb.g, rgb.b) != (0, 0, 0))
+        .count();
+    assert!(lit >= 9);
+    // gradient from green over yellow to red
+    assert_rgb(&data.rgb[0], 0, 255, 0);
+    assert_rgb(&data.rgb[8], 255, 57, 0);
+}
+
+#[test]
+fn test_beat_pulse() {
+    let silence = (0.0, 0.0);
+    let bass = (60.0, 0.8);
+    let mut segments = vec![silence; 30];
+    segments.extend_from_slice(&[bass; 10]);
+    segments.extend_from_slice(&[silence; 30]);
+    segments.extend_from_slice(&[bass; 10]);
+    segments.extend_from_slice(&[silence; 20]);
+    let path = write_wav("lightoros_input_audio_spectrum_beat.wav", &segments);
+    let mut plugin = create_plugin(json!({
+        "path": path,
+        "repeat": false,
+        "fps": 100,
+        "visualization": "pulse",
+        "leds": 3,
+        "colors": [[255, 0, 0], [0, 255, 0], [0, 0, 255]]
+    }));
+
+    let mut beats = Vec::new();
+    let mut colors = Vec::new();
+    for frame in 0..100 {
+        let data = plugin.get().unwrap();
+        if data.meta.get("beat").unwrap() == "true" {
+            beats.push(frame);
+            colors.push(data.rgb[0]);
+        }
+    }
+    assert!(plugin.get().is_err());
+
+    assert_eq!(beats.len(), 2);
+    assert!(beats[0] >= 29 && beats[0] < 40);
+    assert!(beats[1] >= 69 && beats[1] < 80);
+    // every beat switches to the next color
+    assert_rgb(&colors[0], 0, 255, 0);
+    assert_rgb(&colors[1], 0, 0, 255);
+}
+
+#[test]
+fn test_read_pcm() {
+    let path = temp_path("lightoros_input_audio_spectrum.pcm");
+    // 2 frames of stereo samples at 20 fps and 8kHz
+    let frame: Vec<u8> = (0..400)
+        .flat_map(|_| vec![0x00, 0x40, 0x00, 0xC0])
+        .collect();
+    let mut data = frame.clone();
+    data.extend_from_slice(&frame);
+    data.extend_from_slice(&[0, 0]);
+    std::fs::write(&path, data).unwrap();
+
+    let mut plugin = create_plugin(json!({
+        "path": path,
+        "format": "pcm",
+        "sample_rate": 8000,
+        "channels": 2,
+        "fps": 20,
+        "fft_size": 64,
+        "visualization": "vu",
+        "leds": 4
+    }));
+
+    let start = Instant::now();
+    for _ in 0..4 {
+        let data = plugin.get().unwrap();
+        // the channels cancel each other out
+        assert!(data
+            .rgb
+            .iter()
+            .all(|rgb| (rgb.r, rgb.g, rgb.b) == (0, 0, 0)));
+    }
+    // the file is played in real time and starts over at its end
+    assert!(start.elapsed() >= Duration::from_millis(145));
+}
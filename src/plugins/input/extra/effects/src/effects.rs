@@ -0,0 +1,436 @@
+use std::f32::consts::PI;
+
+use lightoros_plugin_base::RGB;
+
+use crate::palette::{scale, Palette, Rng};
+
+pub const EFFECTS: [&str; 9] = [
+    "rainbow",
+    "color_wipe",
+    "breathing",
+    "fire",
+    "plasma",
+    "twinkle",
+    "meteor",
+    "knight_rider",
+    "noise",
+];
+
+/// Pixels are stored row by row starting with the top row, a strip has a height of 1
+pub trait Effect: Send {
+    /// Renders the frame for the effect time in seconds, which already includes the speed.
+    /// `delta` is the effect time passed since the previous frame.
+    fn render(&mut self, time: f32, delta: f32, palette: &Palette, pixels: &mut [RGB]);
+}
+
+/// Creates the effect with the given name, None for unknown names
+pub fn create(name: &str, width: usize, height: usize, seed: u32) -> Option<Box<dyn Effect>> {
+    let effect: Box<dyn Effect> = match name {
+        "rainbow" => Box::new(Rainbow { width, height }),
+        "color_wipe" => Box::new(ColorWipe),
+        "breathing" => Box::new(Breathing),
+        "fire" => Box::new(Fire::new(width, height, seed)),
+        "plasma" => Box::new(Plasma { width, height }),
+        "twinkle" => Box::new(Twinkle {
+            buffer: FadeBuffer::new(width * height),
+            rng: Rng::new(seed),
+            pending: 0.0,
+        }),
+        "meteor" => Box::new(Meteor {
+            buffer: FadeBuffer::new(width * height),
+            rng: Rng::new(seed),
+        }),
+        "knight_rider" => Box::new(KnightRider {
+            width,
+            buffer: FadeBuffer::new(width * height),
+        }),
+        "noise" => Box::new(Noise::new(width, seed)),
+        _ => return None,
+    };
+    Some(effect)
+}
+
+/// Palette used if none is configured
+pub fn default_palette(name: &str) -> Vec<RGB> {
+    let colors: &[[u8; 3]] = match name {
+        "color_wipe" => &[[255, 0, 0], [0, 255, 0], [0, 0, 255]],
+        "breathing" | "twinkle" | "meteor" => &[[255, 255, 255]],
+        "fire" => &[
+            [0, 0, 0],
+            [160, 0, 0],
+            [255, 80, 0],
+            [255, 200, 0],
+            [255, 255, 255],
+        ],
+        "knight_rider" => &[[255, 0, 0]],
+        // rainbow, plasma and noise
+        _ => &[
+            [255, 0, 0],
+            [255, 255, 0],
+            [0, 255, 0],
+            [0, 255, 255],
+            [0, 0, 255],
+            [255, 0, 255],
+        ],
+    };
+    colors
+        .iter()
+        .map(|color| RGB {
+            r: color[0],
+            g: color[1],
+            b: color[2],
+        })
+        .collect()
+}
+
+// colors of fading effects with more precision than RGB
+struct FadeBuffer {
+    values: Vec<[f32; 3]>,
+}
+
+impl FadeBuffer {
+    fn new(size: usize) -> FadeBuffer {
+        FadeBuffer {
+            values: vec![[0.0; 3]; size],
+        }
+    }
+
+    fn fade(&mut self, index: usize, factor: f32) {
+        for channel in self.values[index].iter_mut() {
+            *channel *= factor;
+        }
+    }
+
+    fn set(&mut self, index: usize, rgb: RGB) {
+        self.values[index] = [rgb.r as f32, rgb.g as f32, rgb.b as f32];
+    }
+
+    fn write(&self, pixels: &mut [RGB]) {
+        for (pixel, value) in pixels.iter_mut().zip(self.values.iter()) {
+            *pixel = RGB {
+                r: value[0].round() as u8,
+                g: value[1].round() as u8,
+                b: value[2].round() as u8,
+            };
+        }
+    }
+}
+
+// fading factor for the effect time, `per_second` is the part left after one second
+fn fading(per_second: f32, delta: f32) -> f32 {
+    per_second.powf(delta)
+}
+
+// palette moving along the strip or diagonally over the matrix
+struct Rainbow {
+    width: usize,
+    height: usize,
+}
+
+impl Effect for Rainbow {
+    fn render(&mut self, time: f32, _delta: f32, palette: &Palette, pixels: &mut [RGB]) {
+        let size = (self.width + self.height - 1) as f32;
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            let (x, y) = (index % self.width, index / self.width);
+            *pixel = palette.cyclic((x + y) as f32 / size - time * 0.2);
+        }
+    }
+}
+
+// fills the pixels one after another with the next palette color
+struct ColorWipe;
+
+impl Effect for ColorWipe {
+    fn render(&mut self, time: f32, _delta: f32, palette: &Palette, pixels: &mut [RGB]) {
+        let progress = time * 0.5;
+        let wipe = progress.floor() as usize;
+        let filled = ((progress - progress.floor()) * pixels.len() as f32) as usize;
+        let (color, previous) = (palette.get(wipe + 1), palette.get(wipe));
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = if index < filled { color } else { previous };
+        }
+    }
+}
+
+// all pixels fade in and out, every breath uses the next palette color
+struct Breathing;
+
+impl Effect for Breathing {
+    fn render(&mut self, time: f32, _delta: f32, palette: &Palette, pixels: &mut [RGB]) {
+        let breath = time / 4.0;
+        let level = (1.0 - (2.0 * PI * breath).cos()) / 2.0;
+        let color = scale(palette.get(breath.floor() as usize), level);
+        for pixel in pixels.iter_mut() {
+            *pixel = color;
+        }
+    }
+}
+
+// Fire2012 simulation, flames rise along a strip or up each column of a matrix
+struct Fire {
+    // pixel indexes of each flame starting at its base
+    flames: Vec<Vec<usize>>,
+    heat: Vec<Vec<u8>>,
+    rng: Rng,
+    steps: f32,
+}
+
+// simulation steps per second of effect time
+const FIRE_STEPS: f32 = 60.0;
+const FIRE_COOLING: u32 = 55;
+const FIRE_SPARKING: u32 = 120;
+
+impl Fire {
+    fn new(width: usize, height: usize, seed: u32) -> Fire {
+        let flames: Vec<Vec<usize>> = if height == 1 {
+            vec![(0..width).collect()]
+        } else {
+            (0..width)
+                .map(|x| (0..height).rev().map(|y| y * width + x).collect())
+                .collect()
+        };
+        let heat = flames.iter().map(|flame| vec![0; flame.len()]).collect();
+        Fire {
+            flames,
+            heat,
+            rng: Rng::new(seed),
+            steps: 0.0,
+        }
+    }
+
+    fn step(&mut self) {
+        for heat in self.heat.iter_mut() {
+            let size = heat.len();
+            let cooling = FIRE_COOLING * 10 / size as u32 + 2;
+            for cell in heat.iter_mut() {
+                *cell = cell.saturating_sub(self.rng.below(cooling).min(255) as u8);
+            }
+            // heat drifts up and diffuses
+            for k in (2..size).rev() {
+                heat[k] = ((heat[k - 1] as u16 + 2 * heat[k - 2] as u16) / 3) as u8;
+            }
+            if self.rng.below(255) < FIRE_SPARKING {
+                let spark = self.rng.below(size.min(7) as u32) as usize;
+                heat[spark] = heat[spark].saturating_add(160 + self.rng.below(95) as u8);
+            }
+        }
+    }
+}
+
+impl Effect for Fire {
+    fn render(&mut self, _time: f32, delta: f32, palette: &Palette, pixels: &mut [RGB]) {
+        self.steps += delta * FIRE_STEPS;
+        while self.steps >= 1.0 {
+            self.step();
+            self.steps -= 1.0;
+        }
+        for (flame, heat) in self.flames.iter().zip(self.heat.iter()) {
+            for (index, cell) in flame.iter().zip(heat.iter()) {
+                pixels[*index] = palette.gradient(*cell as f32 / 255.0);
+            }
+        }
+    }
+}
+
+// overlapping sine waves
+struct Plasma {
+    width: usize,
+    height: usize,
+}
+
+impl Effect for Plasma {
+    fn render(&mut self, time: f32, _delta: f32, palette: &Palette, pixels: &mut [RGB]) {
+        let center_x = self.width as f32 / 2.0;
+        let center_y = self.height as f32 / 2.0;
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            let x = (index % self.width) as f32;
+            let y = (index / self.width) as f32;
+            let distance = ((x - center_x).powi(2) + (y - center_y).powi(2)).sqrt();
+            let value = (x * 0.5 + time).sin()
+                + ((y * 0.5 + time) / 2.0).sin()
+                + ((x + y) * 0.25 + time).sin()
+                + (distance * 0.5 - time).sin();
+            *pixel = palette.cyclic((value + 4.0) / 8.0);
+        }
+    }
+}
+
+// random pixels light up in random palette colors and fade out
+struct Twinkle {
+    buffer: FadeBuffer,
+    rng: Rng,
+    // pixels to light up, fractions are kept for the next frames
+    pending: f32,
+}
+
+impl Effect for Twinkle {
+    fn render(&mut self, _time: f32, delta: f32, palette: &Palette, pixels: &mut [RGB]) {
+        let factor = fading(0.1, delta);
+        for index in 0..pixels.len() {
+            self.buffer.fade(index, factor);
+        }
+        // every pixel lights up every two seconds on average
+        self.pending += delta * pixels.len() as f32 / 2.0;
+        while self.pending >= 1.0 {
+            let index = self.rng.below(pixels.len() as u32) as usize;
+            let color = palette.get(self.rng.next_u32() as usize);
+            self.buffer.set(index, color);
+            self.pending -= 1.0;
+        }
+        self.buffer.write(pixels);
+    }
+}
+
+// bright head running along the pixels with a randomly decaying tail
+struct Meteor {
+    buffer: FadeBuffer,
+    rng: Rng,
+}
+
+impl Effect for Meteor {
+    fn render(&mut self, time: f32, delta: f32, palette: &Palette, pixels: &mut [RGB]) {
+        let size = pixels.len();
+        // the head leaves the pixels for the length of the tail before starting over
+        let run = time * 0.5;
+        let position = ((run - run.floor()) * size as f32 * 1.5) as usize;
+        let factor = fading(0.01, delta);
+        for index in 0..size {
+            if self.rng.next_f32() < 0.5 {
+                self.buffer.fade(index, factor);
+            }
+        }
+        if position < size {
+            self.buffer.set(position, palette.get(run.floor() as usize));
+        }
+        self.buffer.write(pixels);
+    }
+}
+
+// scanner bouncing from left to right with a fading trail
+struct KnightRider {
+    width: usize,
+    buffer: FadeBuffer,
+}
+
+impl Effect for KnightRider {
+    fn render(&mut self, time: f32, delta: f32, palette: &Palette, pixels: &mut [RGB]) {
+        let phase = time * 0.5 - (time * 0.5).floor();
+        let bounce = if phase < 0.5 {
+            phase * 2.0
+        } else {
+            2.0 - phase * 2.0
+        };
+        let position = (bounce * (self.width - 1) as f32).round() as usize;
+        let factor = fading(0.001, delta);
+        for index in 0..pixels.len() {
+            self.buffer.fade(index, factor);
+            if index % self.width == position {
+                self.buffer.set(index, palette.get(0));
+            }
+        }
+        self.buffer.write(pixels);
+    }
+}
+
+// Perlin noise slowly moving through the palette
+struct Noise {
+    width: usize,
+    permutation: Vec<usize>,
+}
+
+impl Noise {
+    fn new(width: usize, seed: u32) -> Noise {
+        let mut rng = Rng::new(seed);
+        let mut permutation: Vec<usize> = (0..256).collect();
+        for i in (1..256).rev() {
+            permutation.swap(i, rng.below(i as u32 + 1) as usize);
+        }
+        // doubled to avoid wrapping the indexes
+        let doubled = permutation
+            .iter()
+            .chain(permutation.iter())
+            .cloned()
+            .collect();
+        Noise {
+            width,
+            permutation: doubled,
+        }
+    }
+
+    // improved Perlin noise in the range -1.0-1.0
+    fn noise(&self, x: f32, y: f32, z: f32) -> f32 {
+        let p = &self.permutation;
+        let (xi, yi, zi) = (
+            x.floor() as usize & 255,
+            y.floor() as usize & 255,
+            z.floor() as usize & 255,
+        );
+        let (x, y, z) = (x - x.floor(), y - y.floor(), z - z.floor());
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let a = p[xi] + yi;
+        let aa = p[a] + zi;
+        let ab = p[a + 1] + zi;
+        let b = p[xi + 1] + yi;
+        let ba = p[b] + zi;
+        let bb = p[b + 1] + zi;
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(p[ab], x, y - 1.0, z),
+                    grad(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p[aa + 1], x, y, z - 1.0),
+                    grad(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(p[ab + 1], x, y - 1.0, z - 1.0),
+                    grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+fn grad(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+impl Effect for Noise {
+    fn render(&mut self, time: f32, _delta: f32, palette: &Palette, pixels: &mut [RGB]) {
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            let x = (index % self.width) as f32 * 0.15;
+            let y = (index / self.width) as f32 * 0.15;
+            let value = self.noise(x, y, time * 0.3);
+            *pixel = palette.cyclic(value + time * 0.05);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+
+use serde::Deserialize;
+
+mod effects;
+mod palette;
+
+use effects::Effect;
+use palette::{scale, Palette};
+
+const NAME: &str = "EffectsExtraInput";
+
+// effects with random elements look the same after each start
+const SEED: u32 = 0x2545_f491;
+
+#[derive(Deserialize, Debug)]
+struct Size {
+    width: usize,
+    height: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    effect: String,
+    screen: Size,
+    #[serde(default = "default_fps")]
+    fps: f32,
+    #[serde(default = "default_speed")]
+    speed: f32,
+    #[serde(default = "default_brightness")]
+    brightness: u8,
+    palette: Option<Vec<[u8; 3]>>,
+}
+
+fn default_fps() -> f32 {
+    30.0
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+fn default_brightness() -> u8 {
+    100
+}
+
+struct EffectsExtraInput {
+    width: usize,
+    height: usize,
+    fps: f32,
+    effect_name: String,
+    effect: Box<dyn Effect>,
+    // configured palette, otherwise each effect uses its own one
+    palette: Option<Palette>,
+    speed: f32,
+    brightness: u8,
+    time: f32,
+    pixels: Vec<RGB>,
+    next_frame: Option<Instant>,
+}
+
+impl std::fmt::Display for EffectsExtraInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl EffectsExtraInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        let (width, height) = (config.screen.width, config.screen.height);
+        if width == 0 || height == 0 {
+            return plugin_err!("Invalid screen size: {}x{}", width, height);
+        }
+        if config.fps <= 0.0 {
+            return plugin_err!("Invalid config value 'fps': {}", config.fps);
+        }
+        let palette = match config.palette {
+            Some(colors) if colors.is_empty() => {
+                return plugin_err!("Config value 'palette' must not be empty")
+            }
+            Some(colors) => Some(Palette::new(colors.iter().map(to_rgb).collect())),
+            None => None,
+        };
+
+        let mut plugin = EffectsExtraInput {
+            width,
+            height,
+            fps: config.fps,
+            effect_name: String::new(),
+            effect: effects::create("rainbow", width, height, SEED).unwrap(),
+            palette,
+            speed: 1.0,
+            brightness: 100,
+            time: 0.0,
+            pixels: vec![RGB { r: 0, g: 0, b: 0 }; width * height],
+            next_frame: None,
+        };
+        plugin.set_effect(&config.effect)?;
+        plugin.set_speed(&config.speed.to_string())?;
+        plugin.set_brightness(&config.brightness.to_string())?;
+        Ok(Box::new(plugin))
+    }
+
+    fn set_effect(&mut self, name: &str) -> PluginResult<()> {
+        self.effect = match effects::create(name, self.width, self.height, SEED) {
+            Some(effect) => effect,
+            None => {
+                return plugin_err!(
+                    "Unknown effect '{}'. Available effects: {}",
+                    name,
+                    effects::EFFECTS.join(", ")
+                )
+            }
+        };
+        self.effect_name = name.to_string();
+        self.time = 0.0;
+        Ok(())
+    }
+
+    fn set_speed(&mut self, value: &str) -> PluginResult<()> {
+        match value.parse::<f32>() {
+            Ok(speed) if speed > 0.0 => self.speed = speed,
+            _ => return plugin_err!("Invalid speed: {}", value),
+        }
+        Ok(())
+    }
+
+    fn set_brightness(&mut self, value: &str) -> PluginResult<()> {
+        match value.parse::<u8>() {
+            Ok(brightness) if brightness <= 100 => self.brightness = brightness,
+            _ => return plugin_err!("Invalid brightness: {}. Valid range: [0-100]", value),
+        }
+        Ok(())
+    }
+
+    // colors are passed as a list of comma separated channel values
+    fn set_palette(&mut self, value: &str) -> PluginResult<()> {
+        let channels: Vec<u8> = match value.split(',').map(|c| c.trim().parse::<u8>()).collect() {
+            Ok(channels) => channels,
+            Err(_) => return plugin_err!("Invalid palette: {}", value),
+        };
+        if channels.is_empty() || !channels.len().is_multiple_of(3) {
+            return plugin_err!("Invalid palette: {}", value);
+        }
+        let colors = channels
+            .chunks(3)
+            .map(|rgb| RGB {
+                r: rgb[0],
+                g: rgb[1],
+                b: rgb[2],
+            })
+            .collect();
+        self.palette = Some(Palette::new(colors));
+        Ok(())
+    }
+}
+
+fn to_rgb(color: &[u8; 3]) -> RGB {
+    RGB {
+        r: color[0],
+        g: color[1],
+        b: color[2],
+    }
+}
+
+impl PluginInputTrait for EffectsExtraInput {
+    fn init(&mut self) -> PluginResult<()> {
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        // the previous frame is shown until the frame interval has passed
+        let interval = Duration::from_secs_f32(1.0 / self.fps);
+        let now = Instant::now();
+        match self.next_frame {
+            Some(next_frame) if next_frame > now => {
+                thread::sleep(next_frame - now);
+                self.next_frame = Some(next_frame + interval);
+            }
+            _ => self.next_frame = Some(now + interval),
+        }
+
+        let palette = match &self.palette {
+            Some(palette) => palette.clone(),
+            None => Palette::new(effects::default_palette(&self.effect_name)),
+        };
+        let delta = self.speed / self.fps;
+        self.effect
+            .render(self.time, delta, &palette, &mut self.pixels);
+        self.time += delta;
+
+        let brightness = self.brightness as f32 / 100.0;
+        let rgb: Vec<RGB> = self
+            .pixels
+            .iter()
+            .map(|pixel| scale(*pixel, brightness))
+            .collect();
+        Ok(plugin_data!(rgb, {
+            "width" => self.width,
+            "height" => self.height,
+        }))
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        match name {
+            "effect" => self.set_effect(value)?,
+            "speed" => self.set_speed(value)?,
+            "brightness" => self.set_brightness(value)?,
+            "palette" => self.set_palette(value)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    EffectsExtraInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
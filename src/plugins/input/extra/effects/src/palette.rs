@@ -0,0 +1,91 @@
+use lightoros_plugin_base::RGB;
+
+/// Colors used by the effects, at least one color
+#[derive(Clone)]
+pub struct Palette {
+    colors: Vec<RGB>,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<RGB>) -> Palette {
+        Palette { colors }
+    }
+
+    /// Color with the given index, the index wraps around
+    pub fn get(&self, index: usize) -> RGB {
+        self.colors[index % self.colors.len()]
+    }
+
+    /// Color at the position in the range 0.0-1.0 of a gradient through all colors
+    pub fn gradient(&self, position: f32) -> RGB {
+        if self.colors.len() == 1 {
+            return self.colors[0];
+        }
+        let scaled = position.clamp(0.0, 1.0) * (self.colors.len() - 1) as f32;
+        let index = (scaled as usize).min(self.colors.len() - 2);
+        mix(
+            self.colors[index],
+            self.colors[index + 1],
+            scaled - index as f32,
+        )
+    }
+
+    /// Like gradient, but the last color blends back into the first one and positions wrap around
+    pub fn cyclic(&self, position: f32) -> RGB {
+        let scaled = (position - position.floor()) * self.colors.len() as f32;
+        let index = (scaled as usize).min(self.colors.len() - 1);
+        mix(self.get(index), self.get(index + 1), scaled - index as f32)
+    }
+}
+
+fn mix(from: RGB, to: RGB, fraction: f32) -> RGB {
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * fraction).round() as u8;
+    RGB {
+        r: mix(from.r, to.r),
+        g: mix(from.g, to.g),
+        b: mix(from.b, to.b),
+    }
+}
+
+/// Scales the color by the factor in the range 0.0-1.0
+pub fn scale(rgb: RGB, factor: f32) -> RGB {
+    let scale = |value: u8| (value as f32 * factor.clamp(0.0, 1.0)).round() as u8;
+    RGB {
+        r: scale(rgb.r),
+        g: scale(rgb.g),
+        b: scale(rgb.b),
+    }
+}
+
+/// Small xorshift random number generator, the effects don't need more
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Rng {
+        Rng { state: seed.max(1) }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Random value in the range [0, max)
+    pub fn below(&mut self, max: u32) -> u32 {
+        if max == 0 {
+            return 0;
+        }
+        self.next_u32() % max
+    }
+
+    /// Random value in the range [0.0, 1.0)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
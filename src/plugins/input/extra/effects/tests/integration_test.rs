@@ -0,0 +1,199 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+const EFFECTS: [&str; 9] = [
+    "rainbow",
+    "color_wipe",
+    "breathing",
+    "fire",
+    "plasma",
+    "twinkle",
+    "meteor",
+    "knight_rider",
+    "noise",
+];
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn create_plugin(config: serde_json::Value) -> Box<dyn PluginInputTrait> {
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    plugin
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "EffectsExtraInput");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_extra_effects");
+}
+
+#[test]
+fn test_create() {
+    for effect in EFFECTS.iter() {
+        assert!(
+            call_create(&json!({"effect": effect, "screen": {"width": 10, "height": 1}})).is_ok()
+        );
+    }
+    assert!(call_create(&json!({
+        "effect": "plasma",
+        "screen": {"width": 16, "height": 8},
+        "fps": 60,
+        "speed": 0.5,
+        "brightness": 80,
+        "palette": [[255, 0, 0], [0, 0, 255]]
+    }))
+    .is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    let invalid_configs = vec![
+        json!({}),
+        json!({"screen": {"width": 10, "height": 1}}),
+        json!({"effect": "unknown", "screen": {"width": 10, "height": 1}}),
+        json!({"effect": "fire", "screen": {"width": 0, "height": 1}}),
+        json!({"effect": "fire", "screen": {"width": 10, "height": 1}, "fps": 0}),
+        json!({"effect": "fire", "screen": {"width": 10, "height": 1}, "speed": 0}),
+        json!({"effect": "fire", "screen": {"width": 10, "height": 1}, "brightness": 101}),
+        json!({"effect": "fire", "screen": {"width": 10, "height": 1}, "palette": []}),
+        json!({"effect": "fire", "screen": {"width": 10, "height": 1}, "palette": [[256, 0, 0]]}),
+    ];
+    for config in invalid_configs {
+        assert!(call_create(&config).is_err(), "{}", config);
+    }
+}
+
+#[test]
+fn test_all_effects_render() {
+    for effect in EFFECTS.iter() {
+        let mut plugin = create_plugin(json!({
+            "effect": effect,
+            "screen": {"width": 8, "height": 6},
+            "fps": 1000,
+        }));
+        let mut lit = false;
+        for _ in 0..200 {
+            let data = plugin.get().unwrap();
+            assert_eq!(data.rgb.len(), 48);
+            assert_eq!(data.meta.get("width").unwrap(), "8");
+            assert_eq!(data.meta.get("height").unwrap(), "6");
+            lit |= data
+                .rgb
+                .iter()
+                .any(|rgb| rgb.r > 0 || rgb.g > 0 || rgb.b > 0);
+        }
+        assert!(lit, "effect '{}' stays black", effect);
+    }
+}
+
+#[test]
+fn test_palette_and_brightness() {
+    let mut plugin = create_plugin(json!({
+        "effect": "color_wipe",
+        "screen": {"width": 4, "height": 1},
+        "fps": 1000,
+        "palette": [[0, 0, 255], [0, 255, 0]]
+    }));
+    let data = plugin.get().unwrap();
+    for rgb in data.rgb.iter() {
+        assert_rgb(rgb, 0, 0, 255);
+    }
+
+    let mut plugin = create_plugin(json!({
+        "effect": "color_wipe",
+        "screen": {"width": 4, "height": 1},
+        "fps": 1000,
+        "brightness": 50,
+    }));
+    let data = plugin.get().unwrap();
+    for rgb in data.rgb.iter() {
+        assert_rgb(rgb, 128, 0, 0);
+    }
+
+    let mut plugin = create_plugin(json!({
+        "effect": "rainbow",
+        "screen": {"width": 4, "height": 4},
+        "fps": 1000,
+        "brightness": 0,
+    }));
+    for _ in 0..10 {
+        let data = plugin.get().unwrap();
+        for rgb in data.rgb.iter() {
+            assert_rgb(rgb, 0, 0, 0);
+        }
+    }
+}
+
+#[test]
+fn test_set_parameter() {
+    let mut plugin = create_plugin(json!({
+        "effect": "rainbow",
+        "screen": {"width": 4, "height": 1},
+        "fps": 1000,
+    }));
+    assert!(plugin.get().is_ok());
+
+    assert!(plugin.set_parameter("effect", "color_wipe").unwrap());
+    let data = plugin.get().unwrap();
+    // the new effect starts with the default palette of the effect
+    assert_rgb(&data.rgb[0], 255, 0, 0);
+
+    assert!(plugin.set_parameter("palette", "0,255,0, 0,0,255").unwrap());
+    assert!(plugin.set_parameter("brightness", "100").unwrap());
+    assert!(plugin.set_parameter("speed", "2.5").unwrap());
+    assert!(plugin.set_parameter("effect", "breathing").unwrap());
+    assert!(plugin.set_parameter("effect", "color_wipe").unwrap());
+    let data = plugin.get().unwrap();
+    // a configured palette is kept for all effects
+    assert_rgb(&data.rgb[3], 0, 255, 0);
+
+    assert!(!plugin.set_parameter("unknown", "1").unwrap());
+    assert!(plugin.set_parameter("effect", "unknown").is_err());
+    assert!(plugin.set_parameter("speed", "0").is_err());
+    assert!(plugin.set_parameter("speed", "fast").is_err());
+    assert!(plugin.set_parameter("brightness", "101").is_err());
+    assert!(plugin.set_parameter("palette", "255,0").is_err());
+    assert!(plugin.set_parameter("palette", "").is_err());
+    assert!(plugin.get().is_ok());
+}
+
+#[test]
+fn test_fps() {
+    let mut plugin = create_plugin(json!({
+        "effect": "plasma",
+        "screen": {"width": 4, "height": 4},
+        "fps": 20,
+    }));
+    let start = Instant::now();
+    for _ in 0..6 {
+        plugin.get().unwrap();
+    }
+    // the first frame is returned immediately, then one frame every 50ms
+    assert!(start.elapsed() >= Duration::from_millis(250));
+    assert!(start.elapsed() < Duration::from_millis(1000));
+}
@@ -1,49 +1,35 @@
-use rlua;
-use rlua::{Context, FromLua, Function, Lua, Value};
-
 use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
 use lightoros_plugin_base::*;
 
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use std::sync::mpsc;
-use std::{thread, time};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
 
 use serde::Deserialize;
 
-const NAME: &str = "LuaExtraInput";
+use lightoros_plugin_lua::watch::{self, FileWatcher};
+use lightoros_plugin_lua::{parameter_value, runtime, Options};
 
-#[derive(Copy, Clone, Debug)]
-pub struct LRGB {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
-}
+mod script;
 
-impl<'lua> FromLua<'lua> for LRGB {
-    fn from_lua(value: Value<'lua>, _: Context<'lua>) -> rlua::Result<Self> {
-        if let Value::Table(table) = value {
-            Ok(LRGB {
-                r: table.raw_get(1).unwrap(),
-                g: table.raw_get(2).unwrap(),
-                b: table.raw_get(3).unwrap(),
-            })
-        } else {
-            Err(rlua::Error::FromLuaConversionError {
-                from: "LUA type",
-                to: "LRGB",
-                message: Some("expected table".to_string()),
-            })
-        }
-    }
-}
+use script::Script;
 
-#[derive(Deserialize, Debug)]
+const NAME: &str = "LuaExtraInput";
+
+// interval of the checks for changed scripts
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Deserialize, Debug, Clone)]
 struct Effect {
     name: String,
+    // time in ms to run the effect, 0 runs it until the effect is switched
+    #[serde(default)]
     duration: u64,
+    #[serde(default)]
+    args: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -56,15 +42,49 @@ struct Size {
 struct Config {
     source_folder: String,
     on_start_effect: Effect,
+    #[serde(default)]
+    playlist: Vec<Effect>,
     screen: Size,
+    #[serde(default = "default_sandbox")]
+    sandbox: bool,
+    // limits for the calculation of a frame, 0 disables a limit
+    #[serde(default = "default_max_frame_time")]
+    max_frame_time: u64,
+    #[serde(default)]
+    max_frame_instructions: u64,
+    // reload the running script when it or one of its modules has changed
+    #[serde(default = "default_watch")]
+    watch: bool,
+}
+
+fn default_sandbox() -> bool {
+    true
+}
+
+fn default_max_frame_time() -> u64 {
+    1000
+}
+
+fn default_watch() -> bool {
+    true
 }
 
 struct LuaExtraInput {
-    rx: mpsc::Receiver<Vec<RGB>>,
-    tx: mpsc::Sender<Vec<RGB>>,
     config: Config,
-    current_effect_index: u32,
-    logger: Logger,
+    // the start effect followed by the playlist
+    effects: Vec<Effect>,
+    current_effect_index: usize,
+    current_effect: Effect,
+    script: Option<Script>,
+    // the current script has set the screen at least once
+    script_active: bool,
+    deadline: Option<Instant>,
+    // effect switch and arguments requested by set_parameter(), applied on the next get()
+    pending_effect: Option<Effect>,
+    pending_args: serde_json::Map<String, serde_json::Value>,
+    // files of the running effect, only set if watching is enabled
+    watcher: Option<FileWatcher>,
+    next_check: Instant,
 }
 
 impl std::fmt::Display for LuaExtraInput {
@@ -78,214 +98,269 @@ impl std::fmt::Display for LuaExtraInput {
 */
 impl LuaExtraInput {
     fn create(config: &serde_json::Value) -> CreateInputPluginResult {
-        let config = plugin_config_or_return!(config.clone());
+        let config: Config = plugin_config_or_return!(config.clone());
 
-        // LUA thread is sending data to the plugin using a channel
-        let (tx, rx) = mpsc::channel();
+        let mut effects = vec![config.on_start_effect.clone()];
+        effects.extend(config.playlist.iter().cloned());
 
         let plugin = LuaExtraInput {
-            tx: tx,
-            rx: rx,
-            config: config,
+            current_effect: config.on_start_effect.clone(),
+            config,
+            effects,
             current_effect_index: 0,
-            logger: Logger::new(NAME.to_string()),
+            script: None,
+            script_active: false,
+            deadline: None,
+            pending_effect: None,
+            pending_args: serde_json::Map::new(),
+            watcher: None,
+            next_check: Instant::now(),
         };
 
         Ok(Box::new(plugin))
     }
-}
-
-impl LuaExtraInput {
-    fn start_script(&mut self) -> () {
-        let thread_tx = self.tx.clone();
-
-        let screen_width = self.config.screen.width;
-        let screen_height = self.config.screen.height;
 
+    fn script_path(&self, name: &str) -> PathBuf {
         let mut path = PathBuf::new();
         path.push(&self.config.source_folder);
-        path.push([&self.config.on_start_effect.name, "lua"].join("."));
-        let mut file = match File::open(&path) {
-            Ok(file) => file,
-            Err(error) => {
-                self.logger.error(&format!(
-                    "Error opening file '{}': {}",
-                    &path.display(),
-                    error
-                ));
-                return;
-            }
+        path.push([name, "lua"].join("."));
+        path
+    }
+
+    fn start_effect(&mut self, effect: Effect) -> PluginResult<()> {
+        // the running effect is kept if the new one can't be read
+        let path = self.script_path(&effect.name);
+        let signature = watch::signature(&path);
+        let source = read_file(&path)?;
+        self.stop_script();
+        let options = Options {
+            folder: PathBuf::from(&self.config.source_folder),
+            sandbox: self.config.sandbox,
+            max_frame_time: self.config.max_frame_time,
+            max_frame_instructions: self.config.max_frame_instructions,
+        };
+        self.script = Some(Script::start(
+            &effect.name,
+            source,
+            effect.args.clone(),
+            &options,
+            (self.config.screen.width, self.config.screen.height),
+        ));
+        self.script_active = false;
+        self.deadline = match effect.duration {
+            0 => None,
+            duration => Some(Instant::now() + Duration::from_millis(duration)),
         };
+        if self.config.watch {
+            let mut watcher = FileWatcher::new();
+            watcher.watch(path, signature);
+            self.watcher = Some(watcher);
+            self.next_check = Instant::now() + WATCH_INTERVAL;
+        }
+        self.current_effect = effect;
+        Ok(())
+    }
+
+    // starts the current effect again for the rest of its duration
+    fn restart_effect(&mut self) -> PluginResult<()> {
+        let deadline = self.deadline;
+        self.start_effect(self.current_effect.clone())?;
+        self.deadline = deadline;
+        Ok(())
+    }
 
-        let mut contents = String::new();
-        match file.read_to_string(&mut contents) {
-            Err(error) => {
-                self.logger.error(&format!(
-                    "Error reading file '{}': {}",
-                    &path.display(),
+    // restarts the effect if the script or one of its modules has changed
+    fn reload_changed(&mut self) -> PluginResult<()> {
+        let now = Instant::now();
+        let watcher = match &mut self.watcher {
+            Some(watcher) if now >= self.next_check => watcher,
+            _ => return Ok(()),
+        };
+        self.next_check = now + WATCH_INTERVAL;
+        if let Some(script) = &self.script {
+            for (module, signature) in script.modules() {
+                watcher.watch(module, signature);
+            }
+        }
+        let changed = watcher.changed();
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        // the running version is kept if a changed file can't be loaded
+        for path in changed {
+            let file_name = path
+                .strip_prefix(&self.config.source_folder)
+                .unwrap_or(&path)
+                .display()
+                .to_string();
+            let checked = read_file(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|source| runtime::check_syntax(&source, &file_name));
+            if let Err(error) = checked {
+                return plugin_err!(
+                    "Reload of effect '{}' failed: {}",
+                    self.current_effect.name,
                     error
-                ));
-                return;
+                );
             }
-            Ok(_) => {}
         }
+        self.restart_effect()
+    }
+
+    // continues with the next effect of the playlist, a single effect is restarted
+    fn next_effect(&mut self) -> PluginResult<()> {
+        self.current_effect_index = (self.current_effect_index + 1) % self.effects.len();
+        self.start_effect(self.effects[self.current_effect_index].clone())
+    }
+
+    fn stop_script(&mut self) {
+        if let Some(mut script) = self.script.take() {
+            script.stop();
+        }
+    }
 
-        let _t = thread::spawn(move || {
-            let lua = Lua::new();
-
-            lua.context(|lua_ctx| {
-                let globals = lua_ctx.globals();
-
-                let args = lua_ctx.create_table().unwrap();
-
-                let screen = lua_ctx.create_table().unwrap();
-                screen.set("width", screen_width).unwrap();
-                screen.set("height", screen_height).unwrap();
-                globals.set("screen", screen).unwrap();
-
-                let color = lua_ctx.create_table().unwrap();
-                let hsv2rgb = lua_ctx
-                    .create_function(|_, (hue, saturation, value): (i32, i32, i32)| {
-                        let v = value as u8;
-                        let mut red: u8 = v;
-                        let mut green: u8 = v;
-                        let mut blue: u8 = v;
-
-                        if saturation > 0 {
-                            let region = hue / 60;
-                            let remainder = (hue - (region * 60)) * 256 / 60;
-
-                            let p = ((value * (255 - saturation)) >> 8) as u8;
-                            let q = ((value * (255 - ((saturation * remainder) >> 8))) >> 8) as u8;
-                            let t = ((value * (255 - ((saturation * (255 - remainder)) >> 8))) >> 8) as u8;
-
-                            match region {
-                                0 => {
-                                    red = v;
-                                    green = t;
-                                    blue = p;
-                                }
-                                1 => {
-                                    red = q;
-                                    green = v;
-                                    blue = p;
-                                }
-                                2 => {
-                                    red = p;
-                                    green = v;
-                                    blue = t;
-                                }
-                                3 => {
-                                    red = p;
-                                    green = q;
-                                    blue = v;
-                                }
-                                4 => {
-                                    red = t;
-                                    green = p;
-                                    blue = v;
-                                }
-                                _ => {
-                                    red = v;
-                                    green = p;
-                                    blue = q;
-                                }
-                            }
-                        }
-
-                        Ok((
-                            rlua::Value::Integer(red as i64),
-                            rlua::Value::Integer(green as i64),
-                            rlua::Value::Integer(blue as i64),
-                        ))
-                    })
-                    .unwrap();
-                color.set("hsv2rgb", hsv2rgb).unwrap();
-                globals.set("color", color).unwrap();
-                globals.set("args", args).unwrap();
-
-                let core_functions: Vec<(&str, Function)> = vec![
-                    (
-                        "setScreen",
-                        lua_ctx
-                            .create_function(move |_: Context, screen: Vec<Vec<LRGB>>| {
-                                let width = screen.len();
-                                let height = screen[0].len();
-
-                                if width != screen_width || height != screen_height {
-                                    return Err(rlua::Error::RuntimeError(format!(
-                                        "Screen size does not match. Expected: {}x{}, got: {}x{}",
-                                        screen_width, screen_height, width, height
-                                    )));
-                                }
-
-                                let mut out: Vec<RGB> = Vec::with_capacity(width * height);
-                                for i in 0..height {
-                                    for j in 0..width {
-                                        let dot = screen[j][i];
-                                        out.push(RGB {
-                                            r: dot.r,
-                                            g: dot.g,
-                                            b: dot.b,
-                                        });
-                                    }
-                                }
-
-                                thread_tx.send(out).ok();
-                                Ok(())
-                            })
-                            .unwrap(),
-                    ),
-                    (
-                        "sleep",
-                        lua_ctx
-                            .create_function(|_, ms: u64| {
-                                let sleep_time = time::Duration::from_millis(ms);
-                                thread::sleep(sleep_time);
-                                Ok(())
-                            })
-                            .unwrap(),
-                    ),
-                    (
-                        "isStopRequested",
-                        lua_ctx
-                            .create_function(|_, ()| Ok(rlua::Value::Boolean(false)))
-                            .unwrap(),
-                    ),
-                ];
-                let api = lua_ctx.create_table_from(core_functions).unwrap();
-                globals.set("api", api).unwrap();
-
-                lua_ctx.load(&contents).exec().unwrap();
-            });
-        });
+    fn apply_pending(&mut self) -> PluginResult<()> {
+        if let Some(mut effect) = self.pending_effect.take() {
+            effect.args.append(&mut self.pending_args);
+            return self.start_effect(effect);
+        }
+        if self.pending_args.is_empty() {
+            return Ok(());
+        }
+        // changed arguments restart the current effect for the rest of its duration
+        let mut effect = self.current_effect.clone();
+        effect.args.append(&mut self.pending_args);
+        let deadline = self.deadline;
+        self.start_effect(effect)?;
+        self.deadline = deadline;
+        Ok(())
     }
 }
 
+fn read_file(path: &Path) -> PluginResult<String> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => {
+            return plugin_err!("Error opening file '{}': {}", path.display(), error);
+        }
+    };
+
+    let mut contents = String::new();
+    if let Err(error) = file.read_to_string(&mut contents) {
+        return plugin_err!("Error reading file '{}': {}", path.display(), error);
+    }
+    Ok(contents)
+}
+
 impl PluginInputTrait for LuaExtraInput {
     fn init(&mut self) -> PluginResult<()> {
-        self.start_script();
-        Ok(())
+        self.start_effect(self.effects[0].clone())
     }
 
     fn get(&mut self) -> PluginResult<TraitData> {
-        // wait on the channel until the LUA thread calls setScreen() which sends the data over the channel
-        let data_out: Vec<RGB> = self.rx.recv().unwrap();
-
-        let result = plugin_data!(data_out, {
-            "width" => self.config.screen.width,
-            "height" => self.config.screen.height,
-        });
-        Ok(result)
+        self.apply_pending()?;
+
+        loop {
+            self.reload_changed()?;
+
+            let script = match &self.script {
+                Some(script) => script,
+                None => return plugin_err!("No effect running"),
+            };
+            // wait on the channel until the LUA thread calls setScreen(), the effect duration is over
+            // or the files have to be checked for changes
+            let now = Instant::now();
+            let check = self.watcher.as_ref().map(|_| self.next_check);
+            let received = match (self.deadline, check) {
+                (Some(deadline), _) if deadline <= now => Err(RecvTimeoutError::Timeout),
+                (Some(deadline), Some(check)) => script
+                    .frames
+                    .recv_timeout(deadline.min(check).saturating_duration_since(now)),
+                (Some(until), None) | (None, Some(until)) => script
+                    .frames
+                    .recv_timeout(until.saturating_duration_since(now)),
+                (None, None) => script
+                    .frames
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match received {
+                Ok(data_out) => {
+                    self.script_active = true;
+                    let result = plugin_data!(data_out, {
+                        "width" => self.config.screen.width,
+                        "height" => self.config.screen.height,
+                    });
+                    return Ok(result);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if self
+                        .deadline
+                        .is_some_and(|deadline| deadline <= Instant::now())
+                    {
+                        self.next_effect()?;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    let name = self.current_effect.name.clone();
+                    let active = self.script_active;
+                    let error = self.script.as_mut().and_then(|script| script.finish());
+                    if let Some(error) = error {
+                        // a failed script is restarted for the rest of the effect duration
+                        self.restart_effect()?;
+                        return plugin_err!("Effect '{}' failed: {}", name, error);
+                    }
+                    // the script has ended on its own, a switched effect outside of the playlist is restarted
+                    if self.effects[self.current_effect_index].name == name {
+                        self.next_effect()?;
+                    } else {
+                        self.start_effect(self.current_effect.clone())?;
+                    }
+                    if !active {
+                        return plugin_err!("Effect '{}' ended without setting the screen", name);
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        if name == "effect" {
+            if !self.script_path(value).is_file() {
+                return plugin_err!("Unknown effect '{}'", value);
+            }
+            // effects of the playlist keep their configuration and the playlist continues after them
+            let effect = match self.effects.iter().position(|effect| effect.name == value) {
+                Some(index) => {
+                    self.current_effect_index = index;
+                    self.effects[index].clone()
+                }
+                None => Effect {
+                    name: value.to_string(),
+                    duration: 0,
+                    args: serde_json::Map::new(),
+                },
+            };
+            self.pending_effect = Some(effect);
+        } else {
+            // other parameters are passed as arguments to the script
+            self.pending_args
+                .insert(name.to_string(), parameter_value(value));
+        }
+        Ok(true)
+    }
+}
+
+impl Drop for LuaExtraInput {
+    fn drop(&mut self) {
+        self.stop_script();
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
     LuaExtraInput::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Input)
-}
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
@@ -0,0 +1,214 @@
+use rlua::{Context, FromLua, Function, Value};
+
+use lightoros_plugin_base::*;
+use lightoros_plugin_lua::runtime::{self, stopped_error, Modules};
+use lightoros_plugin_lua::watch::Signature;
+use lightoros_plugin_lua::{helpers, json_to_lua, Budget, Options, PixelBuffer, Runtime, LRGB};
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Effect script running in its own thread, since scripts are executed in a blocking way
+pub struct Script {
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<Option<String>>>,
+    // files of the modules loaded by the script
+    modules: Modules,
+    /// screens set by the script, disconnected when the script has ended
+    pub frames: mpsc::Receiver<Vec<RGB>>,
+}
+
+impl Script {
+    pub fn start(
+        name: &str,
+        source: String,
+        args: serde_json::Map<String, serde_json::Value>,
+        options: &Options,
+        screen: (usize, usize),
+    ) -> Script {
+        // a script waits in setScreen() until the previous screen is taken
+        let (tx, rx) = mpsc::sync_channel(1);
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let runtime = Runtime::new(options, stop.clone());
+        let modules = match &runtime {
+            Ok(runtime) => runtime.modules.clone(),
+            Err(_) => Modules::default(),
+        };
+
+        let thread_stop = stop.clone();
+        let chunk_name = format!("@{}.lua", name);
+        let handle = thread::spawn(move || {
+            let result = runtime.and_then(|runtime| {
+                let budget = runtime.budget.clone();
+                runtime.lua.context(|lua_ctx| {
+                    setup(lua_ctx, &args, screen, tx, budget, thread_stop.clone())?;
+                    lua_ctx.load(&source).set_name(&chunk_name)?.exec()
+                })
+            });
+            // scripts not checking api.isStopRequested() are stopped by an error in the API functions
+            match result {
+                Err(err) if !thread_stop.load(Ordering::SeqCst) => {
+                    Some(runtime::error_message(&err))
+                }
+                _ => None,
+            }
+        });
+
+        Script {
+            stop,
+            handle: Some(handle),
+            modules,
+            frames: rx,
+        }
+    }
+
+    /// Requests the script to stop and waits for the end of its thread
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // releases a script waiting in setScreen(), it can't send another screen after the stop request
+        while self.frames.try_recv().is_ok() {}
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+
+    /// Files of the modules the script has loaded so far
+    pub fn modules(&self) -> Vec<(PathBuf, Signature)> {
+        match self.modules.lock() {
+            Ok(modules) => modules.clone(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Waits for the end of a script which has ended on its own and returns its error
+    pub fn finish(&mut self) -> Option<String> {
+        match self.handle.take()?.join() {
+            Ok(error) => error,
+            Err(_) => Some("Script thread panicked".to_string()),
+        }
+    }
+}
+
+impl Drop for Script {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn setup(
+    lua_ctx: Context,
+    args: &serde_json::Map<String, serde_json::Value>,
+    (screen_width, screen_height): (usize, usize),
+    thread_tx: mpsc::SyncSender<Vec<RGB>>,
+    budget: Budget,
+    stop: Arc<AtomicBool>,
+) -> rlua::Result<()> {
+    let globals = lua_ctx.globals();
+
+    let lua_args = lua_ctx.create_table()?;
+    for (key, value) in args {
+        lua_args.set(key.as_str(), json_to_lua(lua_ctx, value)?)?;
+    }
+
+    let screen = lua_ctx.create_table()?;
+    screen.set("width", screen_width)?;
+    screen.set("height", screen_height)?;
+    globals.set("screen", screen)?;
+
+    globals.set("args", lua_args)?;
+
+    let screen_stop = stop.clone();
+    let screen_budget = budget.clone();
+    let sleep_stop = stop.clone();
+    let mut core_functions: Vec<(&str, Function)> = vec![
+        (
+            "setScreen",
+            lua_ctx.create_function(move |lua_ctx: Context, screen: Value| {
+                if screen_stop.load(Ordering::SeqCst) {
+                    return Err(stopped_error());
+                }
+
+                // pixel buffers are sent as they are, tables of columns are converted
+                let out = if let Value::UserData(buffer) = &screen {
+                    let buffer = buffer.borrow::<PixelBuffer>()?;
+                    if buffer.width != screen_width || buffer.height != screen_height {
+                        return Err(rlua::Error::RuntimeError(format!(
+                            "Screen size does not match. Expected: {}x{}, got: {}x{}",
+                            screen_width, screen_height, buffer.width, buffer.height
+                        )));
+                    }
+                    buffer.pixels.clone()
+                } else {
+                    let screen = Vec::<Vec<LRGB>>::from_lua(screen, lua_ctx)?;
+
+                    let width = screen.len();
+                    let height = screen.first().map(|column| column.len()).unwrap_or(0);
+
+                    if width != screen_width
+                        || screen.iter().any(|column| column.len() != screen_height)
+                    {
+                        return Err(rlua::Error::RuntimeError(format!(
+                            "Screen size does not match. Expected: {}x{}, got: {}x{}",
+                            screen_width, screen_height, width, height
+                        )));
+                    }
+
+                    (0..height)
+                        .flat_map(|y| screen.iter().map(move |column| column[y]))
+                        .map(|dot| RGB {
+                            r: dot.r,
+                            g: dot.g,
+                            b: dot.b,
+                        })
+                        .collect()
+                };
+
+                // the plugin dropped the receiver, nobody is interested in further screens
+                thread_tx.send(out).map_err(|_| stopped_error())?;
+                // the limits apply to the calculation of each frame
+                screen_budget.reset();
+                Ok(())
+            })?,
+        ),
+        (
+            "sleep",
+            lua_ctx.create_function(move |_, ms: u64| {
+                // sleep in short steps to react to stop requests
+                let end = Instant::now() + Duration::from_millis(ms);
+                while !sleep_stop.load(Ordering::SeqCst) {
+                    let now = Instant::now();
+                    if now >= end {
+                        break;
+                    }
+                    thread::sleep((end - now).min(Duration::from_millis(10)));
+                }
+                // waiting doesn't count as calculation time
+                budget.reset();
+                Ok(())
+            })?,
+        ),
+        (
+            "isStopRequested",
+            lua_ctx.create_function(move |_, ()| Ok(stop.load(Ordering::SeqCst)))?,
+        ),
+    ];
+    core_functions.push((
+        "createBuffer",
+        lua_ctx.create_function(move |_, (width, height): (Option<usize>, Option<usize>)| {
+            PixelBuffer::new(
+                width.unwrap_or(screen_width),
+                height.unwrap_or(screen_height),
+            )
+        })?,
+    ));
+    core_functions.extend(helpers::time_functions(lua_ctx)?);
+    let api = lua_ctx.create_table_from(core_functions)?;
+    globals.set("api", api)?;
+
+    Ok(())
+}
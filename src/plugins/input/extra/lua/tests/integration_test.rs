@@ -1,42 +1,169 @@
-use lightoros_plugin_base::*;
-use lightoros_plugin_base::input::CreateInputPluginResult;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
 use serde_json::json;
 use dlopen::symbor::Library;
 use once_cell::sync::Lazy;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
 
-fn load_lib() -> Library {
-    let lib_path: PathBuf = LIB_PATH.to_path_buf();
-    dlopen::symbor::Library::open(&lib_path).unwrap()
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
 }
 
-fn get_info() -> PluginInfo {
-    let lib = load_lib();
-    let info_func = unsafe { lib.symbol::<fn() -> PluginInfo>("info").unwrap() };
-    info_func()
-}
+// fills the screen with the color from the arguments until the effect is stopped
+const SOLID_SCRIPT: &str = "
+local color = args.color or {255, 0, 0}
+while not api.isStopRequested() do
+    local image = {}
+    for x = 1, screen.width do
+        image[x] = {}
+        for y = 1, screen.height do
+            image[x][y] = color
+        end
+    end
+    api.setScreen(image)
+    api.sleep(args.interval or 10)
+end
+";
+
+// draws on a 5x5 pixel buffer, one screen for each drawing function
+const DRAWING_SCRIPT: &str = "
+local white = {255, 255, 255}
+local buffer = api.createBuffer()
+assert(buffer:width() == 5 and buffer:height() == 5)
+assert(not pcall(api.createBuffer, 100000, 100000))
+assert(not pcall(api.createBuffer, 2^62, 4))
+
+buffer:fill({0, 0, 255})
+buffer:set(1, 1, {255, 0, 0})
+buffer:set(6, 1, white)
+assert(buffer:get(6, 1) == nil)
+assert(buffer:get(1, 1)[1] == 255)
+api.setScreen(buffer)
+
+buffer:fill({0, 0, 0})
+buffer:line(1, 1, 5, 5, white)
+api.setScreen(buffer)
+
+buffer:fill({0, 0, 0})
+buffer:rect(1, 1, 5, 5, white)
+api.setScreen(buffer)
+
+buffer:fill({0, 0, 0})
+buffer:circle(3, 3, 2, white)
+api.setScreen(buffer)
+
+local sprite = api.createBuffer(2, 2)
+sprite:fill({200, 100, 50})
+buffer:fill({0, 0, 0})
+buffer:blit(sprite, 4, 4)
+api.setScreen(buffer)
+
+buffer:shift(-3, 0)
+buffer:fade(0.5)
+api.setScreen(buffer)
+
+buffer:shift(-1, -1, true)
+api.setScreen(buffer)
+
+local max, min = math.maxinteger, math.mininteger
+buffer:line(min, min, max, max, white)
+buffer:circle(max, min, max, white, true)
+buffer:rect(max, max, max, max, white, true)
+buffer:rect(min, min, max, max, white)
+buffer:blit(sprite, max, min)
+buffer:shift(max, min)
+buffer:fill({0, 0, 0})
+buffer:rect(1, 1, 1e15, 1e15, white)
+buffer:line(5, 1, 5, 1e15, white)
+buffer:line(-1e9, -1e9, 1e9, 1e9, white)
+buffer:circle(3, 3, 1e9, white)
+buffer:shift(5 * (1 << 60), -5 * (1 << 60), true)
+api.setScreen(buffer)
+";
+
+// checks the helper functions and sets a green screen if all of them work
+const HELPERS_SCRIPT: &str = "
+local function same(a, b)
+    return a[1] == b[1] and a[2] == b[2] and a[3] == b[3]
+end
+
+assert(same({color.hsv2rgb(0, 255, 255)}, {255, 0, 0}))
+assert(same(color.hsl2rgb(120, 255, 127.5), {0, 255, 0}))
+assert(same(color.hsl2rgb(0, 0, 255), {255, 255, 255}))
+assert(same(color.blend({0, 0, 0}, {255, 100, 10}, 0.5), {128, 50, 5}))
+assert(same(color.palette({{0, 0, 0}, {255, 0, 0}, {0, 0, 255}}, 0.75), {128, 0, 128}))
+assert(same(color.palette({{255, 0, 0}, {0, 0, 255}}, 0.75, true), {128, 0, 128}))
+assert(same(color.gamma({255, 128, 0}, 2.0), {255, 64, 0}))
+
+assert(ease.linear(0.25) == 0.25)
+assert(ease.inQuad(0.5) == 0.25)
+assert(ease.outQuad(0.5) == 0.75)
+assert(ease.inOutSine(2) == 1)
+assert(ease.inOutCubic(0) == 0)
+
+for i = 1, 100 do
+    local value = noise.perlin(i * 0.37, i * 0.11, i * 0.05)
+    assert(value >= 0 and value <= 1)
+end
+assert(noise.perlin(1.5, 2.5) == noise.perlin(1.5, 2.5))
+
+assert(api.time() > 1500000000)
+local time = api.localTime()
+assert(time.hour >= 0 and time.hour < 24 and time.month >= 1 and time.month <= 12)
+
+local buffer = api.createBuffer()
+buffer:fill({0, 255, 0})
+api.setScreen(buffer)
+";
 
 
 fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
-    let lib = load_lib();
-    let create_func = unsafe {
-        lib.symbol::<fn(&serde_json::Value) -> CreateInputPluginResult>("create")
-            .unwrap()
-    };
-    create_func(config)
+    unsafe { declaration().create_input(config, None) }
+}
+
+// folder with the given scripts, unique for each test since the tests run in parallel
+fn script_folder(test: &str, scripts: &[(&str, &str)]) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(format!("lightoros_lua_{}", test));
+    std::fs::create_dir_all(&path).unwrap();
+    for (name, source) in scripts {
+        std::fs::write(path.join(format!("{}.lua", name)), source).unwrap();
+    }
+    path.to_str().unwrap().to_owned()
 }
 
+fn create_plugin(config: serde_json::Value) -> Box<dyn PluginInputTrait> {
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    plugin
+}
+
+// colors of all pixels of the next frame
+fn get_colors(plugin: &mut Box<dyn PluginInputTrait>) -> Vec<(u8, u8, u8)> {
+    let data = plugin.get().unwrap();
+    data.rgb.iter().map(|rgb| (rgb.r, rgb.g, rgb.b)).collect()
+}
 
+fn get_color(plugin: &mut Box<dyn PluginInputTrait>) -> (u8, u8, u8) {
+    let data = plugin.get().unwrap();
+    let rgb = data.rgb[0];
+    (rgb.r, rgb.g, rgb.b)
+}
 
 #[test]
-fn test_get_info() {
-    let plugin_info = get_info();
-    assert_eq!(plugin_info.name, "LuaExtraInput");
-    assert!(plugin_info.kind == lightoros_plugin_base::PluginKind::Input);
-    assert_eq!(plugin_info.api_version, 1);
-    assert_eq!(plugin_info.filename, "lightoros_input_extra_lua");
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "LuaExtraInput");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_extra_lua");
 }
 
 #[test]
@@ -60,3 +187,381 @@ fn test_create_with_empty_config() {
     let config = json!({});
     assert!(call_create(&config).is_err());
 }
+
+#[test]
+fn test_missing_script() {
+    let folder = script_folder("missing", &[]);
+    let mut plugin = call_create(&json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "missing"},
+        "screen": {"width": 2, "height": 1}
+    }))
+    .unwrap();
+    assert!(plugin.init().is_err());
+}
+
+#[test]
+fn test_args() {
+    let folder = script_folder("args", &[("solid", SOLID_SCRIPT)]);
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "solid", "args": {"color": [0, 0, 255]}},
+        "screen": {"width": 3, "height": 2}
+    }));
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 6);
+    assert_eq!(data.meta.get("width").unwrap(), "3");
+    assert_eq!(data.meta.get("height").unwrap(), "2");
+    assert_eq!(get_color(&mut plugin), (0, 0, 255));
+}
+
+#[test]
+fn test_playlist() {
+    let folder = script_folder("playlist", &[("solid", SOLID_SCRIPT)]);
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "solid", "duration": 200, "args": {"color": [255, 0, 0]}},
+        "playlist": [
+            {"name": "solid", "duration": 200, "args": {"color": [0, 255, 0]}}
+        ],
+        "screen": {"width": 2, "height": 1}
+    }));
+
+    let start = Instant::now();
+    let mut colors: Vec<(u8, u8, u8)> = Vec::new();
+    while start.elapsed() < Duration::from_millis(700) {
+        let color = get_color(&mut plugin);
+        if colors.last() != Some(&color) {
+            colors.push(color);
+        }
+    }
+    assert_eq!(
+        colors,
+        vec![(255, 0, 0), (0, 255, 0), (255, 0, 0), (0, 255, 0)]
+    );
+}
+
+#[test]
+fn test_args_keep_duration() {
+    let folder = script_folder("args_duration", &[("solid", SOLID_SCRIPT)]);
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "solid", "duration": 300, "args": {"color": [255, 0, 0]}},
+        "playlist": [
+            {"name": "solid", "args": {"color": [0, 255, 0]}}
+        ],
+        "screen": {"width": 2, "height": 1}
+    }));
+    let start = Instant::now();
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+
+    // the restarted effect ends when the original one would have ended
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(plugin.set_parameter("color", "0,0,255").unwrap());
+    assert_eq!(get_color(&mut plugin), (0, 0, 255));
+    while get_color(&mut plugin) != (0, 255, 0) {}
+    assert!(start.elapsed() < Duration::from_millis(450));
+}
+
+#[test]
+fn test_switch_effect() {
+    let folder = script_folder(
+        "switch",
+        &[("solid", SOLID_SCRIPT), ("white", "api.setScreen({{{255, 255, 255}}, {{255, 255, 255}}})")],
+    );
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "solid"},
+        "screen": {"width": 2, "height": 1}
+    }));
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+
+    // arguments restart the current effect
+    assert!(plugin.set_parameter("color", "0,255,0").unwrap());
+    assert_eq!(get_color(&mut plugin), (0, 255, 0));
+
+    // a script ending on its own is started again
+    assert!(plugin.set_parameter("effect", "white").unwrap());
+    for _ in 0..3 {
+        assert_eq!(get_color(&mut plugin), (255, 255, 255));
+    }
+
+    assert!(plugin.set_parameter("effect", "unknown").is_err());
+    assert_eq!(get_color(&mut plugin), (255, 255, 255));
+}
+
+#[test]
+fn test_stop() {
+    let folder = script_folder("stop", &[("solid", SOLID_SCRIPT)]);
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "solid", "args": {"interval": 60000}},
+        "screen": {"width": 2, "height": 1}
+    }));
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+
+    // the script thread is stopped while sleeping and joined
+    let start = Instant::now();
+    drop(plugin);
+    assert!(start.elapsed() < Duration::from_millis(1000));
+}
+
+#[test]
+fn test_stop_waiting_script() {
+    let script = "while true do api.setScreen({{{255, 0, 0}}, {{255, 0, 0}}}) end";
+    let folder = script_folder("stop_waiting", &[("busy", script)]);
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "busy"},
+        "screen": {"width": 2, "height": 1}
+    }));
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+
+    // the script waits in setScreen() for the next frame to be taken
+    std::thread::sleep(Duration::from_millis(50));
+    let start = Instant::now();
+    drop(plugin);
+    assert!(start.elapsed() < Duration::from_millis(1000));
+}
+
+#[test]
+fn test_script_without_screen() {
+    let folder = script_folder("empty", &[("empty", "local x = 1")]);
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "empty"},
+        "screen": {"width": 2, "height": 1}
+    }));
+    assert!(plugin.get().is_err());
+}
+
+#[test]
+fn test_pixel_buffer() {
+    let folder = script_folder("buffer", &[("drawing", DRAWING_SCRIPT)]);
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "drawing"},
+        "screen": {"width": 5, "height": 5}
+    }));
+    let lit = |colors: &Vec<(u8, u8, u8)>| -> Vec<usize> {
+        (0..colors.len()).filter(|index| colors[*index] != (0, 0, 0)).collect()
+    };
+
+    let colors = get_colors(&mut plugin);
+    assert_eq!(colors[0], (255, 0, 0));
+    assert!(colors[1..].iter().all(|color| *color == (0, 0, 255)));
+
+    assert_eq!(lit(&get_colors(&mut plugin)), vec![0, 6, 12, 18, 24]);
+    assert_eq!(
+        lit(&get_colors(&mut plugin)),
+        vec![0, 1, 2, 3, 4, 5, 9, 10, 14, 15, 19, 20, 21, 22, 23, 24]
+    );
+    assert_eq!(
+        lit(&get_colors(&mut plugin)),
+        vec![1, 2, 3, 5, 9, 10, 14, 15, 19, 21, 22, 23]
+    );
+
+    let colors = get_colors(&mut plugin);
+    assert_eq!(lit(&colors), vec![18, 19, 23, 24]);
+    assert_eq!(colors[18], (200, 100, 50));
+
+    let colors = get_colors(&mut plugin);
+    assert_eq!(lit(&colors), vec![15, 16, 20, 21]);
+    assert_eq!(colors[15], (100, 50, 25));
+
+    // pixels shifted out on the left and top enter again on the right and bottom
+    assert_eq!(lit(&get_colors(&mut plugin)), vec![10, 14, 15, 19]);
+
+    // huge and far away shapes are clipped to the buffer
+    assert_eq!(
+        lit(&get_colors(&mut plugin)),
+        vec![0, 1, 2, 3, 4, 5, 6, 9, 10, 12, 14, 15, 18, 19, 20, 24]
+    );
+}
+
+#[test]
+fn test_helpers() {
+    let folder = script_folder("helpers", &[("helpers", HELPERS_SCRIPT)]);
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "helpers"},
+        "screen": {"width": 2, "height": 2}
+    }));
+    assert_eq!(get_color(&mut plugin), (0, 255, 0));
+}
+
+// message of the error returned for the next frame
+fn get_error(plugin: &mut Box<dyn PluginInputTrait>) -> String {
+    match plugin.get() {
+        Ok(_) => panic!("Expected an error"),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn test_script_error() {
+    let folder = script_folder(
+        "error",
+        &[
+            ("failing", "api.setScreen({{{255, 0, 0}}, {{255, 0, 0}}})\nerror('broken')"),
+            ("size", "local x = 1\napi.setScreen({{{255, 0, 0}}})"),
+            ("syntax", "api.setScreen(\n"),
+        ],
+    );
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "failing"},
+        "screen": {"width": 2, "height": 1}
+    }));
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+    let error = get_error(&mut plugin);
+    assert!(error.contains("failing.lua:2: broken"), "{}", error);
+    // the script is restarted after the error
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+
+    assert!(plugin.set_parameter("effect", "size").unwrap());
+    let error = get_error(&mut plugin);
+    assert!(error.contains("size.lua:2:"), "{}", error);
+    assert!(error.contains("Screen size does not match"), "{}", error);
+
+    assert!(plugin.set_parameter("effect", "syntax").unwrap());
+    let error = get_error(&mut plugin);
+    assert!(error.contains("syntax.lua:"), "{}", error);
+}
+
+#[test]
+fn test_sandbox() {
+    let script = "
+        local safe = io == nil and os == nil and dofile == nil and loadfile == nil
+        api.setScreen({{{safe and 0 or 255, safe and 255 or 0, 0}}})
+    ";
+    let folder = script_folder("sandbox", &[("check", script)]);
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "check"},
+        "screen": {"width": 1, "height": 1}
+    }));
+    assert_eq!(get_color(&mut plugin), (0, 255, 0));
+
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "check"},
+        "screen": {"width": 1, "height": 1},
+        "sandbox": false
+    }));
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+}
+
+#[test]
+fn test_require() {
+    let folder = script_folder(
+        "require",
+        &[
+            ("modules", "local colors = require('lib.colors')\nassert(require('lib.colors') == colors)\napi.setScreen({{colors.blue}})"),
+            ("escape", "require('..modules')"),
+        ],
+    );
+    std::fs::create_dir_all(PathBuf::from(&folder).join("lib")).unwrap();
+    std::fs::write(
+        PathBuf::from(&folder).join("lib").join("colors.lua"),
+        "return {blue = {0, 0, 255}}",
+    )
+    .unwrap();
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "modules"},
+        "screen": {"width": 1, "height": 1}
+    }));
+    assert_eq!(get_color(&mut plugin), (0, 0, 255));
+
+    assert!(plugin.set_parameter("effect", "escape").unwrap());
+    let error = get_error(&mut plugin);
+    assert!(error.contains("Invalid module name"), "{}", error);
+}
+
+#[test]
+fn test_limits() {
+    let folder = script_folder(
+        "limits",
+        &[("loop", "api.setScreen({{{255, 0, 0}}})\nwhile true do end")],
+    );
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "loop"},
+        "screen": {"width": 1, "height": 1},
+        "max_frame_time": 100
+    }));
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+    let start = Instant::now();
+    let error = get_error(&mut plugin);
+    assert!(error.contains("loop.lua:2: Time limit of 100 ms"), "{}", error);
+    assert!(start.elapsed() < Duration::from_millis(1000));
+
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "loop"},
+        "screen": {"width": 1, "height": 1},
+        "max_frame_time": 0,
+        "max_frame_instructions": 100000
+    }));
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+    let error = get_error(&mut plugin);
+    assert!(error.contains("Instruction limit of 100000"), "{}", error);
+
+    // scripts without limits are still stopped in the middle of a loop
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "loop"},
+        "screen": {"width": 1, "height": 1},
+        "max_frame_time": 0
+    }));
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+    let start = Instant::now();
+    drop(plugin);
+    assert!(start.elapsed() < Duration::from_millis(1000));
+}
+
+#[test]
+fn test_reload() {
+    let script = "local colors = require('colors')\nwhile true do api.setScreen({{colors.main}}) api.sleep(10) end";
+    let folder = script_folder(
+        "reload",
+        &[("main", script), ("colors", "return {main = {255, 0, 0}}")],
+    );
+    let module = PathBuf::from(&folder).join("colors.lua");
+    let mut plugin = create_plugin(json!({
+        "source_folder": folder,
+        "on_start_effect": {"name": "main"},
+        "screen": {"width": 1, "height": 1}
+    }));
+    assert_eq!(get_color(&mut plugin), (255, 0, 0));
+
+    // waits for the next check of the files
+    let wait_for = |plugin: &mut Box<dyn PluginInputTrait>, color: (u8, u8, u8)| {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(2000) {
+            if get_color(plugin) == color {
+                return true;
+            }
+        }
+        false
+    };
+
+    std::fs::write(&module, "return {main = {0, 0, 255}}").unwrap();
+    assert!(wait_for(&mut plugin, (0, 0, 255)));
+
+    // a broken version is reported and the running version is kept
+    std::fs::write(&module, "return {main = ").unwrap();
+    let start = Instant::now();
+    let error = loop {
+        match plugin.get() {
+            Ok(_) => assert!(start.elapsed() < Duration::from_millis(2000)),
+            Err(err) => break err.to_string(),
+        }
+    };
+    assert!(error.contains("Reload of effect 'main' failed: colors.lua:1:"), "{}", error);
+    assert_eq!(get_color(&mut plugin), (0, 0, 255));
+
+    std::fs::write(&module, "return {main = {0, 255, 0}}").unwrap();
+    assert!(wait_for(&mut plugin, (0, 255, 0)));
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use lightoros_plugin_base::*;
+
+use crate::builtin::{FONT_3X5, FONT_5X7};
+
+pub const BUILTIN_FONTS: [&str; 2] = ["5x7", "3x5"];
+
+/// Bitmap of a single character
+pub struct Glyph {
+    /// horizontal distance to the next character
+    advance: i32,
+    /// position of the bitmap relative to the current position on the top of the line
+    x: i32,
+    y: i32,
+    width: usize,
+    /// pixels row by row, true for set pixels
+    pixels: Vec<bool>,
+}
+
+pub struct Font {
+    /// height of a line
+    height: usize,
+    glyphs: HashMap<char, Glyph>,
+}
+
+impl Font {
+    /// One of the built-in fonts, None for unknown names
+    pub fn builtin(name: &str) -> Option<Font> {
+        let mut glyphs = HashMap::new();
+        let height = match name {
+            "5x7" => {
+                for (index, columns) in FONT_5X7.iter().enumerate() {
+                    let pixels = (0..7)
+                        .flat_map(|row| columns.iter().map(move |column| column & (1 << row) != 0))
+                        .collect();
+                    glyphs.insert((b' ' + index as u8) as char, fixed_glyph(5, pixels));
+                }
+                7
+            }
+            "3x5" => {
+                for (character, rows) in FONT_3X5.iter() {
+                    let pixels = rows
+                        .iter()
+                        .flat_map(|row| (0..3).rev().map(move |column| row & (1 << column) != 0))
+                        .collect();
+                    glyphs.insert(*character, fixed_glyph(3, pixels));
+                }
+                5
+            }
+            _ => return None,
+        };
+        Some(Font { height, glyphs })
+    }
+
+    /// Loads a font in the Glyph Bitmap Distribution Format
+    pub fn load_bdf(path: &str) -> PluginResult<Font> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Font::parse_bdf(&content),
+            Err(err) => plugin_err!("Failed reading font file '{}': {}", path, err),
+        }
+    }
+
+    pub fn parse_bdf(content: &str) -> PluginResult<Font> {
+        let mut lines = content.lines().map(|line| line.trim());
+        let mut bounding_box: Option<(i32, i32)> = None;
+        let (mut ascent, mut descent): (Option<i32>, Option<i32>) = (None, None);
+        let mut glyphs = HashMap::new();
+
+        while let Some(line) = lines.next() {
+            let mut values = line.split_whitespace();
+            match values.next() {
+                Some("FONTBOUNDINGBOX") => {
+                    let values = bdf_numbers(values, 4, line)?;
+                    // height and vertical offset of the baseline
+                    bounding_box = Some((values[1], values[3]));
+                }
+                Some("FONT_ASCENT") => ascent = Some(bdf_numbers(values, 1, line)?[0]),
+                Some("FONT_DESCENT") => descent = Some(bdf_numbers(values, 1, line)?[0]),
+                Some("STARTCHAR") => {
+                    let (height, y_offset) = match bounding_box {
+                        Some(bounding_box) => bounding_box,
+                        None => return plugin_err!("Invalid BDF font: FONTBOUNDINGBOX missing"),
+                    };
+                    let ascent = ascent.unwrap_or(height + y_offset);
+                    if let Some((character, glyph)) = parse_bdf_char(&mut lines, ascent)? {
+                        glyphs.insert(character, glyph);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let height = match (bounding_box, ascent, descent) {
+            (_, Some(ascent), Some(descent)) => ascent + descent,
+            (Some((height, _)), _, _) => height,
+            _ => return plugin_err!("Invalid BDF font: FONTBOUNDINGBOX missing"),
+        };
+        if glyphs.is_empty() {
+            return plugin_err!("Invalid BDF font: no characters found");
+        }
+        Ok(Font {
+            height: height.max(0) as usize,
+            glyphs,
+        })
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // missing lower case letters are replaced by upper case ones, other characters by '?'
+    fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&character)
+            .or_else(|| self.glyphs.get(&character.to_ascii_uppercase()))
+            .or_else(|| self.glyphs.get(&'?'))
+    }
+
+    /// Width of the text in pixels
+    pub fn text_width(&self, text: &str) -> i32 {
+        let mut position = 0;
+        let mut width = 0;
+        for glyph in text.chars().filter_map(|character| self.glyph(character)) {
+            width = width.max(position + glyph.x + glyph.width as i32);
+            position += glyph.advance;
+        }
+        width
+    }
+
+    /// Draws the text with the top left corner at the position, pixels outside the image are skipped
+    pub fn draw(&self, text: &str, x: i32, y: i32, color: RGB, image: &mut [RGB], width: usize) {
+        let height = (image.len() / width) as i32;
+        let mut position = x;
+        for glyph in text.chars().filter_map(|character| self.glyph(character)) {
+            for (index, _) in glyph.pixels.iter().enumerate().filter(|(_, set)| **set) {
+                let pixel_x = position + glyph.x + (index % glyph.width) as i32;
+                let pixel_y = y + glyph.y + (index / glyph.width) as i32;
+                if pixel_x >= 0 && pixel_x < width as i32 && pixel_y >= 0 && pixel_y < height {
+                    image[pixel_y as usize * width + pixel_x as usize] = color;
+                }
+            }
+            position += glyph.advance;
+        }
+    }
+}
+
+// glyph of the built-in fonts followed by a single empty column
+fn fixed_glyph(width: usize, pixels: Vec<bool>) -> Glyph {
+    Glyph {
+        advance: width as i32 + 1,
+        x: 0,
+        y: 0,
+        width,
+        pixels,
+    }
+}
+
+fn bdf_numbers<'a>(
+    values: impl Iterator<Item = &'a str>,
+    count: usize,
+    line: &str,
+) -> PluginResult<Vec<i32>> {
+    let numbers: Vec<i32> = values.filter_map(|value| value.parse().ok()).collect();
+    if numbers.len() < count {
+        return plugin_err!("Invalid BDF font line: {}", line);
+    }
+    Ok(numbers)
+}
+
+// reads a character up to ENDCHAR, characters without a valid unicode encoding are skipped
+fn parse_bdf_char<'a>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    ascent: i32,
+) -> PluginResult<Option<(char, Glyph)>> {
+    let mut character = None;
+    let mut advance = None;
+    let mut bbx = None;
+    while let Some(line) = lines.next() {
+        let mut values = line.split_whitespace();
+        match values.next() {
+            Some("ENCODING") => {
+                let code = bdf_numbers(values, 1, line)?[0];
+                character = std::char::from_u32(code as u32).filter(|_| code >= 0);
+            }
+            Some("DWIDTH") => advance = Some(bdf_numbers(values, 1, line)?[0]),
+            Some("BBX") => bbx = Some(bdf_numbers(values, 4, line)?),
+            Some("BITMAP") => {
+                let bbx = match bbx {
+                    Some(bbx) => bbx,
+                    None => return plugin_err!("Invalid BDF font: BBX missing before BITMAP"),
+                };
+                let (width, height) = (bbx[0].max(0) as usize, bbx[1].max(0) as usize);
+                let mut pixels = Vec::with_capacity(width * height);
+                for _ in 0..height {
+                    let row = match lines.next() {
+                        Some(row) => row,
+                        None => return plugin_err!("Invalid BDF font: incomplete BITMAP"),
+                    };
+                    // rows are hex values padded to full bytes, the highest bit is the left column
+                    let bits = row.len() * 4;
+                    let value = match u128::from_str_radix(row, 16) {
+                        Ok(value) if bits >= width && bits <= 128 => value,
+                        _ => return plugin_err!("Invalid BDF font bitmap row: {}", row),
+                    };
+                    pixels.extend((0..width).map(|column| value & (1 << (bits - 1 - column)) != 0));
+                }
+                let glyph = Glyph {
+                    advance: advance.unwrap_or(bbx[0] + bbx[2]),
+                    x: bbx[2],
+                    y: ascent - bbx[3] - bbx[1],
+                    width,
+                    pixels,
+                };
+                return Ok(character.map(|character| (character, glyph)));
+            }
+            Some("ENDCHAR") => return plugin_err!("Invalid BDF font: character without BITMAP"),
+            _ => {}
+        }
+    }
+    plugin_err!("Invalid BDF font: ENDCHAR missing")
+}
@@ -0,0 +1,252 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::format::{Item, StrftimeItems};
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+
+use serde::Deserialize;
+
+mod builtin;
+mod font;
+
+use font::Font;
+
+const NAME: &str = "TextExtraInput";
+
+#[derive(Deserialize, Debug)]
+struct Size {
+    width: usize,
+    height: usize,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    Text,
+    Marquee,
+    Clock,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    screen: Size,
+    #[serde(default = "default_mode")]
+    mode: Mode,
+    #[serde(default)]
+    text: String,
+    #[serde(default = "default_format")]
+    format: String,
+    #[serde(default = "default_font")]
+    font: String,
+    font_file: Option<String>,
+    #[serde(default = "default_color")]
+    color: [u8; 3],
+    #[serde(default)]
+    background: [u8; 3],
+    #[serde(default = "default_align")]
+    align: Align,
+    #[serde(default = "default_speed")]
+    speed: f32,
+    #[serde(default = "default_fps")]
+    fps: f32,
+}
+
+fn default_mode() -> Mode {
+    Mode::Text
+}
+
+fn default_format() -> String {
+    "%H:%M".to_string()
+}
+
+fn default_font() -> String {
+    "5x7".to_string()
+}
+
+fn default_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+fn default_align() -> Align {
+    Align::Center
+}
+
+fn default_speed() -> f32 {
+    20.0
+}
+
+fn default_fps() -> f32 {
+    30.0
+}
+
+struct TextExtraInput {
+    config: Config,
+    font: Font,
+    text: String,
+    color: RGB,
+    background: RGB,
+    // frames produced so far, the marquee position is based on it
+    frame: u64,
+    next_frame: Option<Instant>,
+}
+
+impl std::fmt::Display for TextExtraInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl TextExtraInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        if config.screen.width == 0 || config.screen.height == 0 {
+            return plugin_err!(
+                "Invalid screen size: {}x{}",
+                config.screen.width,
+                config.screen.height
+            );
+        }
+        if config.fps <= 0.0 {
+            return plugin_err!("Invalid config value 'fps': {}", config.fps);
+        }
+        if config.mode == Mode::Marquee && config.speed <= 0.0 {
+            return plugin_err!("Invalid config value 'speed': {}", config.speed);
+        }
+        if config.mode == Mode::Clock
+            && StrftimeItems::new(&config.format).any(|item| item == Item::Error)
+        {
+            return plugin_err!("Invalid clock format: {}", config.format);
+        }
+
+        let font = match &config.font_file {
+            Some(path) => Font::load_bdf(path)?,
+            None => match Font::builtin(&config.font) {
+                Some(font) => font,
+                None => {
+                    return plugin_err!(
+                        "Unknown font '{}'. Built-in fonts: {}",
+                        config.font,
+                        font::BUILTIN_FONTS.join(", ")
+                    )
+                }
+            },
+        };
+
+        let plugin = TextExtraInput {
+            font,
+            text: config.text.clone(),
+            color: to_rgb(config.color),
+            background: to_rgb(config.background),
+            frame: 0,
+            next_frame: None,
+            config,
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn render(&self, text: &str) -> Vec<RGB> {
+        let (width, height) = (self.config.screen.width, self.config.screen.height);
+        let mut image = vec![self.background; width * height];
+
+        let text_width = self.font.text_width(text);
+        let x = match self.config.mode {
+            Mode::Marquee => {
+                // the text enters on the right and leaves completely on the left before it starts over
+                let distance =
+                    (self.frame as f64 * self.config.speed as f64 / self.config.fps as f64) as u64;
+                width as i32 - (distance % (width as u64 + text_width as u64)) as i32
+            }
+            _ => match self.config.align {
+                Align::Left => 0,
+                Align::Center => (width as i32 - text_width) / 2,
+                Align::Right => width as i32 - text_width,
+            },
+        };
+        // lines are centered vertically, a remaining pixel is added to the bottom
+        let y = (height as i32 - self.font.height() as i32) / 2;
+
+        self.font.draw(text, x, y, self.color, &mut image, width);
+        image
+    }
+}
+
+fn to_rgb(color: [u8; 3]) -> RGB {
+    RGB {
+        r: color[0],
+        g: color[1],
+        b: color[2],
+    }
+}
+
+impl PluginInputTrait for TextExtraInput {
+    fn init(&mut self) -> PluginResult<()> {
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        let interval = Duration::from_secs_f32(1.0 / self.config.fps);
+        let now = Instant::now();
+        match self.next_frame {
+            Some(next_frame) if next_frame > now => {
+                thread::sleep(next_frame - now);
+                self.next_frame = Some(next_frame + interval);
+            }
+            _ => self.next_frame = Some(now + interval),
+        }
+
+        let rgb = match self.config.mode {
+            Mode::Clock => {
+                let time = chrono::Local::now().format(&self.config.format).to_string();
+                self.render(&time)
+            }
+            _ => self.render(&self.text),
+        };
+        self.frame += 1;
+
+        Ok(plugin_data!(rgb, {
+            "width" => self.config.screen.width,
+            "height" => self.config.screen.height,
+        }))
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        match name {
+            "text" => {
+                self.text = value.to_string();
+                self.frame = 0;
+            }
+            "color" => {
+                let color: Vec<u8> = match value
+                    .split(',')
+                    .map(|channel| channel.trim().parse::<u8>())
+                    .collect()
+                {
+                    Ok(color) => color,
+                    Err(_) => return plugin_err!("Invalid color: {}", value),
+                };
+                if color.len() != 3 {
+                    return plugin_err!("Invalid color: {}", value);
+                }
+                self.color = to_rgb([color[0], color[1], color[2]]);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    TextExtraInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
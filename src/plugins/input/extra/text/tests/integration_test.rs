@@ -0,0 +1,266 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::path::PathBuf;
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+const TEST_FONT: &str = "STARTFONT 2.1
+FONT -test-font
+SIZE 4 75 75
+FONTBOUNDINGBOX 3 4 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 3
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 1
+STARTCHAR A
+ENCODING 65
+SWIDTH 500 0
+DWIDTH 3 0
+BBX 2 3 0 0
+BITMAP
+80
+40
+C0
+ENDCHAR
+ENDFONT
+";
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn create_plugin(config: serde_json::Value) -> Box<dyn PluginInputTrait> {
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    plugin
+}
+
+fn temp_path(name: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(name);
+    path.to_str().unwrap().to_owned()
+}
+
+// coordinates of all pixels with the given color
+fn pixels(data: &TraitData, width: usize, r: u8, g: u8, b: u8) -> Vec<(usize, usize)> {
+    data.rgb
+        .iter()
+        .enumerate()
+        .filter(|(_, rgb)| (rgb.r, rgb.g, rgb.b) == (r, g, b))
+        .map(|(index, _)| (index % width, index / width))
+        .collect()
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "TextExtraInput");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_extra_text");
+}
+
+#[test]
+fn test_create() {
+    assert!(call_create(&json!({"screen": {"width": 32, "height": 8}, "text": "Hello"})).is_ok());
+    assert!(call_create(&json!({
+        "screen": {"width": 32, "height": 8},
+        "mode": "marquee",
+        "text": "Hello world",
+        "font": "3x5",
+        "color": [255, 0, 0],
+        "background": [0, 0, 32],
+        "speed": 10,
+        "fps": 20
+    }))
+    .is_ok());
+    assert!(call_create(&json!({
+        "screen": {"width": 32, "height": 8},
+        "mode": "clock",
+        "format": "%H:%M:%S",
+        "align": "left"
+    }))
+    .is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    let invalid_configs = vec![
+        json!({}),
+        json!({"screen": {"width": 0, "height": 8}}),
+        json!({"screen": {"width": 32, "height": 8}, "mode": "banner"}),
+        json!({"screen": {"width": 32, "height": 8}, "font": "8x8"}),
+        json!({"screen": {"width": 32, "height": 8}, "font_file": "/not/existing/font.bdf"}),
+        json!({"screen": {"width": 32, "height": 8}, "fps": 0}),
+        json!({"screen": {"width": 32, "height": 8}, "mode": "marquee", "speed": 0}),
+        json!({"screen": {"width": 32, "height": 8}, "mode": "clock", "format": "%H:%Q"}),
+        json!({"screen": {"width": 32, "height": 8}, "align": "middle"}),
+    ];
+    for config in invalid_configs {
+        assert!(call_create(&config).is_err(), "{}", config);
+    }
+}
+
+#[test]
+fn test_text() {
+    let mut plugin = create_plugin(json!({
+        "screen": {"width": 7, "height": 9},
+        "text": "I",
+        "color": [0, 255, 0],
+        "background": [0, 0, 1],
+        "fps": 1000
+    }));
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 63);
+    assert_eq!(data.meta.get("width").unwrap(), "7");
+    assert_eq!(data.meta.get("height").unwrap(), "9");
+    // the 5x7 glyph is centered
+    let mut expected = vec![(2, 1), (3, 1), (4, 1)];
+    expected.extend((2..7).map(|y| (3, y)));
+    expected.extend(vec![(2, 7), (3, 7), (4, 7)]);
+    let mut lit = pixels(&data, 7, 0, 255, 0);
+    lit.sort_by_key(|(x, y)| (*y, *x));
+    assert_eq!(lit, expected);
+    assert_eq!(pixels(&data, 7, 0, 0, 1).len(), 63 - 11);
+}
+
+#[test]
+fn test_small_font() {
+    let mut plugin = create_plugin(json!({
+        "screen": {"width": 8, "height": 5},
+        "text": "1",
+        "font": "3x5",
+        "align": "right",
+        "fps": 1000
+    }));
+    let data = plugin.get().unwrap();
+    let mut lit = pixels(&data, 8, 255, 255, 255);
+    lit.sort_by_key(|(x, y)| (*y, *x));
+    assert_eq!(
+        lit,
+        vec![
+            (6, 0),
+            (5, 1),
+            (6, 1),
+            (6, 2),
+            (6, 3),
+            (5, 4),
+            (6, 4),
+            (7, 4)
+        ]
+    );
+
+    // lower case letters are shown as upper case ones
+    let mut upper =
+        create_plugin(json!({"screen": {"width": 8, "height": 5}, "text": "AB", "font": "3x5"}));
+    let mut lower =
+        create_plugin(json!({"screen": {"width": 8, "height": 5}, "text": "ab", "font": "3x5"}));
+    let (upper, lower) = (upper.get().unwrap(), lower.get().unwrap());
+    assert!(!pixels(&upper, 8, 255, 255, 255).is_empty());
+    assert_eq!(
+        pixels(&upper, 8, 255, 255, 255),
+        pixels(&lower, 8, 255, 255, 255)
+    );
+}
+
+#[test]
+fn test_marquee() {
+    let mut plugin = create_plugin(json!({
+        "screen": {"width": 10, "height": 7},
+        "mode": "marquee",
+        "text": "I",
+        "speed": 1000,
+        "fps": 1000
+    }));
+    // the text moves one pixel per frame
+    let frames: Vec<Vec<(usize, usize)>> = (0..16)
+        .map(|_| pixels(&plugin.get().unwrap(), 10, 255, 255, 255))
+        .collect();
+    assert!(frames[0].is_empty());
+    assert!(frames[1].is_empty());
+    assert_eq!(frames[2], vec![(9, 0), (9, 6)]);
+    assert_eq!(frames[3].len(), 9);
+    assert_eq!(frames[10].iter().map(|(x, _)| *x).min(), Some(1));
+    assert_eq!(frames[13], vec![(0, 0), (0, 6)]);
+    assert!(frames[14].is_empty());
+    // the text has left the screen and starts over
+    assert!(frames[15].is_empty());
+}
+
+#[test]
+fn test_clock() {
+    // a format without specifiers is shown as it is
+    let mut clock = create_plugin(json!({
+        "screen": {"width": 20, "height": 7},
+        "mode": "clock",
+        "format": "12:34"
+    }));
+    let mut text = create_plugin(json!({"screen": {"width": 20, "height": 7}, "text": "12:34"}));
+    let clock = pixels(&clock.get().unwrap(), 20, 255, 255, 255);
+    assert!(!clock.is_empty());
+    assert_eq!(clock, pixels(&text.get().unwrap(), 20, 255, 255, 255));
+
+    let mut clock = create_plugin(json!({
+        "screen": {"width": 30, "height": 7},
+        "mode": "clock",
+        "format": "%H:%M"
+    }));
+    assert!(!pixels(&clock.get().unwrap(), 30, 255, 255, 255).is_empty());
+}
+
+#[test]
+fn test_bdf_font() {
+    let path = temp_path("lightoros_text_test.bdf");
+    std::fs::write(&path, TEST_FONT).unwrap();
+    let mut plugin = create_plugin(json!({
+        "screen": {"width": 4, "height": 4},
+        "text": "AA",
+        "font_file": path,
+        "align": "left"
+    }));
+    let mut lit = pixels(&plugin.get().unwrap(), 4, 255, 255, 255);
+    lit.sort_by_key(|(x, y)| (*y, *x));
+    assert_eq!(lit, vec![(0, 0), (3, 0), (1, 1), (0, 2), (1, 2), (3, 2)]);
+
+    std::fs::write(&path, TEST_FONT.replace("FONTBOUNDINGBOX 3 4 0 -1\n", "")).unwrap();
+    assert!(call_create(&json!({"screen": {"width": 4, "height": 4}, "font_file": path})).is_err());
+    std::fs::write(&path, TEST_FONT.replace("C0\n", "")).unwrap();
+    assert!(call_create(&json!({"screen": {"width": 4, "height": 4}, "font_file": path})).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_set_parameter() {
+    let mut plugin = create_plugin(json!({
+        "screen": {"width": 7, "height": 7},
+        "text": "I",
+        "fps": 1000
+    }));
+    assert_eq!(pixels(&plugin.get().unwrap(), 7, 255, 255, 255).len(), 11);
+
+    assert!(plugin.set_parameter("text", "").unwrap());
+    assert!(pixels(&plugin.get().unwrap(), 7, 255, 255, 255).is_empty());
+
+    assert!(plugin.set_parameter("text", "I").unwrap());
+    assert!(plugin.set_parameter("color", "255,0,0").unwrap());
+    assert_eq!(pixels(&plugin.get().unwrap(), 7, 255, 0, 0).len(), 11);
+
+    assert!(plugin.set_parameter("color", "255,0").is_err());
+    assert!(plugin.set_parameter("color", "255,0,256").is_err());
+    assert!(plugin.set_parameter("color", "255,x,0,0").is_err());
+    assert!(plugin.set_parameter("color", "255,0,0,").is_err());
+    assert!(!plugin.set_parameter("speed", "10").unwrap());
+}
@@ -0,0 +1,49 @@
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use lightoros_plugin_wasm::{Config, WasmPlugin};
+
+const NAME: &str = "WasmExtraInput";
+
+struct WasmExtraInput {
+    module: WasmPlugin,
+}
+
+impl std::fmt::Display for WasmExtraInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+/**
+ * The module is loaded once, its get function is called for each frame and sets the frame with the host function set_frame.
+ * Like other inputs the module controls the frame rate, e.g. by calling sleep_ms.
+*/
+impl WasmExtraInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+        let module = WasmPlugin::load(&config, &PluginKind::Input, NAME)?;
+
+        let plugin = WasmExtraInput { module };
+        Ok(Box::new(plugin))
+    }
+}
+
+impl PluginInputTrait for WasmExtraInput {
+    fn init(&mut self) -> PluginResult<()> {
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        self.module.get()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        self.module.set_parameter(name, value)
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    WasmExtraInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
@@ -0,0 +1,166 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::CreateInputPluginResult;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::path::PathBuf;
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+// produces 2x1 frames with the red value counting up and passes its configuration as metadata
+const COUNTER_MODULE: &str = r#"
+(module
+  (import "lightoros" "set_frame" (func $set_frame (param i32 i32 i32 i32)))
+  (import "lightoros" "set_meta" (func $set_meta (param i32 i32 i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "config")
+  (global $heap (mut i32) (i32.const 1024))
+  (global $red (mut i32) (i32.const 0))
+  (global $config_len (mut i32) (i32.const 0))
+
+  (func (export "lightoros_abi_version") (result i32) (i32.const 1))
+
+  (func (export "alloc") (param $size i32) (result i32) (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $size)))
+    (local.get $ptr))
+
+  (func (export "free") (param $ptr i32) (param $size i32)
+    (global.set $heap (local.get $ptr)))
+
+  (func (export "init") (param $ptr i32) (param $len i32) (result i32)
+    (memory.copy (i32.const 512) (local.get $ptr) (local.get $len))
+    (global.set $config_len (local.get $len))
+    (i32.const 0))
+
+  (func (export "get") (result i32)
+    (i32.store8 (i32.const 256) (global.get $red))
+    (i32.store8 (i32.const 259) (global.get $red))
+    (global.set $red (i32.add (global.get $red) (i32.const 1)))
+    (call $set_frame (i32.const 256) (i32.const 6) (i32.const 2) (i32.const 1))
+    (call $set_meta (i32.const 0) (i32.const 6) (i32.const 512) (global.get $config_len))
+    (i32.const 0))
+
+  ;; the parameter 'red' sets the red value to its first digit
+  (func (export "set_parameter") (param $name i32) (param $name_len i32) (param $value i32) (param $value_len i32) (result i32)
+    (if (i32.ne (local.get $name_len) (i32.const 3)) (then (return (i32.const 0))))
+    (global.set $red (i32.sub (i32.load8_u (local.get $value)) (i32.const 48)))
+    (i32.const 1))
+)
+"#;
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+// path of the compiled module in a folder unique for each test since the tests run in parallel
+fn module_file(test: &str, source: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(format!("lightoros_wasm_input_{}", test));
+    std::fs::create_dir_all(&path).unwrap();
+    path.push("module.wasm");
+    std::fs::write(&path, wat::parse_str(source).unwrap()).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+fn create_error(config: &serde_json::Value) -> String {
+    match call_create(config) {
+        Ok(_) => panic!("Expected an error"),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "WasmExtraInput");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_extra_wasm");
+}
+
+#[test]
+fn test_create() {
+    let module = module_file("create", COUNTER_MODULE);
+    assert!(call_create(&json!({ "module": module })).is_ok());
+    assert!(call_create(&json!({})).is_err());
+    assert!(call_create(&json!({"module": "/missing/module.wasm"})).is_err());
+
+    // a transform module is no input
+    let transform = COUNTER_MODULE.replace("(export \"get\")", "(export \"transform\")");
+    let module = module_file("create_kind", &transform);
+    let error = create_error(&json!({ "module": module }));
+    assert!(error.contains("exports no function 'get'"), "{}", error);
+}
+
+#[test]
+fn test_abi_version() {
+    let source = COUNTER_MODULE.replace("(result i32) (i32.const 1)", "(result i32) (i32.const 2)");
+    let module = module_file("abi_version", &source);
+    let error = create_error(&json!({ "module": module }));
+    assert!(error.contains("Unsupported ABI version 2"), "{}", error);
+}
+
+#[test]
+fn test_get() {
+    let module = module_file("get", COUNTER_MODULE);
+    let mut plugin = call_create(&json!({"module": module, "config": {"speed": 2}})).unwrap();
+    assert!(plugin.init().is_ok());
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+    assert_eq!(data.rgb[1].r, 0);
+    assert_eq!(data.meta.get("width").unwrap(), "2");
+    assert_eq!(data.meta.get("height").unwrap(), "1");
+    assert_eq!(data.meta.get("config").unwrap(), r#"{"speed":2}"#);
+
+    // the state of the module is kept between the frames
+    assert_eq!(plugin.get().unwrap().rgb[0].r, 1);
+
+    assert!(plugin.set_parameter("red", "7").unwrap());
+    assert!(!plugin.set_parameter("green", "7").unwrap());
+    assert_eq!(plugin.get().unwrap().rgb[0].r, 7);
+}
+
+#[test]
+fn test_limits() {
+    let source = COUNTER_MODULE.replace(
+        "(func (export \"get\") (result i32)",
+        "(func (export \"get\") (result i32) (loop $forever (br $forever))",
+    );
+    let module = module_file("limits_fuel", &source);
+    let mut plugin = call_create(&json!({"module": module, "fuel": 100_000})).unwrap();
+    let error = plugin.get().err().unwrap().to_string();
+    assert!(error.contains("fuel"), "{}", error);
+    // the module fails again on the next frame instead of blocking the engine
+    assert!(plugin.get().is_err());
+
+    // 100 pages of 64 KiB exceed the memory limit of 1 MiB
+    let source = COUNTER_MODULE.replace(
+        "(memory (export \"memory\") 1)",
+        "(memory (export \"memory\") 100)",
+    );
+    let module = module_file("limits_memory", &source);
+    assert!(call_create(&json!({"module": module, "max_memory": 1_048_576})).is_err());
+    assert!(call_create(&json!({ "module": module })).is_ok());
+}
+
+#[test]
+fn test_trap() {
+    let source = COUNTER_MODULE.replace(
+        "(func (export \"get\") (result i32)",
+        "(func (export \"get\") (result i32) (unreachable)",
+    );
+    let module = module_file("trap", &source);
+    let mut plugin = call_create(&json!({ "module": module })).unwrap();
+    let error = plugin.get().err().unwrap().to_string();
+    assert!(error.contains("Function 'get' failed"), "{}", error);
+}
@@ -0,0 +1,320 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::imageops::FilterType;
+use image::{AnimationDecoder, ImageFormat, RgbImage, RgbaImage};
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+
+use serde::Deserialize;
+
+const NAME: &str = "FilesystemInputImage";
+
+// browsers show animation frames without a delay for 100ms as well
+const DEFAULT_FRAME_DELAY: u64 = 100;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Fit {
+    // image is centered without scaling, overlapping parts are cut off
+    None,
+    // image is scaled to the target size ignoring the aspect ratio
+    Stretch,
+    // image is scaled to fit into the target size, the remaining area is black
+    Contain,
+    // image is scaled to fill the target size, overlapping parts are cut off
+    Cover,
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    files: Vec<String>,
+    #[serde(default = "default_repeat")]
+    repeat: bool,
+    #[serde(default = "default_duration")]
+    duration: u64,
+    #[serde(default = "default_loops")]
+    loops: u32,
+    width: Option<u32>,
+    height: Option<u32>,
+    #[serde(default = "default_fit")]
+    fit: Fit,
+}
+
+fn default_repeat() -> bool {
+    true
+}
+
+fn default_duration() -> u64 {
+    5000
+}
+
+fn default_loops() -> u32 {
+    1
+}
+
+fn default_fit() -> Fit {
+    Fit::Contain
+}
+
+struct Frame {
+    image: RgbImage,
+    delay: Duration,
+}
+
+struct FilesystemImageInput {
+    config: Config,
+    frames: Vec<Frame>,
+    file_index: usize,
+    frame_index: usize,
+    loop_count: u32,
+    next_frame: Option<Instant>,
+}
+
+impl std::fmt::Display for FilesystemImageInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl FilesystemImageInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        match (config.width, config.height) {
+            (Some(0), _) | (_, Some(0)) => {
+                return plugin_err!("Invalid config value: width and height must not be 0")
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                return plugin_err!("Invalid config: width and height must be set together")
+            }
+            _ => {}
+        }
+        if config.loops == 0 {
+            return plugin_err!("Invalid config value 'loops': 0");
+        }
+
+        let plugin = FilesystemImageInput {
+            config,
+            frames: Vec::new(),
+            file_index: 0,
+            frame_index: 0,
+            loop_count: 0,
+            next_frame: None,
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn load_file(&mut self, path: &str) -> PluginResult<()> {
+        let frames = match decode_file(path) {
+            Ok(frames) => frames,
+            Err(error) => return plugin_err!("Error loading image '{}': {}", path, error),
+        };
+        if frames.is_empty() {
+            return plugin_err!("Error: '{}' contains no image", path);
+        }
+
+        let still = frames.len() == 1;
+        self.frames = frames
+            .into_iter()
+            .map(|(image, delay)| Frame {
+                image: self.fit(flatten(&image)),
+                delay: if still {
+                    Duration::from_millis(self.config.duration)
+                } else if delay == Duration::from_millis(0) {
+                    Duration::from_millis(DEFAULT_FRAME_DELAY)
+                } else {
+                    delay
+                },
+            })
+            .collect();
+        self.frame_index = 0;
+        self.loop_count = 0;
+        Ok(())
+    }
+
+    fn fit(&self, image: RgbImage) -> RgbImage {
+        let (width, height) = match (self.config.width, self.config.height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return image,
+        };
+        let (image_width, image_height) = image.dimensions();
+        if (image_width, image_height) == (width, height) {
+            return image;
+        }
+
+        let scale_x = width as f32 / image_width as f32;
+        let scale_y = height as f32 / image_height as f32;
+        let scaled_size = |scale: f32| {
+            (
+                ((image_width as f32 * scale).round() as u32).max(1),
+                ((image_height as f32 * scale).round() as u32).max(1),
+            )
+        };
+
+        match self.config.fit {
+            Fit::Stretch => image::imageops::resize(&image, width, height, FilterType::Triangle),
+            Fit::Cover => {
+                let (scaled_width, scaled_height) = scaled_size(scale_x.max(scale_y));
+                let scaled = image::imageops::resize(
+                    &image,
+                    scaled_width.max(width),
+                    scaled_height.max(height),
+                    FilterType::Triangle,
+                );
+                let x = (scaled.width() - width) / 2;
+                let y = (scaled.height() - height) / 2;
+                image::imageops::crop_imm(&scaled, x, y, width, height).to_image()
+            }
+            Fit::Contain => {
+                let (scaled_width, scaled_height) = scaled_size(scale_x.min(scale_y));
+                let scaled = image::imageops::resize(
+                    &image,
+                    scaled_width.min(width),
+                    scaled_height.min(height),
+                    FilterType::Triangle,
+                );
+                center(&scaled, width, height)
+            }
+            Fit::None => center(&image, width, height),
+        }
+    }
+
+    fn next_file(&mut self) {
+        self.frames.clear();
+        self.file_index += 1;
+        if self.config.repeat && self.file_index == self.config.files.len() {
+            self.file_index = 0;
+        }
+    }
+}
+
+// decodes all frames of a file with their delays, still images have a single frame
+fn decode_file(path: &str) -> image::ImageResult<Vec<(RgbaImage, Duration)>> {
+    let reader = image::io::Reader::open(path)?.with_guessed_format()?;
+    let frames = match reader.format() {
+        Some(ImageFormat::Gif) => {
+            let decoder = GifDecoder::new(BufReader::new(File::open(path)?))?;
+            decoder.into_frames().collect_frames()?
+        }
+        Some(ImageFormat::Png) => {
+            let decoder = PngDecoder::new(BufReader::new(File::open(path)?))?;
+            if decoder.is_apng() {
+                decoder.apng().into_frames().collect_frames()?
+            } else {
+                return Ok(vec![(
+                    reader.decode()?.to_rgba8(),
+                    Duration::from_millis(0),
+                )]);
+            }
+        }
+        _ => {
+            return Ok(vec![(
+                reader.decode()?.to_rgba8(),
+                Duration::from_millis(0),
+            )])
+        }
+    };
+    Ok(frames
+        .into_iter()
+        .map(|frame| {
+            let delay = Duration::from(frame.delay());
+            (frame.into_buffer(), delay)
+        })
+        .collect())
+}
+
+// transparent parts are shown as black
+fn flatten(image: &RgbaImage) -> RgbImage {
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let blend = |value: u8| (value as u16 * a as u16 / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+// places the image in the middle of a black canvas, overlapping parts are cut off
+fn center(image: &RgbImage, width: u32, height: u32) -> RgbImage {
+    let mut canvas = RgbImage::new(width, height);
+    let x = (width as i64 - image.width() as i64) / 2;
+    let y = (height as i64 - image.height() as i64) / 2;
+    image::imageops::overlay(&mut canvas, image, x, y);
+    canvas
+}
+
+impl PluginInputTrait for FilesystemImageInput {
+    fn init(&mut self) -> PluginResult<()> {
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.config.files.is_empty() {
+            return plugin_err!("File list empty");
+        }
+        if self.file_index == self.config.files.len() {
+            return plugin_err!("End of file list reached");
+        }
+
+        if self.frames.is_empty() {
+            let path = self.config.files[self.file_index].clone();
+            if let Err(error) = self.load_file(&path) {
+                // move on to the next file, so a broken file doesn't block the whole list
+                self.next_file();
+                return Err(error);
+            }
+        }
+
+        // the previous frame is shown until its delay has passed
+        if let Some(next_frame) = self.next_frame {
+            let now = Instant::now();
+            if next_frame > now {
+                thread::sleep(next_frame - now);
+            }
+        }
+
+        let frame = &self.frames[self.frame_index];
+        let (width, height) = frame.image.dimensions();
+        let rgb: Vec<RGB> = frame
+            .image
+            .pixels()
+            .map(|pixel| RGB {
+                r: pixel[0],
+                g: pixel[1],
+                b: pixel[2],
+            })
+            .collect();
+        self.next_frame = Some(Instant::now() + frame.delay);
+
+        self.frame_index += 1;
+        if self.frame_index == self.frames.len() {
+            self.frame_index = 0;
+            self.loop_count += 1;
+            // still images are shown once, a single file is played endlessly without reloading it
+            let loops = if self.frames.len() == 1 {
+                1
+            } else {
+                self.config.loops
+            };
+            let endless = self.config.repeat && self.config.files.len() == 1;
+            if self.loop_count == loops && !endless {
+                self.next_file();
+            }
+        }
+
+        Ok(plugin_data!(rgb, {
+            "width" => width,
+            "height" => height,
+        }))
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    FilesystemImageInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
@@ -0,0 +1,257 @@
+use dlopen::symbor::Library;
+use image::codecs::gif::GifEncoder;
+use image::{Delay, RgbaImage};
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn create_plugin(config: serde_json::Value) -> Box<dyn PluginInputTrait> {
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    plugin
+}
+
+fn temp_path(name: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(name);
+    path.to_str().unwrap().to_owned()
+}
+
+fn image(pixels: &[[u8; 4]], width: u32) -> RgbaImage {
+    let data: Vec<u8> = pixels.iter().flatten().cloned().collect();
+    RgbaImage::from_raw(width, pixels.len() as u32 / width, data).unwrap()
+}
+
+fn write_image(name: &str, pixels: &[[u8; 4]], width: u32) -> String {
+    let path = temp_path(name);
+    image(pixels, width).save(&path).unwrap();
+    path
+}
+
+fn write_gif(name: &str, frames: &[[u8; 4]], delay_ms: u32) -> String {
+    let path = temp_path(name);
+    let mut encoder = GifEncoder::new(File::create(&path).unwrap());
+    for pixel in frames {
+        let frame = image::Frame::from_parts(
+            image(&[*pixel], 1),
+            0,
+            0,
+            Delay::from_numer_denom_ms(delay_ms, 1),
+        );
+        encoder.encode_frame(frame).unwrap();
+    }
+    path
+}
+
+fn write_apng(name: &str, frames: &[[u8; 4]], delay_ms: u16) -> String {
+    let path = temp_path(name);
+    let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 1, 1);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_animated(frames.len() as u32, 0).unwrap();
+    encoder.set_frame_delay(delay_ms, 1000).unwrap();
+    let mut writer = encoder.write_header().unwrap();
+    for pixel in frames {
+        writer.write_image_data(pixel).unwrap();
+    }
+    writer.finish().unwrap();
+    path
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "FilesystemInputImage");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_filesystem_image");
+}
+
+#[test]
+fn test_create() {
+    assert!(call_create(&json!({"files": []})).is_ok());
+    assert!(call_create(&json!({
+        "files": [""],
+        "repeat": false,
+        "duration": 1000,
+        "loops": 2,
+        "width": 8,
+        "height": 4,
+        "fit": "cover"
+    }))
+    .is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    assert!(call_create(&json!({})).is_err());
+    assert!(call_create(&json!({"files": [], "width": 8})).is_err());
+    assert!(call_create(&json!({"files": [], "width": 0, "height": 4})).is_err());
+    assert!(call_create(&json!({"files": [], "loops": 0})).is_err());
+    assert!(call_create(&json!({"files": [], "fit": "zoom"})).is_err());
+}
+
+#[test]
+fn test_read_still_image() {
+    let path = write_image(
+        "lightoros_input_filesystem_image_still.png",
+        &[
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [200, 100, 50, 0],
+        ],
+        2,
+    );
+    let mut plugin = create_plugin(json!({"files": [path], "duration": 50}));
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.meta.get("width").unwrap(), "2");
+    assert_eq!(data.meta.get("height").unwrap(), "2");
+    assert_eq!(data.rgb.len(), 4);
+    assert_rgb(&data.rgb[0], 255, 0, 0);
+    assert_rgb(&data.rgb[1], 0, 255, 0);
+    assert_rgb(&data.rgb[2], 0, 0, 255);
+    // transparent pixels are black
+    assert_rgb(&data.rgb[3], 0, 0, 0);
+
+    // the image is shown again after the configured duration
+    let start = Instant::now();
+    let data = plugin.get().unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(40));
+    assert_rgb(&data.rgb[0], 255, 0, 0);
+}
+
+#[test]
+fn test_read_animated_gif() {
+    let path = write_gif(
+        "lightoros_input_filesystem_image_animated.gif",
+        &[[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]],
+        30,
+    );
+    let mut plugin = create_plugin(json!({"files": [path]}));
+
+    let start = Instant::now();
+    assert_rgb(&plugin.get().unwrap().rgb[0], 255, 0, 0);
+    assert_rgb(&plugin.get().unwrap().rgb[0], 0, 255, 0);
+    assert_rgb(&plugin.get().unwrap().rgb[0], 0, 0, 255);
+    assert!(start.elapsed() >= Duration::from_millis(55));
+    // the animation starts over
+    assert_rgb(&plugin.get().unwrap().rgb[0], 255, 0, 0);
+}
+
+#[test]
+fn test_read_animated_png() {
+    let path = write_apng(
+        "lightoros_input_filesystem_image_animated.png",
+        &[[10, 20, 30, 255], [40, 50, 60, 255]],
+        10,
+    );
+    let mut plugin = create_plugin(json!({"files": [path]}));
+
+    assert_rgb(&plugin.get().unwrap().rgb[0], 10, 20, 30);
+    assert_rgb(&plugin.get().unwrap().rgb[0], 40, 50, 60);
+    assert_rgb(&plugin.get().unwrap().rgb[0], 10, 20, 30);
+}
+
+#[test]
+fn test_playlist() {
+    let animation = write_gif(
+        "lightoros_input_filesystem_image_playlist.gif",
+        &[[255, 0, 0, 255], [0, 255, 0, 255]],
+        10,
+    );
+    let still = write_image(
+        "lightoros_input_filesystem_image_playlist.png",
+        &[[0, 0, 255, 255]],
+        1,
+    );
+    let missing = temp_path("lightoros_input_filesystem_image_missing.png");
+    let mut plugin = create_plugin(json!({
+        "files": [animation, missing, still],
+        "repeat": false,
+        "duration": 10,
+        "loops": 2
+    }));
+
+    assert_rgb(&plugin.get().unwrap().rgb[0], 255, 0, 0);
+    assert_rgb(&plugin.get().unwrap().rgb[0], 0, 255, 0);
+    assert_rgb(&plugin.get().unwrap().rgb[0], 255, 0, 0);
+    assert_rgb(&plugin.get().unwrap().rgb[0], 0, 255, 0);
+    // a missing file results in an error and the next file is played
+    assert!(plugin.get().is_err());
+    assert_rgb(&plugin.get().unwrap().rgb[0], 0, 0, 255);
+    // the list is played only once
+    assert!(plugin.get().is_err());
+    assert!(plugin.get().is_err());
+}
+
+#[test]
+fn test_fit() {
+    let path = write_image(
+        "lightoros_input_filesystem_image_fit.png",
+        &[[255, 0, 0, 255], [0, 255, 0, 255]],
+        2,
+    );
+    let get = |fit: &str, width: u32, height: u32| {
+        let mut plugin = create_plugin(json!({
+            "files": [path],
+            "width": width,
+            "height": height,
+            "fit": fit
+        }));
+        let data = plugin.get().unwrap();
+        assert_eq!(data.meta.get("width").unwrap(), &width.to_string());
+        assert_eq!(data.meta.get("height").unwrap(), &height.to_string());
+        data.rgb
+    };
+
+    // centered on a black background
+    let rgb = get("none", 4, 1);
+    assert_eq!(rgb.len(), 4);
+    assert_rgb(&rgb[0], 0, 0, 0);
+    assert_rgb(&rgb[1], 255, 0, 0);
+    assert_rgb(&rgb[2], 0, 255, 0);
+    assert_rgb(&rgb[3], 0, 0, 0);
+
+    // scaled to the full area
+    let rgb = get("stretch", 2, 2);
+    assert_rgb(&rgb[0], 255, 0, 0);
+    assert_rgb(&rgb[2], 255, 0, 0);
+    assert_rgb(&rgb[3], 0, 255, 0);
+
+    // keeps the aspect ratio, the rest stays black
+    let rgb = get("contain", 2, 3);
+    assert_eq!(rgb.len(), 6);
+    assert_rgb(&rgb[0], 0, 0, 0);
+    assert_rgb(&rgb[2], 255, 0, 0);
+    assert_rgb(&rgb[3], 0, 255, 0);
+    assert_rgb(&rgb[5], 0, 0, 0);
+
+    // keeps the aspect ratio, overlapping parts are cut off
+    let rgb = get("cover", 1, 1);
+    assert_eq!(rgb.len(), 1);
+    assert_rgb(&rgb[0], 255, 0, 0);
+}
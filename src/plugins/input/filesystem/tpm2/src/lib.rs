@@ -7,6 +7,7 @@ use std::collections::HashMap;
 
 use lightoros_plugin_base::*;
 use lightoros_plugin_base::input::{PluginInputTrait, CreateInputPluginResult};
+use lightoros_protocols::tpm2;
 
 use serde::Deserialize;
 
@@ -22,7 +23,8 @@ struct Config {
 
 struct FilesystemInput {
     config: Config,
-    frame_offset: usize,
+    decoder: tpm2::Decoder,
+    file_loaded: bool,
     file_index: usize
 }
 
@@ -32,7 +34,8 @@ impl FilesystemInput {
 
         let plugin = FilesystemInput {
             config,
-            frame_offset: 0,
+            decoder: tpm2::Decoder::new(),
+            file_loaded: false,
             file_index: 0,
         };
 
@@ -62,50 +65,52 @@ impl PluginInputTrait for FilesystemInput {
         }
 
         let ref current_file = self.config.files[self.file_index];
-        let path = Path::new(current_file);
-        let mut file = match File::open(&path) {
-            Ok(file) => file,
-            Err(error) => {
-                return plugin_err!("Error opening file '{}': {}", current_file, error);
+        if !self.file_loaded {
+            let path = Path::new(current_file);
+            let mut file = match File::open(&path) {
+                Ok(file) => file,
+                Err(error) => {
+                    return plugin_err!("Error opening file '{}': {}", current_file, error);
+                }
+            };
+
+            let mut data_in: Vec<u8> = Vec::new();
+            match file.read_to_end(&mut data_in) {
+                Err(error) => {
+                    return plugin_err!("Error reading file '{}': {}", current_file, error);
+                }
+                Ok(_) => {}
             }
-        };
-
-        let mut data_in: Vec<u8> = Vec::new();
-        match file.read_to_end(&mut data_in) {
-            Err(error) => {
-                return plugin_err!("Error reading file '{}': {}", current_file, error);
-            }
-            Ok(_) => {}
-        }
-
-        let mut index = self.frame_offset;
-        if data_in[index] != 0xC9 || data_in[index + 1] != 0xDA {
-            return plugin_err!("Error: '{}' is not a tpm2 file - start marker missing.", current_file);
-        }
 
-        let size: u16 = (data_in[index + 2] as u16) << 8 | data_in[index + 3] as u16;
-        let mut data_out: Vec<RGB> = Vec::with_capacity(size as usize / 3);
-        index = index + 4;
-        for _ in 0..(size / 3) {
-            data_out.push(RGB {
-                r: data_in[index],
-                g: data_in[index + 1],
-                b: data_in[index + 2],
-            });
-            index = index + 3;
+            self.decoder.clear();
+            self.decoder.push(&data_in);
+            self.file_loaded = true;
         }
 
-        if data_in[index] != 0x36 {
-            return plugin_err!("Error: '{}' is not a tpm2 file - frame end marker missing.", current_file);
-        }
+        // command and response packets don't carry LED data
+        let packet = loop {
+            match self.decoder.next_packet() {
+                Some(packet) if packet.packet_type == tpm2::PacketType::Data => break Some(packet),
+                Some(_) => continue,
+                None => break None,
+            }
+        };
+        let packet = match packet {
+            Some(packet) => packet,
+            None => {
+                // move on to the next file, so a broken file doesn't block the whole list
+                self.file_loaded = false;
+                self.file_index = self.file_index + 1;
+                return plugin_err!("Error: '{}' contains no complete tpm2 frame.", current_file);
+            }
+        };
 
-        index += 1;
+        let data_out = tpm2::decode_rgb(&packet.payload, ColorOrder::RGB);
 
-        if index == data_in.len() {
-            self.frame_offset = 0;
+        if self.decoder.pending() == 0 {
+            self.file_loaded = false;
             self.file_index = self.file_index + 1;
         } else {
-            self.frame_offset = index;
             thread::sleep(Duration::from_millis(self.config.delay_frame));
         }
 
@@ -117,12 +122,8 @@ impl PluginInputTrait for FilesystemInput {
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
     FilesystemInput::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Input)
-}
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
@@ -1,4 +1,4 @@
-use lightoros_plugin_base::*;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
 use lightoros_plugin_base::input::CreateInputPluginResult;
 use serde_json::json;
 use dlopen::symbor::Library;
@@ -6,35 +6,27 @@ use once_cell::sync::Lazy;
 use std::path::PathBuf;
 
 static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
 
-fn load_lib() -> Library {
-    let lib_path: PathBuf = LIB_PATH.to_path_buf();
-    dlopen::symbor::Library::open(&lib_path).unwrap()
-}
-
-fn get_info() -> PluginInfo {
-    let lib = load_lib();
-    let info_func = unsafe { lib.symbol::<fn() -> PluginInfo>("info").unwrap() };
-    info_func()
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
 }
 
 
 fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
-    let lib = load_lib();
-    let create_func = unsafe {
-        lib.symbol::<fn(&serde_json::Value) -> CreateInputPluginResult>("create")
-            .unwrap()
-    };
-    create_func(config)
+    unsafe { declaration().create_input(config, None) }
 }
 
 #[test]
-fn test_get_info() {
-    let plugin_info = get_info();
-    assert_eq!(plugin_info.name, "FilesystemInputTPM2");
-    assert!(plugin_info.kind == lightoros_plugin_base::PluginKind::Input);
-    assert_eq!(plugin_info.api_version, 1);
-    assert_eq!(plugin_info.filename, "lightoros_input_filesystem_tpm2");
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "FilesystemInputTPM2");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_filesystem_tpm2");
 }
 
 #[test]
@@ -84,3 +76,79 @@ fn test_create_with_empty_files_list() {
 
 }
 
+
+fn write_file(name: &str, content: &[u8]) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(name);
+    std::fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn test_read_frames() {
+    let path = write_file(
+        "lightoros_input_filesystem_tpm2_frames.tp2",
+        &[
+            0xC9, 0xDA, 0x00, 0x03, 1, 2, 3, 0x36, 0xC9, 0xDA, 0x00, 0x06, 4, 5, 6, 7, 8, 9, 0x36,
+        ],
+    );
+    let config = json!({
+        "files": [path],
+        "repeat": true,
+        "delay_frame": 0,
+        "delay_file": 0
+    });
+    let mut plugin = call_create(&config).unwrap();
+    assert!(plugin.init().is_ok());
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 1);
+    assert_eq!((data.rgb[0].r, data.rgb[0].g, data.rgb[0].b), (1, 2, 3));
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+    assert_eq!((data.rgb[1].r, data.rgb[1].g, data.rgb[1].b), (7, 8, 9));
+
+    // starts over with the first file
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 1);
+}
+
+#[test]
+fn test_read_truncated_file() {
+    let path = write_file(
+        "lightoros_input_filesystem_tpm2_truncated.tp2",
+        &[0xC9, 0xDA, 0x00, 0x06, 1, 2, 3],
+    );
+    let config = json!({
+        "files": [path],
+        "repeat": true,
+        "delay_frame": 0,
+        "delay_file": 0
+    });
+    let mut plugin = call_create(&config).unwrap();
+    assert!(plugin.init().is_ok());
+    assert!(plugin.get().is_err());
+}
+
+#[test]
+fn test_skip_command_packets() {
+    let path = write_file(
+        "lightoros_input_filesystem_tpm2_commands.tp2",
+        &[
+            0xC9, 0xC0, 0x00, 0x03, 9, 9, 9, 0x36, 0xC9, 0xDA, 0x00, 0x03, 1, 2, 3, 0x36,
+        ],
+    );
+    let config = json!({
+        "files": [path],
+        "repeat": true,
+        "delay_frame": 0,
+        "delay_file": 0
+    });
+    let mut plugin = call_create(&config).unwrap();
+    assert!(plugin.init().is_ok());
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 1);
+    assert_eq!((data.rgb[0].r, data.rgb[0].g, data.rgb[0].b), (1, 2, 3));
+}
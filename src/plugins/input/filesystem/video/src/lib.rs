@@ -0,0 +1,312 @@
+use std::ffi::CString;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, Error};
+use std::os::raw::{c_char, c_int, c_ushort};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use lightoros_protocols::y4m;
+
+use serde::Deserialize;
+
+const NAME: &str = "FilesystemInputVideo";
+
+// path used for reading from the standard input
+const STDIN_PATH: &str = "-";
+
+// longest accepted Y4M stream or frame header line
+const MAX_HEADER_SIZE: u64 = 1024;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    Y4m,
+    Rgb24,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Matrix {
+    Bt601,
+    Bt709,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Range {
+    Limited,
+    Full,
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    path: String,
+    #[serde(default = "default_format")]
+    format: Format,
+    width: Option<usize>,
+    height: Option<usize>,
+    fps: Option<f32>,
+    #[serde(default = "default_matrix")]
+    matrix: Matrix,
+    range: Option<Range>,
+    #[serde(default)]
+    create_pipe: bool,
+    #[serde(default = "default_repeat")]
+    repeat: bool,
+}
+
+fn default_format() -> Format {
+    Format::Y4m
+}
+
+fn default_matrix() -> Matrix {
+    Matrix::Bt601
+}
+
+fn default_repeat() -> bool {
+    true
+}
+
+struct FilesystemVideoInput {
+    config: Config,
+    reader: Option<Box<dyn BufRead + Send>>,
+    header: Option<y4m::Header>,
+    finished: bool,
+    next_frame: Option<Instant>,
+}
+
+impl std::fmt::Display for FilesystemVideoInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl FilesystemVideoInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        if config.format == Format::Rgb24 {
+            match (config.width, config.height) {
+                (Some(width), Some(height)) if width > 0 && height > 0 => {}
+                _ => {
+                    return plugin_err!(
+                        "Config values 'width' and 'height' are required for rgb24 streams"
+                    )
+                }
+            }
+        }
+        if let Some(fps) = config.fps {
+            if fps <= 0.0 {
+                return plugin_err!("Invalid config value 'fps': {}", fps);
+            }
+        }
+        if config.create_pipe && config.path == STDIN_PATH {
+            return plugin_err!("Cannot create a named pipe for the standard input");
+        }
+
+        let plugin = FilesystemVideoInput {
+            config,
+            reader: None,
+            header: None,
+            finished: false,
+            next_frame: None,
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn open(&mut self) -> PluginResult<()> {
+        let mut reader: Box<dyn BufRead + Send> = if self.config.path == STDIN_PATH {
+            Box::new(BufReader::new(std::io::stdin()))
+        } else {
+            // opening a pipe for reading blocks until another side opens it for writing
+            match File::open(&self.config.path) {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(err) => {
+                    return plugin_err!("Error opening file '{}': {}", self.config.path, err);
+                }
+            }
+        };
+
+        if self.config.format == Format::Y4m {
+            let line = read_line(&mut reader, &self.config.path)?;
+            self.header = Some(y4m::parse_header(&String::from_utf8_lossy(&line))?);
+        }
+        self.reader = Some(reader);
+        Ok(())
+    }
+
+    // returns None at the end of the stream
+    fn read_frame(&mut self) -> PluginResult<Option<(Vec<RGB>, usize, usize)>> {
+        let path = &self.config.path;
+        let reader = self.reader.as_mut().unwrap();
+
+        if let Some(header) = &self.header {
+            let line = read_line(reader, path)?;
+            if line.is_empty() {
+                return Ok(None);
+            }
+            y4m::parse_frame_header(&String::from_utf8_lossy(&line))?;
+
+            let mut data = vec![0u8; header.frame_size()?];
+            if let Err(err) = reader.read_exact(&mut data) {
+                return plugin_err!("Error reading frame from '{}': {}", path, err);
+            }
+
+            let matrix = match self.config.matrix {
+                Matrix::Bt601 => y4m::Matrix::Bt601,
+                Matrix::Bt709 => y4m::Matrix::Bt709,
+            };
+            // the configured range overrides the one of the stream
+            let range = match self.config.range {
+                Some(Range::Limited) => y4m::Range::Limited,
+                Some(Range::Full) => y4m::Range::Full,
+                None => header.range.unwrap_or(y4m::Range::Limited),
+            };
+            let rgb = y4m::decode_rgb(header, &data, matrix, range);
+            return Ok(Some((rgb, header.width, header.height)));
+        }
+
+        let width = self.config.width.unwrap();
+        let height = self.config.height.unwrap();
+        let mut data = vec![0u8; width * height * 3];
+        let mut size = 0;
+        while size < data.len() {
+            match reader.read(&mut data[size..]) {
+                Ok(0) if size == 0 => return Ok(None),
+                Ok(0) => return plugin_err!("Incomplete frame in '{}'", path),
+                Ok(read) => size += read,
+                Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return plugin_err!("Error reading frame from '{}': {}", path, err),
+            }
+        }
+        let rgb = data
+            .chunks(3)
+            .map(|rgb| RGB {
+                r: rgb[0],
+                g: rgb[1],
+                b: rgb[2],
+            })
+            .collect();
+        Ok(Some((rgb, width, height)))
+    }
+
+    // time between two frames, the stream is not paced if the frame rate is unknown
+    fn frame_interval(&self) -> Option<Duration> {
+        let fps = match self.config.fps {
+            Some(fps) => fps as f64,
+            None => {
+                let (num, den) = self.header.as_ref()?.frame_rate?;
+                num as f64 / den as f64
+            }
+        };
+        Some(Duration::from_secs_f64(1.0 / fps))
+    }
+}
+
+// reads a line including the line feed, an empty result means the end of the stream
+fn read_line<R: BufRead + ?Sized>(reader: &mut R, path: &str) -> PluginResult<Vec<u8>> {
+    let mut line = Vec::new();
+    if let Err(err) = reader.take(MAX_HEADER_SIZE).read_until(b'\n', &mut line) {
+        return plugin_err!("Error reading file '{}': {}", path, err);
+    }
+    if !line.is_empty() && line.last() != Some(&b'\n') {
+        return plugin_err!("Invalid Y4M header line in '{}'", path);
+    }
+    Ok(line)
+}
+
+impl PluginInputTrait for FilesystemVideoInput {
+    fn init(&mut self) -> PluginResult<()> {
+        if !self.config.create_pipe {
+            return Ok(());
+        }
+
+        let pipe_path = Path::new(&self.config.path);
+        if pipe_path.exists() {
+            if let Err(err) = std::fs::remove_file(pipe_path) {
+                return plugin_err!(
+                    "File '{}' already exists and could not be removed: {}",
+                    &self.config.path,
+                    err
+                );
+            }
+        }
+
+        let path: CString = CString::new(self.config.path.as_bytes()).unwrap();
+        let result = unsafe { mkfifo(path.as_ptr(), 0o644) };
+        if result != 0 {
+            let err = Error::last_os_error();
+            return plugin_err!(
+                "Failed to create named pipe '{}': {}",
+                &self.config.path,
+                err
+            );
+        }
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.finished {
+            return plugin_err!("End of stream '{}' reached", self.config.path);
+        }
+
+        // at the end of a file it starts over, a pipe waits for the next writer
+        let mut reopened = false;
+        let frame = loop {
+            if self.reader.is_none() {
+                self.open()?;
+            }
+            match self.read_frame() {
+                Ok(Some(frame)) => break frame,
+                Ok(None) => {
+                    self.reader = None;
+                    if reopened {
+                        return plugin_err!("'{}' contains no frames", self.config.path);
+                    }
+                    if !self.config.repeat || self.config.path == STDIN_PATH {
+                        self.finished = true;
+                        return plugin_err!("End of stream '{}' reached", self.config.path);
+                    }
+                    reopened = true;
+                }
+                Err(err) => {
+                    // the stream is opened again with the next call
+                    self.reader = None;
+                    return Err(err);
+                }
+            }
+        };
+
+        // the previous frame is shown until the frame interval has passed
+        if let Some(interval) = self.frame_interval() {
+            let now = Instant::now();
+            match self.next_frame {
+                Some(next_frame) if next_frame > now => {
+                    thread::sleep(next_frame - now);
+                    self.next_frame = Some(next_frame + interval);
+                }
+                _ => self.next_frame = Some(now + interval),
+            }
+        }
+
+        let (rgb, width, height) = frame;
+        Ok(plugin_data!(rgb, {
+            "width" => width,
+            "height" => height,
+        }))
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    FilesystemVideoInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
+
+extern "C" {
+    pub fn mkfifo(pathname: *const c_char, mode: c_ushort) -> c_int;
+}
@@ -0,0 +1,229 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn create_plugin(config: serde_json::Value) -> Box<dyn PluginInputTrait> {
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    plugin
+}
+
+fn temp_path(name: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(name);
+    path.to_str().unwrap().to_owned()
+}
+
+fn write_file(name: &str, content: &[u8]) -> String {
+    let path = temp_path(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+// 2x1 pixels, the first frame is white and black, the second one black and white
+fn y4m_stream(header: &str) -> Vec<u8> {
+    let mut stream = format!("{}\n", header).into_bytes();
+    stream.extend_from_slice(b"FRAME\n");
+    stream.extend_from_slice(&[235, 16, 128, 128, 128, 128]);
+    stream.extend_from_slice(b"FRAME Ixyz\n");
+    stream.extend_from_slice(&[16, 235, 128, 128, 128, 128]);
+    stream
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "FilesystemInputVideo");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_filesystem_video");
+}
+
+#[test]
+fn test_create() {
+    assert!(call_create(&json!({"path": "-"})).is_ok());
+    assert!(call_create(&json!({
+        "path": "/tmp/video",
+        "format": "rgb24",
+        "width": 16,
+        "height": 9,
+        "fps": 25,
+        "create_pipe": true,
+        "repeat": false
+    }))
+    .is_ok());
+    assert!(call_create(&json!({
+        "path": "/tmp/video.y4m",
+        "matrix": "bt709",
+        "range": "full"
+    }))
+    .is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    assert!(call_create(&json!({})).is_err());
+    assert!(call_create(&json!({"path": "-", "format": "rgb24"})).is_err());
+    assert!(
+        call_create(&json!({"path": "-", "format": "rgb24", "width": 0, "height": 1})).is_err()
+    );
+    assert!(call_create(&json!({"path": "-", "fps": 0})).is_err());
+    assert!(call_create(&json!({"path": "-", "matrix": "bt2020"})).is_err());
+    assert!(call_create(&json!({"path": "-", "create_pipe": true})).is_err());
+}
+
+#[test]
+fn test_read_y4m_file() {
+    let path = write_file(
+        "lightoros_input_filesystem_video.y4m",
+        &y4m_stream("YUV4MPEG2 W2 H1 F0:0 C444"),
+    );
+    let mut plugin = create_plugin(json!({"path": path}));
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.meta.get("width").unwrap(), "2");
+    assert_eq!(data.meta.get("height").unwrap(), "1");
+    assert_rgb(&data.rgb[0], 255, 255, 255);
+    assert_rgb(&data.rgb[1], 0, 0, 0);
+
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+    assert_rgb(&data.rgb[1], 255, 255, 255);
+
+    // the file starts over
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 255, 255, 255);
+}
+
+#[test]
+fn test_read_y4m_range() {
+    let path = write_file(
+        "lightoros_input_filesystem_video_range.y4m",
+        &y4m_stream("YUV4MPEG2 W2 H1 C444 XCOLORRANGE=FULL"),
+    );
+    // the range of the stream is used
+    let mut plugin = create_plugin(json!({"path": path}));
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 235, 235, 235);
+    assert_rgb(&data.rgb[1], 16, 16, 16);
+
+    // the configured range overrides it
+    let mut plugin = create_plugin(json!({"path": path, "range": "limited"}));
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 255, 255, 255);
+    assert_rgb(&data.rgb[1], 0, 0, 0);
+}
+
+#[test]
+fn test_frame_rate() {
+    let path = write_file(
+        "lightoros_input_filesystem_video_rate.y4m",
+        &y4m_stream("YUV4MPEG2 W2 H1 F20:1 C444"),
+    );
+    let mut plugin = create_plugin(json!({"path": path}));
+    let start = Instant::now();
+    for _ in 0..4 {
+        plugin.get().unwrap();
+    }
+    // 3 frame intervals of 50ms
+    assert!(start.elapsed() >= Duration::from_millis(145));
+
+    // the configured frame rate overrides the one of the stream
+    let mut plugin = create_plugin(json!({"path": path, "fps": 1000}));
+    let start = Instant::now();
+    for _ in 0..4 {
+        plugin.get().unwrap();
+    }
+    assert!(start.elapsed() < Duration::from_millis(100));
+}
+
+#[test]
+fn test_read_rgb24_file() {
+    let path = write_file(
+        "lightoros_input_filesystem_video.rgb",
+        &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
+    );
+    let mut plugin = create_plugin(json!({
+        "path": path,
+        "format": "rgb24",
+        "width": 1,
+        "height": 2,
+        "repeat": false
+    }));
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.meta.get("width").unwrap(), "1");
+    assert_eq!(data.meta.get("height").unwrap(), "2");
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[1], 4, 5, 6);
+    assert_rgb(&plugin.get().unwrap().rgb[1], 10, 11, 12);
+    // incomplete last frame
+    assert!(plugin.get().is_err());
+    assert_rgb(&plugin.get().unwrap().rgb[0], 1, 2, 3);
+}
+
+#[test]
+fn test_end_of_stream() {
+    let path = write_file(
+        "lightoros_input_filesystem_video_once.y4m",
+        &y4m_stream("YUV4MPEG2 W2 H1 C444"),
+    );
+    let mut plugin = create_plugin(json!({"path": path, "repeat": false}));
+    assert!(plugin.get().is_ok());
+    assert!(plugin.get().is_ok());
+    assert!(plugin.get().is_err());
+    assert!(plugin.get().is_err());
+
+    let path = write_file(
+        "lightoros_input_filesystem_video_invalid.y4m",
+        b"YUV4MPEG2 W2 H1 C420p10\n",
+    );
+    let mut plugin = create_plugin(json!({"path": path}));
+    assert!(plugin.get().is_err());
+}
+
+#[test]
+fn test_read_named_pipe() {
+    let path = temp_path("lightoros_input_filesystem_video_pipe");
+    let mut plugin = create_plugin(json!({"path": path, "create_pipe": true}));
+
+    let writer_path = path.clone();
+    let writer = std::thread::spawn(move || {
+        let mut pipe = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&writer_path)
+            .unwrap();
+        pipe.write_all(&y4m_stream("YUV4MPEG2 W2 H1 C444")).unwrap();
+    });
+
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 255, 255, 255);
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+    writer.join().unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
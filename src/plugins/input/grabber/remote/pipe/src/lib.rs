@@ -123,15 +123,11 @@ impl PluginInputTrait for RemoteScreenPipeGrabberInput {
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
     RemoteScreenPipeGrabberInput::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Input)
-}
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
 
 extern "C" {
     pub fn mkfifo(pathname: *const c_char, mode: c_ushort) -> c_int;
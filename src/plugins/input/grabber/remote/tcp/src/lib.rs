@@ -172,12 +172,8 @@ impl PluginInputTrait for RemoteScreenTcpGrabberInput {
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
     RemoteScreenTcpGrabberInput::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Input)
-}
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
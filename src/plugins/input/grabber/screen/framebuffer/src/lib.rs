@@ -147,15 +147,11 @@ impl PluginInputTrait for FramebufferScreenGrabberInput {
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
     FramebufferScreenGrabberInput::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Input)
-}
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
 
 #[repr(C)]
 #[derive(Clone, Default, Debug)]
@@ -125,15 +125,11 @@ impl PluginInputTrait for OsxScreenGrabberInput {
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
     OsxScreenGrabberInput::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Input)
-}
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
 
 #[link(name = "CoreFoundation", kind = "framework")]
 extern "C" {
@@ -1,4 +1,4 @@
-use lightoros_plugin_base::*;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
 use lightoros_plugin_base::input::CreateInputPluginResult;
 use serde_json::json;
 use dlopen::symbor::Library;
@@ -6,34 +6,26 @@ use once_cell::sync::Lazy;
 use std::path::PathBuf;
 
 static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
 
-fn load_lib() -> Library {
-    let lib_path: PathBuf = LIB_PATH.to_path_buf();
-    dlopen::symbor::Library::open(&lib_path).unwrap()
-}
-
-fn get_info() -> PluginInfo {
-    let lib = load_lib();
-    let info_func = unsafe { lib.symbol::<fn() -> PluginInfo>("info").unwrap() };
-    info_func()
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
 }
 
 fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
-    let lib = load_lib();
-    let create_func = unsafe {
-        lib.symbol::<fn(&serde_json::Value) -> CreateInputPluginResult>("create")
-            .unwrap()
-    };
-    create_func(config)
+    unsafe { declaration().create_input(config, None) }
 }
 
 #[test]
-fn test_get_info() {
-    let plugin_info = get_info();
-    assert_eq!(plugin_info.name, "OsxScreenGrabberInput");
-    assert!(plugin_info.kind == lightoros_plugin_base::PluginKind::Input);
-    assert_eq!(plugin_info.api_version, 1);
-    assert_eq!(plugin_info.filename, "lightoros_input_grabber_screen_osx");
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "OsxScreenGrabberInput");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_grabber_screen_osx");
 }
 
 #[test]
@@ -135,15 +135,11 @@ impl PluginInputTrait for X11ScreenGrabberInput {
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
     X11ScreenGrabberInput::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Input)
-}
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
 
 #[allow(non_upper_case_globals)]
 static ZPixmap: c_int = 2;
@@ -0,0 +1,307 @@
+use std::io::prelude::*;
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use lightoros_protocols::boblight;
+use lightoros_protocols::boblight::{Command, LightProperty};
+
+use serde::Deserialize;
+
+const NAME: &str = "NetTcpInputBoblight";
+
+// clients with this priority are not shown
+const PRIORITY_OFF: i32 = 255;
+// longest command line, clients sending longer lines are disconnected
+const MAX_LINE_LENGTH: usize = 1024 * 1024;
+
+#[derive(Deserialize, Debug)]
+struct LightConfig {
+    name: String,
+    #[serde(default = "default_scan")]
+    vscan: [f32; 2],
+    #[serde(default = "default_scan")]
+    hscan: [f32; 2],
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    #[serde(default = "default_address")]
+    address: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    lights: Vec<LightConfig>,
+}
+
+fn default_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_port() -> u16 {
+    boblight::PORT
+}
+
+fn default_scan() -> [f32; 2] {
+    [0.0, 100.0]
+}
+
+struct Client {
+    id: usize,
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    priority: i32,
+    rgb: Vec<RGB>,
+    used: Vec<bool>,
+    connected: bool,
+}
+
+impl Client {
+    fn send(&mut self, response: &str) {
+        if self.stream.write_all(response.as_bytes()).is_err() {
+            self.connected = false;
+        }
+    }
+}
+
+struct NetTcpBoblightInput {
+    lights: Vec<boblight::Light>,
+    address: String,
+    listener: Option<TcpListener>,
+    clients: Vec<Client>,
+    next_client_id: usize,
+    // id of the client whose lights are shown
+    visible: Option<usize>,
+}
+
+impl std::fmt::Display for NetTcpBoblightInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl NetTcpBoblightInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        if config.lights.is_empty() {
+            return plugin_err!("No lights configured");
+        }
+        let mut lights: Vec<boblight::Light> = Vec::with_capacity(config.lights.len());
+        for light in config.lights {
+            if light.name.is_empty() || light.name.contains(char::is_whitespace) {
+                return plugin_err!("Invalid light name: '{}'", light.name);
+            }
+            if lights.iter().any(|other| other.name == light.name) {
+                return plugin_err!("Duplicate light name: '{}'", light.name);
+            }
+            for value in light.vscan.iter().chain(light.hscan.iter()) {
+                if *value < 0.0 || *value > 100.0 {
+                    return plugin_err!(
+                        "Invalid scan area of light '{}'. Valid range: [0-100]",
+                        light.name
+                    );
+                }
+            }
+            lights.push(boblight::Light {
+                name: light.name,
+                vscan: light.vscan,
+                hscan: light.hscan,
+            });
+        }
+
+        let plugin = NetTcpBoblightInput {
+            lights,
+            address: format!("{}:{}", config.address, config.port),
+            listener: None,
+            clients: Vec::new(),
+            next_client_id: 0,
+            visible: None,
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn accept_clients(&mut self) -> PluginResult<()> {
+        loop {
+            match self.listener.as_ref().unwrap().accept() {
+                Ok((stream, _)) => {
+                    if let Err(err) = stream.set_nonblocking(true) {
+                        return plugin_err!(
+                            "Error configuring boblight client connection: {}",
+                            err
+                        );
+                    }
+                    self.clients.push(Client {
+                        id: self.next_client_id,
+                        stream,
+                        buffer: Vec::new(),
+                        priority: PRIORITY_OFF,
+                        rgb: vec![RGB { r: 0, g: 0, b: 0 }; self.lights.len()],
+                        used: vec![true; self.lights.len()],
+                        connected: true,
+                    });
+                    self.next_client_id += 1;
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => {
+                    return plugin_err!("Error accepting boblight client connection: {}", err)
+                }
+            }
+        }
+    }
+
+    // reads and handles the commands of all clients, returns if any data was received
+    // and the ids of the clients which sent a sync command
+    fn read_clients(&mut self, buffer: &mut [u8]) -> (bool, Vec<usize>) {
+        let mut received = false;
+        let mut synced = Vec::new();
+
+        for client in self.clients.iter_mut() {
+            match client.stream.read(buffer) {
+                Ok(0) => client.connected = false,
+                Ok(size) => {
+                    received = true;
+                    client.buffer.extend_from_slice(&buffer[..size]);
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(_) => client.connected = false,
+            }
+
+            while let Some(position) = client.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = client.buffer.drain(..=position).collect();
+                let line = String::from_utf8_lossy(&line);
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match boblight::parse_command(&line) {
+                    Ok(Command::Sync) => synced.push(client.id),
+                    Ok(command) => handle_command(client, &self.lights, command),
+                    // boblightd drops clients sending invalid commands as well
+                    Err(_) => client.connected = false,
+                }
+                if !client.connected {
+                    break;
+                }
+            }
+            if client.buffer.len() > MAX_LINE_LENGTH {
+                client.connected = false;
+            }
+        }
+        (received, synced)
+    }
+
+    // the connected client with the lowest priority value is visible
+    fn find_visible(&self) -> Option<usize> {
+        self.clients
+            .iter()
+            .filter(|client| client.connected && client.priority < PRIORITY_OFF)
+            .min_by_key(|client| client.priority)
+            .map(|client| client.id)
+    }
+
+    fn create_frame(&self) -> TraitData {
+        let client = self
+            .visible
+            .and_then(|id| self.clients.iter().find(|client| client.id == id));
+        let rgb: Vec<RGB> = match client {
+            Some(client) => client
+                .rgb
+                .iter()
+                .zip(client.used.iter())
+                .map(|(rgb, used)| {
+                    if *used {
+                        *rgb
+                    } else {
+                        RGB { r: 0, g: 0, b: 0 }
+                    }
+                })
+                .collect(),
+            // no visible client anymore, turn the LEDs off
+            None => vec![RGB { r: 0, g: 0, b: 0 }; self.lights.len()],
+        };
+        let pixels = rgb.len();
+        plugin_data!(rgb, {
+            "pixels" => pixels,
+        })
+    }
+}
+
+fn handle_command(client: &mut Client, lights: &[boblight::Light], command: Command) {
+    match command {
+        Command::Hello => client.send("hello\n"),
+        Command::Ping => client.send("ping 1\n"),
+        Command::GetVersion => client.send(&format!("version {}\n", boblight::PROTOCOL_VERSION)),
+        Command::GetLights => client.send(&boblight::encode_lights(lights)),
+        Command::SetPriority(priority) => client.priority = priority,
+        Command::SetLight { name, property } => {
+            // unknown lights are ignored like in boblightd
+            let index = match lights.iter().position(|light| light.name == name) {
+                Some(index) => index,
+                None => return,
+            };
+            match property {
+                LightProperty::Rgb(color) => {
+                    client.rgb[index] = RGB {
+                        r: boblight::to_color_value(color[0]),
+                        g: boblight::to_color_value(color[1]),
+                        b: boblight::to_color_value(color[2]),
+                    }
+                }
+                LightProperty::Use(used) => client.used[index] = used,
+                // smoothing is not supported
+                LightProperty::Speed(_)
+                | LightProperty::Interpolation(_)
+                | LightProperty::SingleChange(_) => {}
+            }
+        }
+        Command::Sync => {}
+    }
+}
+
+impl PluginInputTrait for NetTcpBoblightInput {
+    fn init(&mut self) -> PluginResult<()> {
+        let listener = match TcpListener::bind(&self.address) {
+            Ok(listener) => listener,
+            Err(err) => {
+                return plugin_err!("Error binding TCP socket to '{}': {}", self.address, err)
+            }
+        };
+        if let Err(err) = listener.set_nonblocking(true) {
+            return plugin_err!("Error configuring TCP socket: {}", err);
+        }
+        self.listener = Some(listener);
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.listener.is_none() {
+            return plugin_err!("TCP socket not initialized");
+        }
+
+        let mut buffer = vec![0u8; 65536];
+        loop {
+            self.accept_clients()?;
+            let (received, synced) = self.read_clients(&mut buffer);
+
+            let visible = self.find_visible();
+            let changed = visible != self.visible;
+            self.visible = visible;
+            let updated = visible.is_some_and(|id| synced.contains(&id));
+            self.clients.retain(|client| client.connected);
+
+            if changed || updated {
+                return Ok(self.create_frame());
+            }
+
+            if !received {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    NetTcpBoblightInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
@@ -0,0 +1,200 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::CreateInputPluginResult;
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn create_plugin(
+    config: serde_json::Value,
+) -> (Box<dyn lightoros_plugin_base::input::PluginInputTrait>, u16) {
+    let port = config["port"].as_u64().unwrap() as u16;
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    (plugin, port)
+}
+
+fn lights_config(port: u16) -> serde_json::Value {
+    json!({
+        "address": "127.0.0.1",
+        "port": port,
+        "lights": [
+            { "name": "left", "hscan": [0, 50] },
+            { "name": "right", "hscan": [50, 100] }
+        ]
+    })
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "NetTcpInputBoblight");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_net_tcp_boblight");
+}
+
+#[test]
+fn test_create() {
+    let config = json!({ "lights": [{ "name": "1" }] });
+    assert!(call_create(&config).is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    let config = json!({});
+    assert!(call_create(&config).is_err());
+    let config = json!({ "lights": [] });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "lights": [{ "name": "1" }, { "name": "1" }] });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "lights": [{ "name": "a b" }] });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "lights": [{ "name": "1", "vscan": [0, 101] }] });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_handshake() {
+    let (mut plugin, port) = create_plugin(lights_config(46570));
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client
+        .write_all(b"hello\nget version\nget lights\nping\nset priority 128\n")
+        .unwrap();
+    // the client becomes visible with the priority, all lights are off
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+
+    let mut reader = BufReader::new(client);
+    let mut response = String::new();
+    for _ in 0..6 {
+        reader.read_line(&mut response).unwrap();
+    }
+    assert_eq!(
+        response,
+        "hello\nversion 5\nlights 2\nlight left scan 0 100 0 50\nlight right scan 0 100 50 100\nping 1\n"
+    );
+}
+
+#[test]
+fn test_set_lights() {
+    let (mut plugin, port) = create_plugin(lights_config(46571));
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client.write_all(b"set priority 128\n").unwrap();
+    plugin.get().unwrap();
+
+    client
+        .write_all(b"set light left rgb 1.0 0.5 0.0\nset light right rgb 0 0 1\nset light unknown rgb 1 1 1\nsync\n")
+        .unwrap();
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 255, 128, 0);
+    assert_rgb(&data.rgb[1], 0, 0, 255);
+    assert_eq!(data.meta.get("pixels").unwrap(), "2");
+
+    // unused lights are off
+    client
+        .write_all(b"set light right use 0\nset light left speed 50.0\nsync\n")
+        .unwrap();
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 255, 128, 0);
+    assert_rgb(&data.rgb[1], 0, 0, 0);
+}
+
+#[test]
+fn test_priorities() {
+    let (mut plugin, port) = create_plugin(lights_config(46572));
+
+    let mut client1 = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client1.write_all(b"set priority 100\n").unwrap();
+    plugin.get().unwrap();
+
+    // a client with a higher priority value is hidden, its sync doesn't provide a frame
+    let mut client2 = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client2
+        .write_all(b"set priority 200\nset light left rgb 1 0 0\nsync\n")
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    client1
+        .write_all(b"set light left rgb 0 1 0\nsync\n")
+        .unwrap();
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 255, 0);
+
+    // the next client takes over when the visible one disconnects
+    drop(client1);
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 255, 0, 0);
+
+    // no visible client anymore
+    client2.write_all(b"set priority 255\n").unwrap();
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+}
+
+#[test]
+fn test_invalid_command_disconnects() {
+    let (mut plugin, port) = create_plugin(lights_config(46573));
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client.write_all(b"set priority 1\n").unwrap();
+    plugin.get().unwrap();
+
+    client.write_all(b"set light left rgb red\n").unwrap();
+    // the client is dropped, the lights are turned off
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+    let mut buffer = [0u8; 16];
+    assert_eq!(client.read(&mut buffer).unwrap(), 0);
+}
+
+#[test]
+fn test_line_too_long_disconnects() {
+    let (mut plugin, port) = create_plugin(lights_config(46574));
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client.write_all(b"set priority 1\n").unwrap();
+    plugin.get().unwrap();
+
+    // the client is dropped instead of buffering the line
+    let mut stream = client.try_clone().unwrap();
+    let writer = std::thread::spawn(move || {
+        let chunk = vec![b' '; 64 * 1024];
+        for _ in 0..20 {
+            if stream.write_all(&chunk).is_err() {
+                break;
+            }
+        }
+    });
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+    writer.join().unwrap();
+    let mut buffer = [0u8; 16];
+    let result = client.read(&mut buffer);
+    assert!(matches!(result, Ok(0) | Err(_)), "{:?}", result);
+}
@@ -0,0 +1,438 @@
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use lightoros_protocols::hyperion;
+use lightoros_protocols::hyperion::{Command, Reply};
+
+use serde::Deserialize;
+
+const NAME: &str = "NetTcpInputHyperion";
+
+// replies queued for a client not reading them, the connection is closed above this size
+const MAX_OUTPUT_LENGTH: usize = 4 * 1024 * 1024;
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    #[serde(default = "default_address")]
+    address: String,
+    #[serde(default = "default_protocols")]
+    protocols: Vec<String>,
+    #[serde(default = "default_flatbuffers_port")]
+    flatbuffers_port: u16,
+    #[serde(default = "default_protobuf_port")]
+    protobuf_port: u16,
+    #[serde(default = "default_size")]
+    width: usize,
+    #[serde(default = "default_size")]
+    height: usize,
+    #[serde(default = "default_refresh_interval")]
+    refresh_interval: u64,
+}
+
+fn default_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_protocols() -> Vec<String> {
+    vec!["flatbuffers".to_string(), "protobuf".to_string()]
+}
+
+fn default_flatbuffers_port() -> u16 {
+    hyperion::FLATBUFFERS_PORT
+}
+
+fn default_protobuf_port() -> u16 {
+    hyperion::PROTOBUF_PORT
+}
+
+fn default_size() -> usize {
+    1
+}
+
+fn default_refresh_interval() -> u64 {
+    1000
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Protocol {
+    Flatbuffers,
+    Protobuf,
+}
+
+struct Client {
+    id: usize,
+    protocol: Protocol,
+    stream: TcpStream,
+    decoder: hyperion::MessageDecoder,
+    // priority set by the flatbuffers register command
+    priority: Option<i32>,
+    // replies not taken by the socket yet
+    output: Vec<u8>,
+    connected: bool,
+}
+
+impl Client {
+    fn send_reply(&mut self, reply: &Reply) {
+        let message = match self.protocol {
+            Protocol::Flatbuffers => hyperion::flatbuffers::encode_reply(reply),
+            Protocol::Protobuf => hyperion::protobuf::encode_reply(reply),
+        };
+        self.output
+            .extend_from_slice(&hyperion::frame_message(&message));
+        self.flush();
+        if self.output.len() > MAX_OUTPUT_LENGTH {
+            self.connected = false;
+        }
+    }
+
+    // writes as much of the queued replies as the socket takes
+    fn flush(&mut self) {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => {
+                    self.connected = false;
+                    return;
+                }
+                Ok(size) => {
+                    self.output.drain(..size);
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => return,
+                Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) => {
+                    self.connected = false;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+struct Source {
+    client: usize,
+    rgb: Vec<RGB>,
+    width: usize,
+    height: usize,
+    expires: Option<Instant>,
+}
+
+struct NetTcpHyperionInput {
+    config: Config,
+    protocols: Vec<Protocol>,
+    listeners: Vec<(Protocol, TcpListener)>,
+    clients: Vec<Client>,
+    next_client_id: usize,
+    // Hyperion priorities, the lowest value is visible
+    sources: BTreeMap<i32, Source>,
+    last_sent: Instant,
+}
+
+impl std::fmt::Display for NetTcpHyperionInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl NetTcpHyperionInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        if config.width == 0 || config.height == 0 {
+            return plugin_err!("Invalid frame size {}x{}", config.width, config.height);
+        }
+
+        let mut protocols = Vec::new();
+        for protocol in &config.protocols {
+            match protocol.as_str() {
+                "flatbuffers" => protocols.push(Protocol::Flatbuffers),
+                "protobuf" => protocols.push(Protocol::Protobuf),
+                _ => return plugin_err!("Unsupported protocol: {}", protocol),
+            }
+        }
+        if protocols.is_empty() {
+            return plugin_err!("No protocol configured");
+        }
+
+        let plugin = NetTcpHyperionInput {
+            config,
+            protocols,
+            listeners: Vec::new(),
+            clients: Vec::new(),
+            next_client_id: 0,
+            sources: BTreeMap::new(),
+            last_sent: Instant::now(),
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn bind(&self, protocol: Protocol) -> PluginResult<TcpListener> {
+        let port = match protocol {
+            Protocol::Flatbuffers => self.config.flatbuffers_port,
+            Protocol::Protobuf => self.config.protobuf_port,
+        };
+        let address = format!("{}:{}", self.config.address, port);
+        let listener = match TcpListener::bind(&address) {
+            Ok(listener) => listener,
+            Err(err) => return plugin_err!("Error binding TCP socket to '{}': {}", address, err),
+        };
+        if let Err(err) = listener.set_nonblocking(true) {
+            return plugin_err!("Error configuring TCP socket: {}", err);
+        }
+        Ok(listener)
+    }
+
+    fn accept_clients(&mut self) -> PluginResult<()> {
+        for (protocol, listener) in &self.listeners {
+            loop {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(err) = stream.set_nonblocking(true) {
+                            return plugin_err!("Error configuring Hyperion client connection: {}", err);
+                        }
+                        self.clients.push(Client {
+                            id: self.next_client_id,
+                            protocol: *protocol,
+                            stream,
+                            decoder: hyperion::MessageDecoder::new(),
+                            priority: None,
+                            output: Vec::new(),
+                            connected: true,
+                        });
+                        self.next_client_id += 1;
+                    }
+                    Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(err) => {
+                        return plugin_err!("Error accepting Hyperion client connection: {}", err)
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // reads and handles the commands of all clients, returns if any data was received and if the sources changed
+    fn read_clients(&mut self, buffer: &mut [u8]) -> (bool, bool) {
+        let mut received = false;
+        let mut changed = false;
+
+        for index in 0..self.clients.len() {
+            self.clients[index].flush();
+            match self.clients[index].stream.read(buffer) {
+                Ok(0) => self.clients[index].connected = false,
+                Ok(size) => {
+                    received = true;
+                    self.clients[index].decoder.push(&buffer[..size]);
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(_) => self.clients[index].connected = false,
+            }
+
+            loop {
+                let message = match self.clients[index].decoder.next_message() {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
+                    Err(_) => {
+                        self.clients[index].connected = false;
+                        break;
+                    }
+                };
+                let command = match self.clients[index].protocol {
+                    Protocol::Flatbuffers => hyperion::flatbuffers::decode_request(&message),
+                    Protocol::Protobuf => hyperion::protobuf::decode_request(&message),
+                };
+                let reply = match command {
+                    Some(command) => match self.handle_command(index, command) {
+                        Ok((reply, sources_changed)) => {
+                            changed |= sources_changed;
+                            reply
+                        }
+                        Err(err) => Reply {
+                            error: Some(err.to_string()),
+                            ..Default::default()
+                        },
+                    },
+                    None => Reply {
+                        error: Some("Invalid message".to_string()),
+                        ..Default::default()
+                    },
+                };
+                self.clients[index].send_reply(&reply);
+            }
+        }
+        (received, changed)
+    }
+
+    fn handle_command(&mut self, index: usize, command: Command) -> PluginResult<(Reply, bool)> {
+        let client = &mut self.clients[index];
+        match command {
+            Command::Register { priority, .. } => {
+                client.priority = Some(priority);
+                let reply = Reply {
+                    registered: Some(priority),
+                    ..Default::default()
+                };
+                Ok((reply, false))
+            }
+            Command::Color {
+                priority,
+                color,
+                duration,
+            } => {
+                let priority = match priority.or(client.priority) {
+                    Some(priority) => priority,
+                    None => return plugin_err!("Client is not registered"),
+                };
+                let size = self.config.width * self.config.height;
+                let rgb = RGB {
+                    r: color[0],
+                    g: color[1],
+                    b: color[2],
+                };
+                let source = Source {
+                    client: client.id,
+                    rgb: vec![rgb; size],
+                    width: self.config.width,
+                    height: self.config.height,
+                    expires: expires(duration),
+                };
+                self.sources.insert(priority, source);
+                Ok((Reply::default(), true))
+            }
+            Command::Image {
+                priority,
+                width,
+                height,
+                data,
+                duration,
+            } => {
+                let priority = match priority.or(client.priority) {
+                    Some(priority) => priority,
+                    None => return plugin_err!("Client is not registered"),
+                };
+                if width <= 0 || height <= 0 {
+                    return plugin_err!("Invalid image size {}x{}", width, height);
+                }
+                let size = width as usize * height as usize;
+                // RGBA images are accepted as well, the alpha channel is ignored
+                let bytes_per_pixel = match data.len() / size {
+                    3 | 4 if data.len() % size == 0 => data.len() / size,
+                    _ => return plugin_err!("Image data doesn't match the size {}x{}", width, height),
+                };
+                let source = Source {
+                    client: client.id,
+                    rgb: data
+                        .chunks_exact(bytes_per_pixel)
+                        .map(|pixel| RGB {
+                            r: pixel[0],
+                            g: pixel[1],
+                            b: pixel[2],
+                        })
+                        .collect(),
+                    width: width as usize,
+                    height: height as usize,
+                    expires: expires(duration),
+                };
+                self.sources.insert(priority, source);
+                Ok((Reply::default(), true))
+            }
+            Command::Clear { priority } => {
+                if priority < 0 {
+                    self.sources.clear();
+                } else {
+                    self.sources.remove(&priority);
+                }
+                Ok((Reply::default(), true))
+            }
+            Command::ClearAll => {
+                self.sources.clear();
+                Ok((Reply::default(), true))
+            }
+        }
+    }
+
+    // drops disconnected clients including their sources and expired sources
+    fn remove_sources(&mut self) -> bool {
+        let count = self.sources.len();
+        let now = Instant::now();
+        let disconnected: Vec<usize> = self
+            .clients
+            .iter()
+            .filter(|client| !client.connected)
+            .map(|client| client.id)
+            .collect();
+        self.clients.retain(|client| client.connected);
+        self.sources.retain(|_, source| {
+            !disconnected.contains(&source.client) && source.expires.is_none_or(|time| time > now)
+        });
+        self.sources.len() != count
+    }
+
+    fn create_frame(&mut self) -> TraitData {
+        self.last_sent = Instant::now();
+        match self.sources.values().next() {
+            Some(source) => plugin_data!(source.rgb.clone(), {
+                "width" => source.width,
+                "height" => source.height,
+            }),
+            // all priorities are cleared, turn the LEDs off
+            None => plugin_data!(vec![RGB { r: 0, g: 0, b: 0 }; self.config.width * self.config.height], {
+                "width" => self.config.width,
+                "height" => self.config.height,
+            }),
+        }
+    }
+}
+
+fn expires(duration: i32) -> Option<Instant> {
+    if duration > 0 {
+        Some(Instant::now() + Duration::from_millis(duration as u64))
+    } else {
+        None
+    }
+}
+
+impl PluginInputTrait for NetTcpHyperionInput {
+    fn init(&mut self) -> PluginResult<()> {
+        let mut listeners = Vec::with_capacity(self.protocols.len());
+        for protocol in &self.protocols {
+            listeners.push((*protocol, self.bind(*protocol)?));
+        }
+        self.listeners = listeners;
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.listeners.is_empty() {
+            return plugin_err!("TCP sockets not initialized");
+        }
+
+        let refresh_interval = Duration::from_millis(self.config.refresh_interval);
+        let mut buffer = vec![0u8; 65536];
+        loop {
+            self.accept_clients()?;
+            let (received, mut changed) = self.read_clients(&mut buffer);
+            changed |= self.remove_sources();
+
+            if changed {
+                return Ok(self.create_frame());
+            }
+            // static content like colors is sent again, so the engine keeps this input active
+            if !self.sources.is_empty() && self.last_sent.elapsed() >= refresh_interval {
+                return Ok(self.create_frame());
+            }
+
+            if !received {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    NetTcpHyperionInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
@@ -0,0 +1,290 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::CreateInputPluginResult;
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use lightoros_protocols::hyperion::*;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::time;
+
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn create_plugin(flatbuffers_port: u16, protobuf_port: u16) -> Box<dyn lightoros_plugin_base::input::PluginInputTrait> {
+    let config = json!({
+        "address": "127.0.0.1",
+        "flatbuffers_port": flatbuffers_port,
+        "protobuf_port": protobuf_port,
+        "width": 2,
+        "height": 1,
+        "refresh_interval": 200
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    plugin
+}
+
+fn send(stream: &mut TcpStream, message: &[u8]) {
+    stream.write_all(&frame_message(message)).unwrap();
+}
+
+fn read_message(stream: &mut TcpStream) -> Vec<u8> {
+    let mut size = [0u8; 4];
+    stream.read_exact(&mut size).unwrap();
+    let mut message = vec![0u8; u32::from_be_bytes(size) as usize];
+    stream.read_exact(&mut message).unwrap();
+    message
+}
+
+fn send_flatbuffers(stream: &mut TcpStream, command: &Command) {
+    send(stream, &flatbuffers::encode_request(command).unwrap());
+}
+
+fn send_protobuf(stream: &mut TcpStream, command: &Command) {
+    send(stream, &protobuf::encode_request(command).unwrap());
+}
+
+fn register(port: u16, priority: i32) -> TcpStream {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let command = Command::Register {
+        origin: "test".to_string(),
+        priority,
+    };
+    send_flatbuffers(&mut stream, &command);
+    stream
+}
+
+fn color(priority: Option<i32>, color: [u8; 3], duration: i32) -> Command {
+    Command::Color {
+        priority,
+        color,
+        duration,
+    }
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "NetTcpInputHyperion");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_net_tcp_hyperion");
+}
+
+#[test]
+fn test_create() {
+    let config = json!({});
+    assert!(call_create(&config).is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    let config = json!({ "protocols": ["json"] });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "width": 0 });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_flatbuffers_image() {
+    let mut plugin = create_plugin(46550, 46551);
+
+    let mut stream = register(46550, 150);
+    let image = Command::Image {
+        priority: None,
+        width: 3,
+        height: 2,
+        data: (0..18).collect(),
+        duration: DURATION_INFINITE,
+    };
+    send_flatbuffers(&mut stream, &image);
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 6);
+    assert_rgb(&data.rgb[0], 0, 1, 2);
+    assert_rgb(&data.rgb[5], 15, 16, 17);
+    assert_eq!(data.meta.get("width").unwrap(), "3");
+    assert_eq!(data.meta.get("height").unwrap(), "2");
+
+    let reply = flatbuffers::decode_reply(&read_message(&mut stream)).unwrap();
+    assert_eq!(reply.registered, Some(150));
+    assert_eq!(reply.error, None);
+    let reply = flatbuffers::decode_reply(&read_message(&mut stream)).unwrap();
+    assert_eq!(reply.error, None);
+}
+
+#[test]
+fn test_flatbuffers_errors() {
+    let mut plugin = create_plugin(46552, 46553);
+
+    // commands are rejected until the client is registered
+    let mut stream = TcpStream::connect(("127.0.0.1", 46552)).unwrap();
+    send_flatbuffers(&mut stream, &color(None, [1, 2, 3], DURATION_INFINITE));
+    send(&mut stream, &[1, 2, 3]);
+    let command = Command::Register {
+        origin: "test".to_string(),
+        priority: 100,
+    };
+    send_flatbuffers(&mut stream, &command);
+    let image = Command::Image {
+        priority: None,
+        width: 3,
+        height: 2,
+        data: vec![0; 10],
+        duration: DURATION_INFINITE,
+    };
+    send_flatbuffers(&mut stream, &image);
+    send_flatbuffers(&mut stream, &color(None, [1, 2, 3], DURATION_INFINITE));
+
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+
+    let replies: Vec<Reply> = (0..5)
+        .map(|_| flatbuffers::decode_reply(&read_message(&mut stream)).unwrap())
+        .collect();
+    assert!(replies[0].error.is_some());
+    assert!(replies[1].error.is_some());
+    assert_eq!(replies[2].registered, Some(100));
+    assert!(replies[3].error.is_some());
+    assert_eq!(replies[4].error, None);
+}
+
+#[test]
+fn test_protobuf_color() {
+    let mut plugin = create_plugin(46554, 46555);
+
+    let mut stream = TcpStream::connect(("127.0.0.1", 46555)).unwrap();
+    send_protobuf(&mut stream, &color(Some(100), [255, 128, 0], DURATION_INFINITE));
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+    assert_rgb(&data.rgb[0], 255, 128, 0);
+    assert_rgb(&data.rgb[1], 255, 128, 0);
+    assert_eq!(data.meta.get("width").unwrap(), "2");
+    assert_eq!(data.meta.get("height").unwrap(), "1");
+
+    let reply = protobuf::decode_reply(&read_message(&mut stream)).unwrap();
+    assert_eq!(reply.error, None);
+
+    // colors are sent again to keep the input active
+    let start = time::Instant::now();
+    let data = plugin.get().unwrap();
+    assert!(start.elapsed() >= time::Duration::from_millis(150));
+    assert_rgb(&data.rgb[0], 255, 128, 0);
+}
+
+#[test]
+fn test_priorities() {
+    let mut plugin = create_plugin(46556, 46557);
+
+    let mut flatbuffers_client = register(46556, 150);
+    send_flatbuffers(&mut flatbuffers_client, &color(None, [1, 1, 1], DURATION_INFINITE));
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 1, 1);
+
+    // the lower value has the higher priority
+    let mut protobuf_client = TcpStream::connect(("127.0.0.1", 46557)).unwrap();
+    send_protobuf(&mut protobuf_client, &color(Some(100), [2, 2, 2], DURATION_INFINITE));
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 2, 2, 2);
+
+    send_flatbuffers(&mut flatbuffers_client, &color(None, [3, 3, 3], DURATION_INFINITE));
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 2, 2, 2);
+
+    // the priority of a disconnected client is cleared
+    drop(protobuf_client);
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 3, 3, 3);
+
+    // clearing the last priority turns the LEDs off
+    send_flatbuffers(&mut flatbuffers_client, &Command::Clear { priority: 150 });
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+}
+
+#[test]
+fn test_duration() {
+    let mut plugin = create_plugin(46558, 46559);
+
+    let mut stream = TcpStream::connect(("127.0.0.1", 46559)).unwrap();
+    send_protobuf(&mut stream, &color(Some(200), [1, 1, 1], DURATION_INFINITE));
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 1, 1);
+
+    send_protobuf(&mut stream, &color(Some(100), [2, 2, 2], 100));
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 2, 2, 2);
+
+    let start = time::Instant::now();
+    let data = plugin.get().unwrap();
+    assert!(start.elapsed() >= time::Duration::from_millis(80));
+    assert_rgb(&data.rgb[0], 1, 1, 1);
+}
+
+#[test]
+fn test_slow_reader() {
+    let mut plugin = create_plugin(46544, 46545);
+
+    let mut stream = TcpStream::connect(("127.0.0.1", 46545)).unwrap();
+    // a small receive buffer makes the replies pile up on the server side
+    let size: libc::c_int = 4096;
+    let result = unsafe {
+        libc::setsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVBUF,
+            &size as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    assert_eq!(result, 0);
+    send_protobuf(&mut stream, &color(Some(100), [1, 1, 1], DURATION_INFINITE));
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 1, 1);
+
+    // about 5 MB of error replies, more than the socket buffers take
+    let mut writer = stream.try_clone().unwrap();
+    let sender = std::thread::spawn(move || {
+        writer.write_all(&frame_message(&[0xFF]).repeat(200000)).unwrap();
+    });
+    while !sender.is_finished() {
+        plugin.get().unwrap();
+    }
+    sender.join().unwrap();
+
+    // the client keeps its priority while the server works through the requests
+    let start = time::Instant::now();
+    while start.elapsed() < time::Duration::from_millis(1000) {
+        let data = plugin.get().unwrap();
+        assert_rgb(&data.rgb[0], 1, 1, 1);
+    }
+
+    // the queued replies are sent in order
+    let reply = protobuf::decode_reply(&read_message(&mut stream)).unwrap();
+    assert!(reply.error.is_none());
+    for _ in 0..10 {
+        let reply = protobuf::decode_reply(&read_message(&mut stream)).unwrap();
+        assert!(reply.error.is_some());
+    }
+}
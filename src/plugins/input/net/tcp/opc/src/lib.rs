@@ -0,0 +1,230 @@
+use std::io::prelude::*;
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use lightoros_protocols::opc;
+
+use serde::Deserialize;
+
+const NAME: &str = "NetTcpInputOPC";
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    #[serde(default = "default_address")]
+    address: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    #[serde(default)]
+    channel: u8,
+    #[serde(default = "default_mode")]
+    mode: String,
+    width: Option<usize>,
+    height: Option<usize>,
+}
+
+fn default_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_port() -> u16 {
+    opc::PORT
+}
+
+fn default_mode() -> String {
+    "last".to_string()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Mode {
+    Last,
+    Merge,
+}
+
+struct Client {
+    stream: TcpStream,
+    decoder: opc::Decoder,
+    frame: Vec<RGB>,
+    connected: bool,
+}
+
+struct NetTcpOpcInput {
+    config: Config,
+    mode: Mode,
+    listener: Option<TcpListener>,
+    clients: Vec<Client>,
+}
+
+impl std::fmt::Display for NetTcpOpcInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl NetTcpOpcInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        let mode = match config.mode.as_str() {
+            "last" => Mode::Last,
+            "merge" => Mode::Merge,
+            _ => return plugin_err!("Unsupported mode: {}", config.mode),
+        };
+
+        match (config.width, config.height) {
+            (Some(width), Some(height)) if width > 0 && height > 0 => {}
+            (None, None) => {}
+            _ => return plugin_err!("Invalid frame size, set both width and height or none of them"),
+        }
+
+        let plugin = NetTcpOpcInput {
+            config,
+            mode,
+            listener: None,
+            clients: Vec::new(),
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn accept_clients(&mut self) -> PluginResult<()> {
+        loop {
+            match self.listener.as_ref().unwrap().accept() {
+                Ok((stream, _)) => {
+                    if let Err(err) = stream.set_nonblocking(true) {
+                        return plugin_err!("Error configuring OPC client connection: {}", err);
+                    }
+                    self.clients.push(Client {
+                        stream,
+                        decoder: opc::Decoder::new(),
+                        frame: Vec::new(),
+                        connected: true,
+                    });
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return plugin_err!("Error accepting OPC client connection: {}", err),
+            }
+        }
+    }
+
+    // reads the data of all clients, returns the index of the client which sent the latest frame
+    fn read_clients(&mut self, buffer: &mut [u8]) -> (bool, Option<usize>) {
+        let channel = self.config.channel;
+        let mut received = false;
+        let mut sender = None;
+
+        for (index, client) in self.clients.iter_mut().enumerate() {
+            match client.stream.read(buffer) {
+                Ok(0) => client.connected = false,
+                Ok(size) => {
+                    received = true;
+                    client.decoder.push(&buffer[..size]);
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(_) => client.connected = false,
+            }
+
+            while let Some(message) = client.decoder.next_message() {
+                if message.command != opc::CMD_SET_PIXELS {
+                    continue;
+                }
+                if channel != opc::BROADCAST_CHANNEL
+                    && message.channel != opc::BROADCAST_CHANNEL
+                    && message.channel != channel
+                {
+                    continue;
+                }
+                client.frame = message
+                    .data
+                    .chunks_exact(3)
+                    .map(|chunk| RGB {
+                        r: chunk[0],
+                        g: chunk[1],
+                        b: chunk[2],
+                    })
+                    .collect();
+                sender = Some(index);
+            }
+        }
+        (received, sender)
+    }
+
+    fn create_frame(&self, sender: usize) -> TraitData {
+        let mut data_out = match self.mode {
+            Mode::Last => self.clients[sender].frame.clone(),
+            // the frames of all clients are merged, the highest value of each color channel wins
+            Mode::Merge => {
+                let mut merged: Vec<RGB> = Vec::new();
+                for client in self.clients.iter().filter(|client| client.connected) {
+                    if client.frame.len() > merged.len() {
+                        merged.resize(client.frame.len(), RGB { r: 0, g: 0, b: 0 });
+                    }
+                    for (rgb, client_rgb) in merged.iter_mut().zip(client.frame.iter()) {
+                        rgb.r = std::cmp::max(rgb.r, client_rgb.r);
+                        rgb.g = std::cmp::max(rgb.g, client_rgb.g);
+                        rgb.b = std::cmp::max(rgb.b, client_rgb.b);
+                    }
+                }
+                merged
+            }
+        };
+
+        if let (Some(width), Some(height)) = (self.config.width, self.config.height) {
+            data_out.resize(width * height, RGB { r: 0, g: 0, b: 0 });
+            let pixels = data_out.len();
+            return plugin_data!(data_out, {
+                "width" => width,
+                "height" => height,
+                "pixels" => pixels,
+            });
+        }
+
+        let pixels = data_out.len();
+        plugin_data!(data_out, {
+            "pixels" => pixels,
+        })
+    }
+}
+
+impl PluginInputTrait for NetTcpOpcInput {
+    fn init(&mut self) -> PluginResult<()> {
+        let address = format!("{}:{}", self.config.address, self.config.port);
+        let listener = match TcpListener::bind(&address) {
+            Ok(listener) => listener,
+            Err(err) => return plugin_err!("Error binding TCP socket to '{}': {}", address, err),
+        };
+        if let Err(err) = listener.set_nonblocking(true) {
+            return plugin_err!("Error configuring TCP socket: {}", err);
+        }
+        self.listener = Some(listener);
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.listener.is_none() {
+            return plugin_err!("TCP socket not initialized");
+        }
+
+        let mut buffer = vec![0u8; 65536];
+        loop {
+            self.accept_clients()?;
+            let (received, sender) = self.read_clients(&mut buffer);
+
+            let result = sender.map(|sender| self.create_frame(sender));
+            self.clients.retain(|client| client.connected);
+            if let Some(data) = result {
+                return Ok(data);
+            }
+
+            if !received {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    NetTcpOpcInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
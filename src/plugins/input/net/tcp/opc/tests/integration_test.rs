@@ -0,0 +1,168 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::CreateInputPluginResult;
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use lightoros_protocols::opc;
+use std::io::Write;
+use std::net::TcpStream;
+use std::path::PathBuf;
+
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn message(channel: u8, data: &[u8]) -> Vec<u8> {
+    opc::Message {
+        channel,
+        command: opc::CMD_SET_PIXELS,
+        data: data.to_vec(),
+    }
+    .encode()
+    .unwrap()
+}
+
+fn create_plugin(config: serde_json::Value) -> (Box<dyn lightoros_plugin_base::input::PluginInputTrait>, u16) {
+    let port = config["port"].as_u64().unwrap() as u16;
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    (plugin, port)
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "NetTcpInputOPC");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_net_tcp_opc");
+}
+
+#[test]
+fn test_create() {
+    let config = json!({});
+    assert!(call_create(&config).is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    let config = json!({ "mode": "first" });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "height": 10 });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "channel": 256 });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_receive_frame() {
+    let (mut plugin, port) = create_plugin(json!({
+        "address": "127.0.0.1",
+        "port": 46540
+    }));
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let data = message(1, &[1, 2, 3, 4, 5, 6]);
+    // the message is split over several TCP writes
+    client.write_all(&data[..5]).unwrap();
+    client.flush().unwrap();
+    client.write_all(&data[5..]).unwrap();
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[1], 4, 5, 6);
+    assert_eq!(data.meta.get("pixels").unwrap(), "2");
+}
+
+#[test]
+fn test_receive_channel() {
+    let (mut plugin, port) = create_plugin(json!({
+        "address": "127.0.0.1",
+        "port": 46541,
+        "channel": 2,
+        "width": 3,
+        "height": 1
+    }));
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut system_exclusive = message(2, &[1, 2, 3]);
+    system_exclusive[1] = opc::CMD_SYSTEM_EXCLUSIVE;
+    client.write_all(&system_exclusive).unwrap();
+    client.write_all(&message(1, &[9, 9, 9])).unwrap();
+    client.write_all(&message(2, &[1, 2, 3])).unwrap();
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 3);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[2], 0, 0, 0);
+    assert_eq!(data.meta.get("pixels").unwrap(), "3");
+    assert_eq!(data.meta.get("width").unwrap(), "3");
+
+    // broadcast messages are accepted by all channels
+    client.write_all(&message(0, &[4, 5, 6])).unwrap();
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 4, 5, 6);
+}
+
+#[test]
+fn test_last_writer_wins() {
+    let (mut plugin, port) = create_plugin(json!({
+        "address": "127.0.0.1",
+        "port": 46542
+    }));
+
+    let mut client1 = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client1.write_all(&message(0, &[9, 9, 9, 9, 9, 9])).unwrap();
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+
+    let mut client2 = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client2.write_all(&message(0, &[1, 2, 3])).unwrap();
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 1);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+}
+
+#[test]
+fn test_merge_clients() {
+    let (mut plugin, port) = create_plugin(json!({
+        "address": "127.0.0.1",
+        "port": 46543,
+        "mode": "merge"
+    }));
+
+    let mut client1 = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client1.write_all(&message(0, &[9, 0, 0])).unwrap();
+    plugin.get().unwrap();
+
+    let mut client2 = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    client2.write_all(&message(0, &[1, 2, 3, 4, 5, 6])).unwrap();
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+    assert_rgb(&data.rgb[0], 9, 2, 3);
+    assert_rgb(&data.rgb[1], 4, 5, 6);
+
+    // the frame of a disconnected client is not part of the merged frame anymore
+    drop(client1);
+    client2.write_all(&message(0, &[1, 2, 3])).unwrap();
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 1);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+}
@@ -0,0 +1,394 @@
+use std::io::prelude::*;
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use lightoros_protocols::prismatik;
+use lightoros_protocols::prismatik::Command;
+
+use serde::Deserialize;
+
+const NAME: &str = "NetTcpInputPrismatik";
+
+// longest command line, clients sending longer lines are disconnected
+const MAX_LINE_LENGTH: usize = 1024 * 1024;
+
+#[derive(Deserialize, Debug)]
+struct ProfileConfig {
+    name: String,
+    colors: Vec<[u8; 3]>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    #[serde(default = "default_address")]
+    address: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    leds: usize,
+    api_key: Option<String>,
+    #[serde(default)]
+    profiles: Vec<ProfileConfig>,
+}
+
+fn default_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_port() -> u16 {
+    prismatik::PORT
+}
+
+struct Client {
+    id: usize,
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    authorized: bool,
+    connected: bool,
+}
+
+impl Client {
+    fn send(&mut self, response: &str) {
+        if self
+            .stream
+            .write_all(prismatik::encode_response(response).as_bytes())
+            .is_err()
+        {
+            self.connected = false;
+        }
+    }
+}
+
+struct NetTcpPrismatikInput {
+    config: Config,
+    listener: Option<TcpListener>,
+    clients: Vec<Client>,
+    next_client_id: usize,
+    // id of the client holding the lock, only this client can change the LEDs
+    lock: Option<usize>,
+    frame: Vec<RGB>,
+    profile: Option<usize>,
+    brightness: u8,
+    gamma: f32,
+    enabled: bool,
+}
+
+impl std::fmt::Display for NetTcpPrismatikInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl NetTcpPrismatikInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        if config.leds == 0 {
+            return plugin_err!("Invalid config value 'leds': 0");
+        }
+        for profile in &config.profiles {
+            if profile.name.is_empty() || profile.name.contains(';') {
+                return plugin_err!("Invalid profile name: '{}'", profile.name);
+            }
+            if profile.colors.is_empty() {
+                return plugin_err!("Profile '{}' has no colors", profile.name);
+            }
+        }
+
+        let plugin = NetTcpPrismatikInput {
+            frame: vec![RGB { r: 0, g: 0, b: 0 }; config.leds],
+            config,
+            listener: None,
+            clients: Vec::new(),
+            next_client_id: 0,
+            lock: None,
+            profile: None,
+            brightness: 100,
+            gamma: 1.0,
+            enabled: true,
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn accept_clients(&mut self) -> PluginResult<()> {
+        loop {
+            match self.listener.as_ref().unwrap().accept() {
+                Ok((stream, _)) => {
+                    if let Err(err) = stream.set_nonblocking(true) {
+                        return plugin_err!(
+                            "Error configuring Prismatik client connection: {}",
+                            err
+                        );
+                    }
+                    let mut client = Client {
+                        id: self.next_client_id,
+                        stream,
+                        buffer: Vec::new(),
+                        authorized: self.config.api_key.is_none(),
+                        connected: true,
+                    };
+                    client.send(prismatik::GREETING);
+                    self.clients.push(client);
+                    self.next_client_id += 1;
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => {
+                    return plugin_err!("Error accepting Prismatik client connection: {}", err)
+                }
+            }
+        }
+    }
+
+    // reads and handles the commands of all clients, returns if any data was received and if the LEDs changed
+    fn read_clients(&mut self, buffer: &mut [u8]) -> (bool, bool) {
+        let mut received = false;
+        let mut changed = false;
+
+        for client in self.clients.iter_mut() {
+            match client.stream.read(buffer) {
+                Ok(0) => client.connected = false,
+                Ok(size) => {
+                    received = true;
+                    client.buffer.extend_from_slice(&buffer[..size]);
+                }
+                Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(_) => client.connected = false,
+            }
+        }
+        self.release_lock();
+
+        for index in 0..self.clients.len() {
+            while let Some(position) = self.clients[index].buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = self.clients[index].buffer.drain(..=position).collect();
+                let line = String::from_utf8_lossy(&line);
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let response = match prismatik::parse_command(&line) {
+                    Ok(command) => {
+                        let (response, leds_changed) = self.handle_command(index, command);
+                        changed |= leds_changed;
+                        response
+                    }
+                    Err(_) => "error".to_string(),
+                };
+                self.clients[index].send(&response);
+                if !self.clients[index].connected {
+                    break;
+                }
+            }
+            if self.clients[index].buffer.len() > MAX_LINE_LENGTH {
+                self.clients[index].connected = false;
+            }
+        }
+        self.release_lock();
+        self.clients.retain(|client| client.connected);
+
+        (received, changed)
+    }
+
+    // the lock is released when its owner disconnects
+    fn release_lock(&mut self) {
+        if let Some(id) = self.lock {
+            if !self
+                .clients
+                .iter()
+                .any(|client| client.id == id && client.connected)
+            {
+                self.lock = None;
+            }
+        }
+    }
+
+    // returns the response and if the LEDs changed
+    fn handle_command(&mut self, index: usize, command: Command) -> (String, bool) {
+        let client = &mut self.clients[index];
+
+        if let Command::ApiKey(key) = &command {
+            client.authorized = self
+                .config
+                .api_key
+                .as_ref()
+                .is_none_or(|api_key| api_key == key);
+            let response = if client.authorized { "ok" } else { "fail" };
+            return (response.to_string(), false);
+        }
+        if !client.authorized {
+            return ("authorization required".to_string(), false);
+        }
+
+        let has_lock = self.lock == Some(client.id);
+        let response = match command {
+            Command::Lock => match self.lock {
+                Some(id) if id != client.id => "lock:busy".to_string(),
+                _ => {
+                    self.lock = Some(client.id);
+                    "lock:success".to_string()
+                }
+            },
+            Command::Unlock => {
+                if has_lock {
+                    self.lock = None;
+                    "unlock:success".to_string()
+                } else {
+                    "unlock:not locked".to_string()
+                }
+            }
+            Command::GetStatus => {
+                format!("status:{}", if self.enabled { "on" } else { "off" })
+            }
+            Command::GetStatusApi => {
+                format!(
+                    "statusapi:{}",
+                    if self.lock.is_some() { "busy" } else { "idle" }
+                )
+            }
+            Command::GetCountLeds => format!("countleds:{}", self.config.leds),
+            Command::GetLeds => {
+                // the LED positions are unknown, they are reported as a single row
+                let leds: String = (0..self.config.leds)
+                    .map(|led| format!("{}-{},0,1,1;", led + 1, led))
+                    .collect();
+                format!("leds:{}", leds)
+            }
+            Command::GetProfiles => {
+                let profiles: String = self
+                    .config
+                    .profiles
+                    .iter()
+                    .map(|profile| format!("{};", profile.name))
+                    .collect();
+                format!("profiles:{}", profiles)
+            }
+            Command::GetProfile => {
+                let name = self
+                    .profile
+                    .map_or("", |profile| self.config.profiles[profile].name.as_str());
+                format!("profile:{}", name)
+            }
+            Command::Exit => {
+                client.connected = false;
+                if has_lock {
+                    self.lock = None;
+                }
+                "Goodbye!".to_string()
+            }
+            Command::Unknown(_) => "unknown command".to_string(),
+            _ if !has_lock => "not locked".to_string(),
+            command => return self.handle_locked_command(command),
+        };
+        (response, false)
+    }
+
+    // commands changing the LEDs, only allowed for the client holding the lock
+    fn handle_locked_command(&mut self, command: Command) -> (String, bool) {
+        match command {
+            Command::SetColor(colors) => {
+                // colors of LEDs which don't exist are ignored
+                for (led, rgb) in colors {
+                    if led <= self.frame.len() {
+                        self.frame[led - 1] = RGB {
+                            r: rgb[0],
+                            g: rgb[1],
+                            b: rgb[2],
+                        };
+                    }
+                }
+            }
+            Command::SetProfile(name) => {
+                let profile = match self
+                    .config
+                    .profiles
+                    .iter()
+                    .position(|profile| profile.name == name)
+                {
+                    Some(profile) => profile,
+                    None => return ("fail".to_string(), false),
+                };
+                let colors = &self.config.profiles[profile].colors;
+                for (led, rgb) in self.frame.iter_mut().enumerate() {
+                    let color = colors[led % colors.len()];
+                    *rgb = RGB {
+                        r: color[0],
+                        g: color[1],
+                        b: color[2],
+                    };
+                }
+                self.profile = Some(profile);
+            }
+            Command::SetBrightness(brightness) => self.brightness = brightness,
+            Command::SetGamma(gamma) => self.gamma = gamma,
+            // smoothing is not supported
+            Command::SetSmooth(_) => return ("ok".to_string(), false),
+            Command::SetStatus(enabled) => self.enabled = enabled,
+            _ => return ("unknown command".to_string(), false),
+        }
+        ("ok".to_string(), true)
+    }
+
+    fn adjust(&self, value: u8) -> u8 {
+        let value = 255.0 * (value as f32 / 255.0).powf(self.gamma);
+        (value * self.brightness as f32 / 100.0).round() as u8
+    }
+
+    fn create_frame(&self) -> TraitData {
+        let rgb: Vec<RGB> = if self.enabled {
+            self.frame
+                .iter()
+                .map(|rgb| RGB {
+                    r: self.adjust(rgb.r),
+                    g: self.adjust(rgb.g),
+                    b: self.adjust(rgb.b),
+                })
+                .collect()
+        } else {
+            vec![RGB { r: 0, g: 0, b: 0 }; self.frame.len()]
+        };
+        let pixels = rgb.len();
+        plugin_data!(rgb, {
+            "pixels" => pixels,
+        })
+    }
+}
+
+impl PluginInputTrait for NetTcpPrismatikInput {
+    fn init(&mut self) -> PluginResult<()> {
+        let address = format!("{}:{}", self.config.address, self.config.port);
+        let listener = match TcpListener::bind(&address) {
+            Ok(listener) => listener,
+            Err(err) => return plugin_err!("Error binding TCP socket to '{}': {}", address, err),
+        };
+        if let Err(err) = listener.set_nonblocking(true) {
+            return plugin_err!("Error configuring TCP socket: {}", err);
+        }
+        self.listener = Some(listener);
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.listener.is_none() {
+            return plugin_err!("TCP socket not initialized");
+        }
+
+        let mut buffer = vec![0u8; 65536];
+        loop {
+            self.accept_clients()?;
+            let (received, changed) = self.read_clients(&mut buffer);
+
+            if changed {
+                return Ok(self.create_frame());
+            }
+
+            if !received {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    NetTcpPrismatikInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
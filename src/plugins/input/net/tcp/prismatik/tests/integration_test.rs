@@ -0,0 +1,270 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::CreateInputPluginResult;
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn create_plugin(
+    config: serde_json::Value,
+) -> (Box<dyn lightoros_plugin_base::input::PluginInputTrait>, u16) {
+    let port = config["port"].as_u64().unwrap() as u16;
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    (plugin, port)
+}
+
+struct Connection {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Connection {
+    fn open(port: u16) -> Connection {
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        Connection { stream, reader }
+    }
+
+    // the greeting is sent when the plugin accepts the connection
+    fn greeting(&mut self) {
+        assert!(self.response().starts_with("Lightpack API"));
+    }
+
+    fn send(&mut self, command: &str) {
+        self.stream
+            .write_all(format!("{}\n", command).as_bytes())
+            .unwrap();
+    }
+
+    fn response(&mut self) -> String {
+        let mut response = String::new();
+        self.reader.read_line(&mut response).unwrap();
+        assert!(response.ends_with("\r\n"));
+        response.trim_end().to_string()
+    }
+}
+
+fn config(port: u16) -> serde_json::Value {
+    json!({
+        "address": "127.0.0.1",
+        "port": port,
+        "leds": 3,
+        "profiles": [
+            { "name": "Red", "colors": [[255, 0, 0]] },
+            { "name": "Stripes", "colors": [[1, 2, 3], [4, 5, 6]] }
+        ]
+    })
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "NetTcpInputPrismatik");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_net_tcp_prismatik");
+}
+
+#[test]
+fn test_create() {
+    let config = json!({ "leds": 10 });
+    assert!(call_create(&config).is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    let config = json!({});
+    assert!(call_create(&config).is_err());
+    let config = json!({ "leds": 0 });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "leds": 1, "profiles": [{ "name": "a;b", "colors": [[0, 0, 0]] }] });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "leds": 1, "profiles": [{ "name": "Empty", "colors": [] }] });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_set_color() {
+    let (mut plugin, port) = create_plugin(config(46575));
+    let mut connection = Connection::open(port);
+
+    connection.send("setcolor:1-255,0,0;");
+    connection.send("lock");
+    connection.send("getstatusapi");
+    connection.send("setcolor:1-255,0,0;3-0,0,255;9-1,1,1;");
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 3);
+    assert_rgb(&data.rgb[0], 255, 0, 0);
+    assert_rgb(&data.rgb[1], 0, 0, 0);
+    assert_rgb(&data.rgb[2], 0, 0, 255);
+    assert_eq!(data.meta.get("pixels").unwrap(), "3");
+
+    connection.greeting();
+    assert_eq!(connection.response(), "not locked");
+    assert_eq!(connection.response(), "lock:success");
+    assert_eq!(connection.response(), "statusapi:busy");
+    assert_eq!(connection.response(), "ok");
+
+    connection.send("setbrightness:50");
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 128, 0, 0);
+    assert_eq!(connection.response(), "ok");
+
+    connection.send("setstatus:off");
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+    assert_eq!(connection.response(), "ok");
+}
+
+#[test]
+fn test_lock() {
+    let (mut plugin, port) = create_plugin(config(46576));
+    let mut connection1 = Connection::open(port);
+    let mut connection2 = Connection::open(port);
+
+    connection1.send("lock");
+    connection1.send("setcolor:2-1,2,3");
+    plugin.get().unwrap();
+    connection1.greeting();
+    assert_eq!(connection1.response(), "lock:success");
+    assert_eq!(connection1.response(), "ok");
+
+    // the lock is held by the first client
+    connection2.send("lock");
+    connection2.send("unlock");
+    connection2.send("setcolor:1-9,9,9");
+    connection2.send("getcountleds");
+    std::thread::sleep(Duration::from_millis(50));
+    connection1.send("setcolor:3-7,8,9");
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+    assert_rgb(&data.rgb[1], 1, 2, 3);
+    assert_rgb(&data.rgb[2], 7, 8, 9);
+    assert_eq!(connection1.response(), "ok");
+    connection2.greeting();
+    assert_eq!(connection2.response(), "lock:busy");
+    assert_eq!(connection2.response(), "unlock:not locked");
+    assert_eq!(connection2.response(), "not locked");
+    assert_eq!(connection2.response(), "countleds:3");
+
+    connection1.send("unlock");
+    std::thread::sleep(Duration::from_millis(50));
+    connection2.send("lock");
+    connection2.send("setcolor:1-4,5,6");
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 4, 5, 6);
+    assert_eq!(connection1.response(), "unlock:success");
+    assert_eq!(connection2.response(), "lock:success");
+    assert_eq!(connection2.response(), "ok");
+
+    // the lock is released when the client disconnects
+    drop(connection2);
+    std::thread::sleep(Duration::from_millis(50));
+    connection1.send("lock");
+    connection1.send("setcolor:1-7,8,9");
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 7, 8, 9);
+    assert_eq!(connection1.response(), "lock:success");
+}
+
+#[test]
+fn test_line_too_long_disconnects() {
+    let (mut plugin, port) = create_plugin(config(46578));
+    let connection1 = Connection::open(port);
+    let mut connection2 = Connection::open(port);
+    connection2.send("lock");
+
+    // the first client is dropped instead of buffering the line
+    let mut stream = connection1.stream.try_clone().unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    let client = std::thread::spawn(move || {
+        let chunk = vec![b' '; 64 * 1024];
+        for _ in 0..20 {
+            if stream.write_all(&chunk).is_err() {
+                break;
+            }
+        }
+        let mut reader = connection1.reader;
+        let mut received = String::new();
+        reader.read_to_string(&mut received)
+    });
+
+    // the second client is still served
+    let mut red = 0u8;
+    while !client.is_finished() {
+        red = red % 255 + 1;
+        connection2.send(&format!("setcolor:1-{},0,0", red));
+        let data = plugin.get().unwrap();
+        assert_rgb(&data.rgb[0], red, 0, 0);
+    }
+    let result = client.join().unwrap();
+    assert!(
+        !matches!(&result, Err(err) if err.kind() == std::io::ErrorKind::WouldBlock),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_profiles() {
+    let (mut plugin, port) = create_plugin(config(46577));
+    let mut connection = Connection::open(port);
+
+    connection.send("getprofiles");
+    connection.send("lock");
+    connection.send("setprofile:Unknown");
+    connection.send("setprofile:Stripes");
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[1], 4, 5, 6);
+    assert_rgb(&data.rgb[2], 1, 2, 3);
+    connection.greeting();
+    assert_eq!(connection.response(), "profiles:Red;Stripes;");
+    assert_eq!(connection.response(), "lock:success");
+    assert_eq!(connection.response(), "fail");
+    assert_eq!(connection.response(), "ok");
+
+    connection.send("getprofile");
+    connection.send("help");
+    connection.send("setcolor:1-1");
+    connection.send("exit");
+    // the commands are handled while the plugin waits for the next frame
+    let mut connection2 = Connection::open(port);
+    connection2.send("lock");
+    connection2.send("setcolor:1-1,1,1");
+    std::thread::sleep(Duration::from_millis(50));
+    plugin.get().unwrap();
+
+    let mut lines = String::new();
+    connection.reader.read_to_string(&mut lines).unwrap();
+    assert_eq!(
+        lines,
+        "profile:Stripes\r\nunknown command\r\nerror\r\nGoodbye!\r\n"
+    );
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use lightoros_protocols::ddp;
+
+use serde::Deserialize;
+
+const NAME: &str = "NetUdpInputDDP";
+
+// max UDP payload size
+const MAX_PACKET_LENGTH: usize = 65507;
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    #[serde(default = "default_address")]
+    address: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    #[serde(default = "default_mode")]
+    mode: String,
+    width: Option<usize>,
+    height: Option<usize>,
+    #[serde(default = "default_max_pixels")]
+    max_pixels: usize,
+    #[serde(default = "default_client_timeout")]
+    client_timeout: u64,
+}
+
+fn default_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_port() -> u16 {
+    ddp::PORT
+}
+
+fn default_mode() -> String {
+    "last".to_string()
+}
+
+fn default_max_pixels() -> usize {
+    65536
+}
+
+fn default_client_timeout() -> u64 {
+    5000
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Mode {
+    Last,
+    Merge,
+}
+
+struct Client {
+    // DDP senders may only update parts of the display, the data is kept between the frames
+    buffer: Vec<u8>,
+    frame: Vec<RGB>,
+    last_seen: Instant,
+}
+
+struct NetUdpDdpInput {
+    config: Config,
+    mode: Mode,
+    socket: Option<UdpSocket>,
+    clients: HashMap<SocketAddr, Client>,
+}
+
+impl std::fmt::Display for NetUdpDdpInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl NetUdpDdpInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        let mode = match config.mode.as_str() {
+            "last" => Mode::Last,
+            "merge" => Mode::Merge,
+            _ => return plugin_err!("Unsupported mode: {}", config.mode),
+        };
+
+        match (config.width, config.height) {
+            (Some(width), Some(height)) if width > 0 && height > 0 => {}
+            (None, None) => {}
+            _ => return plugin_err!("Invalid frame size, set both width and height or none of them"),
+        }
+
+        let plugin = NetUdpDdpInput {
+            config,
+            mode,
+            socket: None,
+            clients: HashMap::new(),
+        };
+        Ok(Box::new(plugin))
+    }
+
+    // returns true if the packet completes a frame of the client
+    fn handle_packet(&mut self, data: &[u8], sender: SocketAddr) -> bool {
+        let packet = match ddp::decode(data) {
+            Some(packet) => packet,
+            None => return false,
+        };
+        // queries and configuration packets are not supported
+        if !packet.is_display_data() {
+            return false;
+        }
+
+        let client = self.clients.entry(sender).or_insert_with(|| Client {
+            buffer: Vec::new(),
+            frame: Vec::new(),
+            last_seen: Instant::now(),
+        });
+        client.last_seen = Instant::now();
+
+        let max_size = self.config.max_pixels * 3;
+        let offset = packet.offset as usize;
+        if offset < max_size {
+            let end = std::cmp::min(offset + packet.data.len(), max_size);
+            if client.buffer.len() < end {
+                client.buffer.resize(end, 0);
+            }
+            client.buffer[offset..end].copy_from_slice(&packet.data[..end - offset]);
+        }
+
+        if !packet.is_push() {
+            return false;
+        }
+        client.frame = client
+            .buffer
+            .chunks_exact(3)
+            .map(|chunk| RGB {
+                r: chunk[0],
+                g: chunk[1],
+                b: chunk[2],
+            })
+            .collect();
+        true
+    }
+
+    fn remove_stale_clients(&mut self) {
+        let timeout = Duration::from_millis(self.config.client_timeout);
+        self.clients
+            .retain(|_, client| client.last_seen.elapsed() <= timeout);
+    }
+
+    fn create_frame(&self, sender: SocketAddr) -> TraitData {
+        let mut data_out = match self.mode {
+            Mode::Last => self.clients[&sender].frame.clone(),
+            // the frames of all clients are merged, the highest value of each color channel wins
+            Mode::Merge => {
+                let mut merged: Vec<RGB> = Vec::new();
+                for client in self.clients.values() {
+                    if client.frame.len() > merged.len() {
+                        merged.resize(client.frame.len(), RGB { r: 0, g: 0, b: 0 });
+                    }
+                    for (rgb, client_rgb) in merged.iter_mut().zip(client.frame.iter()) {
+                        rgb.r = std::cmp::max(rgb.r, client_rgb.r);
+                        rgb.g = std::cmp::max(rgb.g, client_rgb.g);
+                        rgb.b = std::cmp::max(rgb.b, client_rgb.b);
+                    }
+                }
+                merged
+            }
+        };
+
+        if let (Some(width), Some(height)) = (self.config.width, self.config.height) {
+            data_out.resize(width * height, RGB { r: 0, g: 0, b: 0 });
+            let pixels = data_out.len();
+            return plugin_data!(data_out, {
+                "width" => width,
+                "height" => height,
+                "pixels" => pixels,
+            });
+        }
+
+        let pixels = data_out.len();
+        plugin_data!(data_out, {
+            "pixels" => pixels,
+        })
+    }
+}
+
+impl PluginInputTrait for NetUdpDdpInput {
+    fn init(&mut self) -> PluginResult<()> {
+        let address = format!("{}:{}", self.config.address, self.config.port);
+        let socket = match UdpSocket::bind(&address) {
+            Ok(socket) => socket,
+            Err(err) => return plugin_err!("Error binding UDP socket to '{}': {}", address, err),
+        };
+
+        // wake up regularly to drop disconnected clients
+        if let Err(err) = socket.set_read_timeout(Some(Duration::from_millis(100))) {
+            return plugin_err!("Error setting UDP socket timeout: {}", err);
+        }
+
+        self.socket = Some(socket);
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.socket.is_none() {
+            return plugin_err!("UDP socket not initialized");
+        }
+
+        let mut buffer = vec![0u8; MAX_PACKET_LENGTH];
+        loop {
+            let result = self.socket.as_ref().unwrap().recv_from(&mut buffer);
+            self.remove_stale_clients();
+
+            match result {
+                Ok((size, sender)) => {
+                    if self.handle_packet(&buffer[..size], sender) {
+                        return Ok(self.create_frame(sender));
+                    }
+                }
+                Err(ref err)
+                    if err.kind() == std::io::ErrorKind::WouldBlock
+                        || err.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(err) => return plugin_err!("Error receiving UDP packet: {}", err),
+            };
+        }
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    NetUdpDdpInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
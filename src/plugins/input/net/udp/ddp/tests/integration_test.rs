@@ -0,0 +1,179 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::CreateInputPluginResult;
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use lightoros_protocols::ddp;
+use std::net::UdpSocket;
+use std::path::PathBuf;
+
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn packet(flags: u8, offset: u32, data: &[u8]) -> Vec<u8> {
+    ddp::Packet {
+        flags,
+        sequence: 0,
+        data_type: ddp::TYPE_RGB24,
+        id: ddp::ID_DISPLAY,
+        offset,
+        data: data.to_vec(),
+    }
+    .encode()
+}
+
+fn create_plugin(config: serde_json::Value) -> (Box<dyn lightoros_plugin_base::input::PluginInputTrait>, u16) {
+    let port = config["port"].as_u64().unwrap() as u16;
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+    (plugin, port)
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "NetUdpInputDDP");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_net_udp_ddp");
+}
+
+#[test]
+fn test_create() {
+    let config = json!({});
+    assert!(call_create(&config).is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    let config = json!({ "mode": "first" });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "width": 10 });
+    assert!(call_create(&config).is_err());
+    let config = json!({ "width": 0, "height": 0 });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_receive_frame() {
+    let (mut plugin, port) = create_plugin(json!({
+        "address": "127.0.0.1",
+        "port": 46530
+    }));
+
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    // the second packet arrives first, only the push flag completes the frame
+    sender.send_to(&packet(0, 3, &[4, 5, 6]), ("127.0.0.1", port)).unwrap();
+    sender.send_to(&packet(ddp::FLAG_PUSH, 0, &[1, 2, 3]), ("127.0.0.1", port)).unwrap();
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[1], 4, 5, 6);
+    assert_eq!(data.meta.get("pixels").unwrap(), "2");
+    assert!(!data.meta.contains_key("width"));
+
+    // the data of the previous frame is kept
+    sender.send_to(&packet(ddp::FLAG_PUSH, 3, &[7, 8, 9]), ("127.0.0.1", port)).unwrap();
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[1], 7, 8, 9);
+}
+
+#[test]
+fn test_receive_fixed_size() {
+    let (mut plugin, port) = create_plugin(json!({
+        "address": "127.0.0.1",
+        "port": 46531,
+        "width": 2,
+        "height": 2,
+        "max_pixels": 3
+    }));
+
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.send_to(&packet(0x01, 6, &[1, 1, 1, 2, 2, 2]), ("127.0.0.1", port)).unwrap();
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 4);
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+    assert_rgb(&data.rgb[2], 1, 1, 1);
+    // pixels exceeding max_pixels are dropped
+    assert_rgb(&data.rgb[3], 0, 0, 0);
+    assert_eq!(data.meta.get("pixels").unwrap(), "4");
+    assert_eq!(data.meta.get("width").unwrap(), "2");
+    assert_eq!(data.meta.get("height").unwrap(), "2");
+}
+
+#[test]
+fn test_ignore_queries() {
+    let (mut plugin, port) = create_plugin(json!({
+        "address": "127.0.0.1",
+        "port": 46532
+    }));
+
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut query = packet(ddp::FLAG_PUSH | ddp::FLAG_QUERY, 0, &[]);
+    query[3] = ddp::ID_STATUS;
+    sender.send_to(&query, ("127.0.0.1", port)).unwrap();
+    sender.send_to(&[1, 2, 3], ("127.0.0.1", port)).unwrap();
+    sender.send_to(&packet(ddp::FLAG_PUSH, 0, &[1, 2, 3]), ("127.0.0.1", port)).unwrap();
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 1);
+}
+
+#[test]
+fn test_last_writer_wins() {
+    let (mut plugin, port) = create_plugin(json!({
+        "address": "127.0.0.1",
+        "port": 46533
+    }));
+
+    let sender1 = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let sender2 = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender1.send_to(&packet(ddp::FLAG_PUSH, 0, &[9, 9, 9, 9, 9, 9]), ("127.0.0.1", port)).unwrap();
+    sender2.send_to(&packet(ddp::FLAG_PUSH, 0, &[1, 2, 3]), ("127.0.0.1", port)).unwrap();
+
+    plugin.get().unwrap();
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 1);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+}
+
+#[test]
+fn test_merge_clients() {
+    let (mut plugin, port) = create_plugin(json!({
+        "address": "127.0.0.1",
+        "port": 46534,
+        "mode": "merge"
+    }));
+
+    let sender1 = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let sender2 = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender1.send_to(&packet(ddp::FLAG_PUSH, 0, &[9, 0, 0]), ("127.0.0.1", port)).unwrap();
+    sender2.send_to(&packet(ddp::FLAG_PUSH, 0, &[1, 2, 3, 4, 5, 6]), ("127.0.0.1", port)).unwrap();
+
+    plugin.get().unwrap();
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+    assert_rgb(&data.rgb[0], 9, 2, 3);
+    assert_rgb(&data.rgb[1], 4, 5, 6);
+    assert_eq!(data.meta.get("pixels").unwrap(), "2");
+}
@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use lightoros_protocols::{artnet, e131};
+
+use serde::Deserialize;
+
+const NAME: &str = "NetUdpInputDMX";
+
+// receivers fall back to immediate output if no sync packet is received for 4 seconds, like Art-Net specifies for ArtSync
+const SYNC_TIMEOUT: Duration = Duration::from_secs(4);
+
+// the receiving threads check in this interval if the plugin is dropped
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+// interval of the check for lost sources if no packets are received
+const SOURCE_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+// packet received by a socket thread, or the receive error
+type ReceivedPacket = Result<(Protocol, Vec<u8>, SocketAddr), String>;
+
+#[derive(Deserialize, Debug)]
+struct UniverseMapping {
+    universe: u16,
+    #[serde(default = "default_start_channel")]
+    start_channel: usize,
+    #[serde(default)]
+    led_offset: usize,
+    led_count: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    #[serde(default = "default_address")]
+    address: String,
+    #[serde(default = "default_protocols")]
+    protocols: Vec<String>,
+    #[serde(default = "default_artnet_port")]
+    artnet_port: u16,
+    #[serde(default = "default_e131_port")]
+    e131_port: u16,
+    #[serde(default = "default_multicast")]
+    multicast: bool,
+    width: usize,
+    height: usize,
+    universes: Vec<UniverseMapping>,
+    #[serde(default = "default_color_order")]
+    color_order: String,
+    #[serde(default = "default_source_timeout")]
+    source_timeout: u64,
+}
+
+fn default_start_channel() -> usize {
+    1
+}
+
+fn default_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_protocols() -> Vec<String> {
+    vec!["artnet".to_string(), "e131".to_string()]
+}
+
+fn default_artnet_port() -> u16 {
+    artnet::PORT
+}
+
+fn default_e131_port() -> u16 {
+    e131::PORT
+}
+
+fn default_multicast() -> bool {
+    true
+}
+
+fn default_color_order() -> String {
+    "rgb".to_string()
+}
+
+fn default_source_timeout() -> u64 {
+    2500
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Protocol {
+    ArtNet,
+    E131,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum SourceId {
+    // Art-Net nodes send from a fixed port, sources are distinguished by IP address
+    ArtNet(IpAddr),
+    E131([u8; 16]),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum SyncGroup {
+    ArtNet,
+    E131(u16),
+}
+
+struct Source {
+    priority: u8,
+    sequence: u8,
+    sync: Option<SyncGroup>,
+    data: Vec<u8>,
+    // synchronized data waiting for the sync packet
+    pending: Option<Vec<u8>>,
+    last_seen: Instant,
+}
+
+#[derive(Default)]
+struct Universe {
+    sources: HashMap<SourceId, Source>,
+    updated: bool,
+}
+
+impl Universe {
+    // data of the sources with the highest priority, equal priorities are merged channel by channel (HTP)
+    fn merge(&self) -> Vec<u8> {
+        let mut merged: Vec<u8> = Vec::new();
+        let priority = match self.sources.values().map(|source| source.priority).max() {
+            Some(priority) => priority,
+            None => return merged,
+        };
+        for source in self.sources.values().filter(|source| source.priority == priority) {
+            if source.data.len() > merged.len() {
+                merged.resize(source.data.len(), 0);
+            }
+            for (value, source_value) in merged.iter_mut().zip(source.data.iter()) {
+                *value = std::cmp::max(*value, *source_value);
+            }
+        }
+        merged
+    }
+
+    // true if the universe gets data which is output without synchronization
+    fn is_unsynchronized(&self) -> bool {
+        self.sources.values().any(|source| source.sync.is_none())
+    }
+}
+
+struct NetUdpDmxInput {
+    config: Config,
+    protocols: Vec<Protocol>,
+    color_order: ColorOrder,
+    packets: Option<Receiver<ReceivedPacket>>,
+    receivers: Vec<JoinHandle<()>>,
+    should_stop: Arc<AtomicBool>,
+    universes: HashMap<u16, Universe>,
+    // time of the last sync packet of the sync groups
+    syncs: HashMap<SyncGroup, Instant>,
+}
+
+impl std::fmt::Display for NetUdpDmxInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl NetUdpDmxInput {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        if config.width == 0 || config.height == 0 {
+            return plugin_err!("Invalid frame size {}x{}", config.width, config.height);
+        }
+
+        let mut protocols = Vec::new();
+        for protocol in &config.protocols {
+            match protocol.as_str() {
+                "artnet" => protocols.push(Protocol::ArtNet),
+                "e131" => protocols.push(Protocol::E131),
+                _ => return plugin_err!("Unsupported protocol: {}", protocol),
+            }
+        }
+        if protocols.is_empty() {
+            return plugin_err!("No protocol configured");
+        }
+
+        if config.universes.is_empty() {
+            return plugin_err!("No universes configured");
+        }
+        let size = match config.width.checked_mul(config.height) {
+            Some(size) => size,
+            None => return plugin_err!("Invalid frame size {}x{}", config.width, config.height),
+        };
+        let mut universes = HashMap::new();
+        for mapping in &config.universes {
+            // end of the channel range, None for a start channel of 0 or an overflow
+            let channels_end = mapping
+                .start_channel
+                .checked_sub(1)
+                .and_then(|first| first.checked_add(mapping.led_count.checked_mul(3)?));
+            if channels_end.is_none_or(|end| end > artnet::MAX_CHANNELS) {
+                return plugin_err!(
+                    "Invalid channel range in universe {}: {} LEDs starting at channel {}",
+                    mapping.universe,
+                    mapping.led_count,
+                    mapping.start_channel
+                );
+            }
+            let leds_end = mapping.led_offset.checked_add(mapping.led_count);
+            if leds_end.is_none_or(|end| end > size) {
+                return plugin_err!(
+                    "LEDs of universe {} exceed the frame size of {} LEDs",
+                    mapping.universe,
+                    size
+                );
+            }
+            universes.insert(mapping.universe, Universe::default());
+        }
+
+        let color_order: ColorOrder = config.color_order.parse()?;
+
+        let plugin = NetUdpDmxInput {
+            config,
+            protocols,
+            color_order,
+            packets: None,
+            receivers: Vec::new(),
+            should_stop: Arc::new(AtomicBool::new(false)),
+            universes,
+            syncs: HashMap::new(),
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn bind(&self, protocol: Protocol) -> PluginResult<UdpSocket> {
+        let port = match protocol {
+            Protocol::ArtNet => self.config.artnet_port,
+            Protocol::E131 => self.config.e131_port,
+        };
+        let address = format!("{}:{}", self.config.address, port);
+        let socket = match UdpSocket::bind(&address) {
+            Ok(socket) => socket,
+            Err(err) => return plugin_err!("Error binding UDP socket to '{}': {}", address, err),
+        };
+        if let Err(err) = socket.set_read_timeout(Some(READ_TIMEOUT)) {
+            return plugin_err!("Error configuring UDP socket: {}", err);
+        }
+
+        if protocol == Protocol::E131 && self.config.multicast {
+            for universe in self.universes.keys() {
+                let group = e131::multicast_address(*universe);
+                if let Err(err) = socket.join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED) {
+                    return plugin_err!("Error joining multicast group {}: {}", group, err);
+                }
+            }
+        }
+        Ok(socket)
+    }
+
+    // receives the packets of the socket in a thread, several sockets are read at the same time
+    fn receive(
+        protocol: Protocol,
+        socket: UdpSocket,
+        sender: Sender<ReceivedPacket>,
+        should_stop: Arc<AtomicBool>,
+    ) -> std::io::Result<JoinHandle<()>> {
+        std::thread::Builder::new()
+            .name(NAME.to_string())
+            .spawn(move || {
+                let mut buffer = [0u8; 1024];
+                while !should_stop.load(Ordering::SeqCst) {
+                    let packet = match socket.recv_from(&mut buffer) {
+                        Ok((size, from)) => Ok((protocol, buffer[..size].to_vec(), from)),
+                        Err(ref err)
+                            if err.kind() == std::io::ErrorKind::WouldBlock
+                                || err.kind() == std::io::ErrorKind::TimedOut =>
+                        {
+                            continue
+                        }
+                        Err(err) => Err(err.to_string()),
+                    };
+                    let failed = packet.is_err();
+                    if sender.send(packet).is_err() || failed {
+                        return;
+                    }
+                }
+            })
+    }
+
+    fn stop_receivers(&mut self) {
+        self.should_stop.store(true, Ordering::SeqCst);
+        for receiver in self.receivers.drain(..) {
+            receiver.join().ok();
+        }
+        self.packets = None;
+    }
+
+    // sync group of data, None if the data is output immediately
+    fn sync_group(&self, group: SyncGroup) -> Option<SyncGroup> {
+        match self.syncs.get(&group) {
+            Some(time) if time.elapsed() < SYNC_TIMEOUT => Some(group),
+            _ => None,
+        }
+    }
+
+    // returns true if a new frame has to be provided
+    fn handle_packet(&mut self, protocol: Protocol, data: &[u8], sender: SocketAddr) -> bool {
+        match protocol {
+            Protocol::ArtNet => match artnet::decode(data) {
+                Some(artnet::Packet::Dmx(packet)) => {
+                    let sync = self.sync_group(SyncGroup::ArtNet);
+                    self.update(
+                        packet.universe,
+                        SourceId::ArtNet(sender.ip()),
+                        e131::DEFAULT_PRIORITY,
+                        None,
+                        sync,
+                        packet.data,
+                    )
+                }
+                Some(artnet::Packet::Sync) => self.synchronize(SyncGroup::ArtNet),
+                None => false,
+            },
+            Protocol::E131 => match e131::decode(data) {
+                Some(e131::Packet::Data(packet)) => {
+                    // only DMX data is used, preview data is meant for visualizers
+                    if packet.start_code != 0 || packet.is_preview() {
+                        return false;
+                    }
+                    let id = SourceId::E131(packet.cid);
+                    if packet.is_stream_terminated() {
+                        return self.remove_source(packet.universe, id);
+                    }
+                    let sync = match packet.sync_address {
+                        0 => None,
+                        address => self.sync_group(SyncGroup::E131(address)),
+                    };
+                    self.update(
+                        packet.universe,
+                        id,
+                        std::cmp::min(packet.priority, e131::MAX_PRIORITY),
+                        Some(packet.sequence),
+                        sync,
+                        packet.data,
+                    )
+                }
+                Some(e131::Packet::Sync(packet)) => {
+                    self.synchronize(SyncGroup::E131(packet.sync_address))
+                }
+                None => false,
+            },
+        }
+    }
+
+    fn update(
+        &mut self,
+        universe_id: u16,
+        id: SourceId,
+        priority: u8,
+        sequence: Option<u8>,
+        sync: Option<SyncGroup>,
+        data: Vec<u8>,
+    ) -> bool {
+        let universe = match self.universes.get_mut(&universe_id) {
+            Some(universe) => universe,
+            None => return false,
+        };
+
+        let source = universe.sources.entry(id).or_insert_with(|| Source {
+            priority,
+            sequence: sequence.unwrap_or(0).wrapping_sub(1),
+            sync,
+            data: Vec::new(),
+            pending: None,
+            last_seen: Instant::now(),
+        });
+        if let Some(sequence) = sequence {
+            if !e131::is_sequence_valid(source.sequence, sequence) {
+                return false;
+            }
+            source.sequence = sequence;
+        }
+        source.priority = priority;
+        source.sync = sync;
+        source.last_seen = Instant::now();
+
+        if sync.is_some() {
+            source.pending = Some(data);
+            return false;
+        }
+        source.data = data;
+        source.pending = None;
+        universe.updated = true;
+        self.is_frame_complete()
+    }
+
+    // a frame is complete once all universes with unsynchronized sources got new data
+    fn is_frame_complete(&self) -> bool {
+        let mut waiting = self
+            .universes
+            .values()
+            .filter(|universe| universe.is_unsynchronized())
+            .peekable();
+        waiting.peek().is_some() && waiting.all(|universe| universe.updated)
+    }
+
+    fn synchronize(&mut self, group: SyncGroup) -> bool {
+        self.syncs.insert(group, Instant::now());
+        let mut updated = false;
+        for universe in self.universes.values_mut() {
+            for source in universe.sources.values_mut() {
+                if source.sync == Some(group) && source.pending.is_some() {
+                    source.data = source.pending.take().unwrap();
+                    universe.updated = true;
+                    updated = true;
+                }
+            }
+        }
+        updated
+    }
+
+    fn remove_source(&mut self, universe_id: u16, id: SourceId) -> bool {
+        match self.universes.get_mut(&universe_id) {
+            Some(universe) => universe.sources.remove(&id).is_some(),
+            None => false,
+        }
+    }
+
+    fn remove_stale_sources(&mut self) -> bool {
+        let timeout = Duration::from_millis(self.config.source_timeout);
+        let mut removed = false;
+        for universe in self.universes.values_mut() {
+            let count = universe.sources.len();
+            universe
+                .sources
+                .retain(|_, source| source.last_seen.elapsed() <= timeout);
+            removed |= universe.sources.len() != count;
+        }
+        removed
+    }
+
+    fn create_frame(&mut self) -> TraitData {
+        let mut data_out = vec![RGB { r: 0, g: 0, b: 0 }; self.config.width * self.config.height];
+
+        for universe in self.universes.values_mut() {
+            universe.updated = false;
+        }
+
+        for mapping in &self.config.universes {
+            let data = self.universes[&mapping.universe].merge();
+            for i in 0..mapping.led_count {
+                let channel = mapping.start_channel - 1 + i * 3;
+                // universes with less channels than configured leave the remaining LEDs black
+                if channel + 3 > data.len() {
+                    break;
+                }
+                data_out[mapping.led_offset + i] = self.color_order.read(&data[channel..channel + 3]);
+            }
+        }
+
+        plugin_data!(data_out, {
+            "width" => self.config.width,
+            "height" => self.config.height,
+        })
+    }
+}
+
+impl PluginInputTrait for NetUdpDmxInput {
+    fn init(&mut self) -> PluginResult<()> {
+        self.stop_receivers();
+        let mut sockets = Vec::with_capacity(self.protocols.len());
+        for protocol in &self.protocols {
+            sockets.push((*protocol, self.bind(*protocol)?));
+        }
+
+        self.should_stop = Arc::new(AtomicBool::new(false));
+        let (sender, packets) = channel();
+        for (protocol, socket) in sockets {
+            match Self::receive(protocol, socket, sender.clone(), self.should_stop.clone()) {
+                Ok(receiver) => self.receivers.push(receiver),
+                Err(err) => {
+                    self.stop_receivers();
+                    return plugin_err!("Error starting UDP receiver: {}", err);
+                }
+            }
+        }
+        self.packets = Some(packets);
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.packets.is_none() {
+            return plugin_err!("UDP sockets not initialized");
+        }
+
+        loop {
+            let result = self
+                .packets
+                .as_ref()
+                .unwrap()
+                .recv_timeout(SOURCE_CHECK_INTERVAL);
+            match result {
+                Ok(Ok((protocol, data, sender))) => {
+                    if self.handle_packet(protocol, &data, sender) {
+                        return Ok(self.create_frame());
+                    }
+                }
+                Ok(Err(err)) => return plugin_err!("Error receiving UDP packet: {}", err),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return plugin_err!("UDP receivers stopped");
+                }
+            }
+
+            // a lost source turns its LEDs off or hands them over to a source with lower priority
+            if self.remove_stale_sources() {
+                return Ok(self.create_frame());
+            }
+        }
+    }
+}
+
+impl Drop for NetUdpDmxInput {
+    fn drop(&mut self) {
+        self.stop_receivers();
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    NetUdpDmxInput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
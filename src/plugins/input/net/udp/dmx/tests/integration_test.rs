@@ -0,0 +1,325 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::CreateInputPluginResult;
+use lightoros_plugin_base::*;
+use lightoros_protocols::{artnet, e131};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::net::UdpSocket;
+use std::path::PathBuf;
+use std::{thread, time};
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn send_packets(port: u16, packets: &[Vec<u8>]) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    for packet in packets {
+        socket.send_to(packet, ("127.0.0.1", port)).unwrap();
+    }
+}
+
+fn artnet_dmx(universe: u16, data: &[u8]) -> Vec<u8> {
+    artnet::DmxPacket {
+        sequence: 0,
+        physical: 0,
+        universe,
+        data: data.to_vec(),
+    }
+    .encode()
+}
+
+fn e131_data(cid: u8, priority: u8, sequence: u8, universe: u16, data: &[u8]) -> e131::DataPacket {
+    e131::DataPacket {
+        cid: [cid; 16],
+        source_name: "test".to_string(),
+        priority,
+        sync_address: 0,
+        sequence,
+        options: 0,
+        universe,
+        start_code: 0,
+        data: data.to_vec(),
+    }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn e131_config(port: u16) -> serde_json::Value {
+    json!({
+        "address": "127.0.0.1",
+        "protocols": ["e131"],
+        "e131_port": port,
+        "multicast": false,
+        "width": 2,
+        "height": 1,
+        "universes": [
+            { "universe": 1, "led_count": 2 }
+        ]
+    })
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "NetUdpInputDMX");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_net_udp_dmx");
+}
+
+#[test]
+fn test_create() {
+    assert!(call_create(&e131_config(46520)).is_ok());
+}
+
+#[test]
+fn test_create_with_empty_config() {
+    let config = json!({});
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_create_with_invalid_mapping() {
+    let mut config = e131_config(46520);
+    // 171 LEDs don't fit into a universe
+    config["width"] = json!(200);
+    config["universes"] = json!([{ "universe": 1, "led_count": 171 }]);
+    assert!(call_create(&config).is_err());
+
+    // LEDs exceed the frame
+    config["universes"] = json!([{ "universe": 1, "led_offset": 190, "led_count": 20 }]);
+    assert!(call_create(&config).is_err());
+
+    config["universes"] = json!([{ "universe": 1, "start_channel": 0, "led_count": 1 }]);
+    assert!(call_create(&config).is_err());
+
+    // ranges overflowing the channel or LED numbers
+    config["universes"] = json!([{ "universe": 1, "led_count": usize::MAX / 3 + 1 }]);
+    assert!(call_create(&config).is_err());
+    config["universes"] = json!([{ "universe": 1, "led_offset": usize::MAX, "led_count": 1 }]);
+    assert!(call_create(&config).is_err());
+    config["width"] = json!(usize::MAX);
+    config["height"] = json!(2);
+    config["universes"] = json!([{ "universe": 1, "led_count": 1 }]);
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_create_with_invalid_protocol() {
+    let mut config = e131_config(46520);
+    config["protocols"] = json!(["dmx"]);
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_artnet_universes() {
+    let config = json!({
+        "address": "127.0.0.1",
+        "protocols": ["artnet"],
+        "artnet_port": 46521,
+        "width": 3,
+        "height": 1,
+        "color_order": "grb",
+        "universes": [
+            { "universe": 0, "led_count": 2 },
+            { "universe": 0x101, "start_channel": 4, "led_offset": 2, "led_count": 1 }
+        ]
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    send_packets(46521, &[artnet_dmx(0, &[2, 1, 3, 5, 4, 6])]);
+
+    // the second universe is unknown until its first packet arrives
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 3);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[1], 4, 5, 6);
+    assert_rgb(&data.rgb[2], 0, 0, 0);
+    assert_eq!(data.meta.get("width").unwrap(), "3");
+    assert_eq!(data.meta.get("height").unwrap(), "1");
+
+    // from now on the frame is provided once both universes are received
+    send_packets(46521, &[artnet_dmx(0x101, &[0, 0, 0, 8, 7, 9])]);
+    thread::sleep(time::Duration::from_millis(50));
+    send_packets(46521, &[artnet_dmx(0, &[2, 1, 3])]);
+
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[1], 0, 0, 0);
+    assert_rgb(&data.rgb[2], 7, 8, 9);
+}
+
+#[test]
+fn test_artnet_sync() {
+    let config = json!({
+        "address": "127.0.0.1",
+        "protocols": ["artnet"],
+        "artnet_port": 46522,
+        "width": 1,
+        "height": 1,
+        "universes": [{ "universe": 1, "led_count": 1 }]
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    // after the first ArtSync the data is held until the next one
+    send_packets(46522, &[artnet::encode_sync()]);
+    send_packets(46522, &[artnet_dmx(1, &[1, 1, 1])]);
+    thread::sleep(time::Duration::from_millis(50));
+    send_packets(46522, &[artnet_dmx(1, &[2, 2, 2]), artnet::encode_sync()]);
+
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 2, 2, 2);
+}
+
+#[test]
+fn test_e131_priority() {
+    let mut plugin = call_create(&e131_config(46523)).unwrap();
+    plugin.init().unwrap();
+
+    send_packets(
+        46523,
+        &[e131_data(1, 150, 1, 1, &[1, 1, 1, 1, 1, 1]).encode()],
+    );
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 1, 1);
+
+    // the source with lower priority is ignored
+    send_packets(
+        46523,
+        &[
+            e131_data(2, 100, 1, 1, &[9, 9, 9, 9, 9, 9]).encode(),
+            e131_data(1, 150, 2, 1, &[3, 3, 3]).encode(),
+        ],
+    );
+    plugin.get().unwrap();
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 3, 3, 3);
+    // the second LED is not part of the universe anymore
+    assert_rgb(&data.rgb[1], 0, 0, 0);
+}
+
+#[test]
+fn test_e131_merge_equal_priority() {
+    let mut plugin = call_create(&e131_config(46524)).unwrap();
+    plugin.init().unwrap();
+
+    send_packets(
+        46524,
+        &[
+            e131_data(1, 100, 1, 1, &[10, 0, 30, 0, 0, 0]).encode(),
+            e131_data(2, 100, 1, 1, &[0, 20, 5, 40, 0, 0]).encode(),
+        ],
+    );
+    plugin.get().unwrap();
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 10, 20, 30);
+    assert_rgb(&data.rgb[1], 40, 0, 0);
+}
+
+#[test]
+fn test_e131_sequence() {
+    let mut plugin = call_create(&e131_config(46525)).unwrap();
+    plugin.init().unwrap();
+
+    send_packets(
+        46525,
+        &[
+            e131_data(1, 100, 10, 1, &[1, 1, 1]).encode(),
+            // out of order
+            e131_data(1, 100, 9, 1, &[2, 2, 2]).encode(),
+            e131_data(1, 100, 11, 1, &[3, 3, 3]).encode(),
+        ],
+    );
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 1, 1);
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 3, 3, 3);
+}
+
+#[test]
+fn test_e131_sync() {
+    let mut plugin = call_create(&e131_config(46526)).unwrap();
+    plugin.init().unwrap();
+
+    let mut packet = e131_data(1, 100, 1, 1, &[1, 1, 1]);
+    packet.sync_address = 7;
+    let other_sync = e131::SyncPacket {
+        cid: [1; 16],
+        sequence: 1,
+        sync_address: 8,
+    };
+    let sync = e131::SyncPacket {
+        cid: [1; 16],
+        sequence: 2,
+        sync_address: 7,
+    };
+    // after the first sync packet the data is held until the next one
+    send_packets(46526, &[sync.encode()]);
+    send_packets(46526, &[packet.encode(), other_sync.encode(), sync.encode()]);
+
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 1, 1);
+}
+
+#[test]
+fn test_e131_sync_fallback() {
+    let mut plugin = call_create(&e131_config(46529)).unwrap();
+    plugin.init().unwrap();
+
+    // without sync packets synchronized data is output immediately
+    let mut packet = e131_data(1, 100, 1, 1, &[4, 4, 4]);
+    packet.sync_address = 7;
+    send_packets(46529, &[packet.encode()]);
+
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 4, 4, 4);
+}
+
+#[test]
+fn test_e131_source_timeout() {
+    let mut config = e131_config(46527);
+    config["source_timeout"] = json!(100);
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    send_packets(46527, &[e131_data(1, 100, 1, 1, &[5, 5, 5]).encode()]);
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 5, 5, 5);
+
+    let start = time::Instant::now();
+    let data = plugin.get().unwrap();
+    assert!(start.elapsed() >= time::Duration::from_millis(90));
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+}
+
+#[test]
+fn test_e131_stream_terminated() {
+    let mut plugin = call_create(&e131_config(46528)).unwrap();
+    plugin.init().unwrap();
+
+    let mut terminated = e131_data(1, 100, 2, 1, &[5, 5, 5]);
+    terminated.options = e131::OPTION_STREAM_TERMINATED;
+    send_packets(
+        46528,
+        &[e131_data(1, 100, 1, 1, &[5, 5, 5]).encode(), terminated.encode()],
+    );
+    plugin.get().unwrap();
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 0, 0, 0);
+}
@@ -0,0 +1,160 @@
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+use lightoros_plugin_base::input::{CreateInputPluginResult, PluginInputTrait};
+use lightoros_plugin_base::*;
+use lightoros_protocols::tpm2;
+
+use serde::Deserialize;
+
+const NAME: &str = "NetUdpInputTPM2";
+
+// max UDP payload size
+const MAX_PACKET_LENGTH: usize = 65507;
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    #[serde(default = "default_address")]
+    address: String,
+    port: u16,
+    width: usize,
+    height: usize,
+    #[serde(default = "default_color_order")]
+    color_order: String,
+    #[serde(default = "default_frame_timeout")]
+    frame_timeout: u64,
+}
+
+fn default_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_color_order() -> String {
+    "grb".to_string()
+}
+
+fn default_frame_timeout() -> u64 {
+    100
+}
+
+struct NetUdpTpm2Input {
+    config: Config,
+    color_order: ColorOrder,
+    socket: Option<UdpSocket>,
+    buffer: Vec<u8>,
+    // packets of the last UDP packet not decoded yet
+    decoder: tpm2::Decoder,
+    assembler: tpm2::FrameAssembler,
+    frame_started: Instant,
+}
+
+impl std::fmt::Display for NetUdpTpm2Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl NetUdpTpm2Input {
+    fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+        let config = plugin_config_or_return!(config.clone());
+
+        if config.width == 0 || config.height == 0 {
+            return plugin_err!("Invalid frame size {}x{}", config.width, config.height);
+        }
+
+        let color_order: ColorOrder = config.color_order.parse()?;
+
+        let plugin = NetUdpTpm2Input {
+            config,
+            color_order,
+            socket: None,
+            buffer: vec![0u8; MAX_PACKET_LENGTH],
+            decoder: tpm2::Decoder::new(),
+            assembler: tpm2::FrameAssembler::new(),
+            frame_started: Instant::now(),
+        };
+        Ok(Box::new(plugin))
+    }
+
+    fn create_frame(&self, payload: &[u8]) -> TraitData {
+        let size = self.config.width * self.config.height;
+        let mut data_out = tpm2::decode_rgb(payload, self.color_order);
+        // the frame size is defined by the config, missing LEDs are black
+        data_out.resize(size, RGB { r: 0, g: 0, b: 0 });
+
+        plugin_data!(data_out, {
+            "width" => self.config.width,
+            "height" => self.config.height,
+        })
+    }
+}
+
+impl PluginInputTrait for NetUdpTpm2Input {
+    fn init(&mut self) -> PluginResult<()> {
+        let address = format!("{}:{}", self.config.address, self.config.port);
+        let socket = match UdpSocket::bind(&address) {
+            Ok(socket) => socket,
+            Err(err) => return plugin_err!("Error binding UDP socket to '{}': {}", address, err),
+        };
+
+        // wake up regularly to drop incomplete frames
+        let timeout = Duration::from_millis(std::cmp::max(self.config.frame_timeout, 1));
+        if let Err(err) = socket.set_read_timeout(Some(timeout)) {
+            return plugin_err!("Error setting UDP socket timeout: {}", err);
+        }
+
+        self.socket = Some(socket);
+        Ok(())
+    }
+
+    fn get(&mut self) -> PluginResult<TraitData> {
+        if self.socket.is_none() {
+            return plugin_err!("UDP socket not initialized");
+        }
+
+        let frame_timeout = Duration::from_millis(self.config.frame_timeout);
+
+        loop {
+            // a UDP packet can carry several frames, the remaining packets are returned first
+            while let Some(packet) = self.decoder.next_packet() {
+                if let Some(payload) = self.assembler.add(packet) {
+                    return Ok(self.create_frame(&payload));
+                }
+                if self.assembler.received() == 1 {
+                    // first packet of a new frame
+                    self.frame_started = Instant::now();
+                }
+            }
+            // every UDP packet carries complete TPM2.net packets, remaining bytes are a broken packet
+            if self.decoder.pending() > 0 {
+                self.decoder.clear();
+            }
+
+            let result = self.socket.as_ref().unwrap().recv_from(&mut self.buffer);
+
+            // packets of an incomplete frame got lost, drop them
+            if self.assembler.is_pending() && self.frame_started.elapsed() > frame_timeout {
+                self.assembler.reset();
+            }
+
+            let size = match result {
+                Ok((size, _)) => size,
+                Err(ref err)
+                    if err.kind() == std::io::ErrorKind::WouldBlock
+                        || err.kind() == std::io::ErrorKind::TimedOut =>
+                {
+                    continue
+                }
+                Err(err) => return plugin_err!("Error receiving UDP packet: {}", err),
+            };
+
+            self.decoder.push(&self.buffer[..size]);
+        }
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateInputPluginResult {
+    NetUdpTpm2Input::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Input, create, include_str!("../config_schema.json"));
@@ -0,0 +1,236 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::input::CreateInputPluginResult;
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::net::UdpSocket;
+use std::path::PathBuf;
+use std::{thread, time};
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateInputPluginResult {
+    unsafe { declaration().create_input(config, None) }
+}
+
+fn send_packets(port: u16, packets: &[Vec<u8>]) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    for packet in packets {
+        socket.send_to(packet, ("127.0.0.1", port)).unwrap();
+    }
+}
+
+fn assert_rgb(rgb: &RGB, r: u8, g: u8, b: u8) {
+    assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+}
+
+fn packet(packet_number: u8, number_of_packets: u8, payload: &[u8]) -> Vec<u8> {
+    let size = payload.len();
+    let mut out = vec![
+        0x9C,
+        0xDA,
+        (size >> 8) as u8,
+        (size & 0xFF) as u8,
+        packet_number,
+        number_of_packets,
+    ];
+    out.extend_from_slice(payload);
+    out.push(0x36);
+    out
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "NetUdpInputTPM2");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Input));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_input_net_udp_tpm2");
+}
+
+#[test]
+fn test_create() {
+    let config = json!({
+        "port": 46500,
+        "width": 10,
+        "height": 1
+    });
+    assert!(call_create(&config).is_ok());
+}
+
+#[test]
+fn test_create_with_empty_config() {
+    let config = json!({});
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_create_with_invalid_size() {
+    let config = json!({
+        "port": 46500,
+        "width": 0,
+        "height": 1
+    });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_create_with_invalid_color_order() {
+    let config = json!({
+        "port": 46500,
+        "width": 1,
+        "height": 1,
+        "color_order": "rgbw"
+    });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_receive_single_packet() {
+    let config = json!({
+        "address": "127.0.0.1",
+        "port": 46501,
+        "width": 2,
+        "height": 1,
+        "color_order": "rgb"
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    send_packets(46501, &[packet(1, 1, &[1, 2, 3, 4, 5, 6])]);
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 2);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[1], 4, 5, 6);
+    assert_eq!(data.meta.get("width").unwrap(), "2");
+    assert_eq!(data.meta.get("height").unwrap(), "1");
+}
+
+#[test]
+fn test_receive_packets_out_of_order() {
+    let config = json!({
+        "address": "127.0.0.1",
+        "port": 46502,
+        "width": 3,
+        "height": 1
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    // default color order is GRB
+    send_packets(
+        46502,
+        &[
+            packet(3, 3, &[8, 7, 9]),
+            packet(1, 3, &[2, 1, 3]),
+            packet(2, 3, &[5, 4, 6]),
+        ],
+    );
+
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 3);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[1], 4, 5, 6);
+    assert_rgb(&data.rgb[2], 7, 8, 9);
+}
+
+#[test]
+fn test_drop_incomplete_frame() {
+    let config = json!({
+        "address": "127.0.0.1",
+        "port": 46503,
+        "width": 2,
+        "height": 1,
+        "color_order": "rgb",
+        "frame_timeout": 50
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    // the second packet of the first frame is lost
+    send_packets(46503, &[packet(1, 2, &[1, 1, 1])]);
+    thread::spawn(|| {
+        thread::sleep(time::Duration::from_millis(300));
+        send_packets(46503, &[packet(2, 2, &[4, 4, 4]), packet(1, 2, &[3, 3, 3])]);
+    });
+
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 3, 3, 3);
+    assert_rgb(&data.rgb[1], 4, 4, 4);
+}
+
+#[test]
+fn test_pad_and_truncate_frame() {
+    let config = json!({
+        "address": "127.0.0.1",
+        "port": 46504,
+        "width": 2,
+        "height": 2,
+        "color_order": "rgb"
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    send_packets(46504, &[packet(1, 1, &[1, 2, 3])]);
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 4);
+    assert_rgb(&data.rgb[0], 1, 2, 3);
+    assert_rgb(&data.rgb[3], 0, 0, 0);
+
+    send_packets(46504, &[packet(1, 1, &[9; 18])]);
+    let data = plugin.get().unwrap();
+    assert_eq!(data.rgb.len(), 4);
+    assert_rgb(&data.rgb[3], 9, 9, 9);
+}
+
+#[test]
+fn test_skip_invalid_packets() {
+    let config = json!({
+        "address": "127.0.0.1",
+        "port": 46505,
+        "width": 1,
+        "height": 1,
+        "color_order": "rgb"
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    send_packets(
+        46505,
+        &[vec![0x01, 0x02, 0x03], packet(1, 1, &[7, 8, 9])],
+    );
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 7, 8, 9);
+}
+
+#[test]
+fn test_receive_several_frames_in_one_packet() {
+    let config = json!({
+        "address": "127.0.0.1",
+        "port": 46506,
+        "width": 1,
+        "height": 1,
+        "color_order": "rgb"
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    let mut datagram = packet(1, 1, &[1, 1, 1]);
+    datagram.extend(packet(1, 1, &[2, 2, 2]));
+    send_packets(46506, &[datagram]);
+
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 1, 1, 1);
+    let data = plugin.get().unwrap();
+    assert_rgb(&data.rgb[0], 2, 2, 2);
+}
@@ -0,0 +1,273 @@
+use rlua::{AnyUserData, UserData, UserDataMethods};
+
+use lightoros_plugin_base::RGB;
+
+use crate::LRGB;
+
+use std::ops::RangeInclusive;
+
+const BLACK: RGB = RGB { r: 0, g: 0, b: 0 };
+
+/// Largest buffer a script can create, the sizes come from the scripts
+pub const MAX_PIXELS: usize = 4096 * 4096;
+
+/// Image passed to the scripts as userdata, so drawing doesn't need Lua tables for each pixel.
+/// Coordinates start with 1 like Lua tables, drawing outside of the buffer is ignored.
+pub struct PixelBuffer {
+    pub width: usize,
+    pub height: usize,
+    /// pixels row by row starting with the top row
+    pub pixels: Vec<RGB>,
+}
+
+impl PixelBuffer {
+    /// Black buffer, fails for buffers with more than `MAX_PIXELS` pixels
+    pub fn new(width: usize, height: usize) -> rlua::Result<PixelBuffer> {
+        match width.checked_mul(height) {
+            Some(size) if size <= MAX_PIXELS => Ok(PixelBuffer {
+                width,
+                height,
+                pixels: vec![BLACK; size],
+            }),
+            _ => Err(rlua::Error::RuntimeError(format!(
+                "Invalid buffer size {}x{}, at most {} pixels are allowed",
+                width, height, MAX_PIXELS
+            ))),
+        }
+    }
+
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        if x >= 1 && y >= 1 && x <= self.width as i64 && y <= self.height as i64 {
+            Some((y as usize - 1) * self.width + x as usize - 1)
+        } else {
+            None
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, color: RGB) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = color;
+        }
+    }
+
+    fn line(&mut self, (x0, y0): (i64, i64), (x1, y1): (i64, i64), color: RGB) {
+        let ((x0, y0), (x1, y1)) = match self.clip_line((x0, y0), (x1, y1)) {
+            Some(line) => line,
+            None => return,
+        };
+        // Bresenham's line algorithm
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+        loop {
+            self.set(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            if 2 * error >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if 2 * error <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    // Liang-Barsky clipping to the buffer with a margin of a pixel,
+    // lines inside of the buffer are kept as they are
+    fn clip_line(
+        &self,
+        (x0, y0): (i64, i64),
+        (x1, y1): (i64, i64),
+    ) -> Option<((i64, i64), (i64, i64))> {
+        let (x0, y0, x1, y1) = (x0 as f64, y0 as f64, x1 as f64, y1 as f64);
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let (max_x, max_y) = (self.width as f64 + 1.0, self.height as f64 + 1.0);
+        let (mut start, mut end) = (0.0f64, 1.0f64);
+        for (p, q) in [(-dx, x0), (dx, max_x - x0), (-dy, y0), (dy, max_y - y0)] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return None;
+                }
+            } else if p < 0.0 {
+                start = start.max(q / p);
+            } else {
+                end = end.min(q / p);
+            }
+        }
+        if start > end {
+            return None;
+        }
+        let point = |t: f64| ((x0 + t * dx).round() as i64, (y0 + t * dy).round() as i64);
+        Some((point(start), point(end)))
+    }
+
+    fn rect(&mut self, x: i64, y: i64, width: i64, height: i64, color: RGB, filled: bool) {
+        let right = x as i128 + width as i128 - 1;
+        let bottom = y as i128 + height as i128 - 1;
+        for row in clip(y as i128, height as i128, self.height) {
+            for column in clip(x as i128, width as i128, self.width) {
+                let border =
+                    row == y || row as i128 == bottom || column == x || column as i128 == right;
+                if filled || border {
+                    self.set(column, row, color);
+                }
+            }
+        }
+    }
+
+    fn circle(&mut self, center_x: i64, center_y: i64, radius: i64, color: RGB, filled: bool) {
+        // pixels with their center inside of the circle, the outline keeps the pixels close to the radius
+        let outer = (radius as f64 + 0.5).powi(2);
+        let inner = (radius as f64 - 0.5).powi(2);
+        let size = 2 * radius as i128 + 1;
+        for row in clip(center_y as i128 - radius as i128, size, self.height) {
+            for column in clip(center_x as i128 - radius as i128, size, self.width) {
+                let (x, y) = (
+                    column as i128 - center_x as i128,
+                    row as i128 - center_y as i128,
+                );
+                let distance = (x * x + y * y) as f64;
+                if distance <= outer && (filled || distance >= inner) {
+                    self.set(column, row, color);
+                }
+            }
+        }
+    }
+
+    fn blit(&mut self, source: &PixelBuffer, x: i64, y: i64) {
+        for row in clip(y as i128, source.height as i128, self.height) {
+            for column in clip(x as i128, source.width as i128, self.width) {
+                let source_row = (row as i128 - y as i128) as usize;
+                let source_column = (column as i128 - x as i128) as usize;
+                let color = source.pixels[source_row * source.width + source_column];
+                self.set(column, row, color);
+            }
+        }
+    }
+
+    fn fade(&mut self, factor: f64) {
+        let factor = factor.clamp(0.0, 1.0);
+        let scale = |value: u8| (value as f64 * factor).round() as u8;
+        for pixel in self.pixels.iter_mut() {
+            *pixel = RGB {
+                r: scale(pixel.r),
+                g: scale(pixel.g),
+                b: scale(pixel.b),
+            };
+        }
+    }
+
+    fn shift(&mut self, dx: i64, dy: i64, wrap: bool) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        if width == 0 || height == 0 {
+            return;
+        }
+        // wrapping by whole sizes changes nothing, other shifts beyond the buffer leave it black
+        let (dx, dy) = if wrap {
+            (dx.rem_euclid(width), dy.rem_euclid(height))
+        } else {
+            (dx, dy)
+        };
+        let source = std::mem::replace(&mut self.pixels, vec![BLACK; self.width * self.height]);
+        for y in 0..height {
+            for x in 0..width {
+                let (mut target_x, mut target_y) = (x.saturating_add(dx), y.saturating_add(dy));
+                if wrap {
+                    target_x = target_x.rem_euclid(width);
+                    target_y = target_y.rem_euclid(height);
+                }
+                if target_x >= 0 && target_x < width && target_y >= 0 && target_y < height {
+                    self.pixels[(target_y * width + target_x) as usize] =
+                        source[(y * width + x) as usize];
+                }
+            }
+        }
+    }
+}
+
+// coordinates of `length` pixels from `start` inside of a buffer side with `size` pixels,
+// drawing is limited to the buffer since the sizes come from the scripts
+fn clip(start: i128, length: i128, size: usize) -> RangeInclusive<i64> {
+    let first = start.clamp(1, size as i128 + 1);
+    let last = (start + length - 1).clamp(0, size as i128);
+    first as i64..=last as i64
+}
+
+fn to_rgb(color: LRGB) -> RGB {
+    RGB {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+    }
+}
+
+impl UserData for PixelBuffer {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("width", |_, this, ()| Ok(this.width));
+        methods.add_method("height", |_, this, ()| Ok(this.height));
+        methods.add_method("get", |_, this, (x, y): (i64, i64)| {
+            Ok(this.index(x, y).map(|index| {
+                let pixel = this.pixels[index];
+                LRGB {
+                    r: pixel.r,
+                    g: pixel.g,
+                    b: pixel.b,
+                }
+            }))
+        });
+        methods.add_method_mut("set", |_, this, (x, y, color): (i64, i64, LRGB)| {
+            this.set(x, y, to_rgb(color));
+            Ok(())
+        });
+        methods.add_method_mut("fill", |_, this, color: LRGB| {
+            let color = to_rgb(color);
+            for pixel in this.pixels.iter_mut() {
+                *pixel = color;
+            }
+            Ok(())
+        });
+        methods.add_method_mut(
+            "line",
+            |_, this, (x0, y0, x1, y1, color): (i64, i64, i64, i64, LRGB)| {
+                this.line((x0, y0), (x1, y1), to_rgb(color));
+                Ok(())
+            },
+        );
+        methods.add_method_mut(
+            "rect",
+            |_, this, (x, y, width, height, color, filled): (i64, i64, i64, i64, LRGB, Option<bool>)| {
+                this.rect(x, y, width, height, to_rgb(color), filled.unwrap_or(false));
+                Ok(())
+            },
+        );
+        methods.add_method_mut(
+            "circle",
+            |_, this, (x, y, radius, color, filled): (i64, i64, i64, LRGB, Option<bool>)| {
+                this.circle(x, y, radius, to_rgb(color), filled.unwrap_or(false));
+                Ok(())
+            },
+        );
+        methods.add_method_mut(
+            "blit",
+            |_, this, (source, x, y): (AnyUserData, i64, i64)| {
+                let source = source.borrow::<PixelBuffer>()?;
+                this.blit(&source, x, y);
+                Ok(())
+            },
+        );
+        methods.add_method_mut("fade", |_, this, factor: f64| {
+            this.fade(factor);
+            Ok(())
+        });
+        methods.add_method_mut(
+            "shift",
+            |_, this, (dx, dy, wrap): (i64, i64, Option<bool>)| {
+                this.shift(dx, dy, wrap.unwrap_or(false));
+                Ok(())
+            },
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use rlua::{Context, Function, Table};
+
+use chrono::{Datelike, Local, Timelike};
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::noise::Perlin;
+use crate::LRGB;
+
+type Easing = (&'static str, fn(f64) -> f64);
+
+// noise looks the same on each start of an effect
+const NOISE_SEED: u32 = 0x9e37_79b9;
+
+/// Adds the helper tables `color`, `ease` and `noise` to the globals
+pub fn register(lua_ctx: Context) -> rlua::Result<()> {
+    let globals = lua_ctx.globals();
+    globals.set("color", color_functions(lua_ctx)?)?;
+    globals.set("ease", ease_functions(lua_ctx)?)?;
+
+    let noise = lua_ctx.create_table()?;
+    let perlin = Perlin::new(NOISE_SEED);
+    noise.set(
+        "perlin",
+        lua_ctx.create_function(move |_, (x, y, z): (f64, Option<f64>, Option<f64>)| {
+            Ok(perlin.get(x, y.unwrap_or(0.0), z.unwrap_or(0.0)))
+        })?,
+    )?;
+    globals.set("noise", noise)?;
+    Ok(())
+}
+
+/// Time functions added to the `api` table
+pub fn time_functions(lua_ctx: Context) -> rlua::Result<Vec<(&'static str, Function)>> {
+    Ok(vec![
+        (
+            "time",
+            lua_ctx.create_function(|_, ()| {
+                let time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|time| time.as_secs_f64())
+                    .unwrap_or(0.0);
+                Ok(time)
+            })?,
+        ),
+        (
+            "localTime",
+            lua_ctx.create_function(|lua_ctx, ()| {
+                let now = Local::now();
+                let time = lua_ctx.create_table()?;
+                time.set("year", now.year())?;
+                time.set("month", now.month())?;
+                time.set("day", now.day())?;
+                time.set("weekday", now.weekday().number_from_monday())?;
+                time.set("hour", now.hour())?;
+                time.set("minute", now.minute())?;
+                time.set("second", now.second())?;
+                time.set("millisecond", now.nanosecond() / 1_000_000 % 1000)?;
+                Ok(time)
+            })?,
+        ),
+    ])
+}
+
+fn color_functions(lua_ctx: Context) -> rlua::Result<Table> {
+    let color = lua_ctx.create_table()?;
+    color.set(
+        "hsv2rgb",
+        lua_ctx.create_function(|_, (hue, saturation, value): (i32, i32, i32)| {
+            let (red, green, blue) = hsv2rgb(hue, saturation, value);
+            Ok((red as i64, green as i64, blue as i64))
+        })?,
+    )?;
+    color.set(
+        "hsl2rgb",
+        lua_ctx.create_function(|_, (hue, saturation, lightness): (f64, f64, f64)| {
+            Ok(hsl2rgb(hue, saturation / 255.0, lightness / 255.0))
+        })?,
+    )?;
+    color.set(
+        "blend",
+        lua_ctx.create_function(|_, (from, to, amount): (LRGB, LRGB, f64)| {
+            Ok(blend(from, to, amount))
+        })?,
+    )?;
+    color.set(
+        "palette",
+        lua_ctx.create_function(
+            |_, (colors, position, cyclic): (Vec<LRGB>, f64, Option<bool>)| {
+                if colors.is_empty() {
+                    return Err(rlua::Error::RuntimeError("Empty palette".to_string()));
+                }
+                Ok(palette(&colors, position, cyclic.unwrap_or(false)))
+            },
+        )?,
+    )?;
+    color.set(
+        "gamma",
+        lua_ctx.create_function(|_, (color, gamma): (LRGB, f64)| {
+            let correct = |value: u8| (255.0 * (value as f64 / 255.0).powf(gamma)).round() as u8;
+            Ok(LRGB {
+                r: correct(color.r),
+                g: correct(color.g),
+                b: correct(color.b),
+            })
+        })?,
+    )?;
+    Ok(color)
+}
+
+fn ease_functions(lua_ctx: Context) -> rlua::Result<Table> {
+    let easings: [Easing; 8] = [
+        ("linear", |t| t),
+        ("inQuad", |t| t * t),
+        ("outQuad", |t| t * (2.0 - t)),
+        ("inOutQuad", |t| {
+            if t < 0.5 {
+                2.0 * t * t
+            } else {
+                -1.0 + (4.0 - 2.0 * t) * t
+            }
+        }),
+        ("inCubic", |t| t * t * t),
+        ("outCubic", |t| (t - 1.0).powi(3) + 1.0),
+        ("inOutCubic", |t| {
+            if t < 0.5 {
+                4.0 * t * t * t
+            } else {
+                (t - 1.0) * (2.0 * t - 2.0) * (2.0 * t - 2.0) + 1.0
+            }
+        }),
+        ("inOutSine", |t| {
+            -((std::f64::consts::PI * t).cos() - 1.0) / 2.0
+        }),
+    ];
+    let ease = lua_ctx.create_table()?;
+    for (name, easing) in easings.iter() {
+        let easing = *easing;
+        // the progress is limited to the range 0.0-1.0
+        ease.set(
+            *name,
+            lua_ctx.create_function(move |_, t: f64| Ok(easing(t.clamp(0.0, 1.0))))?,
+        )?;
+    }
+    Ok(ease)
+}
+
+fn hsv2rgb(hue: i32, saturation: i32, value: i32) -> (u8, u8, u8) {
+    let v = value as u8;
+    if saturation <= 0 {
+        return (v, v, v);
+    }
+
+    let region = hue / 60;
+    let remainder = (hue - (region * 60)) * 256 / 60;
+
+    let p = ((value * (255 - saturation)) >> 8) as u8;
+    let q = ((value * (255 - ((saturation * remainder) >> 8))) >> 8) as u8;
+    let t = ((value * (255 - ((saturation * (255 - remainder)) >> 8))) >> 8) as u8;
+
+    match region {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    }
+}
+
+// saturation and lightness in the range 0.0-1.0
+fn hsl2rgb(hue: f64, saturation: f64, lightness: f64) -> LRGB {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let (saturation, lightness) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f64| ((value + m) * 255.0).round() as u8;
+    LRGB {
+        r: channel(r),
+        g: channel(g),
+        b: channel(b),
+    }
+}
+
+fn blend(from: LRGB, to: LRGB, amount: f64) -> LRGB {
+    let amount = amount.clamp(0.0, 1.0);
+    let mix = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * amount).round() as u8;
+    LRGB {
+        r: mix(from.r, to.r),
+        g: mix(from.g, to.g),
+        b: mix(from.b, to.b),
+    }
+}
+
+// color at the position in the range 0.0-1.0 of a gradient through all colors,
+// a cyclic palette blends the last color back into the first one and the position wraps around
+fn palette(colors: &[LRGB], position: f64, cyclic: bool) -> LRGB {
+    if colors.len() == 1 {
+        return colors[0];
+    }
+    let (position, segments) = if cyclic {
+        (position.rem_euclid(1.0), colors.len())
+    } else {
+        (position.clamp(0.0, 1.0), colors.len() - 1)
+    };
+    let scaled = position * segments as f64;
+    let index = (scaled as usize).min(segments - 1);
+    blend(
+        colors[index],
+        colors[(index + 1) % colors.len()],
+        scaled - index as f64,
+    )
+}
@@ -0,0 +1,104 @@
+//! Lua runtime shared by the Lua plugins: sandbox, limits, modules and the helpers available to the scripts
+
+use rlua::{Context, FromLua, ToLua, Value};
+
+pub mod buffer;
+pub mod helpers;
+mod noise;
+pub mod runtime;
+pub mod watch;
+
+pub use buffer::PixelBuffer;
+pub use runtime::{Budget, Options, Runtime};
+
+#[derive(Copy, Clone, Debug)]
+pub struct LRGB {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl<'lua> FromLua<'lua> for LRGB {
+    fn from_lua(value: Value<'lua>, _: Context<'lua>) -> rlua::Result<Self> {
+        if let Value::Table(table) = value {
+            // results of calculations are rounded and limited to the range of a channel
+            let channel = |index: i64| -> rlua::Result<u8> {
+                let value: f64 = table.raw_get(index)?;
+                Ok(value.round().clamp(0.0, 255.0) as u8)
+            };
+            Ok(LRGB {
+                r: channel(1)?,
+                g: channel(2)?,
+                b: channel(3)?,
+            })
+        } else {
+            Err(rlua::Error::FromLuaConversionError {
+                from: "LUA type",
+                to: "LRGB",
+                message: Some("expected table".to_string()),
+            })
+        }
+    }
+}
+
+impl<'lua> ToLua<'lua> for LRGB {
+    fn to_lua(self, lua_ctx: Context<'lua>) -> rlua::Result<Value<'lua>> {
+        let table = lua_ctx.create_sequence_from(vec![self.r, self.g, self.b])?;
+        Ok(Value::Table(table))
+    }
+}
+
+/// Converts a value of the plugin configuration to a Lua value
+pub fn json_to_lua<'lua>(
+    lua_ctx: Context<'lua>,
+    value: &serde_json::Value,
+) -> rlua::Result<Value<'lua>> {
+    let value = match value {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(value) => Value::Boolean(*value),
+        serde_json::Value::Number(value) => match value.as_i64() {
+            Some(value) => Value::Integer(value),
+            None => Value::Number(value.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(value) => Value::String(lua_ctx.create_string(value)?),
+        serde_json::Value::Array(values) => {
+            let table = lua_ctx.create_table()?;
+            for (index, value) in values.iter().enumerate() {
+                table.set(index + 1, json_to_lua(lua_ctx, value)?)?;
+            }
+            Value::Table(table)
+        }
+        serde_json::Value::Object(values) => {
+            let table = lua_ctx.create_table()?;
+            for (key, value) in values {
+                table.set(key.as_str(), json_to_lua(lua_ctx, value)?)?;
+            }
+            Value::Table(table)
+        }
+    };
+    Ok(value)
+}
+
+/// Converts a runtime parameter to a value passed to the scripts.
+/// Parameter values are numbers, booleans, strings or comma separated lists of them.
+pub fn parameter_value(value: &str) -> serde_json::Value {
+    if value.contains(',') {
+        return serde_json::Value::Array(
+            value
+                .split(',')
+                .map(|value| parameter_value(value.trim()))
+                .collect(),
+        );
+    }
+    if let Ok(number) = value.parse::<i64>() {
+        return number.into();
+    }
+    if let Ok(number) = value.parse::<f64>() {
+        return number.into();
+    }
+    match value {
+        "true" => true.into(),
+        "false" => false.into(),
+        _ => value.into(),
+    }
+}
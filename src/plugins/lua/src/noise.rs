@@ -0,0 +1,89 @@
+/// Improved Perlin noise in up to 3 dimensions
+pub struct Perlin {
+    permutation: Vec<u8>,
+}
+
+impl Perlin {
+    /// The permutation is shuffled with the seed, so each seed gives a different noise
+    pub fn new(seed: u32) -> Perlin {
+        let mut values: Vec<u8> = (0..=255).collect();
+        // xorshift random numbers for a Fisher-Yates shuffle
+        let mut state = seed.max(1);
+        for index in (1..values.len()).rev() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            values.swap(index, state as usize % (index + 1));
+        }
+        let mut permutation = values.clone();
+        permutation.extend(values);
+        Perlin { permutation }
+    }
+
+    /// Noise value in the range 0.0-1.0
+    pub fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        let p = &self.permutation;
+        let (cell_x, cell_y, cell_z) = (
+            (x.floor() as i64 & 255) as usize,
+            (y.floor() as i64 & 255) as usize,
+            (z.floor() as i64 & 255) as usize,
+        );
+        let (x, y, z) = (x - x.floor(), y - y.floor(), z - z.floor());
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let a = p[cell_x] as usize + cell_y;
+        let aa = p[a] as usize + cell_z;
+        let ab = p[a + 1] as usize + cell_z;
+        let b = p[cell_x + 1] as usize + cell_y;
+        let ba = p[b] as usize + cell_z;
+        let bb = p[b + 1] as usize + cell_z;
+
+        let value = lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(p[ab], x, y - 1.0, z),
+                    grad(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p[aa + 1], x, y, z - 1.0),
+                    grad(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(p[ab + 1], x, y - 1.0, z - 1.0),
+                    grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        );
+        ((value + 1.0) / 2.0).clamp(0.0, 1.0)
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
@@ -0,0 +1,240 @@
+use rlua::{Context, HookTriggers, Lua, StdLib, Table, Value};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::helpers;
+use crate::watch::{self, Signature};
+
+// instructions between two checks of the limits
+const HOOK_INSTRUCTIONS: u64 = 1000;
+
+// registry table with the modules loaded by require()
+const MODULES: &str = "lightoros_modules";
+
+/// Folder, sandbox and limits of the scripts
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// folder of the modules loaded with require()
+    pub folder: PathBuf,
+    /// only the safe parts of the standard library are available
+    pub sandbox: bool,
+    /// time in ms a script may run between two frames, 0 for no limit
+    pub max_frame_time: u64,
+    /// Lua instructions a script may execute between two frames, 0 for no limit
+    pub max_frame_instructions: u64,
+}
+
+// time and instructions used since the last frame
+struct Usage {
+    start: Instant,
+    instructions: u64,
+}
+
+/// Time and instructions a script has used for the current frame
+#[derive(Clone)]
+pub struct Budget(Arc<Mutex<Usage>>);
+
+impl Budget {
+    fn new() -> Budget {
+        Budget(Arc::new(Mutex::new(Usage {
+            start: Instant::now(),
+            instructions: 0,
+        })))
+    }
+
+    /// Starts the next frame with the full budget
+    pub fn reset(&self) {
+        if let Ok(mut usage) = self.0.lock() {
+            usage.start = Instant::now();
+            usage.instructions = 0;
+        }
+    }
+}
+
+/// Files and their signature at loading time of the modules loaded by a script
+pub type Modules = Arc<Mutex<Vec<(PathBuf, Signature)>>>;
+
+/// Lua state with the sandbox, the limits, require() and the helper tables `color`, `ease` and `noise`
+pub struct Runtime {
+    pub lua: Lua,
+    pub budget: Budget,
+    pub modules: Modules,
+}
+
+impl Runtime {
+    /// Scripts are stopped by an error as soon as `stop` is set
+    pub fn new(options: &Options, stop: Arc<AtomicBool>) -> rlua::Result<Runtime> {
+        let lua = if options.sandbox {
+            // io, os, package and debug are not available in the sandbox
+            Lua::new_with(
+                StdLib::BASE
+                    | StdLib::COROUTINE
+                    | StdLib::TABLE
+                    | StdLib::STRING
+                    | StdLib::UTF8
+                    | StdLib::MATH,
+            )
+        } else {
+            Lua::new()
+        };
+        let budget = Budget::new();
+        let modules: Modules = Arc::new(Mutex::new(Vec::new()));
+        set_limits(&lua, options, budget.clone(), stop);
+
+        lua.context(|lua_ctx| -> rlua::Result<()> {
+            let globals = lua_ctx.globals();
+            if options.sandbox {
+                // files can only be loaded as modules from the script folder
+                globals.set("dofile", Value::Nil)?;
+                globals.set("loadfile", Value::Nil)?;
+            }
+            lua_ctx.set_named_registry_value(MODULES, lua_ctx.create_table()?)?;
+            let folder = options.folder.clone();
+            let loaded = modules.clone();
+            globals.set(
+                "require",
+                lua_ctx.create_function(move |lua_ctx, name: String| {
+                    require(lua_ctx, &folder, &loaded, name)
+                })?,
+            )?;
+            helpers::register(lua_ctx)
+        })?;
+
+        Ok(Runtime {
+            lua,
+            budget,
+            modules,
+        })
+    }
+}
+
+/// Error of the API functions once the script has to stop
+pub fn stopped_error() -> rlua::Error {
+    rlua::Error::RuntimeError("Script stopped".to_string())
+}
+
+/// First line of the error with the location in the script, tracebacks are dropped
+pub fn error_message(err: &rlua::Error) -> String {
+    match err {
+        rlua::Error::CallbackError { traceback, cause } => {
+            // errors of API functions get the location of the calling script line
+            let location = traceback
+                .lines()
+                .skip(1)
+                .map(|line| line.trim())
+                .find(|line| !line.starts_with("[C]"))
+                .and_then(|line| line.split(": in ").next());
+            match location {
+                Some(location) => format!("{}: {}", location, error_message(cause)),
+                None => error_message(cause),
+            }
+        }
+        rlua::Error::RuntimeError(message) => first_line(message),
+        rlua::Error::SyntaxError { message, .. } => first_line(message),
+        err => first_line(&err.to_string()),
+    }
+}
+
+fn first_line(message: &str) -> String {
+    message.lines().next().unwrap_or_default().to_string()
+}
+
+/// Compiles a script without running it to find syntax errors
+pub fn check_syntax(source: &str, file_name: &str) -> Result<(), String> {
+    let lua = Lua::new_with(StdLib::BASE);
+    lua.context(|lua_ctx| {
+        lua_ctx
+            .load(source)
+            .set_name(&format!("@{}", file_name))?
+            .into_function()
+            .map(|_| ())
+    })
+    .map_err(|err| error_message(&err))
+}
+
+// the hook is called every few instructions to stop scripts and to enforce the limits
+fn set_limits(lua: &Lua, options: &Options, budget: Budget, stop: Arc<AtomicBool>) {
+    let max_time = Duration::from_millis(options.max_frame_time);
+    let max_instructions = options.max_frame_instructions;
+    let triggers = HookTriggers {
+        every_nth_instruction: Some(HOOK_INSTRUCTIONS as u32),
+        ..Default::default()
+    };
+    lua.set_hook(triggers, move |_, debug| {
+        if stop.load(Ordering::SeqCst) {
+            return Err(stopped_error());
+        }
+        let mut usage = budget.0.lock().map_err(|_| stopped_error())?;
+        usage.instructions += HOOK_INSTRUCTIONS;
+
+        let exceeded = if max_instructions > 0 && usage.instructions > max_instructions {
+            format!(
+                "Instruction limit of {} per frame exceeded",
+                max_instructions
+            )
+        } else if max_time > Duration::from_millis(0) && usage.start.elapsed() > max_time {
+            format!(
+                "Time limit of {} ms per frame exceeded",
+                max_time.as_millis()
+            )
+        } else {
+            return Ok(());
+        };
+        let source = debug.source();
+        let location = String::from_utf8_lossy(source.short_src.unwrap_or(b"?"));
+        Err(rlua::Error::RuntimeError(format!(
+            "{}:{}: {}",
+            location,
+            debug.curr_line(),
+            exceeded
+        )))
+    });
+}
+
+// modules are loaded from the script folder, a name like 'lib.colors' loads 'lib/colors.lua'
+fn require<'lua>(
+    lua_ctx: Context<'lua>,
+    folder: &Path,
+    loaded: &Mutex<Vec<(PathBuf, Signature)>>,
+    name: String,
+) -> rlua::Result<Value<'lua>> {
+    let modules: Table = lua_ctx.named_registry_value(MODULES)?;
+    match modules.get::<_, Value>(name.as_str())? {
+        Value::Nil => {}
+        module => return Ok(module),
+    }
+
+    let valid = name.split('.').all(|part| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    });
+    if !valid {
+        return Err(rlua::Error::RuntimeError(format!(
+            "Invalid module name '{}'",
+            name
+        )));
+    }
+    let file_name = format!("{}.lua", name.replace('.', "/"));
+    let path = folder.join(&file_name);
+    if let Ok(mut loaded) = loaded.lock() {
+        loaded.push((path.clone(), watch::signature(&path)));
+    }
+    let source = fs::read_to_string(&path).map_err(|err| {
+        rlua::Error::RuntimeError(format!("Module '{}' not found: {}", name, err))
+    })?;
+
+    let module: Value = lua_ctx
+        .load(&source)
+        .set_name(&format!("@{}", file_name))?
+        .call(name.as_str())?;
+    // modules without a return value are marked as loaded like in the standard require
+    let module = match module {
+        Value::Nil => Value::Boolean(true),
+        module => module,
+    };
+    modules.set(name, module.clone())?;
+    Ok(module)
+}
@@ -0,0 +1,45 @@
+use std::collections::hash_map::{Entry, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Modification time and size of a file, None for files which can't be read
+pub type Signature = Option<(SystemTime, u64)>;
+
+/// Detects changes of files by polling their modification time and size
+#[derive(Default)]
+pub struct FileWatcher {
+    files: HashMap<PathBuf, Signature>,
+}
+
+impl FileWatcher {
+    pub fn new() -> FileWatcher {
+        FileWatcher::default()
+    }
+
+    /// Adds a file with its signature at the time it was read, files already watched are ignored
+    pub fn watch(&mut self, path: PathBuf, signature: Signature) {
+        if let Entry::Vacant(entry) = self.files.entry(path) {
+            entry.insert(signature);
+        }
+    }
+
+    /// Files changed since the last call, each change is reported once
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, last) in self.files.iter_mut() {
+            let current = signature(path);
+            if current != *last {
+                *last = current;
+                changed.push(path.clone());
+            }
+        }
+        changed.sort();
+        changed
+    }
+}
+
+pub fn signature(path: &Path) -> Signature {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
@@ -0,0 +1,45 @@
+use lightoros_plugin_base::output::{CreateOutputPluginResult, PluginOutputTrait};
+use lightoros_plugin_base::*;
+use lightoros_plugin_wasm::{Config, WasmPlugin};
+
+const NAME: &str = "WasmExtraOutput";
+
+struct WasmExtraOutput {
+    module: WasmPlugin,
+}
+
+impl std::fmt::Display for WasmExtraOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+/**
+ * The module is loaded once, its send function is called for each frame.
+ * Modules have no access to files or the network, they can only log the frames or pass them on with the host functions.
+*/
+impl WasmExtraOutput {
+    fn create(config: &serde_json::Value) -> CreateOutputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+        let module = WasmPlugin::load(&config, &PluginKind::Output, NAME)?;
+
+        let plugin = WasmExtraOutput { module };
+        Ok(Box::new(plugin))
+    }
+}
+
+impl PluginOutputTrait for WasmExtraOutput {
+    fn init(&mut self) -> PluginResult<()> {
+        Ok(())
+    }
+
+    fn send(&mut self, data: &TraitData) -> PluginResult<()> {
+        self.module.send(data)
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateOutputPluginResult {
+    WasmExtraOutput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Output, create, include_str!("../config_schema.json"));
@@ -0,0 +1,108 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::output::CreateOutputPluginResult;
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::path::PathBuf;
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+// logs the frames and fails for frames with more than the configured number of bytes
+const LOG_MODULE: &str = r#"
+(module
+  (import "lightoros" "log" (func $log (param i32 i32 i32)))
+  (import "lightoros" "set_error" (func $set_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "frame")
+  (data (i32.const 16) "frame too large")
+  (global $heap (mut i32) (i32.const 1024))
+  (global $max_len (mut i32) (i32.const 0))
+
+  (func (export "lightoros_abi_version") (result i32) (i32.const 1))
+
+  (func (export "alloc") (param $size i32) (result i32) (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $size)))
+    (local.get $ptr))
+
+  ;; the configuration is a single number
+  (func (export "init") (param $ptr i32) (param $len i32) (result i32)
+    (global.set $max_len (i32.sub (i32.load8_u (local.get $ptr)) (i32.const 48)))
+    (i32.const 0))
+
+  (func (export "send") (param $ptr i32) (param $len i32) (param $width i32) (param $height i32) (result i32)
+    (if (i32.gt_u (local.get $len) (global.get $max_len)) (then
+      (call $set_error (i32.const 16) (i32.const 15))
+      (return (i32.const 2))))
+    (call $log (i32.const 1) (i32.const 0) (i32.const 5))
+    (i32.const 0))
+)
+"#;
+
+fn call_create(config: &serde_json::Value) -> CreateOutputPluginResult {
+    unsafe { declaration().create_output(config, None) }
+}
+
+// path of the compiled module in a folder unique for each test since the tests run in parallel
+fn module_file(test: &str, source: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(format!("lightoros_wasm_output_{}", test));
+    std::fs::create_dir_all(&path).unwrap();
+    path.push("module.wasm");
+    std::fs::write(&path, wat::parse_str(source).unwrap()).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "WasmExtraOutput");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Output));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_output_extra_wasm");
+}
+
+#[test]
+fn test_create() {
+    let module = module_file("create", LOG_MODULE);
+    assert!(call_create(&json!({"module": module, "config": 6})).is_ok());
+    assert!(call_create(&json!({})).is_err());
+
+    // an input module is no output
+    let source = LOG_MODULE.replace("(export \"send\")", "(export \"get\")");
+    let module = module_file("create_kind", &source);
+    assert!(call_create(&json!({ "module": module })).is_err());
+}
+
+#[test]
+fn test_send() {
+    let module = module_file("send", LOG_MODULE);
+    let mut plugin = call_create(&json!({"module": module, "config": 6})).unwrap();
+    assert!(plugin.init().is_ok());
+
+    let rgb = vec![RGB { r: 1, g: 2, b: 3 }; 2];
+    assert!(plugin.send(&plugin_data!(rgb, {})).is_ok());
+
+    let rgb = vec![RGB { r: 1, g: 2, b: 3 }; 3];
+    let error = plugin.send(&plugin_data!(rgb, {})).err().unwrap();
+    assert_eq!(error.to_string(), "Function 'send' failed: frame too large");
+}
+
+#[test]
+fn test_error_code() {
+    let source = LOG_MODULE.replace("(call $set_error (i32.const 16) (i32.const 15))", "");
+    let module = module_file("error_code", &source);
+    let mut plugin = call_create(&json!({"module": module, "config": 0})).unwrap();
+    let rgb = vec![RGB { r: 1, g: 2, b: 3 }];
+    let error = plugin.send(&plugin_data!(rgb, {})).err().unwrap();
+    assert_eq!(error.to_string(), "Function 'send' failed with code 2");
+}
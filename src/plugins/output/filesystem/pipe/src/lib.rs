@@ -10,6 +10,7 @@ use std::ops::Deref;
 
 use lightoros_plugin_base::output::{CreateOutputPluginResult, PluginOutputTrait};
 use lightoros_plugin_base::*;
+use lightoros_protocols::tpm2;
 use serde::Deserialize;
 
 const NAME: &str = "FilesystemPipeOutput";
@@ -91,18 +92,8 @@ impl PluginOutputTrait for FilesystemPipeOutput {
         let mut out: Vec<u8>;
 
         if self.config.protocol == "tpm2" {
-            let out_size = rgb_data.len() * 3;
-            out = Vec::with_capacity(out_size + 5);
-            out.push(0xC9);
-            out.push(0xDA);
-            out.push(((out_size >> 8) & 0xFF) as u8);
-            out.push((out_size & 0xFF) as u8);
-            for rgb in rgb_data {
-                out.push(rgb.g);
-                out.push(rgb.r);
-                out.push(rgb.b);
-            }
-            out.push(0x36);
+            let encoder = tpm2::Encoder::new(tpm2::Framing::Serial, ColorOrder::GRB);
+            out = encoder.encode_frame(rgb_data)?.remove(0);
         } else if self.config.protocol == "raw" {
             out = Vec::with_capacity(rgb_data.len());
             for rgb in rgb_data {
@@ -127,15 +118,11 @@ impl PluginOutputTrait for FilesystemPipeOutput {
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateOutputPluginResult {
     FilesystemPipeOutput::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Output)
-}
+plugin_abi!(1, NAME, PluginKind::Output, create, include_str!("../config_schema.json"));
 
 extern "C" {
     pub fn mkfifo(pathname: *const c_char, mode: c_ushort) -> c_int;
@@ -1,23 +1,47 @@
 use serde::Deserialize;
 
 use std::net;
+use std::net::ToSocketAddrs;
 use std::ops::Deref;
 
 use lightoros_plugin_base::*;
 use lightoros_plugin_base::output::{PluginOutputTrait, CreateOutputPluginResult};
+use lightoros_protocols::tpm2;
 
 const NAME: &str = "NetUdpOutputTPM2";
 
+// max UDP payload size
+const MAX_PACKET_LENGTH: usize = 65507;
+
 #[derive(Deserialize, Debug)]
 struct Config {
     ip: String,
     port: u16,
     max_packet_length: u16,
+    #[serde(default)]
+    destinations: Vec<String>,
+    #[serde(default = "default_color_order")]
+    color_order: String,
+    #[serde(default = "default_multicast_ttl")]
+    multicast_ttl: u32,
+    #[serde(default)]
+    init_commands: Vec<Vec<u8>>,
+}
+
+fn default_color_order() -> String {
+    "grb".to_string()
+}
+
+fn default_multicast_ttl() -> u32 {
+    1
 }
 
 struct NetUdpTpm2Output {
-    address: net::SocketAddr,
-    max_packet_length: u16
+    addresses: Vec<net::SocketAddr>,
+    encoder: tpm2::Encoder,
+    multicast_ttl: u32,
+    init_commands: Vec<Vec<u8>>,
+    socket: Option<net::UdpSocket>,
 }
 
 impl std::fmt::Display for NetUdpTpm2Output {
@@ -29,107 +53,120 @@ impl std::fmt::Display for NetUdpTpm2Output {
 impl NetUdpTpm2Output {
     fn create(config: &serde_json::Value) -> CreateOutputPluginResult {
         let config = plugin_config_or_return!(config.clone());
-                
+
+        let mut addresses = Vec::with_capacity(config.destinations.len() + 1);
         let addr_str = format!("{}:{}", config.ip, config.port);
-        let address = match addr_str.parse::<net::SocketAddr>() {
-            Ok(address) => address,
+        match addr_str.parse::<net::SocketAddr>() {
+            Ok(address) => addresses.push(address),
             Err(err) => return plugin_err!("Error parsing IP address: {}", err),
         };
+        for destination in &config.destinations {
+            match destination.to_socket_addrs() {
+                Ok(mut resolved) => match resolved.next() {
+                    Some(address) => addresses.push(address),
+                    None => return plugin_err!("Error resolving destination '{}'", destination),
+                },
+                Err(err) => {
+                    return plugin_err!("Error parsing destination '{}': {}", destination, err)
+                }
+            };
+        }
+
+        let ipv4 = addresses[0].is_ipv4();
+        if addresses.iter().any(|address| address.is_ipv4() != ipv4) {
+            return plugin_err!("Mixing IPv4 and IPv6 destinations is not supported");
+        }
+
+        let max_packet_length = config.max_packet_length as usize;
+        if max_packet_length > MAX_PACKET_LENGTH {
+            return plugin_err!(
+                "Invalid config value 'max_packet_length': {}. Max UDP packet length: {}",
+                max_packet_length,
+                MAX_PACKET_LENGTH
+            );
+        }
+
+        let color_order: ColorOrder = config.color_order.parse()?;
+        let encoder = tpm2::Encoder::new(tpm2::Framing::Net, color_order)
+            .with_max_packet_length(max_packet_length)?;
+
+        for command in &config.init_commands {
+            encoder.encode_command(command)?;
+        }
 
-        // FIXME max_packet_length must be at least long enough for a minimal TPM2 packet
         let plugin = NetUdpTpm2Output {
-            address: address,
-            max_packet_length: config.max_packet_length,
+            addresses,
+            encoder,
+            multicast_ttl: config.multicast_ttl,
+            init_commands: config.init_commands,
+            socket: None,
         };
         Ok(Box::new(plugin))
     }
+
+    fn bind(&self) -> PluginResult<net::UdpSocket> {
+        let bind_address = if self.addresses[0].is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = match net::UdpSocket::bind(bind_address) {
+            Ok(socket) => socket,
+            Err(err) => return plugin_err!("Error creating UDP Socket: {}", err),
+        };
+        if let Err(err) = socket.set_broadcast(true) {
+            return plugin_err!("Error enabling broadcast on UDP socket: {}", err);
+        }
+        if self.addresses[0].is_ipv4() {
+            if let Err(err) = socket.set_multicast_ttl_v4(self.multicast_ttl) {
+                return plugin_err!("Error setting multicast TTL on UDP socket: {}", err);
+            }
+        }
+        Ok(socket)
+    }
+
+    fn send_packet(&self, packet: &[u8]) -> PluginResult<()> {
+        let socket = self.socket.as_ref().unwrap();
+        for address in &self.addresses {
+            if let Err(err) = socket.send_to(packet, address) {
+                return plugin_err!("Error sending UDP message to {}: {}", address, err);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl PluginOutputTrait for NetUdpTpm2Output {
     fn init(&mut self) -> PluginResult<()> {
+        self.socket = Some(self.bind()?);
+
+        for command in &self.init_commands {
+            let packet = self.encoder.encode_command(command)?;
+            self.send_packet(&packet)?;
+        }
         Ok(())
     }
-    
+
     fn send(&mut self, data: &TraitData) -> PluginResult<()> {
-        let socket = match net::UdpSocket::bind("0.0.0.0:0") {
-            Ok(socket) => socket,
-            Err(err) => return plugin_err!("Error creating UDP Socket: {}", err)
-        };
+        if self.socket.is_none() {
+            self.socket = Some(self.bind()?);
+        }
 
         let rgb_data = data.rgb.deref();
-        let out_size = rgb_data.len() * 3;
-
-        if out_size + 5 > self.max_packet_length as usize {
-            // split into chunks, use TMP2.net protocol
-            // FIXME length of all packets should be ideally the same to make life(memory allocation) on the receiver side easier
-            let max_frame_size: usize = self.max_packet_length as usize - 7;
-            let rgb_per_frame: usize = max_frame_size / 3;
-
-            let mut number_of_packets: usize = rgb_data.len() / rgb_per_frame;
-            if rgb_data.len() % rgb_per_frame != 0 {
-                number_of_packets += 1
-            }
-
-            if number_of_packets > 255 {
-                return plugin_err!("Error sending TPM2.net message: the number of total packets needed ({}) exceeds the allowed limit of 255", number_of_packets);
-            }
+        if rgb_data.is_empty() {
+            return Ok(());
+        }
 
-            for i in 0..number_of_packets {
-                let rgb_frame = if i == number_of_packets - 1 { rgb_data.len() - i * rgb_per_frame } else {  rgb_per_frame };
-                let frame_size = rgb_frame * 3;
-                
-                let mut out: Vec<u8> = Vec::with_capacity(frame_size + 5);
-                out.push(0x9C);
-                out.push(0xDA);
-                out.push(((frame_size >> 8) & 0xFF) as u8);
-                out.push((frame_size & 0xFF) as u8);
-                out.push((i + 1) as u8);
-                out.push(number_of_packets as u8);
-
-                let idx_start = i * rgb_per_frame;
-                let idx_end = idx_start + rgb_frame;
-                for idx in idx_start..idx_end {
-                    let rgb = rgb_data[idx];
-                    out.push(rgb.g);
-                    out.push(rgb.r);
-                    out.push(rgb.b);
-                }
-    
-                out.push(0x36);
-                let result = socket.send_to(&out, self.address);
-                if result.is_err() {
-                    return plugin_err!("Error sending UDP message: {}", result.err().unwrap());
-                }        
-            }
-        } else {
-            let mut out: Vec<u8> = Vec::with_capacity(out_size + 5);
-            out.push(0xC9);
-            out.push(0xDA);
-            out.push(((out_size >> 8) & 0xFF) as u8);
-            out.push((out_size & 0xFF) as u8);
-    
-            for rgb in rgb_data {
-                out.push(rgb.g);
-                out.push(rgb.r);
-                out.push(rgb.b);
-            }
-            out.push(0x36);
-            let result = socket.send_to(&out, self.address);
-            if result.is_err() {
-                return plugin_err!("Error sending UDP message: {}", result.err().unwrap());
-            }
+        for packet in self.encoder.encode_frame(rgb_data)? {
+            self.send_packet(&packet)?;
         }
 
         Ok(())
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateOutputPluginResult {
     NetUdpTpm2Output::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Output)
-}
+plugin_abi!(1, NAME, PluginKind::Output, create, include_str!("../config_schema.json"));
@@ -1,40 +1,34 @@
 use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
 use lightoros_plugin_base::output::CreateOutputPluginResult;
 use lightoros_plugin_base::*;
 use once_cell::sync::Lazy;
 use serde_json::json;
+use std::net::UdpSocket;
 use std::path::PathBuf;
 use std::{thread, time};
 
 static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
 
-fn load_lib() -> Library {
-    let lib_path: PathBuf = LIB_PATH.to_path_buf();
-    dlopen::symbor::Library::open(&lib_path).unwrap()
-}
-
-fn get_info() -> PluginInfo {
-    let lib = load_lib();
-    let info_func = unsafe { lib.symbol::<fn() -> PluginInfo>("info").unwrap() };
-    info_func()
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
 }
 
 fn call_create(config: &serde_json::Value) -> CreateOutputPluginResult {
-    let lib = load_lib();
-    let create_func = unsafe {
-        lib.symbol::<fn(&serde_json::Value) -> CreateOutputPluginResult>("create")
-            .unwrap()
-    };
-    create_func(config)
+    unsafe { declaration().create_output(config, None) }
 }
 
 #[test]
-fn test_get_info() {
-    let plugin_info = get_info();
-    assert_eq!(plugin_info.name, "NetUdpOutputTPM2");
-    assert!(plugin_info.kind == lightoros_plugin_base::PluginKind::Output);
-    assert_eq!(plugin_info.api_version, 1);
-    assert_eq!(plugin_info.filename, "lightoros_output_net_udp_tpm2");
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "NetUdpOutputTPM2");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Output));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_output_net_udp_tpm2");
 }
 
 #[test]
@@ -61,6 +55,166 @@ fn test_create_with_wrong_dattype_in_config() {
     assert!(call_create(&config).is_err());
 }
 
+fn create_receiver() -> (UdpSocket, u16) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(time::Duration::from_millis(1000)))
+        .unwrap();
+    let port = socket.local_addr().unwrap().port();
+    (socket, port)
+}
+
+fn receive_packet(socket: &UdpSocket) -> Vec<u8> {
+    let mut buffer = vec![0u8; 65536];
+    let (size, _) = socket.recv_from(&mut buffer).unwrap();
+    buffer.truncate(size);
+    buffer
+}
+
+#[test]
+fn test_create_with_invalid_max_packet_length() {
+    let config = json!({
+        "ip": "127.0.0.1",
+        "port": 45000,
+        "max_packet_length": 9,
+    });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_create_with_invalid_destination() {
+    let config = json!({
+        "ip": "127.0.0.1",
+        "port": 45000,
+        "max_packet_length": 1024,
+        "destinations": ["127.0.0.1"]
+    });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_send_single_packet() {
+    let (receiver, port) = create_receiver();
+    let config = json!({
+        "ip": "127.0.0.1",
+        "port": port,
+        "max_packet_length": 1024,
+        "color_order": "rgb"
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    let out: Vec<RGB> = vec![RGB { r: 1, g: 2, b: 3 }, RGB { r: 4, g: 5, b: 6 }];
+    let data = plugin_data!(out, {});
+    plugin.send(&data).unwrap();
+
+    let packet = receive_packet(&receiver);
+    assert_eq!(
+        packet,
+        [0x9C, 0xDA, 0x00, 0x06, 0x01, 0x01, 1, 2, 3, 4, 5, 6, 0x36]
+    );
+}
+
+#[test]
+fn test_send_split_frame() {
+    let (receiver, port) = create_receiver();
+    // 10 LEDs per packet at most
+    let config = json!({
+        "ip": "127.0.0.1",
+        "port": port,
+        "max_packet_length": 37,
+        "color_order": "rgb"
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    let mut out: Vec<RGB> = Vec::with_capacity(25);
+    for i in 0..25 {
+        out.push(RGB {
+            r: i,
+            g: i + 100,
+            b: i + 200,
+        });
+    }
+    let data = plugin_data!(out, {});
+    plugin.send(&data).unwrap();
+
+    // 25 LEDs are split into 3 packets: 9, 9 and 7 LEDs
+    let mut frame: Vec<u8> = Vec::new();
+    let sizes = [27, 27, 21];
+    for (i, &size) in sizes.iter().enumerate() {
+        let packet = receive_packet(&receiver);
+        assert_eq!(packet.len(), size + 7);
+        assert_eq!(packet[0], 0x9C);
+        assert_eq!(packet[1], 0xDA);
+        assert_eq!(((packet[2] as usize) << 8) | packet[3] as usize, size);
+        assert_eq!(packet[4] as usize, i + 1);
+        assert_eq!(packet[5], 3);
+        assert_eq!(packet[size + 6], 0x36);
+        frame.extend_from_slice(&packet[6..size + 6]);
+    }
+
+    for i in 0..25 {
+        assert_eq!(frame[i * 3], i as u8);
+        assert_eq!(frame[i * 3 + 1], i as u8 + 100);
+        assert_eq!(frame[i * 3 + 2], i as u8 + 200);
+    }
+}
+
+#[test]
+fn test_send_too_many_packets() {
+    let config = json!({
+        "ip": "127.0.0.1",
+        "port": 45000,
+        "max_packet_length": 10,
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    let out: Vec<RGB> = vec![RGB { r: 0, g: 0, b: 0 }; 256];
+    let data = plugin_data!(out, {});
+    assert!(plugin.send(&data).is_err());
+}
+
+#[test]
+fn test_send_to_multiple_destinations() {
+    let (receiver1, port1) = create_receiver();
+    let (receiver2, port2) = create_receiver();
+    let config = json!({
+        "ip": "127.0.0.1",
+        "port": port1,
+        "max_packet_length": 1024,
+        "destinations": [format!("127.0.0.1:{}", port2)]
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    let out: Vec<RGB> = vec![RGB { r: 1, g: 2, b: 3 }];
+    let data = plugin_data!(out, {});
+    plugin.send(&data).unwrap();
+
+    // default color order is GRB
+    let expected = [0x9C, 0xDA, 0x00, 0x03, 0x01, 0x01, 2, 1, 3, 0x36];
+    assert_eq!(receive_packet(&receiver1), expected);
+    assert_eq!(receive_packet(&receiver2), expected);
+}
+
+#[test]
+fn test_send_init_commands() {
+    let (receiver, port) = create_receiver();
+    let config = json!({
+        "ip": "127.0.0.1",
+        "port": port,
+        "max_packet_length": 1024,
+        "init_commands": [[0x0A, 0x80]]
+    });
+    let mut plugin = call_create(&config).unwrap();
+    plugin.init().unwrap();
+
+    let packet = receive_packet(&receiver);
+    assert_eq!(packet, [0x9C, 0xC0, 0x00, 0x02, 0x01, 0x01, 0x0A, 0x80, 0x36]);
+}
+
 #[test]
 fn test_send_all() {
     let config = json!({
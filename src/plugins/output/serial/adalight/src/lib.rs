@@ -0,0 +1,153 @@
+use serde::Deserialize;
+
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+use lightoros_output_serial_base::{SerialConfig, SerialDevice};
+use lightoros_plugin_base::output::{CreateOutputPluginResult, PluginOutputTrait};
+use lightoros_plugin_base::*;
+
+const NAME: &str = "SerialOutputAdalight";
+
+const HANDSHAKE: &[u8] = b"Ada\n";
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    #[serde(flatten)]
+    serial: SerialConfig,
+    #[serde(default = "default_color_order")]
+    color_order: String,
+    #[serde(default)]
+    handshake: bool,
+    #[serde(default = "default_handshake_timeout")]
+    handshake_timeout: u64,
+}
+
+fn default_color_order() -> String {
+    "rgb".to_string()
+}
+
+fn default_handshake_timeout() -> u64 {
+    3000
+}
+
+struct SerialAdalightOutput {
+    config: Config,
+    device: SerialDevice,
+    color_order: ColorOrder,
+    logger: Logger,
+}
+
+impl std::fmt::Display for SerialAdalightOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl SerialAdalightOutput {
+    fn create(config: &serde_json::Value) -> CreateOutputPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        let device = SerialDevice::new(&config.serial)?;
+        let color_order: ColorOrder = config.color_order.parse()?;
+
+        let plugin = SerialAdalightOutput {
+            config,
+            device,
+            color_order,
+            logger: Logger::new(NAME.to_string()),
+        };
+
+        Ok(Box::new(plugin))
+    }
+
+    fn open(&mut self) -> PluginResult<()> {
+        self.device.open()?;
+
+        if self.config.handshake {
+            if let Err(err) = self.wait_for_handshake() {
+                self.device.close();
+                return Err(err);
+            }
+        }
+
+        self.logger
+            .debug(&format!("Serial port '{}' opened", self.device.name()));
+        Ok(())
+    }
+
+    // an Adalight device announces itself by sending "Ada\n" after it has been reset
+    fn wait_for_handshake(&mut self) -> PluginResult<()> {
+        let deadline = Instant::now() + Duration::from_millis(self.config.handshake_timeout);
+        let mut received: Vec<u8> = Vec::with_capacity(HANDSHAKE.len());
+        let mut buffer = [0u8; 64];
+
+        while Instant::now() < deadline {
+            let count = self.device.read(&mut buffer)?;
+            for byte in &buffer[..count] {
+                received.push(*byte);
+                if received.ends_with(HANDSHAKE) {
+                    return Ok(());
+                }
+            }
+        }
+
+        plugin_err!(
+            "No Adalight device detected on serial port '{}'",
+            self.device.name()
+        )
+    }
+
+    fn encode(&self, rgb_data: &[RGB]) -> Vec<u8> {
+        let count = rgb_data.len() - 1;
+        let hi = ((count >> 8) & 0xFF) as u8;
+        let lo = (count & 0xFF) as u8;
+
+        let mut out: Vec<u8> = Vec::with_capacity(rgb_data.len() * 3 + 6);
+        out.push(b'A');
+        out.push(b'd');
+        out.push(b'a');
+        out.push(hi);
+        out.push(lo);
+        out.push(hi ^ lo ^ 0x55);
+
+        for rgb in rgb_data {
+            out.extend_from_slice(&self.color_order.apply(rgb));
+        }
+        out
+    }
+}
+
+impl PluginOutputTrait for SerialAdalightOutput {
+    fn init(&mut self) -> PluginResult<()> {
+        self.open()
+    }
+
+    fn send(&mut self, data: &TraitData) -> PluginResult<()> {
+        let rgb_data = data.rgb.deref();
+        if rgb_data.is_empty() {
+            return Ok(());
+        }
+        if rgb_data.len() > 0x10000 {
+            return plugin_err!(
+                "Too many LEDs for the Adalight protocol: {}",
+                rgb_data.len()
+            );
+        }
+
+        if !self.device.is_open() {
+            // connection got lost, try to reopen the port
+            self.open()?;
+        }
+
+        let out = self.encode(rgb_data);
+        self.device.write_all(&out)?;
+        Ok(())
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateOutputPluginResult {
+    SerialAdalightOutput::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Output, create, include_str!("../config_schema.json"));
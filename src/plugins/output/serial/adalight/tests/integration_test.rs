@@ -0,0 +1,205 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::output::CreateOutputPluginResult;
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::prelude::*;
+use std::os::unix::io::FromRawFd;
+use std::path::PathBuf;
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateOutputPluginResult {
+    unsafe { declaration().create_output(config, None) }
+}
+
+// returns the master side, the slave side (kept open to prevent hangups) and the slave device path
+fn open_pty() -> (File, File, String) {
+    let mut master: libc::c_int = 0;
+    let mut slave: libc::c_int = 0;
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    assert_eq!(result, 0);
+    let name = unsafe { CStr::from_ptr(libc::ttyname(slave)) }
+        .to_str()
+        .unwrap()
+        .to_owned();
+    unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave), name) }
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "SerialOutputAdalight");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Output));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_output_serial_adalight");
+}
+
+#[test]
+fn test_create() {
+    let config = json!({
+        "port": ""
+    });
+    assert!(call_create(&config).is_ok());
+}
+
+#[test]
+fn test_create_with_full_config() {
+    let config = json!({
+        "port": "",
+        "baud_rate": 500000,
+        "data_bits": 8,
+        "parity": "even",
+        "stop_bits": 2,
+        "flow_control": "hardware",
+        "color_order": "grb",
+        "timeout": 100,
+        "handshake": true,
+        "handshake_timeout": 1000
+    });
+    assert!(call_create(&config).is_ok());
+}
+
+#[test]
+fn test_create_with_empty_config() {
+    let config = json!({});
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_create_with_invalid_settings() {
+    let config = json!({
+        "port": "",
+        "data_bits": 9
+    });
+    assert!(call_create(&config).is_err());
+
+    let config = json!({
+        "port": "",
+        "parity": "mark"
+    });
+    assert!(call_create(&config).is_err());
+
+    let config = json!({
+        "port": "",
+        "color_order": "rgbw"
+    });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_init_with_invalid_port() {
+    let config = json!({
+        "port": "invalid"
+    });
+    let plugin = call_create(&config);
+    assert!(plugin.is_ok());
+    let mut plugin = plugin.unwrap();
+    assert!(plugin.init().is_err());
+    let out: Vec<RGB> = vec![RGB { r: 1, g: 2, b: 3 }];
+    let data = plugin_data!(out, {});
+    assert!(plugin.send(&data).is_err());
+}
+
+#[test]
+fn test_send_frame() {
+    let (mut master, _slave, name) = open_pty();
+    let config = json!({
+        "port": name,
+        "color_order": "grb"
+    });
+    let mut plugin = call_create(&config).unwrap();
+    assert!(plugin.init().is_ok());
+
+    let out: Vec<RGB> = vec![RGB { r: 1, g: 2, b: 3 }, RGB { r: 4, g: 5, b: 6 }];
+    let data = plugin_data!(out, {});
+    assert!(plugin.send(&data).is_ok());
+
+    let mut received = [0u8; 12];
+    master.read_exact(&mut received).unwrap();
+    assert_eq!(
+        received,
+        [b'A', b'd', b'a', 0x00, 0x01, 0x54, 2, 1, 3, 5, 4, 6]
+    );
+}
+
+#[test]
+fn test_handshake() {
+    let (mut master, _slave, name) = open_pty();
+    let config = json!({
+        "port": name,
+        "handshake": true,
+        "handshake_timeout": 2000
+    });
+    let mut plugin = call_create(&config).unwrap();
+
+    // device announces itself after the port has been opened
+    let writer = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        master.write_all(b"Ada\n").unwrap();
+        master
+    });
+    assert!(plugin.init().is_ok());
+    writer.join().unwrap();
+}
+
+#[test]
+fn test_handshake_timeout() {
+    let (_master, _slave, name) = open_pty();
+    let config = json!({
+        "port": name,
+        "timeout": 100,
+        "handshake": true,
+        "handshake_timeout": 500
+    });
+    let mut plugin = call_create(&config).unwrap();
+    assert!(plugin.init().is_err());
+}
+
+#[test]
+fn test_handshake_on_reopen() {
+    let (mut master, _slave, name) = open_pty();
+    let config = json!({
+        "port": name,
+        "timeout": 100,
+        "handshake": true,
+        "handshake_timeout": 300
+    });
+    let mut plugin = call_create(&config).unwrap();
+    assert!(plugin.init().is_err());
+
+    // the closed port is reopened on the next frame, the device has to announce itself again
+    let device = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        master.write_all(b"Ada\n").unwrap();
+        let mut received = [0u8; 9];
+        master.read_exact(&mut received).unwrap();
+        received
+    });
+    let out: Vec<RGB> = vec![RGB { r: 1, g: 2, b: 3 }];
+    let data = plugin_data!(out, {});
+    assert!(plugin.send(&data).is_ok());
+    assert_eq!(
+        device.join().unwrap(),
+        [b'A', b'd', b'a', 0x00, 0x00, 0x55, 1, 2, 3]
+    );
+}
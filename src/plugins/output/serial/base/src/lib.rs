@@ -0,0 +1,187 @@
+use serde::Deserialize;
+
+use std::io::prelude::*;
+use std::time::Duration;
+
+use serial::prelude::*;
+
+use lightoros_plugin_base::*;
+
+/// Serial port parameters shared by the serial output plugins.
+/// Meant to be flattened into the plugin configuration.
+#[derive(Deserialize, Debug)]
+pub struct SerialConfig {
+    pub port: String,
+    #[serde(default = "default_baud_rate")]
+    pub baud_rate: usize,
+    #[serde(default = "default_data_bits")]
+    pub data_bits: u8,
+    #[serde(default = "default_parity")]
+    pub parity: String,
+    #[serde(default = "default_stop_bits")]
+    pub stop_bits: u8,
+    #[serde(default = "default_flow_control")]
+    pub flow_control: String,
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_baud_rate() -> usize {
+    115200
+}
+
+fn default_data_bits() -> u8 {
+    8
+}
+
+fn default_parity() -> String {
+    "none".to_string()
+}
+
+fn default_stop_bits() -> u8 {
+    1
+}
+
+fn default_flow_control() -> String {
+    "none".to_string()
+}
+
+fn default_timeout() -> u64 {
+    1000
+}
+
+impl SerialConfig {
+    fn port_settings(&self) -> PluginResult<serial::PortSettings> {
+        let char_size = match self.data_bits {
+            5 => serial::Bits5,
+            6 => serial::Bits6,
+            7 => serial::Bits7,
+            8 => serial::Bits8,
+            _ => {
+                return plugin_err!(
+                    "Invalid config value 'data_bits': {}. Valid range: [5-8]",
+                    self.data_bits
+                )
+            }
+        };
+
+        let parity = match self.parity.as_str() {
+            "none" => serial::ParityNone,
+            "odd" => serial::ParityOdd,
+            "even" => serial::ParityEven,
+            _ => return plugin_err!("Invalid config value 'parity': {}", self.parity),
+        };
+
+        let stop_bits = match self.stop_bits {
+            1 => serial::Stop1,
+            2 => serial::Stop2,
+            _ => return plugin_err!("Invalid config value 'stop_bits': {}", self.stop_bits),
+        };
+
+        let flow_control = match self.flow_control.as_str() {
+            "none" => serial::FlowNone,
+            "software" => serial::FlowSoftware,
+            "hardware" => serial::FlowHardware,
+            _ => {
+                return plugin_err!(
+                    "Invalid config value 'flow_control': {}",
+                    self.flow_control
+                )
+            }
+        };
+
+        Ok(serial::PortSettings {
+            baud_rate: serial::BaudRate::from_speed(self.baud_rate),
+            char_size,
+            parity,
+            stop_bits,
+            flow_control,
+        })
+    }
+}
+
+/// Serial port which is opened and configured once and kept open.
+/// A failed write or read closes the port, reopening it is up to the plugin, e.g. to repeat a handshake.
+pub struct SerialDevice {
+    name: String,
+    settings: serial::PortSettings,
+    timeout: Duration,
+    port: Option<serial::SystemPort>,
+}
+
+impl SerialDevice {
+    pub fn new(config: &SerialConfig) -> PluginResult<SerialDevice> {
+        let settings = config.port_settings()?;
+
+        Ok(SerialDevice {
+            name: config.port.clone(),
+            settings,
+            timeout: Duration::from_millis(config.timeout),
+            port: None,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.port.is_some()
+    }
+
+    pub fn open(&mut self) -> PluginResult<()> {
+        let mut port = match serial::open(&self.name) {
+            Ok(port) => port,
+            Err(err) => return plugin_err!("Could not open serial port '{}': {}", self.name, err),
+        };
+
+        if let Err(err) = port.configure(&self.settings) {
+            return plugin_err!("Could not configure serial port '{}': {}", self.name, err);
+        }
+
+        if let Err(err) = port.set_timeout(self.timeout) {
+            return plugin_err!(
+                "Could not set timeout for serial port '{}': {}",
+                self.name,
+                err
+            );
+        }
+
+        self.port = Some(port);
+        Ok(())
+    }
+
+    pub fn close(&mut self) {
+        self.port = None;
+    }
+
+    pub fn write_all(&mut self, data: &[u8]) -> PluginResult<()> {
+        let port = match self.port.as_mut() {
+            Some(port) => port,
+            None => return plugin_err!("Serial port '{}' is not open", self.name),
+        };
+
+        if let Err(err) = port.write_all(data) {
+            self.close();
+            return plugin_err!("Could not write to serial port '{}': {}", self.name, err);
+        }
+        Ok(())
+    }
+
+    /// Reads available data into the buffer. Returns 0 if nothing was received within the port timeout.
+    pub fn read(&mut self, buffer: &mut [u8]) -> PluginResult<usize> {
+        let port = match self.port.as_mut() {
+            Some(port) => port,
+            None => return plugin_err!("Serial port '{}' is not open", self.name),
+        };
+
+        match port.read(buffer) {
+            Ok(count) => Ok(count),
+            Err(ref err) if err.kind() == std::io::ErrorKind::TimedOut => Ok(0),
+            Err(err) => {
+                self.close();
+                plugin_err!("Could not read from serial port '{}': {}", self.name, err)
+            }
+        }
+    }
+}
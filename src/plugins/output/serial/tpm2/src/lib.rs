@@ -1,20 +1,34 @@
 use serde::Deserialize;
 
-use std::io::prelude::*;
 use std::ops::Deref;
+use std::time::{Duration, Instant};
 
+use lightoros_output_serial_base::{SerialConfig, SerialDevice};
 use lightoros_plugin_base::*;
 use lightoros_plugin_base::output::{PluginOutputTrait, CreateOutputPluginResult};
+use lightoros_protocols::tpm2;
 
 const NAME: &str = "SerialOutputTPM2";
 
 #[derive(Deserialize, Debug)]
 struct Config {
-    port: String,
+    #[serde(flatten)]
+    serial: SerialConfig,
+    #[serde(default)]
+    acknowledge: bool,
+    #[serde(default = "default_acknowledge_timeout")]
+    acknowledge_timeout: u64,
+}
+
+fn default_acknowledge_timeout() -> u64 {
+    500
 }
 
 struct SerialTpm2Output {
     config: Config,
+    device: SerialDevice,
+    encoder: tpm2::Encoder,
+    decoder: tpm2::Decoder,
 }
 
 impl std::fmt::Display for SerialTpm2Output {
@@ -25,65 +39,72 @@ impl std::fmt::Display for SerialTpm2Output {
 
 impl SerialTpm2Output {
     fn create(config: &serde_json::Value) -> CreateOutputPluginResult {
-        let config = plugin_config_or_return!(config.clone());
+        let config: Config = plugin_config_or_return!(config.clone());
 
-        let plugin = SerialTpm2Output { config };
+        let device = SerialDevice::new(&config.serial)?;
+
+        let plugin = SerialTpm2Output {
+            config,
+            device,
+            encoder: tpm2::Encoder::new(tpm2::Framing::Serial, ColorOrder::GRB),
+            decoder: tpm2::Decoder::new().with_acknowledge(),
+        };
 
         Ok(Box::new(plugin))
     }
+
+    // a receiver confirms a frame with an acknowledgement byte or a response packet
+    fn wait_for_acknowledgement(&mut self) -> PluginResult<()> {
+        let deadline = Instant::now() + Duration::from_millis(self.config.acknowledge_timeout);
+        let mut buffer = [0u8; 64];
+        self.decoder.clear();
+
+        while Instant::now() < deadline {
+            let count = self.device.read(&mut buffer)?;
+            self.decoder.push(&buffer[..count]);
+            while let Some(message) = self.decoder.next_message() {
+                match message {
+                    tpm2::Message::Acknowledge => return Ok(()),
+                    tpm2::Message::Packet(packet) => {
+                        if packet.packet_type == tpm2::PacketType::Response {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+
+        plugin_err!(
+            "No TPM2 receiver responding on serial port '{}'",
+            self.device.name()
+        )
+    }
 }
 
 impl PluginOutputTrait for SerialTpm2Output {
     fn init(&mut self) -> PluginResult<()> {
-        Ok(())
+        self.device.open()
     }
 
     fn send(&mut self, data: &TraitData) -> PluginResult<()> {
-        let mut serial = match serial::open(&self.config.port) {
-            Ok(serial) => serial,
-            Err(err) => {
-                return plugin_err!(
-                    "Could not open serial port '{}': {}",
-                    self.config.port,
-                    err
-                )
-            }
-        };
-
         let rgb_data = data.rgb.deref();
-        let out_size = rgb_data.len() * 3;
-
-        let mut out: Vec<u8> = Vec::with_capacity(out_size + 5);
-        out.push(0xC9);
-        out.push(0xDA);
-        out.push(((out_size >> 8) & 0xFF) as u8);
-        out.push((out_size & 0xFF) as u8);
-
-        for rgb in rgb_data {
-            out.push(rgb.g);
-            out.push(rgb.r);
-            out.push(rgb.b);
+        let packets = self.encoder.encode_frame(rgb_data)?;
+
+        if !self.device.is_open() {
+            // connection got lost, try to reopen the port
+            self.device.open()?;
         }
-        out.push(0x36);
-
-        let result = serial.write(&out);
-        if result.is_err() {
-            return plugin_err!(
-                "Could not write to serial port '{}': {}",
-                self.config.port,
-                result.err().unwrap()
-            );
+        self.device.write_all(&packets[0])?;
+
+        if self.config.acknowledge {
+            self.wait_for_acknowledgement()?;
         }
         Ok(())
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateOutputPluginResult {
     SerialTpm2Output::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Output)
-}
+plugin_abi!(1, NAME, PluginKind::Output, create, include_str!("../config_schema.json"));
@@ -1,42 +1,60 @@
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
 use lightoros_plugin_base::*;
 use lightoros_plugin_base::output::CreateOutputPluginResult;
 use serde_json::json;
 use dlopen::symbor::Library;
 use once_cell::sync::Lazy;
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::prelude::*;
+use std::os::unix::io::FromRawFd;
 use std::path::PathBuf;
 
 //const PORT: &str = "/dev/tty.usbmodem14101";
 
 static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
 
-fn load_lib() -> Library {
-    let lib_path: PathBuf = LIB_PATH.to_path_buf();
-    dlopen::symbor::Library::open(&lib_path).unwrap()
-}
-
-fn get_info() -> PluginInfo {
-    let lib = load_lib();
-    let info_func = unsafe { lib.symbol::<fn() -> PluginInfo>("info").unwrap() };
-    info_func()
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
 }
 
 
 fn call_create(config: &serde_json::Value) -> CreateOutputPluginResult {
-    let lib = load_lib();
-    let create_func = unsafe {
-        lib.symbol::<fn(&serde_json::Value) -> CreateOutputPluginResult>("create")
-            .unwrap()
+    unsafe { declaration().create_output(config, None) }
+}
+
+// returns the master side, the slave side (kept open to prevent hangups) and the slave device path
+fn open_pty() -> (File, File, String) {
+    let mut master: libc::c_int = 0;
+    let mut slave: libc::c_int = 0;
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
     };
-    create_func(config)
+    assert_eq!(result, 0);
+    let name = unsafe { CStr::from_ptr(libc::ttyname(slave)) }
+        .to_str()
+        .unwrap()
+        .to_owned();
+    unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave), name) }
 }
 
 #[test]
-fn test_get_info() {
-    let plugin_info = get_info();
-    assert_eq!(plugin_info.name, "SerialOutputTPM2");
-    assert!(plugin_info.kind == lightoros_plugin_base::PluginKind::Output);
-    assert_eq!(plugin_info.api_version, 1);
-    assert_eq!(plugin_info.filename, "lightoros_output_serial_tpm2");
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "SerialOutputTPM2");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Output));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_output_serial_tpm2");
 }
 
 #[test]
@@ -74,6 +92,77 @@ fn test_send_to_invalid_port() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_create_with_invalid_settings() {
+    let config = json!({
+        "port": "",
+        "stop_bits": 3
+    });
+    assert!(call_create(&config).is_err());
+}
+
+#[test]
+fn test_init_with_invalid_port() {
+    let config = json!({
+        "port": "invalid"
+    });
+    let plugin = call_create(&config);
+    assert!(plugin.is_ok());
+    assert!(plugin.unwrap().init().is_err());
+}
+
+#[test]
+fn test_send_frames_over_persistent_port() {
+    let (mut master, _slave, name) = open_pty();
+    let config = json!({
+        "port": name,
+        "baud_rate": 500000
+    });
+    let mut plugin = call_create(&config).unwrap();
+    assert!(plugin.init().is_ok());
+
+    let out: Vec<RGB> = vec![RGB { r: 1, g: 2, b: 3 }];
+    let data = plugin_data!(out, {});
+    for _ in 0..3 {
+        assert!(plugin.send(&data).is_ok());
+    }
+
+    let mut received = [0u8; 24];
+    master.read_exact(&mut received).unwrap();
+    for frame in received.chunks(8) {
+        assert_eq!(frame, [0xC9, 0xDA, 0x00, 0x03, 2, 1, 3, 0x36]);
+    }
+}
+
+#[test]
+fn test_send_with_acknowledge() {
+    let (mut master, _slave, name) = open_pty();
+    let config = json!({
+        "port": name,
+        "timeout": 100,
+        "acknowledge": true,
+        "acknowledge_timeout": 1000
+    });
+    let mut plugin = call_create(&config).unwrap();
+    assert!(plugin.init().is_ok());
+
+    let out: Vec<RGB> = vec![RGB { r: 1, g: 2, b: 3 }];
+    let data = plugin_data!(out, {});
+
+    // receiver acknowledges the frame
+    let receiver = std::thread::spawn(move || {
+        let mut received = [0u8; 8];
+        master.read_exact(&mut received).unwrap();
+        master.write_all(&[0xAC]).unwrap();
+        master
+    });
+    assert!(plugin.send(&data).is_ok());
+    let _master = receiver.join().unwrap();
+
+    // no receiver, no acknowledgement
+    assert!(plugin.send(&data).is_err());
+}
+
 // /dev/tty.usbmodem14201
 
 /*
@@ -56,14 +56,30 @@ impl PluginTransformTrait for ConvertDimTransform {
         let result = plugin_data!(data_out, {});
         Ok(result)
     }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        if name != "brightness" {
+            return Ok(false);
+        }
+        match value.parse::<u8>() {
+            Ok(brightness) if brightness <= 100 => {
+                self.config.brightness = brightness;
+                Ok(true)
+            }
+            _ => plugin_err!("Invalid value for 'brightness': {}. Valid range: [0-100]", value),
+        }
+    }
+
+    fn get_parameter(&self, name: &str) -> Option<String> {
+        match name {
+            "brightness" => Some(self.config.brightness.to_string()),
+            _ => None,
+        }
+    }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateTransformPluginResult {
     ConvertDimTransform::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Transform)
-}
+plugin_abi!(1, NAME, PluginKind::Transform, create, include_str!("../config_schema.json"));
@@ -1,4 +1,5 @@
 use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
 use lightoros_plugin_base::transform::CreateTransformPluginResult;
 use lightoros_plugin_base::*;
 use once_cell::sync::Lazy;
@@ -6,34 +7,26 @@ use serde_json::json;
 use std::path::PathBuf;
 
 static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
 
-fn load_lib() -> Library {
-    let lib_path: PathBuf = LIB_PATH.to_path_buf();
-    dlopen::symbor::Library::open(&lib_path).unwrap()
-}
-
-fn get_info() -> PluginInfo {
-    let lib = load_lib();
-    let info_func = unsafe { lib.symbol::<fn() -> PluginInfo>("info").unwrap() };
-    info_func()
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
 }
 
 fn call_create(config: &serde_json::Value) -> CreateTransformPluginResult {
-    let lib = load_lib();
-    let create_func = unsafe {
-        lib.symbol::<fn(&serde_json::Value) -> CreateTransformPluginResult>("create")
-            .unwrap()
-    };
-    create_func(config)
+    unsafe { declaration().create_transform(config, None) }
 }
 
 #[test]
-fn test_get_info() {
-    let plugin_info = get_info();
-    assert_eq!(plugin_info.name, "ConvertDimTransform");
-    assert!(plugin_info.kind == lightoros_plugin_base::PluginKind::Transform);
-    assert_eq!(plugin_info.api_version, 1);
-    assert_eq!(plugin_info.filename, "lightoros_transform_convert_dim");
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "ConvertDimTransform");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Transform));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_transform_convert_dim");
 }
 
 #[test]
@@ -63,3 +56,23 @@ fn test_create_with_empty_config() {
     let config = json!({});
     assert!(call_create(&config).is_err());
 }
+
+#[test]
+fn test_set_brightness_parameter() {
+    let config = json!({
+        "brightness": 100
+    });
+    let mut plugin = call_create(&config).unwrap();
+    assert_eq!(plugin.get_parameter("brightness").unwrap(), "100");
+
+    assert!(plugin.set_parameter("brightness", "50").unwrap());
+    assert_eq!(plugin.get_parameter("brightness").unwrap(), "50");
+    let data = plugin
+        .transform(&plugin_data!(vec![RGB { r: 200, g: 100, b: 0 }], {}))
+        .unwrap();
+    assert_eq!((data.rgb[0].r, data.rgb[0].g, data.rgb[0].b), (100, 50, 0));
+
+    assert!(plugin.set_parameter("brightness", "101").is_err());
+    assert!(!plugin.set_parameter("gamma_red", "1.5").unwrap());
+    assert!(plugin.get_parameter("gamma_red").is_none());
+}
@@ -1,4 +1,5 @@
 use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
 use lightoros_plugin_base::transform::CreateTransformPluginResult;
 use lightoros_plugin_base::*;
 use once_cell::sync::Lazy;
@@ -6,35 +7,27 @@ use serde_json::json;
 use std::path::PathBuf;
 
 static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
 
-fn load_lib() -> Library {
-    let lib_path: PathBuf = LIB_PATH.to_path_buf();
-    dlopen::symbor::Library::open(&lib_path).unwrap()
-}
-
-fn get_info() -> PluginInfo {
-    let lib = load_lib();
-    let info_func = unsafe { lib.symbol::<fn() -> PluginInfo>("info").unwrap() };
-    info_func()
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
 }
 
 fn call_create(config: &serde_json::Value) -> CreateTransformPluginResult {
-    let lib = load_lib();
-    let create_func = unsafe {
-        lib.symbol::<fn(&serde_json::Value) -> CreateTransformPluginResult>("create")
-            .unwrap()
-    };
-    create_func(config)
+    unsafe { declaration().create_transform(config, None) }
 }
 
 #[test]
-fn test_get_info() {
-    let plugin_info = get_info();
-    assert_eq!(plugin_info.name, "ConvertRectangleTransform");
-    assert!(plugin_info.kind == lightoros_plugin_base::PluginKind::Transform);
-    assert_eq!(plugin_info.api_version, 1);
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "ConvertRectangleTransform");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Transform));
+    assert_eq!(declaration.api_version, 1);
     assert_eq!(
-        plugin_info.filename,
+        declaration.filename(),
         "lightoros_transform_convert_rectangle"
     );
 }
@@ -0,0 +1,125 @@
+use serde::Deserialize;
+
+use std::collections::HashMap;
+
+use lightoros_plugin_base::transform::{CreateTransformPluginResult, PluginTransformTrait};
+use lightoros_plugin_base::*;
+
+const NAME: &str = "ConvertSerpentineTransform";
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Direction {
+    Rows,
+    Columns,
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    #[serde(default = "default_start")]
+    start: Corner,
+    #[serde(default = "default_direction")]
+    direction: Direction,
+    #[serde(default = "default_serpentine")]
+    serpentine: bool,
+}
+
+fn default_start() -> Corner {
+    Corner::TopLeft
+}
+
+fn default_direction() -> Direction {
+    Direction::Rows
+}
+
+fn default_serpentine() -> bool {
+    true
+}
+
+struct ConvertSerpentineTransform {
+    config: Config,
+}
+
+impl std::fmt::Display for ConvertSerpentineTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+impl ConvertSerpentineTransform {
+    fn create(config: &serde_json::Value) -> CreateTransformPluginResult {
+        let config = plugin_config_or_return!(config.clone());
+
+        let plugin = ConvertSerpentineTransform { config };
+        Ok(Box::new(plugin))
+    }
+}
+
+impl PluginTransformTrait for ConvertSerpentineTransform {
+    fn transform(&self, data: &TraitData) -> PluginResult<TraitData> {
+        let rgb_data = &data.rgb;
+        let meta: &HashMap<String, String> = &data.meta;
+
+        let src_width: usize = get_meta_value(meta, "width")?;
+        let src_height: usize = get_meta_value(meta, "height")?;
+        if rgb_data.len() != src_width * src_height {
+            return plugin_err!(
+                "Image size {}x{} does not match the data size {}",
+                src_width,
+                src_height,
+                rgb_data.len()
+            );
+        }
+
+        // lines are the rows or columns of the matrix in the order of the LEDs
+        let (lines, length) = match self.config.direction {
+            Direction::Rows => (src_height, src_width),
+            Direction::Columns => (src_width, src_height),
+        };
+        let flip_x =
+            self.config.start == Corner::TopRight || self.config.start == Corner::BottomRight;
+        let flip_y =
+            self.config.start == Corner::BottomLeft || self.config.start == Corner::BottomRight;
+
+        let mut data_out: Vec<RGB> = Vec::with_capacity(rgb_data.len());
+        for line in 0..lines {
+            let reverse = self.config.serpentine && line % 2 == 1;
+            for position in 0..length {
+                let position = if reverse {
+                    length - 1 - position
+                } else {
+                    position
+                };
+                let (mut x, mut y) = match self.config.direction {
+                    Direction::Rows => (position, line),
+                    Direction::Columns => (line, position),
+                };
+                if flip_x {
+                    x = src_width - 1 - x;
+                }
+                if flip_y {
+                    y = src_height - 1 - y;
+                }
+                data_out.push(rgb_data[y * src_width + x]);
+            }
+        }
+
+        let result = plugin_data!(data_out, {});
+        Ok(result)
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateTransformPluginResult {
+    ConvertSerpentineTransform::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Transform, create, include_str!("../config_schema.json"));
@@ -0,0 +1,115 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::transform::CreateTransformPluginResult;
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::path::PathBuf;
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+fn call_create(config: &serde_json::Value) -> CreateTransformPluginResult {
+    unsafe { declaration().create_transform(config, None) }
+}
+
+// transforms a 3x2 image with the coordinates of each pixel as red and green value
+fn transform(config: serde_json::Value) -> Vec<(u8, u8)> {
+    let plugin = call_create(&config).unwrap();
+    let mut image: Vec<RGB> = Vec::new();
+    for y in 0..2 {
+        for x in 0..3 {
+            image.push(RGB { r: x, g: y, b: 0 });
+        }
+    }
+    let data = plugin_data!(image, {
+        "width" => 3,
+        "height" => 2,
+    });
+    let result = plugin.transform(&data).unwrap();
+    result.rgb.iter().map(|rgb| (rgb.r, rgb.g)).collect()
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "ConvertSerpentineTransform");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Transform));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(
+        declaration.filename(),
+        "lightoros_transform_convert_serpentine"
+    );
+}
+
+#[test]
+fn test_create() {
+    assert!(call_create(&json!({})).is_ok());
+    assert!(call_create(&json!({
+        "start": "bottom_right",
+        "direction": "columns",
+        "serpentine": false
+    }))
+    .is_ok());
+}
+
+#[test]
+fn test_create_with_invalid_config() {
+    assert!(call_create(&json!({"start": "center"})).is_err());
+    assert!(call_create(&json!({"direction": "diagonal"})).is_err());
+}
+
+#[test]
+fn test_rows() {
+    assert_eq!(
+        transform(json!({})),
+        vec![(0, 0), (1, 0), (2, 0), (2, 1), (1, 1), (0, 1)]
+    );
+    assert_eq!(
+        transform(json!({"serpentine": false})),
+        vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]
+    );
+    assert_eq!(
+        transform(json!({"start": "top_right"})),
+        vec![(2, 0), (1, 0), (0, 0), (0, 1), (1, 1), (2, 1)]
+    );
+    assert_eq!(
+        transform(json!({"start": "bottom_left"})),
+        vec![(0, 1), (1, 1), (2, 1), (2, 0), (1, 0), (0, 0)]
+    );
+}
+
+#[test]
+fn test_columns() {
+    assert_eq!(
+        transform(json!({"direction": "columns"})),
+        vec![(0, 0), (0, 1), (1, 1), (1, 0), (2, 0), (2, 1)]
+    );
+    assert_eq!(
+        transform(json!({"direction": "columns", "start": "bottom_right"})),
+        vec![(2, 1), (2, 0), (1, 0), (1, 1), (0, 1), (0, 0)]
+    );
+    assert_eq!(
+        transform(json!({"direction": "columns", "start": "bottom_left", "serpentine": false})),
+        vec![(0, 1), (0, 0), (1, 1), (1, 0), (2, 1), (2, 0)]
+    );
+}
+
+#[test]
+fn test_invalid_data() {
+    let plugin = call_create(&json!({})).unwrap();
+    let image = vec![RGB { r: 0, g: 0, b: 0 }; 5];
+    assert!(plugin.transform(&plugin_data!(image.clone(), {})).is_err());
+    let data = plugin_data!(image, {
+        "width" => 3,
+        "height" => 2,
+    });
+    assert!(plugin.transform(&data).is_err());
+}
@@ -48,12 +48,8 @@ impl PluginTransformTrait for ConvertShiftTransform {
     }
 }
 
-#[no_mangle]
 pub fn create(config: &serde_json::Value) -> CreateTransformPluginResult {
     ConvertShiftTransform::create(config)
 }
 
-#[no_mangle]
-pub fn info() -> PluginInfo {
-    plugin_info!(1, NAME, PluginKind::Transform)
-}
+plugin_abi!(1, NAME, PluginKind::Transform, create, include_str!("../config_schema.json"));
@@ -0,0 +1,254 @@
+use rlua::{AnyUserData, Context, Function, Table, Value};
+use serde::Deserialize;
+
+use lightoros_plugin_base::transform::{CreateTransformPluginResult, PluginTransformTrait};
+use lightoros_plugin_base::*;
+use lightoros_plugin_lua::runtime::{self, Options};
+use lightoros_plugin_lua::{helpers, json_to_lua, parameter_value, PixelBuffer, Runtime};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+const NAME: &str = "ScriptLuaTransform";
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    script: String,
+    #[serde(default = "default_function")]
+    function: String,
+    #[serde(default)]
+    args: serde_json::Map<String, serde_json::Value>,
+    #[serde(default = "default_sandbox")]
+    sandbox: bool,
+    // limits for the transformation of a frame, 0 disables a limit
+    #[serde(default = "default_max_frame_time")]
+    max_frame_time: u64,
+    #[serde(default)]
+    max_frame_instructions: u64,
+}
+
+fn default_function() -> String {
+    "transform".to_string()
+}
+
+fn default_sandbox() -> bool {
+    true
+}
+
+fn default_max_frame_time() -> u64 {
+    100
+}
+
+struct ScriptLuaTransform {
+    runtime: Runtime,
+    function: String,
+    args: serde_json::Map<String, serde_json::Value>,
+}
+
+impl std::fmt::Display for ScriptLuaTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+/**
+ * The script is loaded once, the transform function is called for each frame with a pixel buffer and the metadata.
+ * Values stored by the script in globals or upvalues are kept between the frames.
+*/
+impl ScriptLuaTransform {
+    fn create(config: &serde_json::Value) -> CreateTransformPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+
+        let path = PathBuf::from(&config.script);
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(error) => {
+                return plugin_err!("Error reading file '{}': {}", path.display(), error);
+            }
+        };
+        // modules are loaded from the folder of the script
+        let folder = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let options = Options {
+            folder,
+            sandbox: config.sandbox,
+            max_frame_time: config.max_frame_time,
+            max_frame_instructions: config.max_frame_instructions,
+        };
+
+        let runtime = match Runtime::new(&options, Arc::new(AtomicBool::new(false))) {
+            Ok(runtime) => runtime,
+            Err(error) => return plugin_err!("Error creating the Lua runtime: {}", error),
+        };
+        let loaded = runtime.lua.context(|lua_ctx| -> rlua::Result<bool> {
+            setup(lua_ctx, &config.args)?;
+            lua_ctx
+                .load(&source)
+                .set_name(&format!("@{}", file_name))?
+                .exec()?;
+            let function: Value = lua_ctx.globals().get(config.function.as_str())?;
+            Ok(matches!(function, Value::Function(_)))
+        });
+        match loaded {
+            Ok(true) => {}
+            Ok(false) => {
+                return plugin_err!(
+                    "Script '{}' has no function '{}'",
+                    path.display(),
+                    config.function
+                )
+            }
+            Err(error) => {
+                return plugin_err!(
+                    "Error loading script '{}': {}",
+                    path.display(),
+                    runtime::error_message(&error)
+                )
+            }
+        }
+
+        let plugin = ScriptLuaTransform {
+            runtime,
+            function: config.function,
+            args: config.args,
+        };
+        Ok(Box::new(plugin))
+    }
+}
+
+fn setup(lua_ctx: Context, args: &serde_json::Map<String, serde_json::Value>) -> rlua::Result<()> {
+    let globals = lua_ctx.globals();
+
+    let lua_args = lua_ctx.create_table()?;
+    for (key, value) in args {
+        lua_args.set(key.as_str(), json_to_lua(lua_ctx, value)?)?;
+    }
+    globals.set("args", lua_args)?;
+
+    let mut api_functions: Vec<(&str, Function)> = vec![(
+        "createBuffer",
+        lua_ctx.create_function(|_, (width, height): (usize, usize)| {
+            PixelBuffer::new(width, height)
+        })?,
+    )];
+    api_functions.extend(helpers::time_functions(lua_ctx)?);
+    globals.set("api", lua_ctx.create_table_from(api_functions)?)?;
+    Ok(())
+}
+
+// images have their size in the metadata, other data is passed as a single row
+fn frame_size(data: &TraitData) -> (usize, usize) {
+    let value = |key: &str| {
+        data.meta
+            .get(key)
+            .and_then(|value| value.parse::<usize>().ok())
+    };
+    match (value("width"), value("height")) {
+        (Some(width), Some(height)) if width.checked_mul(height) == Some(data.rgb.len()) => {
+            (width, height)
+        }
+        _ => (data.rgb.len(), 1),
+    }
+}
+
+// metadata values are strings, numbers and booleans are converted
+fn meta_from_lua(table: Table) -> rlua::Result<HashMap<String, String>> {
+    let mut meta = HashMap::new();
+    for pair in table.pairs::<String, Value>() {
+        let (key, value) = pair?;
+        let value = match value {
+            Value::String(value) => value.to_str()?.to_string(),
+            Value::Integer(value) => value.to_string(),
+            Value::Number(value) => value.to_string(),
+            Value::Boolean(value) => value.to_string(),
+            _ => {
+                return Err(rlua::Error::RuntimeError(format!(
+                    "Invalid value of metadata '{}'",
+                    key
+                )))
+            }
+        };
+        meta.insert(key, value);
+    }
+    Ok(meta)
+}
+
+impl PluginTransformTrait for ScriptLuaTransform {
+    fn transform(&self, data: &TraitData) -> PluginResult<TraitData> {
+        let (width, height) = frame_size(data);
+
+        self.runtime.budget.reset();
+        let result = self.runtime.lua.context(|lua_ctx| {
+            let function: Function = lua_ctx.globals().get(self.function.as_str())?;
+            let buffer = lua_ctx.create_userdata(PixelBuffer {
+                width,
+                height,
+                pixels: data.rgb.clone(),
+            })?;
+            let meta = lua_ctx.create_table_from(
+                data.meta
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str())),
+            )?;
+
+            let (buffer, meta): (AnyUserData, Option<Table>) = function.call((buffer, meta))?;
+            let buffer = buffer.borrow::<PixelBuffer>()?;
+            let mut meta = match meta {
+                Some(meta) => meta_from_lua(meta)?,
+                None => data.meta.clone(),
+            };
+            // the size of images is updated, e.g. after resizing by the script
+            let resized = buffer.width != width || buffer.height != height;
+            if resized || meta.contains_key("width") || meta.contains_key("height") {
+                meta.insert("width".to_string(), buffer.width.to_string());
+                meta.insert("height".to_string(), buffer.height.to_string());
+            }
+            Ok(TraitData {
+                rgb: buffer.pixels.clone(),
+                meta,
+            })
+        });
+
+        match result {
+            Ok(data) => Ok(data),
+            Err(error) => plugin_err!("Script failed: {}", runtime::error_message(&error)),
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        // parameters are passed to the script as arguments
+        let value = parameter_value(value);
+        let result = self.runtime.lua.context(|lua_ctx| {
+            let args: Table = lua_ctx.globals().get("args")?;
+            args.set(name, json_to_lua(lua_ctx, &value)?)
+        });
+        if let Err(error) = result {
+            return plugin_err!("Failed setting argument '{}': {}", name, error);
+        }
+        self.args.insert(name.to_string(), value);
+        Ok(true)
+    }
+
+    fn get_parameter(&self, name: &str) -> Option<String> {
+        let value = self.args.get(name)?;
+        let text = |value: &serde_json::Value| match value {
+            serde_json::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        match value {
+            serde_json::Value::Array(values) => {
+                Some(values.iter().map(text).collect::<Vec<_>>().join(","))
+            }
+            value => Some(text(value)),
+        }
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateTransformPluginResult {
+    ScriptLuaTransform::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Transform, create, include_str!("../config_schema.json"));
@@ -0,0 +1,196 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::transform::{CreateTransformPluginResult, PluginTransformTrait};
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+// swaps red and blue of each pixel and counts the frames in the metadata
+const SWAP_SCRIPT: &str = "
+local frames = 0
+
+function transform(buffer, meta)
+    for y = 1, buffer:height() do
+        for x = 1, buffer:width() do
+            local color = buffer:get(x, y)
+            buffer:set(x, y, {color[3], color[2], color[1]})
+        end
+    end
+    frames = frames + 1
+    meta.frames = frames
+    return buffer, meta
+end
+";
+
+fn call_create(config: &serde_json::Value) -> CreateTransformPluginResult {
+    unsafe { declaration().create_transform(config, None) }
+}
+
+// path of the script in a folder unique for each test since the tests run in parallel
+fn script_file(test: &str, source: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(format!("lightoros_lua_transform_{}", test));
+    std::fs::create_dir_all(&path).unwrap();
+    path.push("script.lua");
+    std::fs::write(&path, source).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+// 2x1 image with a red and a green pixel
+fn image() -> TraitData {
+    let rgb = vec![RGB { r: 255, g: 0, b: 0 }, RGB { r: 0, g: 255, b: 0 }];
+    plugin_data!(rgb, {
+        "width" => 2,
+        "height" => 1,
+    })
+}
+
+fn colors(data: &TraitData) -> Vec<(u8, u8, u8)> {
+    data.rgb.iter().map(|rgb| (rgb.r, rgb.g, rgb.b)).collect()
+}
+
+fn transform_error(plugin: &dyn PluginTransformTrait, data: &TraitData) -> String {
+    match plugin.transform(data) {
+        Ok(_) => panic!("Expected an error"),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "ScriptLuaTransform");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Transform));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_transform_script_lua");
+}
+
+#[test]
+fn test_create() {
+    let script = script_file("create", SWAP_SCRIPT);
+    assert!(call_create(&json!({ "script": script })).is_ok());
+    assert!(call_create(&json!({})).is_err());
+    assert!(call_create(&json!({"script": "/missing/script.lua"})).is_err());
+    assert!(call_create(&json!({"script": script, "function": "missing"})).is_err());
+
+    let script = script_file(
+        "create_error",
+        "function transform(buffer)\nerror('broken')",
+    );
+    let error = call_create(&json!({ "script": script }))
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.contains("script.lua:"), "{}", error);
+}
+
+#[test]
+fn test_transform() {
+    let script = script_file("transform", SWAP_SCRIPT);
+    let plugin = call_create(&json!({ "script": script })).unwrap();
+    let data = plugin.transform(&image()).unwrap();
+    assert_eq!(colors(&data), vec![(0, 0, 255), (0, 255, 0)]);
+    assert_eq!(data.meta.get("width").unwrap(), "2");
+    assert_eq!(data.meta.get("height").unwrap(), "1");
+    assert_eq!(data.meta.get("frames").unwrap(), "1");
+
+    // the state of the script is kept between the frames
+    let data = plugin.transform(&image()).unwrap();
+    assert_eq!(data.meta.get("frames").unwrap(), "2");
+
+    // a size not matching the data is ignored
+    let data = plugin_data!(Vec::new(), {
+        "width" => 4294967296_u64,
+        "height" => 4294967296_u64,
+    });
+    let data = plugin.transform(&data).unwrap();
+    assert_eq!(data.meta.get("width").unwrap(), "0");
+    assert_eq!(data.meta.get("height").unwrap(), "1");
+}
+
+#[test]
+fn test_resize() {
+    let script = "
+        function transform(buffer)
+            local result = api.createBuffer(1, 3)
+            result:fill(buffer:get(args.pixel, 1))
+            return result
+        end
+    ";
+    let script = script_file("resize", script);
+    let mut plugin = call_create(&json!({"script": script, "args": {"pixel": 1}})).unwrap();
+    let data = plugin.transform(&image()).unwrap();
+    assert_eq!(colors(&data), vec![(255, 0, 0); 3]);
+    assert_eq!(data.meta.get("width").unwrap(), "1");
+    assert_eq!(data.meta.get("height").unwrap(), "3");
+
+    assert!(plugin.set_parameter("pixel", "2").unwrap());
+    assert_eq!(plugin.get_parameter("pixel").unwrap(), "2");
+    let data = plugin.transform(&image()).unwrap();
+    assert_eq!(colors(&data), vec![(0, 255, 0); 3]);
+}
+
+#[test]
+fn test_script_error() {
+    let script = "
+        function transform(buffer, meta)
+            if meta.fail then
+                error('broken')
+            end
+            if meta.loop then
+                while true do end
+            end
+            if meta.huge then
+                return api.createBuffer(100000, 100000)
+            end
+            return buffer
+        end
+    ";
+    let script = script_file("error", script);
+    let plugin = call_create(&json!({ "script": script, "max_frame_time": 50 })).unwrap();
+    let mut data = image();
+    data.meta.insert("fail".to_string(), "true".to_string());
+    let error = transform_error(&*plugin, &data);
+    assert!(error.contains("script.lua:4: broken"), "{}", error);
+
+    let mut data = image();
+    data.meta.insert("loop".to_string(), "true".to_string());
+    let error = transform_error(&*plugin, &data);
+    assert!(error.contains("Time limit of 50 ms"), "{}", error);
+
+    let mut data = image();
+    data.meta.insert("huge".to_string(), "true".to_string());
+    let error = transform_error(&*plugin, &data);
+    assert!(error.contains("Invalid buffer size 100000x100000"), "{}", error);
+
+    // the next frame gets the full budget
+    assert!(plugin.transform(&image()).is_ok());
+}
+
+#[test]
+fn test_performance() {
+    let script = script_file("performance", SWAP_SCRIPT);
+    let plugin = call_create(&json!({ "script": script })).unwrap();
+    let rgb = vec![RGB { r: 1, g: 2, b: 3 }; 300];
+    let data = plugin_data!(rgb, {});
+
+    // a second of frames for 300 LEDs at 60 fps
+    let start = Instant::now();
+    for _ in 0..60 {
+        let result = plugin.transform(&data).unwrap();
+        assert_eq!(result.rgb[299].r, 3);
+    }
+    assert!(start.elapsed() < Duration::from_millis(1000));
+}
@@ -0,0 +1,56 @@
+use lightoros_plugin_base::transform::{CreateTransformPluginResult, PluginTransformTrait};
+use lightoros_plugin_base::*;
+use lightoros_plugin_wasm::{Config, WasmPlugin};
+
+use std::sync::Mutex;
+
+const NAME: &str = "ScriptWasmTransform";
+
+struct ScriptWasmTransform {
+    // calls of the module change its memory, transform() only gets a shared reference
+    module: Mutex<WasmPlugin>,
+}
+
+impl std::fmt::Display for ScriptWasmTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        NAME.fmt(f)
+    }
+}
+
+/**
+ * The module is loaded once, its transform function is called for each frame with the RGB data and the frame size.
+ * The frame is passed on unchanged if the module does not set a new one.
+*/
+impl ScriptWasmTransform {
+    fn create(config: &serde_json::Value) -> CreateTransformPluginResult {
+        let config: Config = plugin_config_or_return!(config.clone());
+        let module = WasmPlugin::load(&config, &PluginKind::Transform, NAME)?;
+
+        let plugin = ScriptWasmTransform {
+            module: Mutex::new(module),
+        };
+        Ok(Box::new(plugin))
+    }
+}
+
+impl PluginTransformTrait for ScriptWasmTransform {
+    fn transform(&self, data: &TraitData) -> PluginResult<TraitData> {
+        match self.module.lock() {
+            Ok(mut module) => module.transform(data),
+            Err(_) => plugin_err!("Module is not usable after a panic"),
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        match self.module.get_mut() {
+            Ok(module) => module.set_parameter(name, value),
+            Err(_) => plugin_err!("Module is not usable after a panic"),
+        }
+    }
+}
+
+pub fn create(config: &serde_json::Value) -> CreateTransformPluginResult {
+    ScriptWasmTransform::create(config)
+}
+
+plugin_abi!(1, NAME, PluginKind::Transform, create, include_str!("../config_schema.json"));
@@ -0,0 +1,200 @@
+use dlopen::symbor::Library;
+use lightoros_plugin_base::abi::{DeclarationFunction, PluginDeclaration, ABI_VERSION};
+use lightoros_plugin_base::transform::{CreateTransformPluginResult, PluginTransformTrait};
+use lightoros_plugin_base::*;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+static LIB_PATH: Lazy<PathBuf> = Lazy::new(test_cdylib::build_current_project);
+static LIB: Lazy<Library> = Lazy::new(|| Library::open(LIB_PATH.as_path()).unwrap());
+
+fn declaration() -> &'static PluginDeclaration {
+    let get_declaration =
+        unsafe { LIB.symbol::<DeclarationFunction>("lightoros_plugin").unwrap() };
+    unsafe { &*get_declaration(ABI_VERSION) }
+}
+
+// inverts the colors if the metadata 'invert' is set and marks the frame, fails for empty frames
+const INVERT_MODULE: &str = r#"
+(module
+  (import "lightoros" "set_frame" (func $set_frame (param i32 i32 i32 i32)))
+  (import "lightoros" "get_meta" (func $get_meta (param i32 i32 i32 i32) (result i32)))
+  (import "lightoros" "set_meta" (func $set_meta (param i32 i32 i32 i32)))
+  (import "lightoros" "set_error" (func $set_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "invert")
+  (data (i32.const 16) "inverted")
+  (data (i32.const 32) "yes")
+  (data (i32.const 48) "empty frame")
+  (global $heap (mut i32) (i32.const 1024))
+
+  (func (export "lightoros_abi_version") (result i32) (i32.const 1))
+
+  (func (export "alloc") (param $size i32) (result i32) (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $size)))
+    (local.get $ptr))
+
+  (func (export "free") (param $ptr i32) (param $size i32)
+    (global.set $heap (local.get $ptr)))
+
+  (func (export "transform") (param $ptr i32) (param $len i32) (param $width i32) (param $height i32) (result i32)
+    (local $i i32)
+    (if (i32.eqz (local.get $len)) (then
+      (call $set_error (i32.const 48) (i32.const 11))
+      (return (i32.const 1))))
+    (if (i32.lt_s (call $get_meta (i32.const 0) (i32.const 6) (i32.const 64) (i32.const 16)) (i32.const 0))
+      (then (return (i32.const 0))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+        (i32.store8
+          (i32.add (local.get $ptr) (local.get $i))
+          (i32.sub (i32.const 255) (i32.load8_u (i32.add (local.get $ptr) (local.get $i)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (call $set_frame (local.get $ptr) (local.get $len) (local.get $width) (local.get $height))
+    (call $set_meta (i32.const 16) (i32.const 8) (i32.const 32) (i32.const 3))
+    (i32.const 0))
+)
+"#;
+
+fn call_create(config: &serde_json::Value) -> CreateTransformPluginResult {
+    unsafe { declaration().create_transform(config, None) }
+}
+
+// path of the compiled module in a folder unique for each test since the tests run in parallel
+fn module_file(test: &str, source: &str) -> String {
+    let mut path = std::env::temp_dir();
+    path.push(format!("lightoros_wasm_transform_{}", test));
+    std::fs::create_dir_all(&path).unwrap();
+    path.push("module.wasm");
+    std::fs::write(&path, wat::parse_str(source).unwrap()).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+// 2x1 image with a red and a green pixel
+fn image() -> TraitData {
+    let rgb = vec![RGB { r: 255, g: 0, b: 0 }, RGB { r: 0, g: 255, b: 0 }];
+    plugin_data!(rgb, {
+        "width" => 2,
+        "height" => 1,
+        "invert" => true,
+    })
+}
+
+fn colors(data: &TraitData) -> Vec<(u8, u8, u8)> {
+    data.rgb.iter().map(|rgb| (rgb.r, rgb.g, rgb.b)).collect()
+}
+
+fn transform_error(plugin: &dyn PluginTransformTrait, data: &TraitData) -> String {
+    match plugin.transform(data) {
+        Ok(_) => panic!("Expected an error"),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn test_declaration() {
+    let declaration = declaration();
+    assert!(declaration.check().is_ok());
+    assert_eq!(declaration.name(), "ScriptWasmTransform");
+    assert!(declaration.kind() == Some(lightoros_plugin_base::PluginKind::Transform));
+    assert_eq!(declaration.api_version, 1);
+    assert_eq!(declaration.filename(), "lightoros_transform_script_wasm");
+}
+
+#[test]
+fn test_create() {
+    let module = module_file("create", INVERT_MODULE);
+    assert!(call_create(&json!({ "module": module })).is_ok());
+    assert!(call_create(&json!({})).is_err());
+    assert!(call_create(&json!({"module": "/missing/module.wasm"})).is_err());
+
+    let mut path = std::env::temp_dir();
+    path.push("lightoros_wasm_transform_invalid.wasm");
+    std::fs::write(&path, "no module").unwrap();
+    assert!(call_create(&json!({ "module": path })).is_err());
+}
+
+#[test]
+fn test_transform() {
+    let module = module_file("transform", INVERT_MODULE);
+    let plugin = call_create(&json!({ "module": module })).unwrap();
+    let data = plugin.transform(&image()).unwrap();
+    assert_eq!(colors(&data), vec![(0, 255, 255), (255, 0, 255)]);
+    assert_eq!(data.meta.get("width").unwrap(), "2");
+    assert_eq!(data.meta.get("invert").unwrap(), "true");
+    assert_eq!(data.meta.get("inverted").unwrap(), "yes");
+
+    // without a new frame from the module the frame is passed on unchanged
+    let rgb = vec![RGB { r: 1, g: 2, b: 3 }; 3];
+    let data = plugin.transform(&plugin_data!(rgb, {})).unwrap();
+    assert_eq!(colors(&data), vec![(1, 2, 3); 3]);
+    assert!(data.meta.is_empty());
+}
+
+#[test]
+fn test_module_error() {
+    let module = module_file("error", INVERT_MODULE);
+    let plugin = call_create(&json!({ "module": module })).unwrap();
+    let error = transform_error(&*plugin, &plugin_data!(vec![], {}));
+    assert_eq!(error, "Function 'transform' failed: empty frame");
+    assert!(plugin.transform(&image()).is_ok());
+
+    // frames which do not match the size are rejected by the host
+    let source = INVERT_MODULE.replace(
+        "(local.get $ptr) (local.get $len) (local.get $width)",
+        "(local.get $ptr) (i32.const 3) (local.get $width)",
+    );
+    let module = module_file("error_size", &source);
+    let plugin = call_create(&json!({ "module": module })).unwrap();
+    let error = transform_error(&*plugin, &image());
+    assert!(error.contains("does not match"), "{}", error);
+
+    // sizes which overflow are rejected as well
+    let source = INVERT_MODULE.replace(
+        "(local.get $len) (local.get $width) (local.get $height)",
+        "(local.get $len) (i32.const 65536) (i32.const 65536)",
+    );
+    let module = module_file("error_overflow", &source);
+    let plugin = call_create(&json!({ "module": module })).unwrap();
+    let error = transform_error(&*plugin, &image());
+    assert!(error.contains("does not match"), "{}", error);
+    let data = plugin_data!(vec![], {
+        "width" => 4294967296u64,
+        "height" => 4294967296u64,
+    });
+    let error = transform_error(&*plugin, &data);
+    assert_eq!(error, "Function 'transform' failed: empty frame");
+
+    // the buffer is checked against the memory of the module before it is copied
+    let source = INVERT_MODULE.replace(
+        "(local.get $ptr) (local.get $len) (local.get $width)",
+        "(local.get $ptr) (i32.const 2147483647) (local.get $width)",
+    );
+    let module = module_file("error_range", &source);
+    let plugin = call_create(&json!({ "module": module })).unwrap();
+    let error = transform_error(&*plugin, &image());
+    assert!(error.contains("Invalid memory range"), "{}", error);
+}
+
+#[test]
+fn test_performance() {
+    let module = module_file("performance", INVERT_MODULE);
+    let plugin = call_create(&json!({ "module": module })).unwrap();
+    let rgb = vec![RGB { r: 1, g: 2, b: 3 }; 300];
+    let data = plugin_data!(rgb, {
+        "invert" => true,
+    });
+
+    // a second of frames for 300 LEDs at 60 fps
+    let start = Instant::now();
+    for _ in 0..60 {
+        let result = plugin.transform(&data).unwrap();
+        assert_eq!(result.rgb[299].b, 252);
+    }
+    assert!(start.elapsed() < Duration::from_millis(1000));
+}
@@ -0,0 +1,434 @@
+use wasmi::{
+    Caller, Config as EngineConfig, Engine, Error, Extern, Instance, Linker, Memory, Module, Store,
+    StoreLimits, StoreLimitsBuilder, WasmParams,
+};
+
+use lightoros_plugin_base::*;
+
+use std::collections::HashMap;
+use std::fs;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{frame_size, Config, ABI_VERSION};
+
+// module of the functions imported by the plugins
+const HOST_MODULE: &str = "lightoros";
+
+// state of the host functions during a call of the module
+struct HostState {
+    limits: StoreLimits,
+    logger: Logger,
+    // error message set by the module for the current call
+    error: Option<String>,
+    // metadata of the frame passed to the module
+    meta_in: HashMap<String, String>,
+    // frame and metadata set by the module
+    frame: Option<Vec<RGB>>,
+    meta_out: HashMap<String, String>,
+}
+
+/// Instance of a WebAssembly module implementing an input, transform or output plugin
+pub struct WasmPlugin {
+    store: Store<HostState>,
+    instance: Instance,
+    memory: Memory,
+    fuel: u64,
+}
+
+impl WasmPlugin {
+    /// Loads the module, checks the ABI version and the export of the plugin kind and initializes it with its configuration
+    pub fn load(config: &Config, kind: &PluginKind, name: &str) -> PluginResult<WasmPlugin> {
+        let bytes = match fs::read(&config.module) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                return plugin_err!("Error reading module '{}': {}", config.module, error)
+            }
+        };
+
+        let mut engine_config = EngineConfig::default();
+        engine_config.consume_fuel(config.fuel > 0);
+        let engine = Engine::new(&engine_config);
+        let module = match Module::new(&engine, &bytes) {
+            Ok(module) => module,
+            Err(error) => {
+                return plugin_err!("Invalid WebAssembly module '{}': {}", config.module, error)
+            }
+        };
+
+        let state = HostState {
+            limits: StoreLimitsBuilder::new()
+                .memory_size(config.max_memory)
+                .instances(1)
+                .build(),
+            logger: Logger::new(format!("{}:{}", name, config.module)),
+            error: None,
+            meta_in: HashMap::new(),
+            frame: None,
+            meta_out: HashMap::new(),
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        if config.fuel > 0 {
+            // the start function of the module gets the fuel of a call
+            store.set_fuel(config.fuel).ok();
+        }
+
+        let linker = match host_functions(&engine) {
+            Ok(linker) => linker,
+            Err(error) => return plugin_err!("Error creating the host functions: {}", error),
+        };
+        let instance = match linker
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+        {
+            Ok(instance) => instance,
+            Err(error) => {
+                return plugin_err!("Error instantiating module '{}': {}", config.module, error)
+            }
+        };
+        let memory = match instance.get_memory(&store, "memory") {
+            Some(memory) => memory,
+            None => return plugin_err!("Module '{}' exports no memory", config.module),
+        };
+
+        let mut plugin = WasmPlugin {
+            store,
+            instance,
+            memory,
+            fuel: config.fuel,
+        };
+
+        let version = plugin.call("lightoros_abi_version", ())?;
+        if version != ABI_VERSION {
+            return plugin_err!(
+                "Unsupported ABI version {} of module '{}'. Supported version: {}",
+                version,
+                config.module,
+                ABI_VERSION
+            );
+        }
+        let function = match kind {
+            PluginKind::Input => "get",
+            PluginKind::Transform => "transform",
+            PluginKind::Output => "send",
+        };
+        if !plugin.has_function(function) {
+            return plugin_err!(
+                "Module '{}' exports no function '{}'",
+                config.module,
+                function
+            );
+        }
+
+        if plugin.has_function("init") {
+            let module_config = match &config.config {
+                serde_json::Value::Null => "{}".to_string(),
+                value => value.to_string(),
+            };
+            plugin.refuel();
+            let (ptr, len) = plugin.write(module_config.as_bytes())?;
+            let status = plugin.call("init", (ptr, len));
+            plugin.free(ptr, len)?;
+            plugin.check("init", status?)?;
+        }
+        Ok(plugin)
+    }
+
+    /// Calls `get` of an input module and returns the frame it has set
+    pub fn get(&mut self) -> PluginResult<TraitData> {
+        self.prepare(HashMap::new());
+        self.refuel();
+        let status = self.call("get", ())?;
+        self.check("get", status)?;
+
+        let state = self.store.data_mut();
+        match state.frame.take() {
+            Some(rgb) => Ok(TraitData {
+                rgb,
+                meta: std::mem::take(&mut state.meta_out),
+            }),
+            None => plugin_err!("Module has not set a frame"),
+        }
+    }
+
+    /// Calls `transform` of a transform module, the frame is passed on unchanged if the module sets no frame
+    pub fn transform(&mut self, data: &TraitData) -> PluginResult<TraitData> {
+        self.call_with_frame("transform", data)?;
+
+        let state = self.store.data_mut();
+        let rgb = match state.frame.take() {
+            Some(rgb) => rgb,
+            None => data.rgb.clone(),
+        };
+        Ok(TraitData {
+            rgb,
+            meta: std::mem::take(&mut state.meta_out),
+        })
+    }
+
+    /// Calls `send` of an output module
+    pub fn send(&mut self, data: &TraitData) -> PluginResult<()> {
+        self.call_with_frame("send", data)
+    }
+
+    /// Calls the optional `set_parameter` of the module, false if the module has no such parameter
+    pub fn set_parameter(&mut self, name: &str, value: &str) -> PluginResult<bool> {
+        if !self.has_function("set_parameter") {
+            return Ok(false);
+        }
+        self.prepare(HashMap::new());
+        self.refuel();
+        let (name_ptr, name_len) = self.write(name.as_bytes())?;
+        let (value_ptr, value_len) = self.write(value.as_bytes())?;
+        let status = self.call("set_parameter", (name_ptr, name_len, value_ptr, value_len));
+        self.free(value_ptr, value_len)?;
+        self.free(name_ptr, name_len)?;
+        match status? {
+            0 => Ok(false),
+            status if status > 0 => Ok(true),
+            status => self.check("set_parameter", status).map(|_| false),
+        }
+    }
+
+    // passes the frame as RGB bytes with its size
+    fn call_with_frame(&mut self, function: &str, data: &TraitData) -> PluginResult<()> {
+        let (width, height) = frame_size(data);
+        self.prepare(data.meta.clone());
+        self.refuel();
+
+        let bytes: Vec<u8> = data
+            .rgb
+            .iter()
+            .flat_map(|rgb| vec![rgb.r, rgb.g, rgb.b])
+            .collect();
+        let (ptr, len) = self.write(&bytes)?;
+        let status = self.call(function, (ptr, len, width as i32, height as i32));
+        self.free(ptr, len)?;
+        self.check(function, status?)
+    }
+
+    fn prepare(&mut self, meta: HashMap<String, String>) {
+        let state = self.store.data_mut();
+        state.error = None;
+        state.frame = None;
+        state.meta_out = meta.clone();
+        state.meta_in = meta;
+    }
+
+    // each call of the plugin gets the full fuel, shared by all functions of the module called for it
+    fn refuel(&mut self) {
+        if self.fuel > 0 {
+            self.store.set_fuel(self.fuel).ok();
+        }
+    }
+
+    fn has_function(&self, name: &str) -> bool {
+        matches!(
+            self.instance.get_export(&self.store, name),
+            Some(Extern::Func(_))
+        )
+    }
+
+    fn call<Params: WasmParams>(&mut self, name: &str, params: Params) -> PluginResult<i32> {
+        let function = match self
+            .instance
+            .get_typed_func::<Params, i32>(&self.store, name)
+        {
+            Ok(function) => function,
+            Err(error) => return plugin_err!("Invalid export '{}': {}", name, error),
+        };
+        match function.call(&mut self.store, params) {
+            Ok(result) => Ok(result),
+            Err(error) => plugin_err!("Function '{}' failed: {}", name, error),
+        }
+    }
+
+    // status codes other than 0 are errors with the message set by the module
+    fn check(&mut self, name: &str, status: i32) -> PluginResult<()> {
+        if status == 0 {
+            return Ok(());
+        }
+        match self.store.data_mut().error.take() {
+            Some(error) => plugin_err!("Function '{}' failed: {}", name, error),
+            None => plugin_err!("Function '{}' failed with code {}", name, status),
+        }
+    }
+
+    // copies the data to memory allocated by the module
+    fn write(&mut self, data: &[u8]) -> PluginResult<(i32, i32)> {
+        let len = data.len() as i32;
+        let ptr = self.call("alloc", (len,))?;
+        if let Err(error) = self
+            .memory
+            .write(&mut self.store, ptr as u32 as usize, data)
+        {
+            return plugin_err!("Error writing to the memory of the module: {}", error);
+        }
+        Ok((ptr, len))
+    }
+
+    // the memory is released by the module if it exports a `free` function
+    fn free(&mut self, ptr: i32, len: i32) -> PluginResult<()> {
+        if !self.has_function("free") {
+            return Ok(());
+        }
+        let function = match self
+            .instance
+            .get_typed_func::<(i32, i32), ()>(&self.store, "free")
+        {
+            Ok(function) => function,
+            Err(error) => return plugin_err!("Invalid export 'free': {}", error),
+        };
+        match function.call(&mut self.store, (ptr, len)) {
+            Ok(_) => Ok(()),
+            Err(error) => plugin_err!("Function 'free' failed: {}", error),
+        }
+    }
+}
+
+fn memory(caller: &Caller<HostState>) -> Result<Memory, Error> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Error::new("Module exports no memory"))
+}
+
+fn read_bytes(caller: &Caller<HostState>, ptr: i32, len: i32) -> Result<Vec<u8>, Error> {
+    if len < 0 {
+        return Err(Error::new(format!("Invalid length: {}", len)));
+    }
+    // the range is checked against the memory before copying, the length comes from the module
+    let start = ptr as u32 as usize;
+    let data = memory(caller)?.data(caller);
+    start
+        .checked_add(len as usize)
+        .and_then(|end| data.get(start..end))
+        .map(|bytes| bytes.to_vec())
+        .ok_or_else(|| Error::new(format!("Invalid memory range: {} bytes at {}", len, start)))
+}
+
+fn read_string(caller: &Caller<HostState>, ptr: i32, len: i32) -> Result<String, Error> {
+    String::from_utf8(read_bytes(caller, ptr, len)?).map_err(|_| Error::new("Invalid UTF-8 string"))
+}
+
+// functions imported by the modules, the ABI is described in the developer documentation
+fn host_functions(engine: &Engine) -> Result<Linker<HostState>, Error> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap(
+        HOST_MODULE,
+        "log",
+        |caller: Caller<HostState>, level: i32, ptr: i32, len: i32| -> Result<(), Error> {
+            let message = read_string(&caller, ptr, len)?;
+            match level {
+                0 => caller.data().logger.error(&message),
+                _ => caller.data().logger.debug(&message),
+            }
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "set_error",
+        |mut caller: Caller<HostState>, ptr: i32, len: i32| -> Result<(), Error> {
+            let message = read_string(&caller, ptr, len)?;
+            caller.data_mut().error = Some(message);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(HOST_MODULE, "time_ms", || -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_millis() as i64)
+            .unwrap_or(0)
+    })?;
+    linker.func_wrap(HOST_MODULE, "sleep_ms", |ms: i32| {
+        thread::sleep(Duration::from_millis(ms.max(0) as u64));
+    })?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "set_frame",
+        |mut caller: Caller<HostState>,
+         ptr: i32,
+         len: i32,
+         width: i32,
+         height: i32|
+         -> Result<(), Error> {
+            let bytes = read_bytes(&caller, ptr, len)?;
+            if bytes.len() % 3 != 0 {
+                return Err(Error::new(format!(
+                    "Frame length {} is not a multiple of 3",
+                    len
+                )));
+            }
+            let rgb: Vec<RGB> = bytes
+                .chunks(3)
+                .map(|rgb| RGB {
+                    r: rgb[0],
+                    g: rgb[1],
+                    b: rgb[2],
+                })
+                .collect();
+            if width > 0
+                && height > 0
+                && (width as usize).checked_mul(height as usize) != Some(rgb.len())
+            {
+                return Err(Error::new(format!(
+                    "Frame size {}x{} does not match the data length {}",
+                    width, height, len
+                )));
+            }
+
+            let state = caller.data_mut();
+            if width > 0 && height > 0 {
+                state
+                    .meta_out
+                    .insert("width".to_string(), width.to_string());
+                state
+                    .meta_out
+                    .insert("height".to_string(), height.to_string());
+            }
+            state.frame = Some(rgb);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "get_meta",
+        |mut caller: Caller<HostState>,
+         key_ptr: i32,
+         key_len: i32,
+         ptr: i32,
+         len: i32|
+         -> Result<i32, Error> {
+            let key = read_string(&caller, key_ptr, key_len)?;
+            let value = match caller.data().meta_in.get(&key) {
+                Some(value) => value.clone().into_bytes(),
+                None => return Ok(-1),
+            };
+            // values not fitting into the buffer are not copied, the length tells the required size
+            if value.len() <= len.max(0) as usize {
+                memory(&caller)?
+                    .write(&mut caller, ptr as u32 as usize, &value)
+                    .map_err(|error| Error::new(error.to_string()))?;
+            }
+            Ok(value.len() as i32)
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "set_meta",
+        |mut caller: Caller<HostState>,
+         key_ptr: i32,
+         key_len: i32,
+         ptr: i32,
+         len: i32|
+         -> Result<(), Error> {
+            let key = read_string(&caller, key_ptr, key_len)?;
+            let value = read_string(&caller, ptr, len)?;
+            caller.data_mut().meta_out.insert(key, value);
+            Ok(())
+        },
+    )?;
+    Ok(linker)
+}
@@ -0,0 +1,54 @@
+//! Host for plugins compiled to WebAssembly, shared by the WebAssembly input, transform and output plugins.
+//!
+//! A module implements the host ABI described in the developer documentation.
+//! It runs in an interpreter with limited memory and fuel, so a faulty module returns an error instead of crashing the engine.
+
+use serde::Deserialize;
+
+use lightoros_plugin_base::TraitData;
+
+mod host;
+
+pub use host::WasmPlugin;
+
+/// Version of the host ABI, returned by the export `lightoros_abi_version` of the modules
+pub const ABI_VERSION: i32 = 1;
+
+/// Configuration of the WebAssembly plugins
+#[derive(Deserialize, Debug)]
+pub struct Config {
+    /// path of the `.wasm` file
+    pub module: String,
+    /// configuration passed to the module as JSON
+    #[serde(default)]
+    pub config: serde_json::Value,
+    /// maximum size of the memory of the module in bytes
+    #[serde(default = "default_max_memory")]
+    pub max_memory: usize,
+    /// fuel for each call of the module, roughly the number of executed instructions. 0 for no limit.
+    #[serde(default = "default_fuel")]
+    pub fuel: u64,
+}
+
+fn default_max_memory() -> usize {
+    16 * 1024 * 1024
+}
+
+fn default_fuel() -> u64 {
+    100_000_000
+}
+
+/// Size of a frame, 0x0 for data without `width` and `height` metadata like data for LED strips
+pub fn frame_size(data: &TraitData) -> (usize, usize) {
+    let value = |key: &str| {
+        data.meta
+            .get(key)
+            .and_then(|value| value.parse::<usize>().ok())
+    };
+    match (value("width"), value("height")) {
+        (Some(width), Some(height)) if width.checked_mul(height) == Some(data.rgb.len()) => {
+            (width, height)
+        }
+        _ => (0, 0),
+    }
+}
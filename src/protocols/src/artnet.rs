@@ -0,0 +1,88 @@
+//! Art-Net DMX protocol.
+//!
+//! Only the packets needed to receive DMX data are supported: ArtDmx carrying the data of a single universe
+//! and ArtSync which tells the receivers to output the previously received data at the same time.
+
+pub const PORT: u16 = 6454;
+pub const ID: &[u8; 8] = b"Art-Net\0";
+pub const PROTOCOL_VERSION: u16 = 14;
+
+pub const OP_DMX: u16 = 0x5000;
+pub const OP_SYNC: u16 = 0x5200;
+
+pub const MAX_CHANNELS: usize = 512;
+
+const DMX_HEADER_SIZE: usize = 18;
+const SYNC_SIZE: usize = 14;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DmxPacket {
+    /// 0 if the sender doesn't use sequence numbers
+    pub sequence: u8,
+    pub physical: u8,
+    /// 15 bit port address: net (7 bits), sub-net (4 bits) and universe (4 bits)
+    pub universe: u16,
+    pub data: Vec<u8>,
+}
+
+impl DmxPacket {
+    pub fn encode(&self) -> Vec<u8> {
+        let size = std::cmp::min(self.data.len(), MAX_CHANNELS);
+        // the length has to be even
+        let length = size + size % 2;
+        let mut out = Vec::with_capacity(DMX_HEADER_SIZE + length);
+        out.extend_from_slice(ID);
+        out.extend_from_slice(&OP_DMX.to_le_bytes());
+        out.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+        out.push(self.sequence);
+        out.push(self.physical);
+        out.push((self.universe & 0xFF) as u8);
+        out.push(((self.universe >> 8) & 0x7F) as u8);
+        out.extend_from_slice(&(length as u16).to_be_bytes());
+        out.extend_from_slice(&self.data[..size]);
+        out.resize(DMX_HEADER_SIZE + length, 0);
+        out
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Packet {
+    Dmx(DmxPacket),
+    Sync,
+}
+
+pub fn encode_sync() -> Vec<u8> {
+    let mut out = Vec::with_capacity(SYNC_SIZE);
+    out.extend_from_slice(ID);
+    out.extend_from_slice(&OP_SYNC.to_le_bytes());
+    out.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+    out.extend_from_slice(&[0, 0]);
+    out
+}
+
+/// Decodes a single UDP packet. Returns `None` for invalid or unsupported packets.
+pub fn decode(data: &[u8]) -> Option<Packet> {
+    if data.len() < 12 || &data[..8] != ID {
+        return None;
+    }
+    let opcode = u16::from_le_bytes([data[8], data[9]]);
+    match opcode {
+        OP_DMX => {
+            if data.len() < DMX_HEADER_SIZE {
+                return None;
+            }
+            let length = u16::from_be_bytes([data[16], data[17]]) as usize;
+            if length == 0 || length > MAX_CHANNELS || data.len() < DMX_HEADER_SIZE + length {
+                return None;
+            }
+            Some(Packet::Dmx(DmxPacket {
+                sequence: data[12],
+                physical: data[13],
+                universe: ((data[15] as u16 & 0x7F) << 8) | data[14] as u16,
+                data: data[DMX_HEADER_SIZE..DMX_HEADER_SIZE + length].to_vec(),
+            }))
+        }
+        OP_SYNC => Some(Packet::Sync),
+        _ => None,
+    }
+}
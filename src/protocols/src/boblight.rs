@@ -0,0 +1,100 @@
+//! Boblight protocol, used e.g. by the boblight add-on of Kodi.
+//!
+//! Clients send text commands terminated by a newline. Color values are floats in the range 0.0-1.0.
+
+use lightoros_plugin_base::*;
+
+pub const PORT: u16 = 19333;
+pub const PROTOCOL_VERSION: u32 = 5;
+
+/// Light announced to the clients. The scan areas are percentages of the picture the client
+/// uses to calculate the color of the light.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Light {
+    pub name: String,
+    pub vscan: [f32; 2],
+    pub hscan: [f32; 2],
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LightProperty {
+    Rgb([f32; 3]),
+    Speed(f32),
+    Interpolation(bool),
+    Use(bool),
+    SingleChange(f32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Hello,
+    Ping,
+    GetVersion,
+    GetLights,
+    SetPriority(i32),
+    SetLight {
+        name: String,
+        property: LightProperty,
+    },
+    Sync,
+}
+
+pub fn parse_command(line: &str) -> PluginResult<Command> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["hello"] => Ok(Command::Hello),
+        ["ping"] => Ok(Command::Ping),
+        ["get", "version"] => Ok(Command::GetVersion),
+        ["get", "lights"] => Ok(Command::GetLights),
+        ["set", "priority", priority] => Ok(Command::SetPriority(parse(priority)?)),
+        ["set", "light", name, property @ ..] => {
+            let property = match property {
+                ["rgb", r, g, b] => LightProperty::Rgb([parse(r)?, parse(g)?, parse(b)?]),
+                ["speed", speed] => LightProperty::Speed(parse(speed)?),
+                ["interpolation", value] => LightProperty::Interpolation(parse_bool(value)?),
+                ["use", value] => LightProperty::Use(parse_bool(value)?),
+                ["singlechange", value] => LightProperty::SingleChange(parse(value)?),
+                _ => return plugin_err!("Invalid light property: {}", line),
+            };
+            Ok(Command::SetLight {
+                name: name.to_string(),
+                property,
+            })
+        }
+        ["sync"] => Ok(Command::Sync),
+        _ => plugin_err!("Unknown command: {}", line),
+    }
+}
+
+/// Response to "get lights"
+pub fn encode_lights(lights: &[Light]) -> String {
+    let mut out = format!("lights {}\n", lights.len());
+    for light in lights {
+        out.push_str(&format!(
+            "light {} scan {} {} {} {}\n",
+            light.name, light.vscan[0], light.vscan[1], light.hscan[0], light.hscan[1]
+        ));
+    }
+    out
+}
+
+/// Converts a boblight color value to a byte, values outside of 0.0-1.0 are clamped
+pub fn to_color_value(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> PluginResult<T> {
+    match value.parse::<T>() {
+        Ok(value) => Ok(value),
+        Err(_) => plugin_err!("Invalid value: {}", value),
+    }
+}
+
+// libboblight sends 0 and 1, the words are accepted as well
+fn parse_bool(value: &str) -> PluginResult<bool> {
+    match value {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => plugin_err!("Invalid value: {}", value),
+    }
+}
@@ -0,0 +1,124 @@
+//! Distributed Display Protocol (DDP).
+//!
+//! A DDP packet carries a chunk of the pixel data of a display starting at a byte offset.
+//! The push flag marks the last packet of a frame, receivers show the frame after receiving it.
+
+use lightoros_plugin_base::*;
+
+pub const PORT: u16 = 4048;
+
+pub const VERSION_1: u8 = 0x40;
+pub const VERSION_MASK: u8 = 0xC0;
+pub const FLAG_TIMECODE: u8 = 0x10;
+pub const FLAG_STORAGE: u8 = 0x08;
+pub const FLAG_REPLY: u8 = 0x04;
+pub const FLAG_QUERY: u8 = 0x02;
+pub const FLAG_PUSH: u8 = 0x01;
+
+pub const TYPE_RGB24: u8 = 0x0B;
+
+pub const ID_DISPLAY: u8 = 1;
+pub const ID_CONFIG: u8 = 250;
+pub const ID_STATUS: u8 = 251;
+pub const ID_DMX: u8 = 254;
+pub const ID_ALL: u8 = 255;
+
+/// Max data length of a packet recommended by the specification, 480 RGB pixels
+pub const MAX_DATA_LENGTH: usize = 1440;
+
+const HEADER_SIZE: usize = 10;
+const TIMECODE_SIZE: usize = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub flags: u8,
+    /// 4 bit sequence number, 0 if not used
+    pub sequence: u8,
+    pub data_type: u8,
+    pub id: u8,
+    /// offset of the data in bytes
+    pub offset: u32,
+    pub data: Vec<u8>,
+}
+
+impl Packet {
+    pub fn is_push(&self) -> bool {
+        self.flags & FLAG_PUSH != 0
+    }
+
+    pub fn is_query(&self) -> bool {
+        self.flags & FLAG_QUERY != 0
+    }
+
+    /// True if the packet carries pixel data for a display
+    pub fn is_display_data(&self) -> bool {
+        !self.is_query() && self.flags & FLAG_REPLY == 0 && (self.id == ID_DISPLAY || self.id == ID_ALL)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE + self.data.len());
+        // timecodes are not supported when encoding
+        out.push((self.flags & !FLAG_TIMECODE & !VERSION_MASK) | VERSION_1);
+        out.push(self.sequence & 0x0F);
+        out.push(self.data_type);
+        out.push(self.id);
+        out.extend_from_slice(&self.offset.to_be_bytes());
+        out.extend_from_slice(&(self.data.len() as u16).to_be_bytes());
+        out.extend_from_slice(&self.data);
+        out
+    }
+}
+
+/// Encodes a frame into packets of max `MAX_DATA_LENGTH` bytes, the last one with the push flag set
+pub fn encode_frame(rgb_data: &[RGB], sequence: u8) -> Vec<Vec<u8>> {
+    let mut data = Vec::with_capacity(rgb_data.len() * 3);
+    for rgb in rgb_data {
+        data.extend_from_slice(&[rgb.r, rgb.g, rgb.b]);
+    }
+    let chunks: Vec<&[u8]> = if data.is_empty() {
+        vec![&data[..]]
+    } else {
+        data.chunks(MAX_DATA_LENGTH).collect()
+    };
+    let count = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            Packet {
+                flags: if i + 1 == count { FLAG_PUSH } else { 0 },
+                sequence,
+                data_type: TYPE_RGB24,
+                id: ID_DISPLAY,
+                offset: (i * MAX_DATA_LENGTH) as u32,
+                data: chunk.to_vec(),
+            }
+            .encode()
+        })
+        .collect()
+}
+
+/// Decodes a single UDP packet. Returns `None` for invalid packets.
+pub fn decode(data: &[u8]) -> Option<Packet> {
+    if data.len() < HEADER_SIZE || data[0] & VERSION_MASK != VERSION_1 {
+        return None;
+    }
+    let flags = data[0];
+    let length = u16::from_be_bytes([data[8], data[9]]) as usize;
+    let start = if flags & FLAG_TIMECODE != 0 {
+        HEADER_SIZE + TIMECODE_SIZE
+    } else {
+        HEADER_SIZE
+    };
+    if data.len() < start + length {
+        return None;
+    }
+    Some(Packet {
+        flags,
+        sequence: data[1] & 0x0F,
+        data_type: data[2],
+        id: data[3],
+        offset: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+        data: data[start..start + length].to_vec(),
+    })
+}
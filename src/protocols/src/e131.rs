@@ -0,0 +1,188 @@
+//! E1.31 (Streaming ACN / sACN) protocol.
+//!
+//! Supports data packets and universe synchronization packets. Universe discovery packets are ignored.
+
+use std::net::Ipv4Addr;
+
+pub const PORT: u16 = 5568;
+pub const ACN_ID: &[u8; 12] = b"ASC-E1.17\0\0\0";
+
+pub const VECTOR_ROOT_DATA: u32 = 0x0000_0004;
+pub const VECTOR_ROOT_EXTENDED: u32 = 0x0000_0008;
+pub const VECTOR_FRAMING_DATA: u32 = 0x0000_0002;
+pub const VECTOR_FRAMING_SYNC: u32 = 0x0000_0001;
+pub const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+
+pub const OPTION_PREVIEW: u8 = 0x80;
+pub const OPTION_STREAM_TERMINATED: u8 = 0x40;
+pub const OPTION_FORCE_SYNC: u8 = 0x20;
+
+pub const DEFAULT_PRIORITY: u8 = 100;
+pub const MAX_PRIORITY: u8 = 200;
+pub const MAX_CHANNELS: usize = 512;
+pub const MIN_UNIVERSE: u16 = 1;
+pub const MAX_UNIVERSE: u16 = 63999;
+
+const DATA_HEADER_SIZE: usize = 126;
+const SYNC_SIZE: usize = 49;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataPacket {
+    /// unique id of the sender
+    pub cid: [u8; 16],
+    pub source_name: String,
+    pub priority: u8,
+    /// universe of the synchronization packets the data has to wait for, 0 if not synchronized
+    pub sync_address: u16,
+    pub sequence: u8,
+    pub options: u8,
+    pub universe: u16,
+    pub start_code: u8,
+    pub data: Vec<u8>,
+}
+
+impl DataPacket {
+    pub fn is_preview(&self) -> bool {
+        self.options & OPTION_PREVIEW != 0
+    }
+
+    pub fn is_stream_terminated(&self) -> bool {
+        self.options & OPTION_STREAM_TERMINATED != 0
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let size = std::cmp::min(self.data.len(), MAX_CHANNELS);
+        let total = DATA_HEADER_SIZE + size;
+        let mut out = Vec::with_capacity(total);
+        encode_root_layer(&mut out, total, VECTOR_ROOT_DATA, &self.cid);
+
+        // framing layer
+        out.extend_from_slice(&flags_and_length(total - 38));
+        out.extend_from_slice(&VECTOR_FRAMING_DATA.to_be_bytes());
+        let mut name = [0u8; 64];
+        let name_bytes = self.source_name.as_bytes();
+        let name_size = std::cmp::min(name_bytes.len(), 63);
+        name[..name_size].copy_from_slice(&name_bytes[..name_size]);
+        out.extend_from_slice(&name);
+        out.push(self.priority);
+        out.extend_from_slice(&self.sync_address.to_be_bytes());
+        out.push(self.sequence);
+        out.push(self.options);
+        out.extend_from_slice(&self.universe.to_be_bytes());
+
+        // DMP layer
+        out.extend_from_slice(&flags_and_length(total - 115));
+        out.push(VECTOR_DMP_SET_PROPERTY);
+        out.push(0xA1);
+        out.extend_from_slice(&0u16.to_be_bytes());
+        out.extend_from_slice(&1u16.to_be_bytes());
+        out.extend_from_slice(&((size + 1) as u16).to_be_bytes());
+        out.push(self.start_code);
+        out.extend_from_slice(&self.data[..size]);
+        out
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncPacket {
+    pub cid: [u8; 16],
+    pub sequence: u8,
+    pub sync_address: u16,
+}
+
+impl SyncPacket {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(SYNC_SIZE);
+        encode_root_layer(&mut out, SYNC_SIZE, VECTOR_ROOT_EXTENDED, &self.cid);
+        out.extend_from_slice(&flags_and_length(SYNC_SIZE - 38));
+        out.extend_from_slice(&VECTOR_FRAMING_SYNC.to_be_bytes());
+        out.push(self.sequence);
+        out.extend_from_slice(&self.sync_address.to_be_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Packet {
+    Data(DataPacket),
+    Sync(SyncPacket),
+}
+
+/// Multicast group a universe is sent to
+pub fn multicast_address(universe: u16) -> Ipv4Addr {
+    Ipv4Addr::new(239, 255, (universe >> 8) as u8, (universe & 0xFF) as u8)
+}
+
+/// Checks the sequence number of a packet against the last received one.
+/// Returns false for packets arriving out of order which have to be ignored.
+pub fn is_sequence_valid(last: u8, current: u8) -> bool {
+    let diff = current.wrapping_sub(last) as i8;
+    diff > 0 || diff <= -20
+}
+
+fn flags_and_length(length: usize) -> [u8; 2] {
+    (0x7000 | (length as u16 & 0x0FFF)).to_be_bytes()
+}
+
+fn encode_root_layer(out: &mut Vec<u8>, total: usize, vector: u32, cid: &[u8; 16]) {
+    out.extend_from_slice(&0x0010u16.to_be_bytes());
+    out.extend_from_slice(&0x0000u16.to_be_bytes());
+    out.extend_from_slice(ACN_ID);
+    out.extend_from_slice(&flags_and_length(total - 16));
+    out.extend_from_slice(&vector.to_be_bytes());
+    out.extend_from_slice(cid);
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Decodes a single UDP packet. Returns `None` for invalid or unsupported packets.
+pub fn decode(data: &[u8]) -> Option<Packet> {
+    if data.len() < SYNC_SIZE || read_u16(data, 0) != 0x0010 || &data[4..16] != ACN_ID {
+        return None;
+    }
+    let mut cid = [0u8; 16];
+    cid.copy_from_slice(&data[22..38]);
+
+    match (read_u32(data, 18), read_u32(data, 40)) {
+        (VECTOR_ROOT_DATA, VECTOR_FRAMING_DATA) => {
+            if data.len() < DATA_HEADER_SIZE || data[117] != VECTOR_DMP_SET_PROPERTY {
+                return None;
+            }
+            let count = read_u16(data, 123) as usize;
+            if count == 0 || count > MAX_CHANNELS + 1 || data.len() < DATA_HEADER_SIZE - 1 + count
+            {
+                return None;
+            }
+            let name_end = data[44..108].iter().position(|&c| c == 0).unwrap_or(64);
+            Some(Packet::Data(DataPacket {
+                cid,
+                source_name: String::from_utf8_lossy(&data[44..44 + name_end]).to_string(),
+                priority: data[108],
+                sync_address: read_u16(data, 109),
+                sequence: data[111],
+                options: data[112],
+                universe: read_u16(data, 113),
+                start_code: data[125],
+                data: data[DATA_HEADER_SIZE..DATA_HEADER_SIZE - 1 + count].to_vec(),
+            }))
+        }
+        (VECTOR_ROOT_EXTENDED, VECTOR_FRAMING_SYNC) => Some(Packet::Sync(SyncPacket {
+            cid,
+            sequence: data[44],
+            sync_address: read_u16(data, 45),
+        })),
+        _ => None,
+    }
+}
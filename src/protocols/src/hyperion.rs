@@ -0,0 +1,587 @@
+//! Hyperion flatbuffers and protobuf protocol.
+//!
+//! Both protocols are used by screen grabbers to push images and colors to a Hyperion server over TCP.
+//! Every message is prefixed by its size (4 bytes, big endian). Only the small subset of flatbuffers and
+//! protobuf used by the Hyperion messages is implemented, so no code generator is needed.
+
+use lightoros_plugin_base::*;
+
+pub const FLATBUFFERS_PORT: u16 = 19400;
+pub const PROTOBUF_PORT: u16 = 19445;
+
+/// Upper limit for the size of a single message, enough for a raw 4K image
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Duration of commands which are valid until they are cleared
+pub const DURATION_INFINITE: i32 = -1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// flatbuffers only, sets the priority used by the following commands of the client
+    Register { origin: String, priority: i32 },
+    /// `color` is an RGB triple. The priority is only part of protobuf commands.
+    Color {
+        priority: Option<i32>,
+        color: [u8; 3],
+        duration: i32,
+    },
+    /// `data` contains the RGB values of the image line by line
+    Image {
+        priority: Option<i32>,
+        width: i32,
+        height: i32,
+        data: Vec<u8>,
+        duration: i32,
+    },
+    /// priority -1 clears all priorities
+    Clear { priority: i32 },
+    /// protobuf only
+    ClearAll,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Reply {
+    pub error: Option<String>,
+    /// flatbuffers only, the priority confirmed by a register command
+    pub registered: Option<i32>,
+    pub video: Option<i32>,
+}
+
+fn color_from_int(value: i32) -> [u8; 3] {
+    [
+        ((value >> 16) & 0xFF) as u8,
+        ((value >> 8) & 0xFF) as u8,
+        (value & 0xFF) as u8,
+    ]
+}
+
+fn color_to_int(color: &[u8; 3]) -> i32 {
+    ((color[0] as i32) << 16) | ((color[1] as i32) << 8) | color[2] as i32
+}
+
+/// Adds the size prefix to a message
+pub fn frame_message(message: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(message.len() + 4);
+    out.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    out.extend_from_slice(message);
+    out
+}
+
+/// Splits a TCP stream into messages. Data can be pushed in chunks of any size.
+pub struct MessageDecoder {
+    buffer: Vec<u8>,
+}
+
+impl MessageDecoder {
+    pub fn new() -> MessageDecoder {
+        MessageDecoder { buffer: Vec::new() }
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next complete message without the size prefix.
+    /// Fails if a message exceeds `MAX_MESSAGE_SIZE`, the stream can't be used anymore in this case.
+    pub fn next_message(&mut self) -> PluginResult<Option<Vec<u8>>> {
+        if self.buffer.len() < 4 {
+            return Ok(None);
+        }
+        let size = u32::from_be_bytes([self.buffer[0], self.buffer[1], self.buffer[2], self.buffer[3]])
+            as usize;
+        if size > MAX_MESSAGE_SIZE {
+            return plugin_err!("Hyperion message of {} bytes exceeds the size limit", size);
+        }
+        if self.buffer.len() < size + 4 {
+            return Ok(None);
+        }
+        let message = self.buffer[4..size + 4].to_vec();
+        self.buffer.drain(..size + 4);
+        Ok(Some(message))
+    }
+}
+
+impl Default for MessageDecoder {
+    fn default() -> Self {
+        MessageDecoder::new()
+    }
+}
+
+/// Messages as defined in `hyperion_request.fbs` and `hyperion_reply.fbs` of the Hyperion project
+pub mod flatbuffers {
+    use super::*;
+
+    const COMMAND_COLOR: u8 = 1;
+    const COMMAND_IMAGE: u8 = 2;
+    const COMMAND_CLEAR: u8 = 3;
+    const COMMAND_REGISTER: u8 = 4;
+    const IMAGE_TYPE_RAW: u8 = 1;
+
+    fn read_u16(buf: &[u8], pos: usize) -> Option<u16> {
+        Some(u16::from_le_bytes([*buf.get(pos)?, *buf.get(pos + 1)?]))
+    }
+
+    fn read_u32(buf: &[u8], pos: usize) -> Option<u32> {
+        let bytes = buf.get(pos..pos + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    struct Table<'a> {
+        buf: &'a [u8],
+        pos: usize,
+        vtable: usize,
+        vtable_size: usize,
+    }
+
+    impl<'a> Table<'a> {
+        fn new(buf: &'a [u8], pos: usize) -> Option<Table<'a>> {
+            let soffset = read_u32(buf, pos)? as i32 as i64;
+            let vtable = pos as i64 - soffset;
+            if vtable < 0 {
+                return None;
+            }
+            let vtable = vtable as usize;
+            let vtable_size = read_u16(buf, vtable)? as usize;
+            if vtable_size < 4 || vtable + vtable_size > buf.len() {
+                return None;
+            }
+            Some(Table {
+                buf,
+                pos,
+                vtable,
+                vtable_size,
+            })
+        }
+
+        fn field(&self, id: usize) -> Option<usize> {
+            let entry = 4 + id * 2;
+            if entry + 2 > self.vtable_size {
+                return None;
+            }
+            match read_u16(self.buf, self.vtable + entry)? {
+                0 => None,
+                offset => Some(self.pos + offset as usize),
+            }
+        }
+
+        fn u8(&self, id: usize) -> Option<u8> {
+            self.buf.get(self.field(id)?).cloned()
+        }
+
+        fn i32(&self, id: usize) -> Option<i32> {
+            read_u32(self.buf, self.field(id)?).map(|value| value as i32)
+        }
+
+        fn offset(&self, id: usize) -> Option<usize> {
+            let pos = self.field(id)?;
+            Some(pos + read_u32(self.buf, pos)? as usize)
+        }
+
+        fn table(&self, id: usize) -> Option<Table<'a>> {
+            Table::new(self.buf, self.offset(id)?)
+        }
+
+        fn bytes(&self, id: usize) -> Option<&'a [u8]> {
+            let pos = self.offset(id)?;
+            let len = read_u32(self.buf, pos)? as usize;
+            self.buf.get(pos + 4..pos + 4 + len)
+        }
+
+        fn string(&self, id: usize) -> Option<String> {
+            Some(String::from_utf8_lossy(self.bytes(id)?).to_string())
+        }
+    }
+
+    enum Value {
+        U8(u8),
+        I32(i32),
+        Table(Vec<(usize, Value)>),
+        Bytes(Vec<u8>),
+    }
+
+    fn align(buf: &mut Vec<u8>) {
+        while !buf.len().is_multiple_of(4) {
+            buf.push(0);
+        }
+    }
+
+    fn patch_offset(buf: &mut [u8], slot: usize, target: usize) {
+        let offset = ((target - slot) as u32).to_le_bytes();
+        buf[slot..slot + 4].copy_from_slice(&offset);
+    }
+
+    // the buffer is written front to back, so all offsets to child objects point forward
+    fn write_table(buf: &mut Vec<u8>, fields: &[(usize, Value)]) -> usize {
+        let field_count = fields.iter().map(|(id, _)| id + 1).max().unwrap_or(0);
+        let vtable = buf.len();
+        buf.extend_from_slice(&((4 + field_count * 2) as u16).to_le_bytes());
+        buf.extend_from_slice(&((4 + fields.len() * 4) as u16).to_le_bytes());
+        for id in 0..field_count {
+            let offset = match fields.iter().position(|(field_id, _)| *field_id == id) {
+                Some(index) => 4 + index * 4,
+                None => 0,
+            };
+            buf.extend_from_slice(&(offset as u16).to_le_bytes());
+        }
+        align(buf);
+
+        let table = buf.len();
+        buf.extend_from_slice(&((table - vtable) as i32).to_le_bytes());
+        let mut children = Vec::new();
+        for (_, value) in fields {
+            match value {
+                Value::U8(value) => buf.extend_from_slice(&[*value, 0, 0, 0]),
+                Value::I32(value) => buf.extend_from_slice(&value.to_le_bytes()),
+                _ => {
+                    children.push((buf.len(), value));
+                    buf.extend_from_slice(&[0, 0, 0, 0]);
+                }
+            }
+        }
+        for (slot, value) in children {
+            let target = match value {
+                Value::Table(fields) => write_table(buf, fields),
+                Value::Bytes(bytes) => {
+                    let pos = buf.len();
+                    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                    buf.extend_from_slice(bytes);
+                    // strings are zero terminated, it doesn't hurt for byte vectors
+                    buf.push(0);
+                    align(buf);
+                    pos
+                }
+                _ => unreachable!(),
+            };
+            patch_offset(buf, slot, target);
+        }
+        table
+    }
+
+    fn encode(root: Vec<(usize, Value)>) -> Vec<u8> {
+        let mut buf = vec![0u8; 4];
+        let root = write_table(&mut buf, &root);
+        patch_offset(&mut buf, 0, root);
+        buf
+    }
+
+    /// Decodes a request message. Returns `None` for invalid messages.
+    pub fn decode_request(buf: &[u8]) -> Option<Command> {
+        let request = Table::new(buf, read_u32(buf, 0)? as usize)?;
+        let command = request.table(1)?;
+        match request.u8(0)? {
+            COMMAND_COLOR => Some(Command::Color {
+                priority: None,
+                color: color_from_int(command.i32(0).unwrap_or(-1)),
+                duration: command.i32(1).unwrap_or(DURATION_INFINITE),
+            }),
+            COMMAND_IMAGE => {
+                if command.u8(0)? != IMAGE_TYPE_RAW {
+                    return None;
+                }
+                let image = command.table(1)?;
+                Some(Command::Image {
+                    priority: None,
+                    width: image.i32(1).unwrap_or(-1),
+                    height: image.i32(2).unwrap_or(-1),
+                    data: image.bytes(0).unwrap_or(&[]).to_vec(),
+                    duration: command.i32(2).unwrap_or(DURATION_INFINITE),
+                })
+            }
+            COMMAND_CLEAR => Some(Command::Clear {
+                priority: command.i32(0).unwrap_or(0),
+            }),
+            COMMAND_REGISTER => Some(Command::Register {
+                origin: command.string(0)?,
+                priority: command.i32(1).unwrap_or(0),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Encodes a request message. Fails for commands not supported by the flatbuffers protocol.
+    pub fn encode_request(command: &Command) -> PluginResult<Vec<u8>> {
+        let (command_type, fields) = match command {
+            Command::Color {
+                color, duration, ..
+            } => (
+                COMMAND_COLOR,
+                vec![
+                    (0, Value::I32(color_to_int(color))),
+                    (1, Value::I32(*duration)),
+                ],
+            ),
+            Command::Image {
+                width,
+                height,
+                data,
+                duration,
+                ..
+            } => (
+                COMMAND_IMAGE,
+                vec![
+                    (0, Value::U8(IMAGE_TYPE_RAW)),
+                    (
+                        1,
+                        Value::Table(vec![
+                            (0, Value::Bytes(data.clone())),
+                            (1, Value::I32(*width)),
+                            (2, Value::I32(*height)),
+                        ]),
+                    ),
+                    (2, Value::I32(*duration)),
+                ],
+            ),
+            Command::Clear { priority } => (COMMAND_CLEAR, vec![(0, Value::I32(*priority))]),
+            Command::Register { origin, priority } => (
+                COMMAND_REGISTER,
+                vec![
+                    (0, Value::Bytes(origin.as_bytes().to_vec())),
+                    (1, Value::I32(*priority)),
+                ],
+            ),
+            Command::ClearAll => {
+                return plugin_err!("Command not supported by the Hyperion flatbuffers protocol")
+            }
+        };
+        Ok(encode(vec![
+            (0, Value::U8(command_type)),
+            (1, Value::Table(fields)),
+        ]))
+    }
+
+    pub fn encode_reply(reply: &Reply) -> Vec<u8> {
+        let mut fields = Vec::new();
+        if let Some(error) = &reply.error {
+            fields.push((0, Value::Bytes(error.as_bytes().to_vec())));
+        }
+        fields.push((1, Value::I32(reply.video.unwrap_or(-1))));
+        fields.push((2, Value::I32(reply.registered.unwrap_or(-1))));
+        encode(fields)
+    }
+
+    pub fn decode_reply(buf: &[u8]) -> Option<Reply> {
+        let reply = Table::new(buf, read_u32(buf, 0)? as usize)?;
+        let optional = |value: Option<i32>| value.filter(|value| *value != -1);
+        Some(Reply {
+            error: reply.string(0),
+            video: optional(reply.i32(1)),
+            registered: optional(reply.i32(2)),
+        })
+    }
+}
+
+/// Messages as defined in `message.proto` of the Hyperion project
+pub mod protobuf {
+    use super::*;
+
+    const COMMAND_COLOR: u64 = 1;
+    const COMMAND_IMAGE: u64 = 2;
+    const COMMAND_CLEAR: u64 = 3;
+    const COMMAND_CLEAR_ALL: u64 = 4;
+
+    const FIELD_COMMAND: u32 = 1;
+    const FIELD_COLOR_REQUEST: u32 = 10;
+    const FIELD_IMAGE_REQUEST: u32 = 11;
+    const FIELD_CLEAR_REQUEST: u32 = 12;
+
+    const REPLY_TYPE_REPLY: u64 = 1;
+
+    const WIRE_VARINT: u8 = 0;
+    const WIRE_FIXED64: u8 = 1;
+    const WIRE_BYTES: u8 = 2;
+    const WIRE_FIXED32: u8 = 5;
+
+    enum Field<'a> {
+        Varint(u64),
+        Bytes(&'a [u8]),
+    }
+
+    struct Message<'a> {
+        fields: Vec<(u32, Field<'a>)>,
+    }
+
+    impl<'a> Message<'a> {
+        fn parse(buf: &'a [u8]) -> Option<Message<'a>> {
+            let mut fields = Vec::new();
+            let mut pos = 0;
+            while pos < buf.len() {
+                let key = read_varint(buf, &mut pos)?;
+                let id = (key >> 3) as u32;
+                match (key & 0x07) as u8 {
+                    WIRE_VARINT => fields.push((id, Field::Varint(read_varint(buf, &mut pos)?))),
+                    WIRE_BYTES => {
+                        let len = read_varint(buf, &mut pos)? as usize;
+                        fields.push((id, Field::Bytes(buf.get(pos..pos.checked_add(len)?)?)));
+                        pos += len;
+                    }
+                    WIRE_FIXED64 => pos += 8,
+                    WIRE_FIXED32 => pos += 4,
+                    _ => return None,
+                }
+            }
+            if pos > buf.len() {
+                return None;
+            }
+            Some(Message { fields })
+        }
+
+        // the last occurrence of a field wins
+        fn varint(&self, id: u32) -> Option<u64> {
+            self.fields.iter().rev().find_map(|(field_id, field)| match field {
+                Field::Varint(value) if *field_id == id => Some(*value),
+                _ => None,
+            })
+        }
+
+        fn i32(&self, id: u32) -> Option<i32> {
+            self.varint(id).map(|value| value as i32)
+        }
+
+        fn bytes(&self, id: u32) -> Option<&'a [u8]> {
+            self.fields.iter().rev().find_map(|(field_id, field)| match field {
+                Field::Bytes(value) if *field_id == id => Some(*value),
+                _ => None,
+            })
+        }
+
+        fn message(&self, id: u32) -> Option<Message<'a>> {
+            Message::parse(self.bytes(id)?)
+        }
+    }
+
+    fn read_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+        let mut value: u64 = 0;
+        for shift in 0..10 {
+            let byte = *buf.get(*pos)?;
+            *pos += 1;
+            value |= ((byte & 0x7F) as u64) << (shift * 7);
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8 & 0x7F) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn write_int(out: &mut Vec<u8>, id: u32, value: i64) {
+        write_varint(out, ((id << 3) as u64) | WIRE_VARINT as u64);
+        // negative values are sign extended to 64 bit
+        write_varint(out, value as u64);
+    }
+
+    fn write_bytes(out: &mut Vec<u8>, id: u32, value: &[u8]) {
+        write_varint(out, ((id << 3) as u64) | WIRE_BYTES as u64);
+        write_varint(out, value.len() as u64);
+        out.extend_from_slice(value);
+    }
+
+    /// Decodes a request message. Returns `None` for invalid messages.
+    pub fn decode_request(buf: &[u8]) -> Option<Command> {
+        let request = Message::parse(buf)?;
+        match request.varint(FIELD_COMMAND)? {
+            COMMAND_COLOR => {
+                let color = request.message(FIELD_COLOR_REQUEST)?;
+                Some(Command::Color {
+                    priority: Some(color.i32(1)?),
+                    color: color_from_int(color.i32(2)?),
+                    duration: color.i32(3).unwrap_or(DURATION_INFINITE),
+                })
+            }
+            COMMAND_IMAGE => {
+                let image = request.message(FIELD_IMAGE_REQUEST)?;
+                Some(Command::Image {
+                    priority: Some(image.i32(1)?),
+                    width: image.i32(2)?,
+                    height: image.i32(3)?,
+                    data: image.bytes(4)?.to_vec(),
+                    duration: image.i32(5).unwrap_or(DURATION_INFINITE),
+                })
+            }
+            COMMAND_CLEAR => Some(Command::Clear {
+                priority: request.message(FIELD_CLEAR_REQUEST)?.i32(1)?,
+            }),
+            COMMAND_CLEAR_ALL => Some(Command::ClearAll),
+            _ => None,
+        }
+    }
+
+    /// Encodes a request message. Fails for commands not supported by the protobuf protocol.
+    pub fn encode_request(command: &Command) -> PluginResult<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut inner = Vec::new();
+        match command {
+            Command::Color {
+                priority: Some(priority),
+                color,
+                duration,
+            } => {
+                write_int(&mut inner, 1, *priority as i64);
+                write_int(&mut inner, 2, color_to_int(color) as i64);
+                write_int(&mut inner, 3, *duration as i64);
+                write_int(&mut out, FIELD_COMMAND, COMMAND_COLOR as i64);
+                write_bytes(&mut out, FIELD_COLOR_REQUEST, &inner);
+            }
+            Command::Image {
+                priority: Some(priority),
+                width,
+                height,
+                data,
+                duration,
+            } => {
+                write_int(&mut inner, 1, *priority as i64);
+                write_int(&mut inner, 2, *width as i64);
+                write_int(&mut inner, 3, *height as i64);
+                write_bytes(&mut inner, 4, data);
+                write_int(&mut inner, 5, *duration as i64);
+                write_int(&mut out, FIELD_COMMAND, COMMAND_IMAGE as i64);
+                write_bytes(&mut out, FIELD_IMAGE_REQUEST, &inner);
+            }
+            Command::Clear { priority } => {
+                write_int(&mut inner, 1, *priority as i64);
+                write_int(&mut out, FIELD_COMMAND, COMMAND_CLEAR as i64);
+                write_bytes(&mut out, FIELD_CLEAR_REQUEST, &inner);
+            }
+            Command::ClearAll => write_int(&mut out, FIELD_COMMAND, COMMAND_CLEAR_ALL as i64),
+            _ => return plugin_err!("Command not supported by the Hyperion protobuf protocol"),
+        }
+        Ok(out)
+    }
+
+    pub fn encode_reply(reply: &Reply) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_int(&mut out, 1, REPLY_TYPE_REPLY as i64);
+        write_int(&mut out, 2, reply.error.is_none() as i64);
+        if let Some(error) = &reply.error {
+            write_bytes(&mut out, 3, error.as_bytes());
+        }
+        if let Some(video) = reply.video {
+            write_int(&mut out, 4, video as i64);
+        }
+        out
+    }
+
+    pub fn decode_reply(buf: &[u8]) -> Option<Reply> {
+        let reply = Message::parse(buf)?;
+        let error = reply
+            .bytes(3)
+            .map(|error| String::from_utf8_lossy(error).to_string());
+        if error.is_none() && reply.varint(2) != Some(1) {
+            return Some(Reply {
+                error: Some(String::new()),
+                ..Default::default()
+            });
+        }
+        Some(Reply {
+            error,
+            registered: None,
+            video: reply.i32(4),
+        })
+    }
+}
@@ -0,0 +1,11 @@
+//! Wire protocols shared by the lightoros plugins.
+
+pub mod artnet;
+pub mod boblight;
+pub mod ddp;
+pub mod e131;
+pub mod hyperion;
+pub mod opc;
+pub mod prismatik;
+pub mod tpm2;
+pub mod y4m;
@@ -0,0 +1,90 @@
+//! Open Pixel Control (OPC) protocol.
+//!
+//! A message consists of the channel, the command, the data length (2 bytes, big endian) and the data.
+//! Channel 0 is a broadcast to all channels.
+
+use lightoros_plugin_base::*;
+
+pub const PORT: u16 = 7890;
+
+pub const BROADCAST_CHANNEL: u8 = 0;
+pub const CMD_SET_PIXELS: u8 = 0;
+pub const CMD_SYSTEM_EXCLUSIVE: u8 = 255;
+
+const HEADER_SIZE: usize = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    pub channel: u8,
+    pub command: u8,
+    pub data: Vec<u8>,
+}
+
+impl Message {
+    pub fn set_pixels(channel: u8, rgb_data: &[RGB]) -> Message {
+        let mut data = Vec::with_capacity(rgb_data.len() * 3);
+        for rgb in rgb_data {
+            data.extend_from_slice(&[rgb.r, rgb.g, rgb.b]);
+        }
+        Message {
+            channel,
+            command: CMD_SET_PIXELS,
+            data,
+        }
+    }
+
+    pub fn encode(&self) -> PluginResult<Vec<u8>> {
+        if self.data.len() > 0xFFFF {
+            return plugin_err!("OPC message of {} bytes exceeds the limit of 65535 bytes", self.data.len());
+        }
+        let mut out = Vec::with_capacity(HEADER_SIZE + self.data.len());
+        out.push(self.channel);
+        out.push(self.command);
+        out.extend_from_slice(&(self.data.len() as u16).to_be_bytes());
+        out.extend_from_slice(&self.data);
+        Ok(out)
+    }
+}
+
+/// Incremental decoder for an OPC stream. Data can be pushed in chunks of any size.
+pub struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder { buffer: Vec::new() }
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Number of buffered bytes not decoded yet
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn next_message(&mut self) -> Option<Message> {
+        if self.buffer.len() < HEADER_SIZE {
+            return None;
+        }
+        let length = u16::from_be_bytes([self.buffer[2], self.buffer[3]]) as usize;
+        if self.buffer.len() < HEADER_SIZE + length {
+            return None;
+        }
+        let message = Message {
+            channel: self.buffer[0],
+            command: self.buffer[1],
+            data: self.buffer[HEADER_SIZE..HEADER_SIZE + length].to_vec(),
+        };
+        self.buffer.drain(..HEADER_SIZE + length);
+        Some(message)
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new()
+    }
+}
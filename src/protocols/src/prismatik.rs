@@ -0,0 +1,100 @@
+//! Lightpack/Prismatik API.
+//!
+//! Clients send text commands terminated by a newline, the responses are terminated by "\r\n".
+//! Commands with parameters separate them by a colon, e.g. "setcolor:1-255,0,0;2-0,255,0;".
+
+use lightoros_plugin_base::*;
+
+pub const PORT: u16 = 3636;
+pub const GREETING: &str = "Lightpack API v1.4 - Prismatik API v2.2 (type \"help\" for more info)";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    ApiKey(String),
+    Lock,
+    Unlock,
+    GetStatus,
+    GetStatusApi,
+    GetCountLeds,
+    GetLeds,
+    GetProfiles,
+    GetProfile,
+    /// LED colors as RGB values, the LED numbers start at 1
+    SetColor(Vec<(usize, [u8; 3])>),
+    SetProfile(String),
+    SetBrightness(u8),
+    SetGamma(f32),
+    SetSmooth(u8),
+    SetStatus(bool),
+    Exit,
+    Unknown(String),
+}
+
+/// Parses a command line. Unknown commands are returned as `Command::Unknown`,
+/// invalid parameters of known commands result in an error.
+pub fn parse_command(line: &str) -> PluginResult<Command> {
+    let line = line.trim();
+    let (name, value) = match line.find(':') {
+        Some(position) => (&line[..position], Some(&line[position + 1..])),
+        None => (line, None),
+    };
+    match (name, value) {
+        ("apikey", Some(key)) => Ok(Command::ApiKey(key.to_string())),
+        ("lock", None) => Ok(Command::Lock),
+        ("unlock", None) => Ok(Command::Unlock),
+        ("getstatus", None) => Ok(Command::GetStatus),
+        ("getstatusapi", None) => Ok(Command::GetStatusApi),
+        ("getcountleds", None) => Ok(Command::GetCountLeds),
+        ("getleds", None) => Ok(Command::GetLeds),
+        ("getprofiles", None) => Ok(Command::GetProfiles),
+        ("getprofile", None) => Ok(Command::GetProfile),
+        ("setcolor", Some(colors)) => parse_colors(colors).map(Command::SetColor),
+        ("setprofile", Some(profile)) => Ok(Command::SetProfile(profile.to_string())),
+        ("setbrightness", Some(value)) => match value.parse::<u8>() {
+            Ok(brightness) if brightness <= 100 => Ok(Command::SetBrightness(brightness)),
+            _ => plugin_err!("Invalid brightness: {}", value),
+        },
+        ("setgamma", Some(value)) => match value.parse::<f32>() {
+            Ok(gamma) if (0.01..=10.0).contains(&gamma) => Ok(Command::SetGamma(gamma)),
+            _ => plugin_err!("Invalid gamma: {}", value),
+        },
+        ("setsmooth", Some(value)) => match value.parse::<u8>() {
+            Ok(smooth) => Ok(Command::SetSmooth(smooth)),
+            _ => plugin_err!("Invalid smooth value: {}", value),
+        },
+        ("setstatus", Some("on")) => Ok(Command::SetStatus(true)),
+        ("setstatus", Some("off")) => Ok(Command::SetStatus(false)),
+        ("setstatus", Some(value)) => plugin_err!("Invalid status: {}", value),
+        ("exit", None) => Ok(Command::Exit),
+        _ => Ok(Command::Unknown(line.to_string())),
+    }
+}
+
+/// Response line including the line terminator
+pub fn encode_response(response: &str) -> String {
+    format!("{}\r\n", response)
+}
+
+// colors are separated by semicolons, each one is "<led>-<r>,<g>,<b>"
+fn parse_colors(value: &str) -> PluginResult<Vec<(usize, [u8; 3])>> {
+    let mut colors = Vec::new();
+    for entry in value.split(';').filter(|entry| !entry.is_empty()) {
+        let mut parts = entry.splitn(2, '-');
+        let led = match parts.next().map(|led| led.trim().parse::<usize>()) {
+            Some(Ok(led)) if led > 0 => led,
+            _ => return plugin_err!("Invalid LED number in '{}'", entry),
+        };
+        let channels: Vec<u8> = match parts.next() {
+            Some(rgb) => match rgb.split(',').map(|c| c.trim().parse::<u8>()).collect() {
+                Ok(channels) => channels,
+                Err(_) => return plugin_err!("Invalid color in '{}'", entry),
+            },
+            None => return plugin_err!("Missing color in '{}'", entry),
+        };
+        if channels.len() != 3 {
+            return plugin_err!("Invalid color in '{}'", entry);
+        }
+        colors.push((led, [channels[0], channels[1], channels[2]]));
+    }
+    Ok(colors)
+}
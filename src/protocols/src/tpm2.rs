@@ -0,0 +1,423 @@
+//! TPM2 (serial) and TPM2.net protocol.
+//!
+//! A TPM2 packet consists of a start byte, the packet type, the payload size (2 bytes, big endian),
+//! the payload and an end byte. TPM2.net packets additionally carry the packet number and the total
+//! number of packets of a frame after the size, which allows splitting big frames into several packets.
+
+use lightoros_plugin_base::*;
+
+pub const START_SERIAL: u8 = 0xC9;
+pub const START_NET: u8 = 0x9C;
+pub const END: u8 = 0x36;
+pub const ACKNOWLEDGE: u8 = 0xAC;
+
+pub const TYPE_DATA: u8 = 0xDA;
+pub const TYPE_COMMAND: u8 = 0xC0;
+pub const TYPE_RESPONSE: u8 = 0xAA;
+
+pub const MAX_PAYLOAD_SIZE: usize = 0xFFFF;
+pub const MAX_PACKETS: usize = 255;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Framing {
+    /// TPM2 as used on serial lines and in files, a frame always fits into a single packet
+    Serial,
+    /// TPM2.net, a frame can be split into up to 255 packets
+    Net,
+}
+
+impl Framing {
+    pub fn start_byte(&self) -> u8 {
+        match self {
+            Framing::Serial => START_SERIAL,
+            Framing::Net => START_NET,
+        }
+    }
+
+    /// Number of bytes added to the payload by the header and the end byte
+    pub fn overhead(&self) -> usize {
+        match self {
+            Framing::Serial => 5,
+            Framing::Net => 7,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PacketType {
+    Data,
+    Command,
+    Response,
+}
+
+impl PacketType {
+    pub fn to_byte(&self) -> u8 {
+        match self {
+            PacketType::Data => TYPE_DATA,
+            PacketType::Command => TYPE_COMMAND,
+            PacketType::Response => TYPE_RESPONSE,
+        }
+    }
+
+    pub fn from_byte(value: u8) -> Option<PacketType> {
+        match value {
+            TYPE_DATA => Some(PacketType::Data),
+            TYPE_COMMAND => Some(PacketType::Command),
+            TYPE_RESPONSE => Some(PacketType::Response),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub framing: Framing,
+    pub packet_type: PacketType,
+    /// 1 based packet number, always 1 for serial packets
+    pub packet_number: u8,
+    /// always 1 for serial packets
+    pub number_of_packets: u8,
+    pub payload: Vec<u8>,
+}
+
+impl Packet {
+    pub fn encode(&self) -> Vec<u8> {
+        let size = self.payload.len();
+        let mut out: Vec<u8> = Vec::with_capacity(size + self.framing.overhead());
+        out.push(self.framing.start_byte());
+        out.push(self.packet_type.to_byte());
+        out.push(((size >> 8) & 0xFF) as u8);
+        out.push((size & 0xFF) as u8);
+        if self.framing == Framing::Net {
+            out.push(self.packet_number);
+            out.push(self.number_of_packets);
+        }
+        out.extend_from_slice(&self.payload);
+        out.push(END);
+        out
+    }
+}
+
+/// Converts RGB data into TPM2 packets
+pub struct Encoder {
+    framing: Framing,
+    color_order: ColorOrder,
+    max_packet_length: usize,
+}
+
+impl Encoder {
+    pub fn new(framing: Framing, color_order: ColorOrder) -> Encoder {
+        Encoder {
+            framing,
+            color_order,
+            max_packet_length: MAX_PAYLOAD_SIZE + framing.overhead(),
+        }
+    }
+
+    /// Limits the size of a single packet including header and end byte.
+    /// Only TPM2.net frames can be split, serial frames exceeding the limit can't be encoded.
+    pub fn with_max_packet_length(mut self, max_packet_length: usize) -> PluginResult<Encoder> {
+        let min = self.framing.overhead() + 3;
+        let max = MAX_PAYLOAD_SIZE + self.framing.overhead();
+        if max_packet_length < min || max_packet_length > max {
+            return plugin_err!(
+                "Invalid TPM2 packet length: {}. Valid range: [{}-{}]",
+                max_packet_length,
+                min,
+                max
+            );
+        }
+        self.max_packet_length = max_packet_length;
+        Ok(self)
+    }
+
+    pub fn max_packet_length(&self) -> usize {
+        self.max_packet_length
+    }
+
+    /// Encodes a frame into one or more packets.
+    /// A TPM2.net frame is split into the smallest possible number of packets with the same amount of LEDs,
+    /// only the last packet may carry less. A receiver can calculate the offset of each packet that way.
+    pub fn encode_frame(&self, rgb_data: &[RGB]) -> PluginResult<Vec<Vec<u8>>> {
+        let max_rgb_per_packet = (self.max_packet_length - self.framing.overhead()) / 3;
+
+        if rgb_data.is_empty() {
+            return Ok(vec![self.encode_packet(PacketType::Data, rgb_data, 1, 1)]);
+        }
+
+        let number_of_packets = rgb_data.len().div_ceil(max_rgb_per_packet);
+        if self.framing == Framing::Serial && number_of_packets > 1 {
+            return plugin_err!(
+                "Error encoding TPM2 frame: {} LEDs exceed the packet limit of {} LEDs",
+                rgb_data.len(),
+                max_rgb_per_packet
+            );
+        }
+        if number_of_packets > MAX_PACKETS {
+            return plugin_err!("Error encoding TPM2.net frame: the number of total packets needed ({}) exceeds the allowed limit of {}", number_of_packets, MAX_PACKETS);
+        }
+        let rgb_per_packet = rgb_data.len().div_ceil(number_of_packets);
+
+        let packets = rgb_data
+            .chunks(rgb_per_packet)
+            .enumerate()
+            .map(|(i, chunk)| self.encode_packet(PacketType::Data, chunk, i + 1, number_of_packets))
+            .collect();
+        Ok(packets)
+    }
+
+    pub fn encode_command(&self, command: &[u8]) -> PluginResult<Vec<u8>> {
+        if command.len() + self.framing.overhead() > self.max_packet_length {
+            return plugin_err!(
+                "TPM2 command of {} bytes does not fit into a single packet",
+                command.len()
+            );
+        }
+        let packet = Packet {
+            framing: self.framing,
+            packet_type: PacketType::Command,
+            packet_number: 1,
+            number_of_packets: 1,
+            payload: command.to_vec(),
+        };
+        Ok(packet.encode())
+    }
+
+    fn encode_packet(
+        &self,
+        packet_type: PacketType,
+        rgb_data: &[RGB],
+        packet_number: usize,
+        number_of_packets: usize,
+    ) -> Vec<u8> {
+        let mut payload: Vec<u8> = Vec::with_capacity(rgb_data.len() * 3);
+        for rgb in rgb_data {
+            payload.extend_from_slice(&self.color_order.apply(rgb));
+        }
+        let packet = Packet {
+            framing: self.framing,
+            packet_type,
+            packet_number: packet_number as u8,
+            number_of_packets: number_of_packets as u8,
+            payload,
+        };
+        packet.encode()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    Packet(Packet),
+    /// single acknowledgement byte sent by some receivers after each frame
+    Acknowledge,
+}
+
+/// Incremental decoder for TPM2 and TPM2.net streams.
+/// Data can be pushed in chunks of any size. Invalid data is skipped until the next valid packet is found.
+pub struct Decoder {
+    buffer: Vec<u8>,
+    acknowledge: bool,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder {
+            buffer: Vec::new(),
+            acknowledge: false,
+        }
+    }
+
+    /// Reports single acknowledgement bytes found between packets as `Message::Acknowledge`
+    pub fn with_acknowledge(mut self) -> Decoder {
+        self.acknowledge = true;
+        self
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Number of buffered bytes not decoded yet
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Returns the next complete message or `None` if more data is needed
+    pub fn next_message(&mut self) -> Option<Message> {
+        loop {
+            let acknowledge = self.acknowledge;
+            let start = self.buffer.iter().position(|&b| {
+                b == START_SERIAL || b == START_NET || (acknowledge && b == ACKNOWLEDGE)
+            });
+            let start = match start {
+                Some(start) => start,
+                None => {
+                    self.buffer.clear();
+                    return None;
+                }
+            };
+            self.buffer.drain(..start);
+
+            if self.buffer[0] == ACKNOWLEDGE {
+                self.buffer.drain(..1);
+                return Some(Message::Acknowledge);
+            }
+
+            let framing = if self.buffer[0] == START_SERIAL {
+                Framing::Serial
+            } else {
+                Framing::Net
+            };
+            let header_size = framing.overhead() - 1;
+            if self.buffer.len() < header_size {
+                return None;
+            }
+
+            let packet_type = match PacketType::from_byte(self.buffer[1]) {
+                Some(packet_type) => packet_type,
+                None => {
+                    // not a packet start, resynchronise on the next byte
+                    self.buffer.drain(..1);
+                    continue;
+                }
+            };
+            let size = (self.buffer[2] as usize) << 8 | self.buffer[3] as usize;
+            let (packet_number, number_of_packets) = match framing {
+                Framing::Serial => (1, 1),
+                Framing::Net => (self.buffer[4], self.buffer[5]),
+            };
+            if framing == Framing::Net
+                && (packet_number == 0 || number_of_packets == 0 || packet_number > number_of_packets)
+            {
+                self.buffer.drain(..1);
+                continue;
+            }
+
+            let packet_size = size + framing.overhead();
+            if self.buffer.len() < packet_size {
+                return None;
+            }
+            if self.buffer[packet_size - 1] != END {
+                self.buffer.drain(..1);
+                continue;
+            }
+
+            let payload = self.buffer[header_size..packet_size - 1].to_vec();
+            self.buffer.drain(..packet_size);
+            return Some(Message::Packet(Packet {
+                framing,
+                packet_type,
+                packet_number,
+                number_of_packets,
+                payload,
+            }));
+        }
+    }
+
+    /// Returns the next complete packet skipping acknowledgements
+    pub fn next_packet(&mut self) -> Option<Packet> {
+        loop {
+            match self.next_message()? {
+                Message::Packet(packet) => return Some(packet),
+                Message::Acknowledge => continue,
+            }
+        }
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new()
+    }
+}
+
+/// Reassembles frames out of TPM2.net data packets which might arrive out of order.
+/// Serial packets are complete frames by themselves.
+pub struct FrameAssembler {
+    number_of_packets: usize,
+    packets: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+impl FrameAssembler {
+    pub fn new() -> FrameAssembler {
+        FrameAssembler {
+            number_of_packets: 0,
+            packets: Vec::new(),
+            received: 0,
+        }
+    }
+
+    /// Drops all packets of an incomplete frame
+    pub fn reset(&mut self) {
+        self.number_of_packets = 0;
+        self.packets.clear();
+        self.received = 0;
+    }
+
+    /// Returns true if packets of an incomplete frame are pending
+    pub fn is_pending(&self) -> bool {
+        self.received > 0
+    }
+
+    /// Number of packets received for the current incomplete frame
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Adds a data packet and returns the payload of the whole frame once all its packets are received
+    pub fn add(&mut self, packet: Packet) -> Option<Vec<u8>> {
+        if packet.packet_type != PacketType::Data {
+            return None;
+        }
+        if packet.framing == Framing::Serial || packet.number_of_packets == 1 {
+            self.reset();
+            return Some(packet.payload);
+        }
+
+        let number_of_packets = packet.number_of_packets as usize;
+        let index = packet.packet_number as usize - 1;
+        if index >= number_of_packets {
+            return None;
+        }
+
+        // a packet of a different frame layout or a duplicate starts a new frame
+        if number_of_packets != self.number_of_packets || self.packets[index].is_some() {
+            self.reset();
+            self.number_of_packets = number_of_packets;
+            self.packets.resize(number_of_packets, None);
+        }
+
+        self.packets[index] = Some(packet.payload);
+        self.received += 1;
+
+        if self.received < self.number_of_packets {
+            return None;
+        }
+
+        let mut frame = Vec::new();
+        for payload in self.packets.iter_mut() {
+            frame.extend_from_slice(&payload.take().unwrap());
+        }
+        self.reset();
+        Some(frame)
+    }
+}
+
+impl Default for FrameAssembler {
+    fn default() -> Self {
+        FrameAssembler::new()
+    }
+}
+
+/// Converts a data payload into RGB values. Trailing bytes not forming a complete LED are ignored.
+pub fn decode_rgb(payload: &[u8], color_order: ColorOrder) -> Vec<RGB> {
+    payload
+        .chunks_exact(3)
+        .map(|chunk| color_order.read(chunk))
+        .collect()
+}
@@ -0,0 +1,217 @@
+//! YUV4MPEG2 (Y4M) video streams, e.g. produced by "ffmpeg -f yuv4mpegpipe".
+//!
+//! A stream starts with a header line "YUV4MPEG2 W<width> H<height> F<num>:<den> ...", each frame
+//! is a "FRAME" line followed by the Y, U and V planes. Only 8 bit color spaces are supported.
+
+use lightoros_plugin_base::*;
+
+pub const STREAM_MAGIC: &str = "YUV4MPEG2";
+pub const FRAME_MAGIC: &str = "FRAME";
+/// Largest frame accepted from a stream header
+pub const MAX_PIXELS: usize = 4096 * 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Chroma {
+    C420,
+    C422,
+    C444,
+    Mono,
+}
+
+/// YUV to RGB conversion matrix
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Matrix {
+    Bt601,
+    Bt709,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Range {
+    /// Y in 16-235, U and V in 16-240
+    Limited,
+    /// all values in 0-255
+    Full,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header {
+    pub width: usize,
+    pub height: usize,
+    /// frames per second as numerator and denominator
+    pub frame_rate: Option<(u32, u32)>,
+    pub chroma: Chroma,
+    /// range announced by the "XCOLORRANGE" extension
+    pub range: Option<Range>,
+}
+
+impl Header {
+    /// Size of the planes of a single frame in bytes
+    pub fn frame_size(&self) -> PluginResult<usize> {
+        let (chroma_width, chroma_height) = self.chroma_size();
+        let size = chroma_width
+            .checked_mul(chroma_height)
+            .and_then(|chroma| chroma.checked_mul(2))
+            .and_then(|chroma| chroma.checked_add(self.width.checked_mul(self.height)?));
+        match size {
+            Some(size) => Ok(size),
+            None => plugin_err!("Invalid Y4M frame size {}x{}", self.width, self.height),
+        }
+    }
+
+    fn chroma_size(&self) -> (usize, usize) {
+        match self.chroma {
+            Chroma::C420 => (self.width.div_ceil(2), self.height.div_ceil(2)),
+            Chroma::C422 => (self.width.div_ceil(2), self.height),
+            Chroma::C444 => (self.width, self.height),
+            Chroma::Mono => (0, 0),
+        }
+    }
+}
+
+/// Parses the stream header line
+pub fn parse_header(line: &str) -> PluginResult<Header> {
+    let mut params = line.split_whitespace();
+    if params.next() != Some(STREAM_MAGIC) {
+        return plugin_err!("Invalid Y4M stream header: {}", line);
+    }
+
+    let mut width = None;
+    let mut height = None;
+    let mut frame_rate = None;
+    let mut chroma = Chroma::C420;
+    let mut range = None;
+    for param in params {
+        let mut chars = param.chars();
+        let tag = chars.next();
+        let value = chars.as_str();
+        match tag {
+            Some('W') => width = value.parse::<usize>().ok(),
+            Some('H') => height = value.parse::<usize>().ok(),
+            Some('F') => frame_rate = Some(parse_ratio(value)?),
+            Some('C') => chroma = parse_chroma(value)?,
+            Some('X') => {
+                range = match value {
+                    "COLORRANGE=FULL" => Some(Range::Full),
+                    "COLORRANGE=LIMITED" => Some(Range::Limited),
+                    _ => range,
+                }
+            }
+            // interlacing, aspect ratio and unknown parameters are ignored
+            _ => {}
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) if width > 0 && height > 0 => {
+            if width
+                .checked_mul(height)
+                .is_none_or(|pixels| pixels > MAX_PIXELS)
+            {
+                return plugin_err!(
+                    "Y4M frame size {}x{} exceeds the limit of {} pixels",
+                    width,
+                    height,
+                    MAX_PIXELS
+                );
+            }
+            Ok(Header {
+                width,
+                height,
+                // a frame rate of 0:0 means unknown
+                frame_rate: frame_rate.filter(|(num, den)| *num > 0 && *den > 0),
+                chroma,
+                range,
+            })
+        }
+        _ => plugin_err!("Invalid or missing Y4M frame size: {}", line),
+    }
+}
+
+/// Checks a frame header line, frame parameters are ignored
+pub fn parse_frame_header(line: &str) -> PluginResult<()> {
+    match line.split_whitespace().next() {
+        Some(FRAME_MAGIC) => Ok(()),
+        _ => plugin_err!("Invalid Y4M frame header: {}", line),
+    }
+}
+
+/// Converts the planes of a frame to RGB
+pub fn decode_rgb(header: &Header, frame: &[u8], matrix: Matrix, range: Range) -> Vec<RGB> {
+    let (chroma_width, chroma_height) = header.chroma_size();
+    let luma_size = header.width * header.height;
+    let chroma_size = chroma_width * chroma_height;
+    let (y_plane, chroma) = frame.split_at(luma_size);
+    let (u_plane, v_plane) = chroma.split_at(chroma_size);
+
+    let (kr, kb) = match matrix {
+        Matrix::Bt601 => (0.299, 0.114),
+        Matrix::Bt709 => (0.2126, 0.0722),
+    };
+
+    let mut rgb = Vec::with_capacity(luma_size);
+    for y in 0..header.height {
+        for x in 0..header.width {
+            let luma = y_plane[y * header.width + x];
+            let (u, v) = if chroma_size == 0 {
+                (128, 128)
+            } else {
+                let cx = x * chroma_width / header.width;
+                let cy = y * chroma_height / header.height;
+                (
+                    u_plane[cy * chroma_width + cx],
+                    v_plane[cy * chroma_width + cx],
+                )
+            };
+            rgb.push(to_rgb(luma, u, v, kr, kb, range));
+        }
+    }
+    rgb
+}
+
+fn to_rgb(y: u8, u: u8, v: u8, kr: f32, kb: f32, range: Range) -> RGB {
+    let (y, cb, cr) = match range {
+        Range::Limited => (
+            (y as f32 - 16.0) / 219.0,
+            (u as f32 - 128.0) / 224.0,
+            (v as f32 - 128.0) / 224.0,
+        ),
+        Range::Full => (
+            y as f32 / 255.0,
+            (u as f32 - 128.0) / 255.0,
+            (v as f32 - 128.0) / 255.0,
+        ),
+    };
+    let r = y + 2.0 * (1.0 - kr) * cr;
+    let b = y + 2.0 * (1.0 - kb) * cb;
+    let g = (y - kr * r - kb * b) / (1.0 - kr - kb);
+    RGB {
+        r: to_color_value(r),
+        g: to_color_value(g),
+        b: to_color_value(b),
+    }
+}
+
+fn to_color_value(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn parse_ratio(value: &str) -> PluginResult<(u32, u32)> {
+    let mut parts = value.splitn(2, ':');
+    match (
+        parts.next().map(str::parse::<u32>),
+        parts.next().map(str::parse::<u32>),
+    ) {
+        (Some(Ok(num)), Some(Ok(den))) => Ok((num, den)),
+        _ => plugin_err!("Invalid Y4M frame rate: {}", value),
+    }
+}
+
+fn parse_chroma(value: &str) -> PluginResult<Chroma> {
+    match value {
+        "420" | "420jpeg" | "420paldv" | "420mpeg2" => Ok(Chroma::C420),
+        "422" => Ok(Chroma::C422),
+        "444" => Ok(Chroma::C444),
+        "mono" => Ok(Chroma::Mono),
+        _ => plugin_err!("Unsupported Y4M color space: {}", value),
+    }
+}
@@ -0,0 +1,52 @@
+use lightoros_protocols::artnet::*;
+
+#[test]
+fn test_encode_dmx() {
+    let packet = DmxPacket {
+        sequence: 7,
+        physical: 1,
+        universe: 0x1234,
+        data: vec![1, 2, 3],
+    };
+    let out = packet.encode();
+    assert_eq!(&out[..8], b"Art-Net\0");
+    assert_eq!(&out[8..], [0x00, 0x50, 0, 14, 7, 1, 0x34, 0x12, 0, 4, 1, 2, 3, 0]);
+}
+
+#[test]
+fn test_dmx_round_trip() {
+    let packet = DmxPacket {
+        sequence: 200,
+        physical: 0,
+        universe: 0x7FFF,
+        data: (0..512).map(|i| i as u8).collect(),
+    };
+    assert_eq!(decode(&packet.encode()), Some(Packet::Dmx(packet)));
+}
+
+#[test]
+fn test_decode_sync() {
+    assert_eq!(decode(&encode_sync()), Some(Packet::Sync));
+}
+
+#[test]
+fn test_decode_invalid() {
+    let mut packet = DmxPacket {
+        sequence: 0,
+        physical: 0,
+        universe: 1,
+        data: vec![255; 10],
+    }
+    .encode();
+
+    // truncated data
+    assert_eq!(decode(&packet[..20]), None);
+    // invalid id
+    packet[0] = b'a';
+    assert_eq!(decode(&packet), None);
+    // unsupported opcode
+    let mut poll = encode_sync();
+    poll[9] = 0x20;
+    assert_eq!(decode(&poll), None);
+    assert_eq!(decode(&[]), None);
+}
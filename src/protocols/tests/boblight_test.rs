@@ -0,0 +1,74 @@
+use lightoros_protocols::boblight::*;
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!(parse_command("hello").unwrap(), Command::Hello);
+    assert_eq!(parse_command("ping").unwrap(), Command::Ping);
+    assert_eq!(parse_command("get version").unwrap(), Command::GetVersion);
+    assert_eq!(parse_command("get lights").unwrap(), Command::GetLights);
+    assert_eq!(parse_command("sync").unwrap(), Command::Sync);
+    assert_eq!(
+        parse_command("set priority 128").unwrap(),
+        Command::SetPriority(128)
+    );
+    assert_eq!(
+        parse_command("set light left rgb 1.000000 0.500000 0\r").unwrap(),
+        Command::SetLight {
+            name: "left".to_string(),
+            property: LightProperty::Rgb([1.0, 0.5, 0.0]),
+        }
+    );
+    assert_eq!(
+        parse_command("set light left use 0").unwrap(),
+        Command::SetLight {
+            name: "left".to_string(),
+            property: LightProperty::Use(false),
+        }
+    );
+    assert_eq!(
+        parse_command("set light 2 speed 100.0").unwrap(),
+        Command::SetLight {
+            name: "2".to_string(),
+            property: LightProperty::Speed(100.0),
+        }
+    );
+}
+
+#[test]
+fn test_parse_invalid_commands() {
+    assert!(parse_command("").is_err());
+    assert!(parse_command("get").is_err());
+    assert!(parse_command("set priority high").is_err());
+    assert!(parse_command("set light left rgb 1 0").is_err());
+    assert!(parse_command("set light left use maybe").is_err());
+    assert!(parse_command("set light left color 1 0 0").is_err());
+}
+
+#[test]
+fn test_encode_lights() {
+    let lights = vec![
+        Light {
+            name: "left".to_string(),
+            vscan: [0.0, 100.0],
+            hscan: [0.0, 50.0],
+        },
+        Light {
+            name: "right".to_string(),
+            vscan: [0.0, 100.0],
+            hscan: [50.0, 100.0],
+        },
+    ];
+    assert_eq!(
+        encode_lights(&lights),
+        "lights 2\nlight left scan 0 100 0 50\nlight right scan 0 100 50 100\n"
+    );
+}
+
+#[test]
+fn test_color_value() {
+    assert_eq!(to_color_value(0.0), 0);
+    assert_eq!(to_color_value(0.5), 128);
+    assert_eq!(to_color_value(1.0), 255);
+    assert_eq!(to_color_value(-1.0), 0);
+    assert_eq!(to_color_value(2.0), 255);
+}
@@ -0,0 +1,81 @@
+use lightoros_plugin_base::*;
+use lightoros_protocols::ddp::*;
+
+#[test]
+fn test_encode() {
+    let packet = Packet {
+        flags: FLAG_PUSH,
+        sequence: 3,
+        data_type: TYPE_RGB24,
+        id: ID_DISPLAY,
+        offset: 0x0102_0304,
+        data: vec![1, 2, 3],
+    };
+    assert_eq!(
+        packet.encode(),
+        [0x41, 3, 0x0B, 1, 1, 2, 3, 4, 0, 3, 1, 2, 3]
+    );
+}
+
+#[test]
+fn test_round_trip() {
+    let packet = Packet {
+        flags: 0,
+        sequence: 15,
+        data_type: TYPE_RGB24,
+        id: ID_ALL,
+        offset: 1440,
+        data: vec![7; 1440],
+    };
+    let decoded = decode(&packet.encode()).unwrap();
+    assert_eq!(decoded.flags, VERSION_1);
+    assert_eq!(decoded.offset, packet.offset);
+    assert_eq!(decoded.data, packet.data);
+    assert!(decoded.is_display_data());
+    assert!(!decoded.is_push());
+}
+
+#[test]
+fn test_decode_timecode() {
+    let data = [0x51, 0, 0x0B, 1, 0, 0, 0, 3, 0, 3, 9, 9, 9, 9, 4, 5, 6];
+    let packet = decode(&data).unwrap();
+    assert!(packet.is_push());
+    assert_eq!(packet.offset, 3);
+    assert_eq!(packet.data, [4, 5, 6]);
+}
+
+#[test]
+fn test_decode_invalid() {
+    // wrong version
+    assert_eq!(decode(&[0x81, 0, 0x0B, 1, 0, 0, 0, 0, 0, 0]), None);
+    // truncated data
+    assert_eq!(decode(&[0x41, 0, 0x0B, 1, 0, 0, 0, 0, 0, 6, 1, 2, 3]), None);
+    assert_eq!(decode(&[0x41]), None);
+}
+
+#[test]
+fn test_query_and_status_packets() {
+    let query = decode(&[0x43, 0, 0, ID_STATUS, 0, 0, 0, 0, 0, 0]).unwrap();
+    assert!(query.is_query());
+    assert!(!query.is_display_data());
+    let config = decode(&[0x41, 0, 0, ID_CONFIG, 0, 0, 0, 0, 0, 0]).unwrap();
+    assert!(!config.is_display_data());
+}
+
+#[test]
+fn test_encode_frame() {
+    let rgb = vec![RGB { r: 1, g: 2, b: 3 }; 500];
+    let packets = encode_frame(&rgb, 1);
+    assert_eq!(packets.len(), 2);
+
+    let first = decode(&packets[0]).unwrap();
+    assert!(!first.is_push());
+    assert_eq!(first.offset, 0);
+    assert_eq!(first.data.len(), 1440);
+
+    let second = decode(&packets[1]).unwrap();
+    assert!(second.is_push());
+    assert_eq!(second.offset, 1440);
+    assert_eq!(second.data.len(), 60);
+    assert_eq!(&second.data[..3], [1, 2, 3]);
+}
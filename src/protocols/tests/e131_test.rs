@@ -0,0 +1,83 @@
+use lightoros_protocols::e131::*;
+
+fn data_packet(universe: u16, data: Vec<u8>) -> DataPacket {
+    DataPacket {
+        cid: [0x11; 16],
+        source_name: "test source".to_string(),
+        priority: DEFAULT_PRIORITY,
+        sync_address: 0,
+        sequence: 1,
+        options: 0,
+        universe,
+        start_code: 0,
+        data,
+    }
+}
+
+#[test]
+fn test_encode_data() {
+    let out = data_packet(0x0102, vec![1, 2, 3]).encode();
+    assert_eq!(out.len(), 129);
+    assert_eq!(&out[..16], b"\x00\x10\x00\x00ASC-E1.17\x00\x00\x00");
+    // flags and length of the root, framing and DMP layer
+    assert_eq!(&out[16..18], [0x70, 113]);
+    assert_eq!(&out[38..40], [0x70, 91]);
+    assert_eq!(&out[115..117], [0x70, 14]);
+    assert_eq!(&out[44..55], b"test source");
+    assert_eq!(&out[108..], [100, 0, 0, 1, 0, 1, 2, 0x70, 14, 2, 0xA1, 0, 0, 0, 1, 0, 4, 0, 1, 2, 3]);
+}
+
+#[test]
+fn test_data_round_trip() {
+    let mut packet = data_packet(MAX_UNIVERSE, (0..512).map(|i| i as u8).collect());
+    packet.priority = MAX_PRIORITY;
+    packet.sync_address = 7;
+    packet.options = OPTION_PREVIEW;
+    let decoded = decode(&packet.encode());
+    assert_eq!(decoded, Some(Packet::Data(packet.clone())));
+    assert!(packet.is_preview());
+    assert!(!packet.is_stream_terminated());
+}
+
+#[test]
+fn test_sync_round_trip() {
+    let packet = SyncPacket {
+        cid: [0x22; 16],
+        sequence: 9,
+        sync_address: 1000,
+    };
+    let out = packet.encode();
+    assert_eq!(out.len(), 49);
+    assert_eq!(decode(&out), Some(Packet::Sync(packet)));
+}
+
+#[test]
+fn test_decode_invalid() {
+    let mut out = data_packet(1, vec![255; 100]).encode();
+    // truncated data
+    assert_eq!(decode(&out[..150]), None);
+    // unsupported DMP vector
+    out[117] = 0x01;
+    assert_eq!(decode(&out), None);
+    // invalid ACN identifier
+    out[117] = VECTOR_DMP_SET_PROPERTY;
+    out[4] = b'X';
+    assert_eq!(decode(&out), None);
+    assert_eq!(decode(&[0; 10]), None);
+}
+
+#[test]
+fn test_multicast_address() {
+    assert_eq!(multicast_address(1).octets(), [239, 255, 0, 1]);
+    assert_eq!(multicast_address(0x1234).octets(), [239, 255, 0x12, 0x34]);
+}
+
+#[test]
+fn test_sequence() {
+    assert!(is_sequence_valid(1, 2));
+    assert!(is_sequence_valid(255, 0));
+    assert!(!is_sequence_valid(5, 5));
+    assert!(!is_sequence_valid(5, 4));
+    assert!(!is_sequence_valid(5, 242));
+    assert!(is_sequence_valid(30, 10));
+}
@@ -0,0 +1,155 @@
+use lightoros_protocols::hyperion::*;
+
+fn image(priority: Option<i32>) -> Command {
+    Command::Image {
+        priority,
+        width: 3,
+        height: 2,
+        data: (0..18).collect(),
+        duration: 1000,
+    }
+}
+
+#[test]
+fn test_message_decoder() {
+    let mut stream = frame_message(&[1, 2, 3]);
+    stream.extend(frame_message(&[]));
+    stream.extend(frame_message(&[4]));
+
+    let mut decoder = MessageDecoder::new();
+    let mut messages = Vec::new();
+    for byte in stream {
+        decoder.push(&[byte]);
+        while let Some(message) = decoder.next_message().unwrap() {
+            messages.push(message);
+        }
+    }
+    assert_eq!(messages, [vec![1, 2, 3], vec![], vec![4]]);
+
+    decoder.push(&[0xFF, 0xFF, 0xFF, 0xFF]);
+    assert!(decoder.next_message().is_err());
+}
+
+#[test]
+fn test_flatbuffers_round_trip() {
+    let commands = [
+        Command::Register {
+            origin: "test grabber".to_string(),
+            priority: 150,
+        },
+        Command::Color {
+            priority: None,
+            color: [1, 2, 3],
+            duration: DURATION_INFINITE,
+        },
+        image(None),
+        Command::Clear { priority: -1 },
+    ];
+    for command in commands.iter() {
+        let encoded = flatbuffers::encode_request(command).unwrap();
+        assert_eq!(encoded.len() % 4, 0);
+        assert_eq!(flatbuffers::decode_request(&encoded).as_ref(), Some(command));
+    }
+    assert!(flatbuffers::encode_request(&Command::ClearAll).is_err());
+}
+
+#[test]
+fn test_flatbuffers_decode_generated_message() {
+    // Register { origin: "ab", priority: 100 } built by the official flatbuffers library
+    let message = [
+        12, 0, 0, 0, 8, 0, 12, 0, 7, 0, 8, 0, 8, 0, 0, 0, 0, 0, 0, 4, 12, 0, 0, 0, 8, 0, 12, 0, 8, 0,
+        4, 0, 8, 0, 0, 0, 100, 0, 0, 0, 4, 0, 0, 0, 2, 0, 0, 0, 97, 98, 0, 0,
+    ];
+    let command = flatbuffers::decode_request(&message);
+    assert_eq!(
+        command,
+        Some(Command::Register {
+            origin: "ab".to_string(),
+            priority: 100
+        })
+    );
+}
+
+#[test]
+fn test_flatbuffers_decode_invalid() {
+    let encoded = flatbuffers::encode_request(&image(None)).unwrap();
+    for len in 0..encoded.len() - 4 {
+        // truncated messages must not panic
+        let _ = flatbuffers::decode_request(&encoded[..len]);
+    }
+    assert_eq!(flatbuffers::decode_request(&[0xFF, 0xFF, 0xFF, 0xFF]), None);
+    assert_eq!(flatbuffers::decode_request(&[]), None);
+}
+
+#[test]
+fn test_flatbuffers_reply() {
+    let reply = Reply {
+        error: None,
+        registered: Some(150),
+        video: None,
+    };
+    assert_eq!(flatbuffers::decode_reply(&flatbuffers::encode_reply(&reply)), Some(reply));
+
+    let reply = Reply {
+        error: Some("Not registered".to_string()),
+        registered: None,
+        video: None,
+    };
+    assert_eq!(flatbuffers::decode_reply(&flatbuffers::encode_reply(&reply)), Some(reply));
+}
+
+#[test]
+fn test_protobuf_round_trip() {
+    let commands = [
+        Command::Color {
+            priority: Some(100),
+            color: [255, 0, 128],
+            duration: DURATION_INFINITE,
+        },
+        image(Some(50)),
+        Command::Clear { priority: 100 },
+        Command::ClearAll,
+    ];
+    for command in commands.iter() {
+        let encoded = protobuf::encode_request(command).unwrap();
+        assert_eq!(protobuf::decode_request(&encoded).as_ref(), Some(command));
+    }
+    assert!(protobuf::encode_request(&image(None)).is_err());
+}
+
+#[test]
+fn test_protobuf_encoding() {
+    let command = Command::Clear { priority: 300 };
+    let encoded = protobuf::encode_request(&command).unwrap();
+    assert_eq!(encoded, [0x08, 0x03, 0x62, 0x03, 0x08, 0xAC, 0x02]);
+
+    // negative values use 10 bytes
+    let command = Command::Clear { priority: -1 };
+    let encoded = protobuf::encode_request(&command).unwrap();
+    assert_eq!(encoded.len(), 4 + 11);
+    assert_eq!(protobuf::decode_request(&encoded), Some(command));
+}
+
+#[test]
+fn test_protobuf_decode_invalid() {
+    let encoded = protobuf::encode_request(&image(Some(1))).unwrap();
+    for len in 0..encoded.len() {
+        assert_eq!(protobuf::decode_request(&encoded[..len]), None);
+    }
+    // missing required field
+    assert_eq!(protobuf::decode_request(&[0x08, 0x01]), None);
+}
+
+#[test]
+fn test_protobuf_reply() {
+    let reply = Reply::default();
+    assert_eq!(protobuf::encode_reply(&reply), [0x08, 0x01, 0x10, 0x01]);
+    assert_eq!(protobuf::decode_reply(&protobuf::encode_reply(&reply)), Some(reply));
+
+    let reply = Reply {
+        error: Some("Invalid image".to_string()),
+        registered: None,
+        video: None,
+    };
+    assert_eq!(protobuf::decode_reply(&protobuf::encode_reply(&reply)), Some(reply));
+}
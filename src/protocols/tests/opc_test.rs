@@ -0,0 +1,48 @@
+use lightoros_plugin_base::*;
+use lightoros_protocols::opc::*;
+
+#[test]
+fn test_encode() {
+    let message = Message::set_pixels(2, &[RGB { r: 1, g: 2, b: 3 }]);
+    assert_eq!(message.encode().unwrap(), [2, 0, 0, 3, 1, 2, 3]);
+
+    let message = Message {
+        channel: 1,
+        command: CMD_SET_PIXELS,
+        data: vec![0; 0x10000],
+    };
+    assert!(message.encode().is_err());
+}
+
+#[test]
+fn test_decode_chunks() {
+    let first = Message::set_pixels(1, &[RGB { r: 1, g: 2, b: 3 }; 2]);
+    let second = Message {
+        channel: BROADCAST_CHANNEL,
+        command: CMD_SYSTEM_EXCLUSIVE,
+        data: vec![0, 1, 0, 1],
+    };
+    let mut stream = first.encode().unwrap();
+    stream.extend(second.encode().unwrap());
+
+    let mut decoder = Decoder::new();
+    let mut messages = Vec::new();
+    for byte in stream {
+        decoder.push(&[byte]);
+        while let Some(message) = decoder.next_message() {
+            messages.push(message);
+        }
+    }
+    assert_eq!(messages, [first, second]);
+    assert_eq!(decoder.pending(), 0);
+}
+
+#[test]
+fn test_decode_empty_message() {
+    let mut decoder = Decoder::new();
+    decoder.push(&[0, 0, 0, 0, 1]);
+    let message = decoder.next_message().unwrap();
+    assert!(message.data.is_empty());
+    assert_eq!(decoder.next_message(), None);
+    assert_eq!(decoder.pending(), 1);
+}
@@ -0,0 +1,59 @@
+use lightoros_protocols::prismatik::*;
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!(
+        parse_command("apikey:secret").unwrap(),
+        Command::ApiKey("secret".to_string())
+    );
+    assert_eq!(parse_command("lock\r\n").unwrap(), Command::Lock);
+    assert_eq!(parse_command("unlock").unwrap(), Command::Unlock);
+    assert_eq!(
+        parse_command("getcountleds").unwrap(),
+        Command::GetCountLeds
+    );
+    assert_eq!(
+        parse_command("setprofile:Movie").unwrap(),
+        Command::SetProfile("Movie".to_string())
+    );
+    assert_eq!(
+        parse_command("setbrightness:50").unwrap(),
+        Command::SetBrightness(50)
+    );
+    assert_eq!(
+        parse_command("setgamma:2.2").unwrap(),
+        Command::SetGamma(2.2)
+    );
+    assert_eq!(
+        parse_command("setstatus:off").unwrap(),
+        Command::SetStatus(false)
+    );
+    assert_eq!(parse_command("exit").unwrap(), Command::Exit);
+    assert_eq!(
+        parse_command("help").unwrap(),
+        Command::Unknown("help".to_string())
+    );
+}
+
+#[test]
+fn test_parse_colors() {
+    assert_eq!(
+        parse_command("setcolor:1-255,0,0;3-0,128,255;").unwrap(),
+        Command::SetColor(vec![(1, [255, 0, 0]), (3, [0, 128, 255])])
+    );
+    assert_eq!(
+        parse_command("setcolor:2-1,2,3").unwrap(),
+        Command::SetColor(vec![(2, [1, 2, 3])])
+    );
+    assert!(parse_command("setcolor:0-1,2,3;").is_err());
+    assert!(parse_command("setcolor:1-1,2;").is_err());
+    assert!(parse_command("setcolor:1-1,2,300;").is_err());
+    assert!(parse_command("setcolor:1;").is_err());
+}
+
+#[test]
+fn test_parse_invalid_values() {
+    assert!(parse_command("setbrightness:101").is_err());
+    assert!(parse_command("setgamma:0").is_err());
+    assert!(parse_command("setstatus:maybe").is_err());
+}
@@ -0,0 +1,347 @@
+use lightoros_plugin_base::*;
+use lightoros_protocols::tpm2::*;
+
+// simple deterministic pseudo random generator, good enough for fuzzing
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, max: usize) -> usize {
+        (self.next() % max as u64) as usize
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+fn create_rgb(count: usize, seed: u64) -> Vec<RGB> {
+    let mut rng = XorShift(seed);
+    (0..count)
+        .map(|_| RGB {
+            r: rng.next() as u8,
+            g: rng.next() as u8,
+            b: rng.next() as u8,
+        })
+        .collect()
+}
+
+fn decode_frames(decoder: &mut Decoder, assembler: &mut FrameAssembler) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    while let Some(packet) = decoder.next_packet() {
+        if let Some(frame) = assembler.add(packet) {
+            frames.push(frame);
+        }
+    }
+    frames
+}
+
+#[test]
+fn test_encode_serial_frame() {
+    let encoder = Encoder::new(Framing::Serial, ColorOrder::GRB);
+    let packets = encoder
+        .encode_frame(&[RGB { r: 1, g: 2, b: 3 }, RGB { r: 4, g: 5, b: 6 }])
+        .unwrap();
+    assert_eq!(packets.len(), 1);
+    assert_eq!(
+        packets[0],
+        [0xC9, 0xDA, 0x00, 0x06, 2, 1, 3, 5, 4, 6, 0x36]
+    );
+}
+
+#[test]
+fn test_encode_serial_frame_too_big() {
+    let encoder = Encoder::new(Framing::Serial, ColorOrder::RGB)
+        .with_max_packet_length(11)
+        .unwrap();
+    assert!(encoder.encode_frame(&create_rgb(3, 1)).is_err());
+}
+
+#[test]
+fn test_encode_net_frame_split() {
+    let encoder = Encoder::new(Framing::Net, ColorOrder::RGB)
+        .with_max_packet_length(37)
+        .unwrap();
+    let packets = encoder.encode_frame(&create_rgb(25, 1)).unwrap();
+    let sizes: Vec<usize> = packets.iter().map(|packet| packet.len()).collect();
+    assert_eq!(sizes, [34, 34, 28]);
+    for (i, packet) in packets.iter().enumerate() {
+        assert_eq!(packet[0], 0x9C);
+        assert_eq!(packet[4] as usize, i + 1);
+        assert_eq!(packet[5], 3);
+    }
+}
+
+#[test]
+fn test_encode_net_frame_too_many_packets() {
+    let encoder = Encoder::new(Framing::Net, ColorOrder::RGB)
+        .with_max_packet_length(10)
+        .unwrap();
+    assert!(encoder.encode_frame(&create_rgb(255, 1)).is_ok());
+    assert!(encoder.encode_frame(&create_rgb(256, 1)).is_err());
+}
+
+#[test]
+fn test_invalid_max_packet_length() {
+    assert!(Encoder::new(Framing::Net, ColorOrder::RGB)
+        .with_max_packet_length(9)
+        .is_err());
+    assert!(Encoder::new(Framing::Serial, ColorOrder::RGB)
+        .with_max_packet_length(0x10005)
+        .is_err());
+}
+
+#[test]
+fn test_encode_command() {
+    let encoder = Encoder::new(Framing::Net, ColorOrder::RGB);
+    assert_eq!(
+        encoder.encode_command(&[0x0A, 0x80]).unwrap(),
+        [0x9C, 0xC0, 0x00, 0x02, 0x01, 0x01, 0x0A, 0x80, 0x36]
+    );
+}
+
+#[test]
+fn test_decode_packet_types() {
+    let mut decoder = Decoder::new();
+    decoder.push(&[0xC9, 0xC0, 0x00, 0x01, 0x05, 0x36]);
+    decoder.push(&[0xC9, 0xAA, 0x00, 0x00, 0x36]);
+    decoder.push(&[0x9C, 0xDA, 0x00, 0x03, 0x02, 0x02, 1, 2, 3, 0x36]);
+
+    let packet = decoder.next_packet().unwrap();
+    assert_eq!(packet.framing, Framing::Serial);
+    assert_eq!(packet.packet_type, PacketType::Command);
+    assert_eq!(packet.payload, [0x05]);
+
+    let packet = decoder.next_packet().unwrap();
+    assert_eq!(packet.packet_type, PacketType::Response);
+    assert!(packet.payload.is_empty());
+
+    let packet = decoder.next_packet().unwrap();
+    assert_eq!(packet.framing, Framing::Net);
+    assert_eq!(packet.packet_type, PacketType::Data);
+    assert_eq!(packet.packet_number, 2);
+    assert_eq!(packet.number_of_packets, 2);
+    assert_eq!(packet.payload, [1, 2, 3]);
+
+    assert!(decoder.next_packet().is_none());
+    assert_eq!(decoder.pending(), 0);
+}
+
+#[test]
+fn test_decode_acknowledge() {
+    let mut decoder = Decoder::new().with_acknowledge();
+    decoder.push(&[0xAC, 0xC9, 0xAA, 0x00, 0x00, 0x36]);
+    assert_eq!(decoder.next_message(), Some(Message::Acknowledge));
+    match decoder.next_message() {
+        Some(Message::Packet(packet)) => assert_eq!(packet.packet_type, PacketType::Response),
+        _ => panic!("response packet expected"),
+    }
+
+    // acknowledge bytes are garbage for a decoder not expecting them
+    let mut decoder = Decoder::new();
+    decoder.push(&[0xAC]);
+    assert_eq!(decoder.next_message(), None);
+}
+
+#[test]
+fn test_decode_truncated_packet() {
+    let mut decoder = Decoder::new();
+    decoder.push(&[0xC9, 0xDA, 0x00, 0x06, 1, 2, 3]);
+    assert!(decoder.next_packet().is_none());
+    assert_eq!(decoder.pending(), 7);
+    decoder.push(&[4, 5, 6, 0x36]);
+    assert_eq!(decoder.next_packet().unwrap().payload, [1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn test_decode_resynchronises() {
+    let mut decoder = Decoder::new();
+    // garbage, a packet with a broken end byte, a start byte with an invalid type and a valid packet
+    decoder.push(&[0x00, 0x12, 0xC9, 0xDA, 0x00, 0x01, 0x07, 0x00, 0x9C, 0x11]);
+    decoder.push(&[0xC9, 0xDA, 0x00, 0x03, 7, 8, 9, 0x36]);
+    let packet = decoder.next_packet().unwrap();
+    assert_eq!(packet.payload, [7, 8, 9]);
+    assert!(decoder.next_packet().is_none());
+}
+
+#[test]
+fn test_decode_invalid_packet_numbers() {
+    let mut decoder = Decoder::new();
+    decoder.push(&[0x9C, 0xDA, 0x00, 0x00, 0x00, 0x01, 0x36]);
+    decoder.push(&[0x9C, 0xDA, 0x00, 0x00, 0x03, 0x02, 0x36]);
+    assert!(decoder.next_packet().is_none());
+}
+
+#[test]
+fn test_assemble_out_of_order() {
+    let encoder = Encoder::new(Framing::Net, ColorOrder::RGB)
+        .with_max_packet_length(37)
+        .unwrap();
+    let rgb = create_rgb(25, 7);
+    let packets = encoder.encode_frame(&rgb).unwrap();
+
+    let mut decoder = Decoder::new();
+    let mut assembler = FrameAssembler::new();
+    for i in [2, 0, 1].iter() {
+        decoder.push(&packets[*i]);
+    }
+    let frames = decode_frames(&mut decoder, &mut assembler);
+    assert_eq!(frames.len(), 1);
+    let decoded = decode_rgb(&frames[0], ColorOrder::RGB);
+    assert_eq!(decoded.len(), 25);
+    for (a, b) in decoded.iter().zip(rgb.iter()) {
+        assert_eq!((a.r, a.g, a.b), (b.r, b.g, b.b));
+    }
+}
+
+#[test]
+fn test_assemble_lost_packet() {
+    let encoder = Encoder::new(Framing::Net, ColorOrder::RGB)
+        .with_max_packet_length(37)
+        .unwrap();
+    let first = encoder.encode_frame(&create_rgb(25, 1)).unwrap();
+    let second = encoder.encode_frame(&create_rgb(25, 2)).unwrap();
+
+    let mut decoder = Decoder::new();
+    let mut assembler = FrameAssembler::new();
+    // second packet of the first frame is lost
+    decoder.push(&first[0]);
+    decoder.push(&first[2]);
+    for packet in &second {
+        decoder.push(packet);
+    }
+    let frames = decode_frames(&mut decoder, &mut assembler);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0], second.iter().fold(Vec::new(), |mut frame, packet| {
+        frame.extend_from_slice(&packet[6..packet.len() - 1]);
+        frame
+    }));
+    assert!(!assembler.is_pending());
+}
+
+#[test]
+fn test_round_trip() {
+    let mut rng = XorShift(0x1234_5678);
+    let orders = [
+        ColorOrder::RGB,
+        ColorOrder::RBG,
+        ColorOrder::GRB,
+        ColorOrder::GBR,
+        ColorOrder::BRG,
+        ColorOrder::BGR,
+    ];
+
+    for round in 0..200 {
+        let framing = if round % 2 == 0 {
+            Framing::Serial
+        } else {
+            Framing::Net
+        };
+        let color_order = orders[rng.below(orders.len())];
+        let count = rng.below(600);
+        let max_packet_length = match framing {
+            Framing::Serial => 0xFFFF,
+            Framing::Net => 10 + rng.below(1500),
+        };
+        let encoder = Encoder::new(framing, color_order)
+            .with_max_packet_length(max_packet_length)
+            .unwrap();
+        let rgb = create_rgb(count, round + 1);
+        let packets = match encoder.encode_frame(&rgb) {
+            Ok(packets) => packets,
+            Err(_) => continue, // too many packets needed
+        };
+
+        let mut stream: Vec<u8> = Vec::new();
+        for packet in &packets {
+            assert!(packet.len() <= max_packet_length);
+            stream.extend_from_slice(packet);
+        }
+
+        // feed the stream in random chunks
+        let mut decoder = Decoder::new();
+        let mut assembler = FrameAssembler::new();
+        let mut frames = Vec::new();
+        let mut offset = 0;
+        while offset < stream.len() {
+            let end = std::cmp::min(stream.len(), offset + 1 + rng.below(200));
+            decoder.push(&stream[offset..end]);
+            frames.append(&mut decode_frames(&mut decoder, &mut assembler));
+            offset = end;
+        }
+
+        assert_eq!(frames.len(), 1);
+        let decoded = decode_rgb(&frames[0], color_order);
+        assert_eq!(decoded.len(), count);
+        for (a, b) in decoded.iter().zip(rgb.iter()) {
+            assert_eq!((a.r, a.g, a.b), (b.r, b.g, b.b));
+        }
+    }
+}
+
+#[test]
+fn test_fuzz_random_data() {
+    let mut rng = XorShift(0xDEAD_BEEF);
+    for _ in 0..500 {
+        let mut decoder = Decoder::new().with_acknowledge();
+        let mut assembler = FrameAssembler::new();
+        let len = rng.below(2048);
+        let mut data = rng.bytes(len);
+        // make start bytes and packet types more likely
+        for byte in data.iter_mut() {
+            match rng.below(16) {
+                0 => *byte = START_SERIAL,
+                1 => *byte = START_NET,
+                2 => *byte = TYPE_DATA,
+                3 => *byte = END,
+                _ => (),
+            }
+        }
+        let mut offset = 0;
+        while offset < data.len() {
+            let end = std::cmp::min(data.len(), offset + 1 + rng.below(64));
+            decoder.push(&data[offset..end]);
+            while let Some(message) = decoder.next_message() {
+                if let Message::Packet(packet) = message {
+                    assert!(packet.packet_number >= 1);
+                    assert!(packet.packet_number <= packet.number_of_packets);
+                    assembler.add(packet);
+                }
+            }
+            offset = end;
+        }
+    }
+}
+
+#[test]
+fn test_fuzz_packets_in_garbage() {
+    let mut rng = XorShift(0xC0FFEE);
+    let encoder = Encoder::new(Framing::Serial, ColorOrder::RGB);
+    for round in 0..200 {
+        let rgb = create_rgb(1 + rng.below(50), round + 1);
+        let packet = &encoder.encode_frame(&rgb).unwrap()[0];
+
+        // garbage without start bytes in front of the packet is always skipped
+        let len = rng.below(100);
+        let mut stream: Vec<u8> = rng
+            .bytes(len)
+            .into_iter()
+            .filter(|b| *b != START_SERIAL && *b != START_NET)
+            .collect();
+        stream.extend_from_slice(packet);
+        let len = rng.below(100);
+        let garbage = rng.bytes(len);
+        stream.extend_from_slice(&garbage);
+
+        let mut decoder = Decoder::new();
+        decoder.push(&stream);
+        let decoded = decoder.next_packet().unwrap();
+        assert_eq!(decoded.payload.len(), rgb.len() * 3);
+    }
+}
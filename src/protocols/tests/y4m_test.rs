@@ -0,0 +1,123 @@
+use lightoros_protocols::y4m::*;
+
+#[test]
+fn test_parse_header() {
+    let header =
+        parse_header("YUV4MPEG2 W320 H240 F30000:1001 Ip A1:1 C420jpeg XYSCSS=420JPEG\n").unwrap();
+    assert_eq!(
+        header,
+        Header {
+            width: 320,
+            height: 240,
+            frame_rate: Some((30000, 1001)),
+            chroma: Chroma::C420,
+            range: None,
+        }
+    );
+    assert_eq!(header.frame_size().unwrap(), 320 * 240 * 3 / 2);
+
+    let header = parse_header("YUV4MPEG2 W3 H3 F0:0 C444 XCOLORRANGE=FULL").unwrap();
+    assert_eq!(header.frame_rate, None);
+    assert_eq!(header.chroma, Chroma::C444);
+    assert_eq!(header.range, Some(Range::Full));
+    assert_eq!(header.frame_size().unwrap(), 27);
+
+    // odd sizes are rounded up for the subsampled planes
+    let header = parse_header("YUV4MPEG2 H3 W3").unwrap();
+    assert_eq!(header.chroma, Chroma::C420);
+    assert_eq!(header.frame_size().unwrap(), 9 + 2 * 4);
+    assert_eq!(
+        parse_header("YUV4MPEG2 W4 H2 C422")
+            .unwrap()
+            .frame_size()
+            .unwrap(),
+        16
+    );
+    assert_eq!(
+        parse_header("YUV4MPEG2 W4 H2 Cmono")
+            .unwrap()
+            .frame_size()
+            .unwrap(),
+        8
+    );
+}
+
+#[test]
+fn test_parse_invalid_header() {
+    assert!(parse_header("").is_err());
+    assert!(parse_header("YUV4MPEG W2 H2").is_err());
+    assert!(parse_header("YUV4MPEG2 W2").is_err());
+    assert!(parse_header("YUV4MPEG2 W0 H2").is_err());
+    assert!(parse_header("YUV4MPEG2 W2 H2 F25").is_err());
+    assert!(parse_header("YUV4MPEG2 W2 H2 C420p10").is_err());
+
+    // frames too large to be allocated
+    assert!(parse_header("YUV4MPEG2 W100000 H100000").is_err());
+    assert!(parse_header("YUV4MPEG2 W4294967296 H4294967296").is_err());
+    assert!(parse_header("YUV4MPEG2 W4096 H4096 C444").is_ok());
+    let header = Header {
+        width: usize::MAX,
+        height: 2,
+        frame_rate: None,
+        chroma: Chroma::Mono,
+        range: None,
+    };
+    assert!(header.frame_size().is_err());
+
+    assert!(parse_frame_header("FRAME\n").is_ok());
+    assert!(parse_frame_header("FRAME Ixyz\n").is_ok());
+    assert!(parse_frame_header("FRAMES\n").is_err());
+}
+
+#[test]
+fn test_decode_rgb() {
+    let header = parse_header("YUV4MPEG2 W2 H1 C444").unwrap();
+    // white and black in limited range
+    let rgb = decode_rgb(
+        &header,
+        &[235, 16, 128, 128, 128, 128],
+        Matrix::Bt601,
+        Range::Limited,
+    );
+    assert_eq!((rgb[0].r, rgb[0].g, rgb[0].b), (255, 255, 255));
+    assert_eq!((rgb[1].r, rgb[1].g, rgb[1].b), (0, 0, 0));
+
+    // red in full range BT.601: Y=76 U=85 V=255
+    let rgb = decode_rgb(
+        &header,
+        &[76, 76, 85, 85, 255, 255],
+        Matrix::Bt601,
+        Range::Full,
+    );
+    assert!(rgb[0].r >= 253 && rgb[0].g <= 2 && rgb[0].b <= 2);
+
+    // red in limited range BT.709: Y=63 U=102 V=240
+    let rgb = decode_rgb(
+        &header,
+        &[63, 63, 102, 102, 240, 240],
+        Matrix::Bt709,
+        Range::Limited,
+    );
+    assert!(rgb[0].r >= 253 && rgb[0].g <= 2 && rgb[0].b <= 2);
+}
+
+#[test]
+fn test_decode_subsampled_rgb() {
+    let header = parse_header("YUV4MPEG2 W2 H2 C420").unwrap();
+    // all four pixels share the chroma values of blue
+    let rgb = decode_rgb(
+        &header,
+        &[41, 41, 41, 41, 240, 110],
+        Matrix::Bt601,
+        Range::Limited,
+    );
+    assert_eq!(rgb.len(), 4);
+    for pixel in rgb {
+        assert!(pixel.r <= 2 && pixel.g <= 2 && pixel.b >= 253);
+    }
+
+    let header = parse_header("YUV4MPEG2 W2 H1 Cmono").unwrap();
+    let rgb = decode_rgb(&header, &[126, 235], Matrix::Bt709, Range::Limited);
+    assert_eq!((rgb[0].r, rgb[0].g, rgb[0].b), (128, 128, 128));
+    assert_eq!((rgb[1].r, rgb[1].g, rgb[1].b), (255, 255, 255));
+}